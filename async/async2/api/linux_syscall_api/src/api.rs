@@ -20,6 +20,13 @@ pub fn init_current_dir() {
     axfs::api::set_current_dir("/").expect("reset current dir failed");
 }
 
+/// 将当前架构上已实现的系统调用的列表写入 /proc/syscalls
+pub fn write_syscall_report() {
+    if let Err(err) = axfs::api::write("/proc/syscalls", crate::implemented_syscalls()) {
+        axlog::warn!("failed to write /proc/syscalls: {:?}", err);
+    }
+}
+
 /// Flags for opening a file
 pub type FileFlags = OpenFlags;
 
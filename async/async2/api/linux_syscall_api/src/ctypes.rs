@@ -1,3 +1,4 @@
+use axfs::api::Kstat;
 use axhal::{
     paging::MappingFlags,
     time::{current_time_nanos, nanos_to_ticks, MICROS_PER_SEC, NANOS_PER_MICROS, NANOS_PER_SEC},
@@ -19,6 +20,7 @@ bitflags! {
 }
 /// sys_times 中指定的结构体类型
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct Tms {
     /// 进程用户态执行时间，单位为us
     pub tms_utime: usize,
@@ -32,7 +34,7 @@ pub struct Tms {
 
 /// sys_gettimeofday 中指定的类型
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct TimeVal {
     /// seconds
     pub sec: usize,
@@ -183,8 +185,122 @@ bitflags! {
     }
 }
 
+bitflags! {
+    #[derive(Debug)]
+    /// 指定 msync 的选项
+    pub struct MSyncFlags: u32 {
+        /// 异步写回，立即返回
+        const MS_ASYNC = 1 << 0;
+        /// 使同一文件的其他映射失效
+        const MS_INVALIDATE = 1 << 1;
+        /// 同步写回，写回完成后才返回
+        const MS_SYNC = 1 << 2;
+    }
+}
+
+/// madvise：无特殊建议
+pub const MADV_NORMAL: usize = 0;
+/// madvise：之后会随机访问
+pub const MADV_RANDOM: usize = 1;
+/// madvise：之后会顺序访问
+pub const MADV_SEQUENTIAL: usize = 2;
+/// madvise：之后很快会访问
+pub const MADV_WILLNEED: usize = 3;
+/// madvise：之后不再需要其中的内容，再次访问时匿名映射的内容为 0
+pub const MADV_DONTNEED: usize = 4;
+/// madvise：内存可以被释放
+pub const MADV_FREE: usize = 8;
+
+/// shmget：总是创建新的共享内存，该共享内存只能通过 shmid 访问
+pub const IPC_PRIVATE: i32 = 0;
+/// shmctl：标记删除共享内存
+pub const IPC_RMID: usize = 0;
+/// shmctl：设置共享内存的属主与权限
+pub const IPC_SET: usize = 1;
+/// shmctl：获取共享内存的信息
+pub const IPC_STAT: usize = 2;
+/// shmctl：部分 libc 会在命令中附加该位，表示使用 64 位版本的结构体
+pub const IPC_64: usize = 0x100;
+/// 共享内存地址需要对齐的边界
+pub const SHMLBA: usize = 4096;
+
+bitflags! {
+    #[derive(Debug)]
+    /// 指定 shmget 的选项，低 9 位为共享内存的访问权限
+    pub struct ShmGetFlags: u32 {
+        /// key 对应的共享内存不存在时创建
+        const IPC_CREAT = 0o1000;
+        /// 与 IPC_CREAT 一起使用，key 对应的共享内存已存在时失败
+        const IPC_EXCL = 0o2000;
+    }
+}
+
+bitflags! {
+    #[derive(Debug)]
+    /// 指定 shmat 的选项
+    pub struct ShmAtFlags: u32 {
+        /// 只读挂载
+        const SHM_RDONLY = 0o10000;
+        /// 将地址向下对齐到 SHMLBA
+        const SHM_RND = 0o20000;
+        /// 替换指定地址上原有的映射
+        const SHM_REMAP = 0o40000;
+        /// 允许执行共享内存中的内容
+        const SHM_EXEC = 0o100000;
+    }
+}
+
+/// shmctl 中共享内存的属主与权限
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IpcPerm {
+    /// shmget 时指定的 key
+    pub key: i32,
+    /// 属主的用户 id
+    pub uid: u32,
+    /// 属主的组 id
+    pub gid: u32,
+    /// 创建者的用户 id
+    pub cuid: u32,
+    /// 创建者的组 id
+    pub cgid: u32,
+    /// 访问权限
+    pub mode: u32,
+    /// 序列号
+    pub seq: u16,
+    /// 填充
+    pub pad: u16,
+    /// 未使用
+    pub unused: [usize; 2],
+}
+
+/// shmctl(IPC_STAT) 中获取的共享内存信息
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ShmidDs {
+    /// 属主与权限
+    pub shm_perm: IpcPerm,
+    /// 创建时指定的大小
+    pub shm_segsz: usize,
+    /// 最后一次挂载的时间
+    pub shm_atime: isize,
+    /// 最后一次解除挂载的时间
+    pub shm_dtime: isize,
+    /// 最后一次修改的时间
+    pub shm_ctime: isize,
+    /// 创建者的进程号
+    pub shm_cpid: i32,
+    /// 最后一次挂载或解除挂载的进程号
+    pub shm_lpid: i32,
+    /// 当前挂载的次数
+    pub shm_nattch: usize,
+    /// 未使用
+    pub unused: [usize; 2],
+}
+
 /// sys_uname 中指定的结构体类型
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct UtsName {
     /// 系统名称
     pub sysname: [u8; 65],
@@ -234,50 +350,51 @@ pub enum SigMaskFlag {
     Setmask = 2,
 }
 
-impl SigMaskFlag {
+impl TryFrom<usize> for SigMaskFlag {
+    type Error = ();
+
     /// turn a usize to SigMaskFlag
-    pub fn from(value: usize) -> Self {
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
         match value {
-            0 => SigMaskFlag::Block,
-            1 => SigMaskFlag::Unblock,
-            2 => SigMaskFlag::Setmask,
-            _ => panic!("SIG_MASK_FLAG::from: invalid value"),
+            0 => Ok(SigMaskFlag::Block),
+            1 => Ok(SigMaskFlag::Unblock),
+            2 => Ok(SigMaskFlag::Setmask),
+            _ => Err(()),
         }
     }
 }
 
-/// sys_prlimit64 使用的数组
-#[repr(C)]
-pub struct RLimit {
-    /// 软上限
-    pub rlim_cur: u64,
-    /// 硬上限
-    pub rlim_max: u64,
-}
-// sys_prlimit64 使用的选项
-/// 用户栈大小
-pub const RLIMIT_STACK: i32 = 3;
-/// 可以打开的 fd 数
-pub const RLIMIT_NOFILE: i32 = 7;
-/// 用户地址空间的最大大小
-pub const RLIMIT_AS: i32 = 9;
-
-/// robust list
-#[repr(C)]
-pub struct RobustList {
-    head: usize,
-    off: usize,
-    pending: usize,
-}
+/// sys_prlimit64 使用的结构体与选项，定义在 axtask 中以便在加载程序、分配文件描述符时检查
+pub use axtask::rlimit::{RLimit, RLIMIT_AS, RLIMIT_NOFILE, RLIMIT_STACK, RLIM_INFINITY};
 
 /// readv/writev使用的结构体
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct IoVec {
     /// base address of the buffer
     pub base: *mut u8,
     /// length of the buffer
     pub len: usize,
 }
+
+/// 一次 readv/writev 最多可以使用的 iovec 个数
+pub const IOV_MAX: usize = 1024;
+
+/// lseek 的 whence 参数：从文件开头计算偏移
+pub const SEEK_SET: usize = 0;
+/// lseek 的 whence 参数：从当前位置计算偏移
+pub const SEEK_CUR: usize = 1;
+/// lseek 的 whence 参数：从文件末尾计算偏移
+pub const SEEK_END: usize = 2;
+/// lseek 的 whence 参数：移动到不小于 offset 的下一个数据区域
+pub const SEEK_DATA: usize = 3;
+/// lseek 的 whence 参数：移动到不小于 offset 的下一个空洞
+pub const SEEK_HOLE: usize = 4;
+/// futex 操作码中的私有标志，表示 futex 只在进程内共享。物理地址作键时无需区分
+pub const FUTEX_PRIVATE_FLAG: i32 = 128;
+/// futex 操作码中的标志，表示超时时间以 CLOCK_REALTIME 计算
+pub const FUTEX_CLOCK_REALTIME: i32 = 256;
+
 /// 对 futex 的操作
 pub enum FutexFlags {
     /// 检查用户地址 uaddr 处的值。如果不是要求的值则等待 wake
@@ -286,6 +403,12 @@ pub enum FutexFlags {
     Wake,
     /// 将等待 uaddr 的线程移动到 uaddr2
     Requeue,
+    /// 在 uaddr 处的值等于 val3 时才进行 requeue
+    CmpRequeue,
+    /// 与 Wait 相同，但超时时间为绝对时间，且只能被 bitset 匹配的 wake 唤醒
+    WaitBitset,
+    /// 与 Wake 相同，但只唤醒 bitset 匹配的线程
+    WakeBitset,
     /// 不支持的操作
     Unsupported,
 }
@@ -293,10 +416,13 @@ pub enum FutexFlags {
 impl FutexFlags {
     /// Create a FutexFlags from a i32 value
     pub fn new(val: i32) -> Self {
-        match val & 0x7f {
+        match val & !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME) {
             0 => FutexFlags::Wait,
             1 => FutexFlags::Wake,
             3 => FutexFlags::Requeue,
+            4 => FutexFlags::CmpRequeue,
+            9 => FutexFlags::WaitBitset,
+            10 => FutexFlags::WakeBitset,
             _ => FutexFlags::Unsupported,
         }
     }
@@ -320,12 +446,30 @@ numeric_enum_macro::numeric_enum! {
         F_SETFL = 4,
         /// 复制 fd，然后设置 cloexec 信息，即 exec 成功时删除该 fd
         F_DUPFD_CLOEXEC = 1030,
+        /// 为 memfd 添加封印
+        F_ADD_SEALS = 1033,
+        /// 获取 memfd 的封印
+        F_GET_SEALS = 1034,
+    }
+}
+
+/// F_GETFD 与 F_SETFD 使用的文件描述符标志，表示 exec 时关闭该文件描述符
+pub const FD_CLOEXEC: usize = 1;
+
+bitflags! {
+    /// sys_close_range 用到的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct CloseRangeFlags: u32 {
+        /// 关闭前先取消与其他任务共享的文件描述符表
+        const UNSHARE = 1 << 1;
+        /// 不关闭文件描述符，而是为它们设置 FD_CLOEXEC 标志
+        const CLOEXEC = 1 << 2;
     }
 }
 
 /// syscall_info 用到的 结构体
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct SysInfo {
     /// 启动时间(以秒计)
     pub uptime: isize,
@@ -354,6 +498,44 @@ pub struct SysInfo {
     pub mem_unit: u32,
 }
 
+/// sys_getrusage 中指定的结构体类型
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct RUsage {
+    /// 用户态执行时间
+    pub ru_utime: TimeVal,
+    /// 内核态执行时间
+    pub ru_stime: TimeVal,
+    /// 最大常驻内存，单位为 KB
+    pub ru_maxrss: isize,
+    /// 共享内存大小
+    pub ru_ixrss: isize,
+    /// 非共享数据段大小
+    pub ru_idrss: isize,
+    /// 非共享栈大小
+    pub ru_isrss: isize,
+    /// 不需要 I/O 的缺页次数
+    pub ru_minflt: isize,
+    /// 需要 I/O 的缺页次数
+    pub ru_majflt: isize,
+    /// 被换出的次数
+    pub ru_nswap: isize,
+    /// 文件系统读入的块数
+    pub ru_inblock: isize,
+    /// 文件系统写出的块数
+    pub ru_oublock: isize,
+    /// 发送的 IPC 消息数
+    pub ru_msgsnd: isize,
+    /// 接收的 IPC 消息数
+    pub ru_msgrcv: isize,
+    /// 收到的信号数
+    pub ru_nsignals: isize,
+    /// 主动让出 CPU 的次数
+    pub ru_nvcsw: isize,
+    /// 被抢占的次数
+    pub ru_nivcsw: isize,
+}
+
 /// sys_getrusage 用到的选项
 #[allow(non_camel_case_types)]
 pub enum RusageFlags {
@@ -385,23 +567,45 @@ pub const SCHED_RESET_ON_FORK: usize = 0x40000000;
 #[derive(Clone, Copy)]
 /// sys_sched_setparam 使用的结构体
 pub struct SchedParam {
-    /// The scheduling policy of the param argument
-    pub sched_priority: usize,
+    /// The scheduling priority, which is 1 to 99 for real-time policies and 0 for the others
+    pub sched_priority: i32,
 }
 
+/// getpriority / setpriority 的 which 参数：who 为线程号
+pub const PRIO_PROCESS: usize = 0;
+/// getpriority / setpriority 的 which 参数：who 为进程组号
+pub const PRIO_PGRP: usize = 1;
+/// getpriority / setpriority 的 which 参数：who 为用户号
+pub const PRIO_USER: usize = 2;
+
 numeric_enum_macro::numeric_enum! {
     #[repr(usize)]
     #[allow(non_camel_case_types)]
     #[derive(PartialEq,Eq)]
-    /// sys_fcntl64 使用的选项
+    /// sys_clock_gettime 等使用的时钟
     pub enum ClockId {
         /// real-time clock
         CLOCK_REALTIME = 0,
         /// monotonic clock
         CLOCK_MONOTONIC = 1,
+        /// 当前进程所有线程消耗的 CPU 时间
+        CLOCK_PROCESS_CPUTIME_ID = 2,
+        /// 当前线程消耗的 CPU 时间
+        CLOCK_THREAD_CPUTIME_ID = 3,
+        /// 不受 NTP 调整影响的 monotonic clock
+        CLOCK_MONOTONIC_RAW = 4,
+        /// 精度较低但更快的 real-time clock
+        CLOCK_REALTIME_COARSE = 5,
+        /// 精度较低但更快的 monotonic clock
+        CLOCK_MONOTONIC_COARSE = 6,
+        /// 包含系统挂起时间的 monotonic clock
+        CLOCK_BOOTTIME = 7,
     }
 }
 
+/// sys_clock_nanosleep 的选项：给出的时间为绝对时间
+pub const TIMER_ABSTIME: usize = 1;
+
 /// 目录项
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -456,6 +660,7 @@ impl DirEnt {
 }
 bitflags! {
     /// sys_renameat2 用到的选项
+    #[derive(Debug, Clone, Copy)]
     pub struct RenameFlags: u32 {
         /// Nothing
         const NONE = 0;
@@ -468,10 +673,116 @@ bitflags! {
     }
 }
 
+/// 若路径为空字符串，则直接对 dirfd 指向的文件进行操作
+pub const AT_EMPTY_PATH: usize = 0x1000;
+/// 不跟随符号链接
+pub const AT_SYMLINK_NOFOLLOW: usize = 0x100;
+/// unlinkat 删除目录而非文件
+pub const AT_REMOVEDIR: usize = 0x200;
+
+/// statx 中请求的基本信息，即 stat 中包含的所有字段
+pub const STATX_BASIC_STATS: u32 = 0x7ff;
+
+/// statx 使用的时间戳
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatxTimestamp {
+    /// 秒
+    pub tv_sec: i64,
+    /// 纳秒
+    pub tv_nsec: u32,
+    /// 保留
+    pub __reserved: i32,
+}
+
+/// sys_statx 中使用的结构体
+/// 具体参数定义信息来自 `https://man7.org/linux/man-pages/man2/statx.2.html`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Statx {
+    /// 已填写的字段掩码
+    pub stx_mask: u32,
+    /// 块大小
+    pub stx_blksize: u32,
+    /// 文件的额外属性
+    pub stx_attributes: u64,
+    /// 硬链接数
+    pub stx_nlink: u32,
+    /// 用户id
+    pub stx_uid: u32,
+    /// 用户组id
+    pub stx_gid: u32,
+    /// 文件类型与权限
+    pub stx_mode: u16,
+    /// padding
+    pub __spare0: u16,
+    /// inode 编号
+    pub stx_ino: u64,
+    /// 文件大小
+    pub stx_size: u64,
+    /// 块个数
+    pub stx_blocks: u64,
+    /// stx_attributes 中支持的属性
+    pub stx_attributes_mask: u64,
+    /// 最后一次访问时间
+    pub stx_atime: StatxTimestamp,
+    /// 创建时间
+    pub stx_btime: StatxTimestamp,
+    /// 最后一次改变状态时间
+    pub stx_ctime: StatxTimestamp,
+    /// 最后一次修改时间
+    pub stx_mtime: StatxTimestamp,
+    /// 设备号（主）
+    pub stx_rdev_major: u32,
+    /// 设备号（次）
+    pub stx_rdev_minor: u32,
+    /// 所在设备号（主）
+    pub stx_dev_major: u32,
+    /// 所在设备号（次）
+    pub stx_dev_minor: u32,
+    /// 挂载点 id
+    pub stx_mnt_id: u64,
+    /// 直接 IO 的内存对齐要求
+    pub stx_dio_mem_align: u32,
+    /// 直接 IO 的偏移对齐要求
+    pub stx_dio_offset_align: u32,
+    /// 保留
+    pub __spare3: [u64; 12],
+}
+
+impl From<Kstat> for Statx {
+    fn from(kstat: Kstat) -> Self {
+        let timestamp = |sec: isize, nsec: isize| StatxTimestamp {
+            tv_sec: sec as i64,
+            tv_nsec: nsec as u32,
+            __reserved: 0,
+        };
+        Self {
+            stx_mask: STATX_BASIC_STATS,
+            stx_blksize: kstat.st_blksize,
+            stx_nlink: kstat.st_nlink as u32,
+            stx_uid: kstat.st_uid,
+            stx_gid: kstat.st_gid,
+            stx_mode: kstat.st_mode as u16,
+            stx_ino: kstat.st_ino,
+            stx_size: kstat.st_size,
+            stx_blocks: kstat.st_blocks,
+            stx_atime: timestamp(kstat.st_atime_sec, kstat.st_atime_nsec),
+            stx_ctime: timestamp(kstat.st_ctime_sec, kstat.st_ctime_nsec),
+            stx_mtime: timestamp(kstat.st_mtime_sec, kstat.st_mtime_nsec),
+            stx_rdev_major: (kstat.st_rdev >> 8) as u32,
+            stx_rdev_minor: (kstat.st_rdev & 0xff) as u32,
+            stx_dev_major: (kstat.st_dev >> 8) as u32,
+            stx_dev_minor: (kstat.st_dev & 0xff) as u32,
+            ..Default::default()
+        }
+    }
+}
+
 /// 文件系统的属性
 /// 具体参数定义信息来自 `https://man7.org/linux/man-pages/man2/statfs64.2.html`
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct FsStat {
     /// 是个 magic number，每个知名的 fs 都各有定义，但显然我们没有
     pub f_type: i64,
@@ -526,6 +837,10 @@ bitflags! {
         const S_IFDIR = 1 << 14;
         /// character device
         const S_IFCHR = 1 << 13;
+        /// FIFO
+        const S_IFIFO = 1 << 12;
+        /// socket
+        const S_IFSOCK = (1 << 15) | (1 << 14);
         /// 是否设置 uid/gid/sticky
         //const S_ISUID = 1 << 14;
         //const S_ISGID = 1 << 13;
@@ -579,7 +894,7 @@ numeric_enum_macro::numeric_enum! {
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 /// sys_clone3 中使用的结构体
 pub struct CloneArgs {
     /// 符号位，对应 axtask 的 CloneFlags
@@ -605,3 +920,264 @@ pub struct CloneArgs {
     /// 该信息 Starry 暂未支持
     pub cgroup: u64,
 }
+
+bitflags! {
+    /// ppoll 使用的事件类型
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PollEvents: u16 {
+        /// 有数据可读
+        const POLLIN = 0x0001;
+        /// 有紧急数据可读
+        const POLLPRI = 0x0002;
+        /// 可以无阻塞地写入
+        const POLLOUT = 0x0004;
+        /// 出现错误，只会出现在返回的 revents 中
+        const POLLERR = 0x0008;
+        /// 对端已经关闭，只会出现在返回的 revents 中
+        const POLLHUP = 0x0010;
+        /// 文件描述符无效，只会出现在返回的 revents 中
+        const POLLNVAL = 0x0020;
+        /// 同 POLLIN
+        const POLLRDNORM = 0x0040;
+        /// 同 POLLOUT
+        const POLLWRNORM = 0x0100;
+        /// 对端关闭了写端
+        const POLLRDHUP = 0x2000;
+    }
+}
+
+/// ppoll 使用的结构体
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    /// 文件描述符，为负数时忽略该项
+    pub fd: i32,
+    /// 关心的事件
+    pub events: u16,
+    /// 返回时写入已发生的事件
+    pub revents: u16,
+}
+
+/// pselect6 的 fd_set 能够表示的文件描述符个数
+pub const FD_SETSIZE: usize = 1024;
+
+/// pselect6 的最后一个参数指向的结构体
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SigSetWithSize {
+    /// 等待期间使用的信号掩码，为空时不修改
+    pub sigset: *const usize,
+    /// 信号掩码的字节数
+    pub size: usize,
+}
+
+bitflags! {
+    /// epoll 使用的事件类型，与 poll 的事件类型数值相同
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct EpollEventFlags: u32 {
+        /// 有数据可读
+        const EPOLLIN = 0x0001;
+        /// 有紧急数据可读
+        const EPOLLPRI = 0x0002;
+        /// 可以无阻塞地写入
+        const EPOLLOUT = 0x0004;
+        /// 出现错误，总是会被报告
+        const EPOLLERR = 0x0008;
+        /// 对端已经关闭，总是会被报告
+        const EPOLLHUP = 0x0010;
+        /// 同 EPOLLIN
+        const EPOLLRDNORM = 0x0040;
+        /// 同 EPOLLOUT
+        const EPOLLWRNORM = 0x0100;
+        /// 对端关闭了写端
+        const EPOLLRDHUP = 0x2000;
+        /// 唤醒多个等待者时只唤醒其中一个，目前只被记录
+        const EPOLLEXCLUSIVE = 1 << 28;
+        /// 阻止系统休眠，目前只被记录
+        const EPOLLWAKEUP = 1 << 29;
+        /// 报告一次事件后停止监听，直到被 EPOLL_CTL_MOD 重新启用
+        const EPOLLONESHOT = 1 << 30;
+        /// 边沿触发，只在就绪状态发生变化后报告一次
+        const EPOLLET = 1 << 31;
+    }
+}
+
+/// epoll_ctl 与 epoll_pwait 使用的结构体，在 x86_64 上是紧凑排列的
+#[cfg_attr(target_arch = "x86_64", repr(C, packed))]
+#[cfg_attr(not(target_arch = "x86_64"), repr(C))]
+#[derive(Debug, Clone, Copy)]
+pub struct EpollEvent {
+    /// 事件类型，见 EpollEventFlags
+    pub events: u32,
+    /// 用户数据，发生事件时原样返回
+    pub data: u64,
+}
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// epoll_ctl 的操作
+    pub enum EpollCtl {
+        /// 添加一个文件描述符
+        ADD = 1,
+        /// 删除一个文件描述符
+        DEL = 2,
+        /// 修改一个文件描述符关心的事件
+        MOD = 3,
+    }
+}
+
+/// epoll_create1 的标志，表示 exec 时关闭该文件描述符
+pub const EPOLL_CLOEXEC: usize = 0x80000;
+
+bitflags! {
+    /// eventfd2 的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct EventFdFlags: u32 {
+        /// 读取时计数器只减一，并返回 1
+        const EFD_SEMAPHORE = 1;
+        /// exec 时关闭该文件描述符
+        const EFD_CLOEXEC = 0x80000;
+        /// 以非阻塞方式读写
+        const EFD_NONBLOCK = 0x800;
+    }
+}
+
+bitflags! {
+    /// timerfd_create 的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct TimerFdFlags: u32 {
+        /// exec 时关闭该文件描述符
+        const TFD_CLOEXEC = 0x80000;
+        /// 以非阻塞方式读取
+        const TFD_NONBLOCK = 0x800;
+    }
+}
+
+/// timerfd_settime 的选项：给出的到期时间为绝对时间
+pub const TFD_TIMER_ABSTIME: usize = 1;
+/// timerfd_settime 的选项：实时时钟被修改时取消定时器，系统中没有可修改的实时时钟，因此不会发生
+pub const TFD_TIMER_CANCEL_ON_SET: usize = 2;
+
+/// timerfd_settime 与 timerfd_gettime 使用的结构体
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ITimerSpec {
+    /// 到期后重新设置的周期，为 0 时只到期一次
+    pub it_interval: TimeSecs,
+    /// 距离下一次到期的时间，为 0 时表示定时器未启动
+    pub it_value: TimeSecs,
+}
+
+bitflags! {
+    /// signalfd4 的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct SignalFdFlags: u32 {
+        /// exec 时关闭该文件描述符
+        const SFD_CLOEXEC = 0x80000;
+        /// 以非阻塞方式读取
+        const SFD_NONBLOCK = 0x800;
+    }
+}
+
+/// 从 signalfd 读出的结构体，大小固定为 128 字节
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SignalFdSigInfo {
+    /// 信号编号
+    pub ssi_signo: u32,
+    /// 错误码
+    pub ssi_errno: i32,
+    /// 信号的来源
+    pub ssi_code: i32,
+    /// 发送者的进程号
+    pub ssi_pid: u32,
+    /// 发送者的 uid
+    pub ssi_uid: u32,
+    /// 其余字段暂不支持，填 0
+    pub pad: [u8; 108],
+}
+
+bitflags! {
+    /// memfd_create 的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct MemfdFlags: u32 {
+        /// exec 时关闭该文件描述符
+        const MFD_CLOEXEC = 1;
+        /// 允许通过 F_ADD_SEALS 为文件添加封印
+        const MFD_ALLOW_SEALING = 2;
+        /// 使用大页，暂不支持
+        const MFD_HUGETLB = 4;
+    }
+}
+
+/// memfd 的文件名（不含 "memfd:" 前缀）的最大长度
+pub const MFD_NAME_MAX_LEN: usize = 249;
+
+bitflags! {
+    /// sys_mount 的选项，只列出需要特殊处理的部分
+    #[derive(Debug, Clone, Copy)]
+    pub struct MountFlags: u32 {
+        /// 以只读方式挂载，暂不支持，被忽略
+        const MS_RDONLY = 1;
+        /// 修改已有挂载的选项，暂不支持
+        const MS_REMOUNT = 32;
+        /// 绑定挂载，暂不支持
+        const MS_BIND = 4096;
+        /// 移动已有的挂载，暂不支持
+        const MS_MOVE = 8192;
+    }
+}
+
+/// 旧版本的 mount 要求 flags 的高 16 位为该值，忽略
+pub const MS_MGC_MSK: u32 = 0xffff0000;
+
+bitflags! {
+    /// sys_umount2 的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct UmountFlags: u32 {
+        /// 强制卸载，与普通卸载相同
+        const MNT_FORCE = 1;
+        /// 即使仍有打开的文件也立即卸载，已打开的文件仍然可以访问
+        const MNT_DETACH = 2;
+        /// 标记为过期，暂不支持
+        const MNT_EXPIRE = 4;
+        /// 不跟随路径最后的符号链接
+        const UMOUNT_NOFOLLOW = 8;
+    }
+}
+
+bitflags! {
+    /// splice 与 tee 的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct SpliceFlags: u32 {
+        /// 尝试移动而不是复制页面，只是一个提示，被忽略
+        const SPLICE_F_MOVE = 1;
+        /// 管道一端没有数据或空位时不阻塞
+        const SPLICE_F_NONBLOCK = 2;
+        /// 后续还有更多数据，只是一个提示，被忽略
+        const SPLICE_F_MORE = 4;
+        /// 只用于 vmsplice，被忽略
+        const SPLICE_F_GIFT = 8;
+    }
+}
+
+/// 单次读写最多传输的字节数，与 Linux 的 MAX_RW_COUNT 相同
+pub const MAX_RW_COUNT: usize = 0x7fff_f000;
+
+/// 读写时在用户缓冲区与内核之间分段拷贝数据，每段的最大字节数
+pub const RW_CHUNK_SIZE: usize = 0x1000;
+
+bitflags! {
+    /// getrandom 的选项
+    #[derive(Debug, Clone, Copy)]
+    pub struct GetrandomFlags: u32 {
+        /// 随机数生成器还没有收集到足够的熵时返回 EAGAIN 而不是等待
+        const GRND_NONBLOCK = 1;
+        /// 与 /dev/random 相同，随机数生成器收集到足够的熵之前等待
+        const GRND_RANDOM = 2;
+        /// 不等待随机数生成器收集到足够的熵，不能与 GRND_RANDOM 同时使用
+        const GRND_INSECURE = 4;
+    }
+}
@@ -4,9 +4,16 @@
 #![allow(unused)]
 mod ctypes;
 use ctypes::*;
+mod strace;
 mod syscall;
 mod syscall_fs;
+mod syscall_mem;
+#[cfg(feature = "net")]
+mod syscall_net;
 mod syscall_task;
+mod syscall_table;
+pub use syscall_table::implemented_syscalls;
+pub use strace::init_strace;
 
 pub use axfs::api::{File, OpenFlags}; 
 pub use axtask::link::{create_link, FilePath};
@@ -21,6 +28,8 @@ pub use api::*;
 pub mod trap;
 
 extern crate alloc;
+use alloc::boxed::Box;
+use core::{future::Future, pin::Pin};
 /// 需要手动引入这个库，否则会报错：`#[panic_handler]` function required, but not found.
 extern crate axruntime;
 
@@ -34,6 +43,45 @@ pub type SyscallError = axerrno::LinuxError;
 /// * `Err(error)` - The syscall failed, and the error is related to `linux_error`
 pub type SyscallResult = Result<isize, SyscallError>;
 
+/// The return value of a syscall handler
+///
+/// * `Done(result)` - The syscall has completed synchronously
+///
+/// * `Pending(future)` - The syscall has to wait for some event, and the result is the output of the future
+///
+/// While a syscall is pending, the task is suspended and releases its kernel stack.
+pub enum SyscallReturn {
+    Done(SyscallResult),
+    Pending(Pin<Box<dyn Future<Output = SyscallResult> + Send>>),
+}
+
+impl SyscallReturn {
+    /// Wrap a future that completes the syscall
+    pub fn pending(future: impl Future<Output = SyscallResult> + Send + 'static) -> Self {
+        Self::Pending(Box::pin(future))
+    }
+
+    /// Wait for the syscall to complete
+    pub async fn wait(self) -> SyscallResult {
+        match self {
+            Self::Done(result) => result,
+            Self::Pending(future) => future.await,
+        }
+    }
+}
+
+impl From<SyscallResult> for SyscallReturn {
+    fn from(result: SyscallResult) -> Self {
+        Self::Done(result)
+    }
+}
+
+impl From<SyscallError> for SyscallReturn {
+    fn from(error: SyscallError) -> Self {
+        Self::Done(Err(error))
+    }
+}
+
 /// Accept the result of a syscall, and return the isize to the user
 pub(crate) fn deal_result(result: SyscallResult) -> isize {
     match result {
@@ -0,0 +1,382 @@
+//! strace 风格的系统调用跟踪
+//!
+//! 向 /proc/strace 写入进程号即可在运行时开启对该进程及其子进程的跟踪，写入 `-<pid>` 关闭，
+//! 写入 `all` 跟踪所有进程，写入 `off` 关闭全部跟踪。被跟踪的任务每完成一次系统调用就输出一行，
+//! 包括系统调用名、按类型解码的参数与返回值，失败时给出 errno 的名称，例如：
+//!
+//! ```text
+//! [12] openat(AT_FDCWD, "/lib/libc.so", O_RDONLY|O_CLOEXEC, 0) = -1 ENOENT (No such file or directory)
+//! ```
+extern crate alloc;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axerrno::AxError;
+use axfs::axfs_ramfs::{register_strace_handler, StraceHandler};
+use axlog::ax_println;
+use axsignal::signal_no::{SignalNo, MAX_SIG_NUM};
+use axtask::{current_task, get_task_ref, Mutex, UserPtr};
+
+use crate::syscall_table::SyscallEntry;
+use crate::SyscallResult;
+
+/// 输出的字符串参数的最大长度，超出部分以 ... 省略
+const MAX_STR_LEN: usize = 64;
+
+/// 向上查找被跟踪的祖先进程时的最大深度
+const MAX_ANCESTOR_DEPTH: usize = 64;
+
+/// openat 中表示当前工作目录的 dirfd
+const AT_FDCWD: i32 = -100;
+
+/// 参数的解码方式
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Arg {
+    /// 有符号十进制数
+    Dec,
+    /// 十六进制数，通常是指针
+    Hex,
+    /// 八进制数，通常是权限
+    Oct,
+    /// 文件描述符
+    Fd,
+    /// *at 系列系统调用的 dirfd
+    DirFd,
+    /// 以 '\0' 结尾的用户态字符串
+    Str,
+    /// open 的标志
+    OpenFlags,
+    /// *at 系列系统调用的 AT_* 标志
+    AtFlags,
+    /// mmap 的 prot
+    Prot,
+    /// mmap 的 flags
+    MapFlags,
+    /// clone 的 flags，低 8 位为退出时发送的信号
+    CloneFlags,
+    /// 信号编号
+    Signal,
+}
+
+use Arg::*;
+
+/// open 的标志位名称，RDONLY、WRONLY、RDWR 由低 2 位单独给出
+const OPEN_FLAGS: &[(usize, &str)] = &[
+    (0o100, "O_CREAT"),
+    (0o200, "O_EXCL"),
+    (0o400, "O_NOCTTY"),
+    (0o1000, "O_TRUNC"),
+    (0o2000, "O_APPEND"),
+    (0o4000, "O_NONBLOCK"),
+    (0o10000, "O_DSYNC"),
+    (0o200000, "O_DIRECTORY"),
+    (0o400000, "O_NOFOLLOW"),
+    (0o2000000, "O_CLOEXEC"),
+    (0o10000000, "O_PATH"),
+];
+
+/// AT_* 标志位的名称
+const AT_FLAGS: &[(usize, &str)] = &[
+    (0x100, "AT_SYMLINK_NOFOLLOW"),
+    (0x200, "AT_REMOVEDIR"),
+    (0x400, "AT_SYMLINK_FOLLOW"),
+    (0x1000, "AT_EMPTY_PATH"),
+];
+
+/// mmap 的 prot 的名称
+const PROT_FLAGS: &[(usize, &str)] = &[(1, "PROT_READ"), (2, "PROT_WRITE"), (4, "PROT_EXEC")];
+
+/// mmap 的 flags 的名称
+const MAP_FLAGS: &[(usize, &str)] = &[
+    (0x1, "MAP_SHARED"),
+    (0x2, "MAP_PRIVATE"),
+    (0x10, "MAP_FIXED"),
+    (0x20, "MAP_ANONYMOUS"),
+    (0x4000, "MAP_NORESERVE"),
+    (0x8000, "MAP_POPULATE"),
+    (0x20000, "MAP_STACK"),
+];
+
+/// clone 的 flags 的名称
+const CLONE_FLAGS: &[(usize, &str)] = &[
+    (0x100, "CLONE_VM"),
+    (0x200, "CLONE_FS"),
+    (0x400, "CLONE_FILES"),
+    (0x800, "CLONE_SIGHAND"),
+    (0x1000, "CLONE_PIDFD"),
+    (0x2000, "CLONE_PTRACE"),
+    (0x4000, "CLONE_VFORK"),
+    (0x8000, "CLONE_PARENT"),
+    (0x10000, "CLONE_THREAD"),
+    (0x20000, "CLONE_NEWNS"),
+    (0x40000, "CLONE_SYSVSEM"),
+    (0x80000, "CLONE_SETTLS"),
+    (0x100000, "CLONE_PARENT_SETTID"),
+    (0x200000, "CLONE_CHILD_CLEARTID"),
+    (0x400000, "CLONE_DETACHED"),
+    (0x800000, "CLONE_UNTRACED"),
+    (0x1000000, "CLONE_CHILD_SETTID"),
+];
+
+/// 未实现的系统调用以十六进制输出全部六个参数
+const UNKNOWN_ARGS: &[Arg] = &[Hex; 6];
+
+/// 被跟踪的进程集合
+#[derive(Default, Debug, PartialEq, Eq)]
+struct StraceFilter {
+    /// 是否跟踪所有进程
+    all: bool,
+    /// 被跟踪的进程号，其子进程同样被跟踪
+    pids: BTreeSet<u64>,
+}
+
+impl StraceFilter {
+    /// 创建一个不跟踪任何进程的集合
+    const fn new() -> Self {
+        Self {
+            all: false,
+            pids: BTreeSet::new(),
+        }
+    }
+
+    /// 是否没有跟踪任何进程
+    fn is_empty(&self) -> bool {
+        !self.all && self.pids.is_empty()
+    }
+
+    /// 进程本身是否被跟踪，不考虑其祖先进程
+    fn contains(&self, pid: u64) -> bool {
+        self.all || self.pids.contains(&pid)
+    }
+
+    /// 执行写入控制文件的以空白分隔的命令：
+    ///
+    /// - `<pid>` 或 `+<pid>`: 跟踪该进程
+    /// - `-<pid>`: 停止跟踪该进程
+    /// - `all`: 跟踪所有进程
+    /// - `off`: 停止跟踪所有进程
+    ///
+    /// 遇到非法命令时返回 InvalidInput，之前的命令仍然生效
+    fn apply(&mut self, commands: &str) -> Result<(), AxError> {
+        for command in commands.split_whitespace() {
+            match command {
+                "all" => self.all = true,
+                "off" => {
+                    self.all = false;
+                    self.pids.clear();
+                }
+                _ => {
+                    let (enable, pid) = match command.strip_prefix('-') {
+                        Some(pid) => (false, pid),
+                        None => (true, command.strip_prefix('+').unwrap_or(command)),
+                    };
+                    let pid = pid.parse::<u64>().map_err(|_| AxError::InvalidInput)?;
+                    if enable {
+                        self.pids.insert(pid);
+                    } else {
+                        self.pids.remove(&pid);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 控制文件的内容：`all`，或每行一个被跟踪的进程号
+    fn content(&self) -> String {
+        if self.all {
+            return String::from("all\n");
+        }
+        self.pids.iter().map(|pid| format!("{}\n", pid)).collect()
+    }
+}
+
+/// 被跟踪的进程，由 /proc/strace 控制
+static STRACE: Mutex<StraceFilter> = Mutex::new(StraceFilter::new());
+
+/// 是否有进程被跟踪，每次系统调用时先检查它，避免获取锁
+static STRACE_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// /proc/strace 背后的跟踪器
+struct Strace;
+
+impl StraceHandler for Strace {
+    fn content(&self) -> String {
+        STRACE.lock().content()
+    }
+
+    fn apply(&self, commands: &str) -> Result<(), AxError> {
+        let mut filter = STRACE.lock();
+        let result = filter.apply(commands);
+        STRACE_ACTIVE.store(!filter.is_empty(), Ordering::Release);
+        result
+    }
+}
+
+/// 将跟踪器注册到 /proc/strace，在此之前写入控制文件会失败
+pub fn init_strace() {
+    register_strace_handler(&Strace);
+}
+
+/// 当前任务是否被跟踪：所在进程或其某个祖先进程在跟踪列表中
+pub fn is_traced() -> bool {
+    if !STRACE_ACTIVE.load(Ordering::Acquire) {
+        return false;
+    }
+    let filter = STRACE.lock();
+    let mut pid = current_task().get_process_id();
+    for _ in 0..MAX_ANCESTOR_DEPTH {
+        if filter.contains(pid) {
+            return true;
+        }
+        let Some(task) = get_task_ref(pid) else {
+            return false;
+        };
+        let parent = task.get_parent();
+        if parent == pid {
+            return false;
+        }
+        pid = parent;
+    }
+    false
+}
+
+/// 读取用户态的字符串并转义，不可访问时返回 None
+fn read_user_str(addr: usize) -> Option<String> {
+    let ptr = UserPtr::<u8>::new(addr);
+    let mut text = String::from("\"");
+    for index in 0..=MAX_STR_LEN {
+        let byte = ptr.add(index).read().ok()?;
+        if byte == 0 {
+            text.push('"');
+            return Some(text);
+        }
+        if index == MAX_STR_LEN {
+            break;
+        }
+        match byte {
+            b'"' => text.push_str("\\\""),
+            b'\\' => text.push_str("\\\\"),
+            b'\n' => text.push_str("\\n"),
+            b'\t' => text.push_str("\\t"),
+            0x20..=0x7e => text.push(byte as char),
+            _ => text.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    text.push_str("\"...");
+    Some(text)
+}
+
+/// 将标志位按名称输出，无法识别的位以十六进制附在最后
+fn format_flags(mut bits: usize, names: &[(usize, &str)], empty: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for &(bit, name) in names {
+        if bits & bit == bit {
+            parts.push(name.to_string());
+            bits &= !bit;
+        }
+    }
+    if bits != 0 {
+        parts.push(format!("{:#x}", bits));
+    }
+    if parts.is_empty() {
+        empty.to_string()
+    } else {
+        parts.join("|")
+    }
+}
+
+/// 按给定的方式解码一个参数
+fn format_arg(kind: Arg, value: usize) -> String {
+    match kind {
+        Dec => format!("{}", value as isize),
+        Hex if value == 0 => "NULL".to_string(),
+        Hex => format!("{:#x}", value),
+        Oct => format!("{:#o}", value),
+        Fd => format!("{}", value as i32),
+        DirFd if value as i32 == AT_FDCWD => "AT_FDCWD".to_string(),
+        DirFd => format!("{}", value as i32),
+        Str if value == 0 => "NULL".to_string(),
+        Str => read_user_str(value).unwrap_or_else(|| format!("{:#x}", value)),
+        OpenFlags => {
+            let mode = match value & 0o3 {
+                0 => "O_RDONLY",
+                1 => "O_WRONLY",
+                2 => "O_RDWR",
+                _ => "O_ACCMODE",
+            };
+            let rest = format_flags(value & !0o3, OPEN_FLAGS, "");
+            if rest.is_empty() {
+                mode.to_string()
+            } else {
+                format!("{}|{}", mode, rest)
+            }
+        }
+        AtFlags => format_flags(value, AT_FLAGS, "0"),
+        Prot => format_flags(value, PROT_FLAGS, "PROT_NONE"),
+        MapFlags => format_flags(value, MAP_FLAGS, "0"),
+        CloneFlags => {
+            let flags = format_flags(value & !0xff, CLONE_FLAGS, "0");
+            match value & 0xff {
+                0 => flags,
+                signal => format!("{}|{}", flags, format_arg(Signal, signal)),
+            }
+        }
+        Signal if (1..=MAX_SIG_NUM).contains(&value) => format!("{:?}", SignalNo::from(value)),
+        Signal => format!("{}", value as isize),
+    }
+}
+
+/// 一次被跟踪的系统调用，在进入时解码参数，在返回时输出
+pub struct SyscallTrace {
+    tid: u64,
+    name: String,
+    args: String,
+}
+
+impl SyscallTrace {
+    /// 进入系统调用时解码参数。参数指向的字符串可能在系统调用中被修改或释放，如 execve，因此需要提前解码
+    ///
+    /// entry 为系统调用表中对应的一项，未实现的系统调用为 None
+    pub fn enter(syscall_id: usize, entry: Option<&SyscallEntry>, args: [usize; 6]) -> Self {
+        let (name, kinds) = match entry {
+            Some(entry) => (entry.name.to_string(), entry.args),
+            None => (format!("syscall_{}", syscall_id), UNKNOWN_ARGS),
+        };
+        let args = kinds
+            .iter()
+            .zip(args)
+            .map(|(kind, value)| format_arg(*kind, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let trace = Self {
+            tid: current_task().tid(),
+            name,
+            args,
+        };
+        // 这两个系统调用不会返回
+        if trace.name == "exit" || trace.name == "exit_group" {
+            trace.print("?");
+        }
+        trace
+    }
+
+    /// 系统调用返回时输出一行跟踪信息
+    pub fn exit(self, result: &SyscallResult) {
+        let ret = match result {
+            Ok(value) if matches!(self.name.as_str(), "mmap" | "brk" | "mremap" | "shmat") => {
+                format!("{:#x}", value)
+            }
+            Ok(value) => format!("{}", value),
+            Err(err) => format!("-1 {:?} ({})", err, err.as_str()),
+        };
+        self.print(&ret);
+    }
+
+    fn print(&self, ret: &str) {
+        ax_println!("[{}] {}({}) = {}", self.tid, self.name, self.args, ret);
+    }
+}
@@ -1,35 +1,36 @@
-use crate::{deal_result, SyscallResult};
-use axlog::info;
+use crate::strace::{is_traced, SyscallTrace};
+use crate::syscall_table::{lookup, SyscallEntry};
+use crate::{deal_result, SyscallError, SyscallResult};
+use axlog::{debug, warn};
 
-#[no_mangle]
-pub fn syscall(syscall_id: usize, args: [usize; 6]) -> isize {
-    #[allow(unused_mut, unused_assignments)]
-    let mut ans: Option<SyscallResult> = None;
-
-    if let Ok(fs_syscall_id) = crate::syscall_fs::FsSyscallId::try_from(syscall_id) {
-        info!(
-            "[syscall] id = {:#?}, args = {:?}, entry",
-            fs_syscall_id, args
-        );
-        (#[allow(unused_assignments)]
-        ans) = Some(crate::syscall_fs::fs_syscall(fs_syscall_id, args));
-    }
-
-    if let Ok(task_syscall_id) = crate::syscall_task::TaskSyscallId::try_from(syscall_id) {
-        info!(
-            "[syscall] id = {:#?}, args = {:?}, entry",
-            task_syscall_id, args
+/// 按系统调用表分发到对应的处理函数，未实现的系统调用返回 ENOSYS
+///
+/// 处理函数返回的 future 在这里等待，等待期间任务被挂起
+async fn dispatch(
+    syscall_id: usize,
+    entry: Option<&'static SyscallEntry>,
+    args: [usize; 6],
+) -> SyscallResult {
+    let Some(entry) = entry else {
+        warn!(
+            "unimplemented syscall id: {}, args = {:?}",
+            syscall_id, args
         );
-        (#[allow(unused_assignments)]
-        ans) = Some(crate::syscall_task::task_syscall(task_syscall_id, args));
-    }
+        return Err(SyscallError::ENOSYS);
+    };
+    debug!("[syscall] id = {}, args = {:?}, entry", entry.name, args);
+    (entry.handler)(args).wait().await
+}
 
-    if ans.is_none() {
-        panic!("unknown syscall id: {}", syscall_id);
-    }
-    let ans = deal_result(ans.unwrap());
-    if syscall_id != 96 && syscall_id != 98 {
-        info!("[syscall] id = {},return {}", syscall_id, ans);
+pub async fn syscall(syscall_id: usize, args: [usize; 6]) -> isize {
+    let entry = lookup(syscall_id);
+    // 被跟踪的任务在进入时解码参数，返回时输出跟踪信息
+    let trace = is_traced().then(|| SyscallTrace::enter(syscall_id, entry, args));
+    let result = dispatch(syscall_id, entry, args).await;
+    if let Some(trace) = trace {
+        trace.exit(&result);
     }
+    let ans = deal_result(result);
+    debug!("[syscall] id = {}, return {}", syscall_id, ans);
     ans
 }
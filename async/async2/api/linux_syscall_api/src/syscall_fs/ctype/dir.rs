@@ -1,21 +1,74 @@
+use super::file::get_inode_number;
 use crate::{normal_file_mode, StMode};
 extern crate alloc;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use axerrno::{AxError, AxResult};
 use axfs::api::{self, FileIO, FileIOType, Kstat, OpenFlags, SeekFrom};
 use axlog::debug;
+use axtask::Mutex;
 
 /// 目录描述符
 pub struct DirDesc {
     /// 目录
     pub dir_path: String,
+    /// 上一次 getdents 返回的最后一个目录项的 cookie，即目录的偏移，下一次从 cookie 更大的目录项开始读取
+    pub cursor: Mutex<u64>,
+}
+
+/// "." 的 cookie，".." 的为其加一，目录下的文件从 FIRST_FILE_COOKIE 开始编号
+pub const DOT_COOKIE: u64 = 1;
+
+/// 目录下第一个文件的 cookie
+const FIRST_FILE_COOKIE: u64 = DOT_COOKIE + 2;
+
+/// 一个目录中各个目录项的 cookie
+struct DirCookies {
+    /// 下一个新出现的目录项的 cookie
+    next: u64,
+    /// 目录项名称到 cookie 的映射
+    names: BTreeMap<String, u64>,
+}
+
+/// 各个目录的目录项 cookie，按目录路径索引
+///
+/// 新出现的目录项总是得到更大的 cookie，已有目录项的 cookie 在其被删除前保持不变，
+/// 因此目录内容在两次 getdents64 之间发生变化时，也能从上次返回的 d_off 处继续读取
+static DIR_COOKIES: Mutex<BTreeMap<String, DirCookies>> = Mutex::new(BTreeMap::new());
+
+/// 获取目录中各个名称的 cookie，返回值与 `names` 一一对应
+///
+/// `names` 应为目录当前的全部内容，不在其中的名称会被移除，但它们的 cookie 不会被再次分配
+pub fn dir_cookies(dir_path: &str, names: &[&str]) -> Vec<u64> {
+    let mut dirs = DIR_COOKIES.lock();
+    let dir = dirs
+        .entry(dir_path.to_string())
+        .or_insert_with(|| DirCookies {
+            next: FIRST_FILE_COOKIE,
+            names: BTreeMap::new(),
+        });
+    let present: BTreeSet<&str> = names.iter().copied().collect();
+    dir.names.retain(|name, _| present.contains(name.as_str()));
+    names
+        .iter()
+        .map(|name| {
+            *dir.names.entry(name.to_string()).or_insert_with(|| {
+                dir.next += 1;
+                dir.next - 1
+            })
+        })
+        .collect()
 }
 
 /// 目录描述符的实现
 impl DirDesc {
     /// 创建一个新的目录描述符
     pub fn new(path: String) -> Self {
-        Self { dir_path: path }
+        Self {
+            dir_path: path,
+            cursor: Mutex::new(0),
+        }
     }
 }
 
@@ -30,8 +83,19 @@ impl FileIO for DirDesc {
     fn flush(&self) -> AxResult {
         Err(AxError::IsADirectory)
     }
-    fn seek(&self, _: SeekFrom) -> AxResult<u64> {
-        Err(AxError::IsADirectory)
+    /// 修改目录的偏移，供 lseek/rewinddir 使用
+    fn seek(&self, pos: SeekFrom) -> AxResult<u64> {
+        let mut cursor = self.cursor.lock();
+        let new_cursor = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::Current(delta) => *cursor as i64 + delta,
+            SeekFrom::End(_) => return Err(AxError::InvalidInput),
+        };
+        if new_cursor < 0 {
+            return Err(AxError::InvalidInput);
+        }
+        *cursor = new_cursor as u64;
+        Ok(new_cursor as u64)
     }
     fn get_type(&self) -> FileIOType {
         FileIOType::DirDesc
@@ -52,15 +116,15 @@ impl FileIO for DirDesc {
     fn get_stat(&self) -> AxResult<Kstat> {
         let kstat = Kstat {
             st_dev: 1,
-            st_ino: 0,
-            st_mode: normal_file_mode(StMode::S_IFDIR).bits(),
+            st_ino: get_inode_number(self.dir_path.as_str()),
+            st_mode: normal_file_mode(StMode::S_IFDIR).bits() | 0o755,
             st_nlink: 1,
             st_uid: 0,
             st_gid: 0,
             st_rdev: 0,
             _pad0: 0,
-            st_size: 0,
-            st_blksize: 0,
+            st_size: 4096,
+            st_blksize: 4096,
             _pad1: 0,
             st_blocks: 0,
             st_atime_sec: 0,
@@ -0,0 +1,224 @@
+//! epoll 实例
+//!
+//! epoll 实例作为 `FileIO` 保存在文件描述符表中。被监听的文件以弱引用保存，
+//! 文件的所有描述符都被关闭后自动停止监听，不会因为被 epoll 引用而无法关闭。
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, Kstat, OpenFlags};
+use axtask::Mutex;
+
+use crate::{EpollEvent, EpollEventFlags, PollEvents, StMode};
+
+/// 获取文件当前的就绪状态
+pub fn file_events(file: &dyn FileIO) -> PollEvents {
+    let mut events = PollEvents::empty();
+    if file.ready_to_read() {
+        events |= PollEvents::POLLIN | PollEvents::POLLRDNORM;
+    }
+    if file.ready_to_write() {
+        events |= PollEvents::POLLOUT | PollEvents::POLLWRNORM;
+    }
+    if file.in_exceptional_conditions() {
+        events |= PollEvents::POLLPRI;
+    }
+    if file.is_hang_up() {
+        events |= PollEvents::POLLHUP;
+    }
+    events
+}
+
+/// 一个被监听的文件
+struct EpollInterest {
+    file: Weak<dyn FileIO>,
+    /// 关心的事件与用户数据
+    event: EpollEvent,
+    /// 上一次检查时的就绪状态，用于边沿触发
+    last_ready: EpollEventFlags,
+    /// 上一次检查时的事件计数，计数变化说明文件可能又收到了新的数据
+    last_count: usize,
+    /// 设置了 EPOLLONESHOT 的文件在报告过一次事件后被禁用
+    disabled: bool,
+}
+
+impl EpollInterest {
+    fn new(file: &Arc<dyn FileIO>, event: EpollEvent) -> Self {
+        Self {
+            file: Arc::downgrade(file),
+            event,
+            last_ready: EpollEventFlags::empty(),
+            last_count: 0,
+            disabled: false,
+        }
+    }
+
+    /// 文件当前已就绪且被关心的事件，EPOLLERR 与 EPOLLHUP 总是会被关心
+    fn ready(&self) -> EpollEventFlags {
+        let Some(file) = self.file.upgrade() else {
+            return EpollEventFlags::empty();
+        };
+        let wanted = EpollEventFlags::from_bits_truncate(self.event.events)
+            | EpollEventFlags::EPOLLERR
+            | EpollEventFlags::EPOLLHUP;
+        EpollEventFlags::from_bits_truncate(file_events(file.as_ref()).bits() as u32) & wanted
+    }
+}
+
+/// epoll 实例
+pub struct EpollFile {
+    /// 以文件描述符为键的被监听文件
+    interests: Mutex<BTreeMap<usize, EpollInterest>>,
+    flags: Mutex<OpenFlags>,
+}
+
+impl EpollFile {
+    /// 创建一个空的 epoll 实例
+    pub fn new(flags: OpenFlags) -> Self {
+        Self {
+            interests: Mutex::new(BTreeMap::new()),
+            flags: Mutex::new(flags),
+        }
+    }
+
+    /// 开始监听文件，该描述符已被监听时返回 `AlreadyExists`
+    pub fn add(&self, fd: usize, file: &Arc<dyn FileIO>, event: EpollEvent) -> AxResult {
+        let mut interests = self.interests.lock();
+        // 原先的文件已被关闭时，该描述符可以重新被监听
+        if interests
+            .get(&fd)
+            .is_some_and(|interest| interest.file.strong_count() > 0)
+        {
+            return Err(AxError::AlreadyExists);
+        }
+        interests.insert(fd, EpollInterest::new(file, event));
+        Ok(())
+    }
+
+    /// 修改关心的事件，同时重新启用被 EPOLLONESHOT 禁用的文件
+    pub fn modify(&self, fd: usize, event: EpollEvent) -> AxResult {
+        let mut interests = self.interests.lock();
+        let interest = interests.get_mut(&fd).ok_or(AxError::NotFound)?;
+        interest.event = event;
+        interest.last_ready = EpollEventFlags::empty();
+        interest.disabled = false;
+        Ok(())
+    }
+
+    /// 停止监听文件
+    pub fn delete(&self, fd: usize) -> AxResult {
+        self.interests
+            .lock()
+            .remove(&fd)
+            .map(|_| ())
+            .ok_or(AxError::NotFound)
+    }
+
+    /// 是否在监听 socket，socket 的状态变化不一定会通知到等待者
+    pub fn has_sockets(&self) -> bool {
+        self.interests.lock().values().any(|interest| {
+            interest
+                .file
+                .upgrade()
+                .is_some_and(|file| file.get_type() == FileIOType::Socket)
+        })
+    }
+
+    /// 检查所有被监听的文件，将就绪的事件写入 events，返回写入的个数
+    ///
+    /// # Arguments
+    /// * `events`: 用于保存就绪的事件
+    /// * `count`: 检查之前获取的事件计数，用于判断边沿触发的文件是否需要再次报告
+    pub fn poll_events(&self, events: &mut [EpollEvent], count: usize) -> usize {
+        let mut interests = self.interests.lock();
+        interests.retain(|_, interest| interest.file.strong_count() > 0);
+        let mut num = 0;
+        for interest in interests.values_mut() {
+            if num == events.len() {
+                break;
+            }
+            if interest.disabled {
+                continue;
+            }
+            let ready = interest.ready();
+            let flags = EpollEventFlags::from_bits_truncate(interest.event.events);
+            let report = if flags.contains(EpollEventFlags::EPOLLET) && interest.last_count == count
+            {
+                ready - interest.last_ready
+            } else {
+                ready
+            };
+            interest.last_ready = ready;
+            interest.last_count = count;
+            if report.is_empty() {
+                continue;
+            }
+            events[num] = EpollEvent {
+                events: report.bits(),
+                data: interest.event.data,
+            };
+            num += 1;
+            if flags.contains(EpollEventFlags::EPOLLONESHOT) {
+                interest.disabled = true;
+            }
+        }
+        num
+    }
+}
+
+impl FileIO for EpollFile {
+    fn read(&self, _buf: &mut [u8]) -> AxResult<usize> {
+        Err(AxError::InvalidInput)
+    }
+
+    fn write(&self, _buf: &[u8]) -> AxResult<usize> {
+        Err(AxError::InvalidInput)
+    }
+
+    fn readable(&self) -> bool {
+        false
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn executable(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> FileIOType {
+        FileIOType::Other
+    }
+
+    fn get_stat(&self) -> AxResult<Kstat> {
+        Ok(Kstat {
+            st_dev: 1,
+            st_mode: (StMode::S_IRUSR | StMode::S_IWUSR).bits(),
+            st_nlink: 1,
+            ..Default::default()
+        })
+    }
+
+    fn set_status(&self, flags: OpenFlags) -> bool {
+        *self.flags.lock() = flags;
+        true
+    }
+
+    fn get_status(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    /// 有被监听的文件就绪时，epoll 实例本身可读，因此可以被嵌套监听
+    fn ready_to_read(&self) -> bool {
+        self.interests
+            .lock()
+            .values()
+            .any(|interest| !interest.disabled && !interest.ready().is_empty())
+    }
+
+    fn ready_to_write(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,132 @@
+//! eventfd 文件
+//!
+//! 内部是一个 64 位计数器：写入时加上写入的值，读取时取出计数器的值并清零；
+//! 以 EFD_SEMAPHORE 创建时每次读取只减一。计数器为 0 时的读取、即将溢出时的写入返回 `WouldBlock`，
+//! 由系统调用决定是否挂起等待。
+extern crate alloc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, Kstat, OpenFlags};
+use axtask::{notify_pollers, Mutex};
+
+use crate::{normal_file_mode, StMode};
+
+/// 计数器能够达到的最大值
+const EVENTFD_MAX: u64 = u64::MAX - 1;
+
+/// eventfd 文件
+pub struct EventFd {
+    /// 计数器，会在就绪状态的检查中被访问，因此使用原子变量
+    count: AtomicU64,
+    /// 是否以信号量的方式读取
+    semaphore: bool,
+    /// 文件状态标志，如 O_NONBLOCK
+    flags: Mutex<OpenFlags>,
+}
+
+impl EventFd {
+    /// 以给定的初始值创建 eventfd
+    pub fn new(initval: u64, semaphore: bool, flags: OpenFlags) -> Self {
+        Self {
+            count: AtomicU64::new(initval),
+            semaphore,
+            flags: Mutex::new(flags | OpenFlags::RDWR),
+        }
+    }
+
+    /// 尝试从计数器中取出值，计数器为 0 时返回 None
+    fn try_take(&self) -> Option<u64> {
+        let mut taken = 0;
+        self.count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                if count == 0 {
+                    return None;
+                }
+                taken = if self.semaphore { 1 } else { count };
+                Some(count - taken)
+            })
+            .ok()?;
+        Some(taken)
+    }
+
+    /// 尝试将值加到计数器上，会溢出时返回 false
+    fn try_add(&self, value: u64) -> bool {
+        self.count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                count.checked_add(value).filter(|&sum| sum <= EVENTFD_MAX)
+            })
+            .is_ok()
+    }
+}
+
+impl FileIO for EventFd {
+    fn read(&self, buf: &mut [u8]) -> AxResult<usize> {
+        if buf.len() < 8 {
+            return Err(AxError::InvalidInput);
+        }
+        let value = self.try_take().ok_or(AxError::WouldBlock)?;
+        buf[..8].copy_from_slice(&value.to_ne_bytes());
+        notify_pollers();
+        Ok(8)
+    }
+
+    fn write(&self, buf: &[u8]) -> AxResult<usize> {
+        if buf.len() < 8 {
+            return Err(AxError::InvalidInput);
+        }
+        let value = u64::from_ne_bytes(buf[..8].try_into().unwrap());
+        if value == u64::MAX {
+            return Err(AxError::InvalidInput);
+        }
+        if !self.try_add(value) {
+            return Err(AxError::WouldBlock);
+        }
+        if value != 0 {
+            notify_pollers();
+        }
+        Ok(8)
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn executable(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> FileIOType {
+        FileIOType::Other
+    }
+
+    fn get_stat(&self) -> AxResult<Kstat> {
+        Ok(Kstat {
+            st_dev: 1,
+            st_mode: normal_file_mode(StMode::S_IFREG).bits(),
+            st_nlink: 1,
+            ..Default::default()
+        })
+    }
+
+    fn set_status(&self, flags: OpenFlags) -> bool {
+        *self.flags.lock() = flags;
+        true
+    }
+
+    fn get_status(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn ready_to_read(&self) -> bool {
+        self.count.load(Ordering::Acquire) > 0
+    }
+
+    fn ready_to_write(&self) -> bool {
+        self.count.load(Ordering::Acquire) < EVENTFD_MAX
+    }
+}
@@ -52,6 +52,9 @@ impl FileIO for FileDesc {
     fn write(&self, buf: &[u8]) -> AxResult<usize> {
         // 如果seek时超出了文件原有大小，则在write的时候进行补零操作
         let mut file = self.file.lock();
+        if self.flags.lock().contains(OpenFlags::APPEND) {
+            file.seek(SeekFrom::End(0))?;
+        }
         let old_offset = file.seek(SeekFrom::Current(0)).unwrap();
         let size = file.metadata().unwrap().size();
         if old_offset > size {
@@ -62,6 +65,21 @@ impl FileIO for FileDesc {
         file.write(buf)
     }
 
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        self.file.lock().read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        // 与 write 相同，偏移超出文件大小时先补零
+        let file = self.file.lock();
+        let size = file.metadata()?.size();
+        if offset > size {
+            let temp_buf: Vec<u8> = vec![0u8; (offset - size) as usize];
+            file.write_at(size, &temp_buf)?;
+        }
+        file.write_at(offset, buf)
+    }
+
     fn flush(&self) -> AxResult {
         self.file.lock().flush()
     }
@@ -141,40 +159,13 @@ impl FileIO for FileDesc {
         *self.flags.lock()
     }
 
-    fn set_close_on_exec(&self, is_set: bool) -> bool {
-        if is_set {
-            // 设置close_on_exec位置
-            *self.flags.lock() |= OpenFlags::CLOEXEC;
-        } else {
-            *self.flags.lock() &= !OpenFlags::CLOEXEC;
-        }
-        true
-    }
-
+    /// 与 Linux 相同，普通文件总是可以无阻塞地读写，读到末尾时 read 返回 0
     fn ready_to_read(&self) -> bool {
-        if !self.readable() {
-            return false;
-        }
-        // 获取当前的位置
-        let now_pos = self.seek(SeekFrom::Current(0)).unwrap();
-        // 获取最后的位置
-        let len = self.seek(SeekFrom::End(0)).unwrap();
-        // 把文件指针复原，因为获取len的时候指向了尾部
-        self.seek(SeekFrom::Start(now_pos)).unwrap();
-        now_pos != len
+        self.readable()
     }
 
     fn ready_to_write(&self) -> bool {
-        if !self.writable() {
-            return false;
-        }
-        // 获取当前的位置
-        let now_pos = self.seek(SeekFrom::Current(0)).unwrap();
-        // 获取最后的位置
-        let len = self.seek(SeekFrom::End(0)).unwrap();
-        // 把文件指针复原，因为获取len的时候指向了尾部
-        self.seek(SeekFrom::Start(now_pos)).unwrap();
-        now_pos != len
+        self.writable()
     }
 }
 
@@ -217,3 +208,9 @@ pub fn new_inode(path: String) -> AxResult<()> {
     inode_name_map.insert(path, inode_number);
     Ok(())
 }
+
+/// 获取路径对应的inode号，若还未分配则先为其分配
+pub fn get_inode_number(path: &str) -> u64 {
+    let _ = new_inode(path.to_string());
+    *INODE_NAME_MAP.lock().get(path).unwrap()
+}
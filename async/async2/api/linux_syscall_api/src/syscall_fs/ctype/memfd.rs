@@ -0,0 +1,173 @@
+//! memfd 文件
+//!
+//! 内容保存在不属于任何目录的 ramfs 节点中，最后一个引用被释放后即被回收。
+//! 写入、扩大与缩小的封印由节点本身检查，因此对共享映射的写回同样受封印约束；
+//! F_SEAL_FUTURE_WRITE 只禁止新的写入者，由本文件在 write 与 mmap 时检查。
+//! 可写的共享映射存在期间不能添加 F_SEAL_WRITE。
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{
+    File, FileIO, FileIOType, Kstat, OpenFlags, OpenOptions, Read, Seek, SeekFrom, Write,
+};
+use axfs::axfs_ramfs::{MemfdNode, MemfdWritableMapping, SEAL_FUTURE_WRITE, SEAL_WRITE};
+use axtask::Mutex;
+
+use crate::{normal_file_mode, StMode};
+
+/// memfd 文件
+pub struct MemFd {
+    /// 文件路径，形如 "/memfd:name (deleted)"
+    path: String,
+    /// 保存文件内容与封印的节点
+    node: Arc<MemfdNode>,
+    /// 以读写方式打开的节点
+    file: Mutex<File>,
+    /// 文件状态标志
+    flags: Mutex<OpenFlags>,
+}
+
+impl MemFd {
+    /// 创建一个空的 memfd
+    ///
+    /// # Arguments
+    /// * `name`: 文件名，只用于显示
+    /// * `seals`: 初始的封印
+    pub fn new(name: &str, seals: u32) -> AxResult<Self> {
+        let node = Arc::new(MemfdNode::new(seals));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open_node(node.clone())?;
+        Ok(Self {
+            path: format!("/memfd:{} (deleted)", name),
+            node,
+            file: Mutex::new(file),
+            flags: Mutex::new(OpenFlags::RDWR),
+        })
+    }
+
+    /// 获取文件的封印
+    pub fn seals(&self) -> u32 {
+        self.node.seals()
+    }
+
+    /// 为文件添加封印，文件已有 F_SEAL_SEAL 时返回 PermissionDenied，
+    /// 存在可写的共享映射时添加 F_SEAL_WRITE 返回 ResourceBusy
+    pub fn add_seals(&self, seals: u32) -> AxResult {
+        self.node.add_seals(seals)
+    }
+
+    /// 获取用于建立内存映射的文件
+    ///
+    /// # Arguments
+    /// * `shared`: 是否为共享映射。私有映射的修改不会写回文件
+    /// * `write`: 映射是否可写。文件有写入封印时不能建立可写的共享映射
+    ///
+    /// 可写的共享映射还会得到一个登记，映射存在期间需要一直持有它
+    pub fn mmap_file(
+        &self,
+        shared: bool,
+        write: bool,
+    ) -> AxResult<(File, Option<MemfdWritableMapping>)> {
+        if !(shared && write) {
+            return Ok((self.file.lock().clone(), None));
+        }
+        if self.seals() & SEAL_FUTURE_WRITE != 0 {
+            return Err(AxError::PermissionDenied);
+        }
+        let mapping = self.node.map_writable()?;
+        Ok((self.file.lock().clone(), Some(mapping)))
+    }
+}
+
+impl FileIO for MemFd {
+    fn read(&self, buf: &mut [u8]) -> AxResult<usize> {
+        self.file.lock().read(buf)
+    }
+
+    fn write(&self, buf: &[u8]) -> AxResult<usize> {
+        if self.seals() & SEAL_FUTURE_WRITE != 0 {
+            return Err(AxError::PermissionDenied);
+        }
+        let mut file = self.file.lock();
+        if self.flags.lock().contains(OpenFlags::APPEND) {
+            file.seek(SeekFrom::End(0))?;
+        }
+        file.write(buf)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        self.file.lock().read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        if self.seals() & SEAL_FUTURE_WRITE != 0 {
+            return Err(AxError::PermissionDenied);
+        }
+        self.file.lock().write_at(offset, buf)
+    }
+
+    fn seek(&self, pos: SeekFrom) -> AxResult<u64> {
+        self.file.lock().seek(pos)
+    }
+
+    fn readable(&self) -> bool {
+        self.flags.lock().readable()
+    }
+
+    fn writable(&self) -> bool {
+        self.flags.lock().writable()
+    }
+
+    fn executable(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> FileIOType {
+        FileIOType::FileDesc
+    }
+
+    fn get_path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn truncate(&self, len: usize) -> AxResult<()> {
+        self.file.lock().truncate(len)
+    }
+
+    fn get_stat(&self) -> AxResult<Kstat> {
+        let attr = self.file.lock().get_attr()?;
+        Ok(Kstat {
+            st_dev: 1,
+            // 节点不在任何目录中，以其地址作为 inode 号
+            st_ino: Arc::as_ptr(&self.node) as u64,
+            st_mode: normal_file_mode(StMode::S_IFREG).bits() | 0o777,
+            st_nlink: 0,
+            st_size: attr.size(),
+            st_blksize: axfs::BLOCK_SIZE as u32,
+            st_blocks: attr.blocks(),
+            ..Default::default()
+        })
+    }
+
+    fn set_status(&self, flags: OpenFlags) -> bool {
+        *self.flags.lock() = flags;
+        true
+    }
+
+    fn get_status(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn ready_to_read(&self) -> bool {
+        self.readable()
+    }
+
+    fn ready_to_write(&self) -> bool {
+        self.writable()
+    }
+}
@@ -1,6 +1,20 @@
 pub mod dir;
 
+pub mod epoll;
+
+pub mod eventfd;
+
 pub mod file;
 
+pub mod memfd;
+
+pub mod pipe;
+
+pub mod signalfd;
+
+pub mod timerfd;
+
 pub use file::FileDesc;
 
+pub use memfd::MemFd;
+
@@ -0,0 +1,298 @@
+//! 匿名管道
+//!
+//! 读端与写端共享一个定长的环形缓冲区。缓冲区为空时的读取、没有足够空位时的写入返回 `WouldBlock`，
+//! 阻塞模式下由系统调用挂起等待；两端在读写或关闭时调用 notify_pollers 唤醒等待者重新尝试。
+extern crate alloc;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, Kstat, OpenFlags};
+use axtask::{notify_pollers, Mutex};
+use spinlock::SpinNoIrq;
+
+use crate::{normal_file_mode, StMode};
+
+/// 管道缓冲区的大小
+pub const PIPE_BUF_SIZE: usize = 0x10000;
+
+/// 长度不超过该值的写入是原子的，不会与其他写者的数据交错
+pub const PIPE_BUF: usize = 4096;
+
+/// 管道使用的环形缓冲区
+struct PipeRingBuffer {
+    arr: Vec<u8>,
+    /// 下一个可读字节的位置
+    head: usize,
+    /// 缓冲区中已有的字节数
+    len: usize,
+}
+
+impl PipeRingBuffer {
+    fn new() -> Self {
+        Self {
+            arr: vec![0; PIPE_BUF_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// 可读的字节数
+    fn available_read(&self) -> usize {
+        self.len
+    }
+
+    /// 可写的字节数
+    fn available_write(&self) -> usize {
+        PIPE_BUF_SIZE - self.len
+    }
+
+    /// 从缓冲区读出尽可能多的数据，返回读出的字节数
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        let count = buf.len().min(self.len);
+        // 数据可能跨过缓冲区末尾，分两段拷贝
+        let first = count.min(PIPE_BUF_SIZE - self.head);
+        buf[..first].copy_from_slice(&self.arr[self.head..self.head + first]);
+        buf[first..count].copy_from_slice(&self.arr[..count - first]);
+        self.head = (self.head + count) % PIPE_BUF_SIZE;
+        self.len -= count;
+        count
+    }
+
+    /// 复制出缓冲区开头尽可能多的数据而不消耗它们，返回复制的字节数
+    fn peek(&self, buf: &mut [u8]) -> usize {
+        let count = buf.len().min(self.len);
+        let first = count.min(PIPE_BUF_SIZE - self.head);
+        buf[..first].copy_from_slice(&self.arr[self.head..self.head + first]);
+        buf[first..count].copy_from_slice(&self.arr[..count - first]);
+        count
+    }
+
+    /// 向缓冲区写入尽可能多的数据，返回写入的字节数
+    fn write(&mut self, buf: &[u8]) -> usize {
+        let count = buf.len().min(self.available_write());
+        let tail = (self.head + self.len) % PIPE_BUF_SIZE;
+        let first = count.min(PIPE_BUF_SIZE - tail);
+        self.arr[tail..tail + first].copy_from_slice(&buf[..first]);
+        self.arr[..count - first].copy_from_slice(&buf[first..count]);
+        self.len += count;
+        count
+    }
+}
+
+/// 管道两端共享的内容
+struct PipeInner {
+    /// 缓冲区，会在等待队列的条件判断中被访问，因此使用自旋锁
+    buffer: SpinNoIrq<PipeRingBuffer>,
+    /// 所有读端均已关闭
+    read_end_closed: AtomicBool,
+    /// 所有写端均已关闭
+    write_end_closed: AtomicBool,
+}
+
+/// 管道的一端
+///
+/// 同一端被 dup 或 fork 复制时共享同一个 `Pipe`，因此 `Pipe` 被释放即代表这一端被彻底关闭
+pub struct Pipe {
+    /// 是否为读端
+    readable: bool,
+    inner: Arc<PipeInner>,
+    /// 文件状态标志，如 O_NONBLOCK
+    flags: Mutex<OpenFlags>,
+}
+
+/// 创建一个管道，返回读端与写端
+pub fn make_pipe(flags: OpenFlags) -> (Arc<Pipe>, Arc<Pipe>) {
+    let inner = Arc::new(PipeInner {
+        buffer: SpinNoIrq::new(PipeRingBuffer::new()),
+        read_end_closed: AtomicBool::new(false),
+        write_end_closed: AtomicBool::new(false),
+    });
+    let status = flags & OpenFlags::NON_BLOCK;
+    let read_end = Arc::new(Pipe {
+        readable: true,
+        inner: inner.clone(),
+        flags: Mutex::new(status | OpenFlags::RDONLY),
+    });
+    let write_end = Arc::new(Pipe {
+        readable: false,
+        inner,
+        flags: Mutex::new(status | OpenFlags::WRONLY),
+    });
+    (read_end, write_end)
+}
+
+impl Pipe {
+    /// 两端是否属于同一个管道
+    pub fn same_pipe(&self, other: &Pipe) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// 写端可以立即写入的字节数
+    ///
+    /// 读端全部关闭时写入会立即失败，此时返回 None
+    pub fn write_space(&self) -> Option<usize> {
+        if self.readable || self.inner.read_end_closed.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(self.inner.buffer.lock().available_write())
+    }
+
+    /// 把本读端中的数据复制到另一个管道的写端，不消耗本端的数据，返回复制的字节数
+    ///
+    /// # Arguments
+    /// * `dst`: 另一个管道的写端
+    /// * `len`: 最多复制的字节数
+    ///
+    /// 本端没有数据或 `dst` 没有空位时返回 `WouldBlock`，本端的写端全部关闭且没有数据时返回 0，
+    /// `dst` 的读端全部关闭时返回 `ConnectionReset`
+    pub fn tee(&self, dst: &Pipe, len: usize) -> AxResult<usize> {
+        if !self.readable || dst.readable {
+            return Err(AxError::InvalidInput);
+        }
+        if len == 0 {
+            return Ok(0);
+        }
+        let (src, out) = (&self.inner, &dst.inner);
+        if out.read_end_closed.load(Ordering::Acquire) {
+            return Err(AxError::ConnectionReset);
+        }
+        // 先检查写端是否关闭再读取，关闭之前写入的数据一定可以被读到
+        let closed = src.write_end_closed.load(Ordering::Acquire);
+        let available = src.buffer.lock().available_read();
+        if available == 0 && closed {
+            return Ok(0);
+        }
+        let space = out.buffer.lock().available_write();
+        if available == 0 || space == 0 {
+            return Err(AxError::WouldBlock);
+        }
+        // 两个缓冲区的锁不同时持有，dst 的空位可能在两次加锁之间被其他写者占用
+        let mut buf = vec![0; len.min(available).min(space)];
+        let count = src.buffer.lock().peek(&mut buf);
+        let written = out.buffer.lock().write(&buf[..count]);
+        if written == 0 {
+            return Err(AxError::WouldBlock);
+        }
+        notify_pollers();
+        Ok(written)
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        // 唤醒另一端的等待者，使其看到 EOF 或 EPIPE
+        if self.readable {
+            self.inner.read_end_closed.store(true, Ordering::Release);
+        } else {
+            self.inner.write_end_closed.store(true, Ordering::Release);
+        }
+        notify_pollers();
+    }
+}
+
+impl FileIO for Pipe {
+    fn read(&self, buf: &mut [u8]) -> AxResult<usize> {
+        if !self.readable {
+            return Err(AxError::PermissionDenied);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let inner = &self.inner;
+        // 先检查写端是否关闭再读取，关闭之前写入的数据一定可以被读到
+        let closed = inner.write_end_closed.load(Ordering::Acquire);
+        let count = inner.buffer.lock().read(buf);
+        if count > 0 {
+            notify_pollers();
+            return Ok(count);
+        }
+        if closed {
+            // 写端全部关闭且没有剩余数据，读到 EOF
+            return Ok(0);
+        }
+        Err(AxError::WouldBlock)
+    }
+
+    fn write(&self, buf: &[u8]) -> AxResult<usize> {
+        if self.readable {
+            return Err(AxError::PermissionDenied);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let inner = &self.inner;
+        if inner.read_end_closed.load(Ordering::Acquire) {
+            // 由调用者向任务发送 SIGPIPE
+            return Err(AxError::ConnectionReset);
+        }
+        // 不超过 PIPE_BUF 的写入需要一次性完成，否则只要有空位就写入
+        let required = buf.len().min(PIPE_BUF);
+        let written = {
+            let mut buffer = inner.buffer.lock();
+            if buffer.available_write() < required {
+                return Err(AxError::WouldBlock);
+            }
+            buffer.write(buf)
+        };
+        notify_pollers();
+        Ok(written)
+    }
+
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        !self.readable
+    }
+
+    fn executable(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> FileIOType {
+        FileIOType::Pipe
+    }
+
+    fn get_stat(&self) -> AxResult<Kstat> {
+        Ok(Kstat {
+            st_dev: 1,
+            st_mode: normal_file_mode(StMode::S_IFIFO).bits(),
+            st_nlink: 1,
+            st_blksize: PIPE_BUF as u32,
+            ..Default::default()
+        })
+    }
+
+    fn set_status(&self, flags: OpenFlags) -> bool {
+        *self.flags.lock() = flags;
+        true
+    }
+
+    fn get_status(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn is_hang_up(&self) -> bool {
+        if self.readable {
+            self.inner.write_end_closed.load(Ordering::Acquire)
+        } else {
+            self.inner.read_end_closed.load(Ordering::Acquire)
+        }
+    }
+
+    fn ready_to_read(&self) -> bool {
+        self.readable
+            && (self.inner.buffer.lock().available_read() > 0
+                || self.inner.write_end_closed.load(Ordering::Acquire))
+    }
+
+    fn ready_to_write(&self) -> bool {
+        !self.readable
+            && (self.inner.buffer.lock().available_write() > 0
+                || self.inner.read_end_closed.load(Ordering::Acquire))
+    }
+}
@@ -0,0 +1,141 @@
+//! signalfd 文件
+//!
+//! 读取时从读者所在线程的未决信号集中取出属于 signalfd 掩码的信号。这些信号通常已被屏蔽，
+//! 否则会先在返回用户态时被递送。发送信号时会唤醒 poll 等待者，因此 poll 与 epoll 能够看到新的信号。
+extern crate alloc;
+use core::mem::size_of;
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, Kstat, OpenFlags};
+use axsignal::info::SigInfo;
+use axtask::{current_task, Mutex};
+
+use crate::{normal_file_mode, SignalFdSigInfo, StMode};
+
+/// signalfd 文件
+pub struct SignalFd {
+    /// 关心的信号集
+    mask: Mutex<usize>,
+    /// 文件状态标志，如 O_NONBLOCK
+    flags: Mutex<OpenFlags>,
+}
+
+impl SignalFd {
+    /// 以给定的信号集创建 signalfd，信号集中不应包含 SIGKILL 与 SIGSTOP
+    pub fn new(mask: usize, flags: OpenFlags) -> Self {
+        Self {
+            mask: Mutex::new(mask),
+            flags: Mutex::new(flags | OpenFlags::RDONLY),
+        }
+    }
+
+    /// 修改关心的信号集
+    pub fn set_mask(&self, mask: usize) {
+        *self.mask.lock() = mask;
+    }
+
+    /// 当前线程未决的、属于该 signalfd 的信号集
+    fn pending(&self) -> usize {
+        let curr = current_task();
+        let tid = curr.tid();
+        let mask = *self.mask.lock();
+        curr.signal_modules
+            .lock()
+            .get(&tid)
+            .map_or(0, |module| module.signal_set.pending & mask)
+    }
+
+    /// 从当前线程的未决信号集中取出编号最小的、属于该 signalfd 的信号，返回信号的信息
+    fn take_signal(&self) -> Option<SigInfo> {
+        let curr = current_task();
+        let tid = curr.tid();
+        let mask = *self.mask.lock();
+        let mut signal_modules = curr.signal_modules.lock();
+        let signal_set = &mut signal_modules.get_mut(&tid)?.signal_set;
+        let pending = signal_set.pending & mask;
+        if pending == 0 {
+            return None;
+        }
+        let sig_num = pending.trailing_zeros() as usize + 1;
+        signal_set.pending &= !(1 << (sig_num - 1));
+        Some(signal_set.info(sig_num))
+    }
+}
+
+impl FileIO for SignalFd {
+    fn read(&self, buf: &mut [u8]) -> AxResult<usize> {
+        let info_size = size_of::<SignalFdSigInfo>();
+        if buf.len() < info_size {
+            return Err(AxError::InvalidInput);
+        }
+        // 一次读取尽可能多的信号
+        let mut count = 0;
+        for chunk in buf.chunks_exact_mut(info_size) {
+            let Some(sig_info) = self.take_signal() else {
+                break;
+            };
+            let info = SignalFdSigInfo {
+                ssi_signo: sig_info.si_signo as u32,
+                ssi_errno: sig_info.si_errno,
+                ssi_code: sig_info.si_code,
+                ssi_pid: sig_info.si_pid as u32,
+                ssi_uid: sig_info.si_uid,
+                pad: [0; 108],
+            };
+            let bytes =
+                unsafe { core::slice::from_raw_parts(&info as *const _ as *const u8, info_size) };
+            chunk.copy_from_slice(bytes);
+            count += 1;
+        }
+        if count == 0 {
+            return Err(AxError::WouldBlock);
+        }
+        Ok(count * info_size)
+    }
+
+    fn write(&self, _buf: &[u8]) -> AxResult<usize> {
+        Err(AxError::InvalidInput)
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn executable(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> FileIOType {
+        FileIOType::Other
+    }
+
+    fn get_stat(&self) -> AxResult<Kstat> {
+        Ok(Kstat {
+            st_dev: 1,
+            st_mode: normal_file_mode(StMode::S_IFREG).bits(),
+            st_nlink: 1,
+            ..Default::default()
+        })
+    }
+
+    fn set_status(&self, flags: OpenFlags) -> bool {
+        *self.flags.lock() = flags;
+        true
+    }
+
+    fn get_status(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn ready_to_read(&self) -> bool {
+        self.pending() != 0
+    }
+
+    fn ready_to_write(&self) -> bool {
+        false
+    }
+}
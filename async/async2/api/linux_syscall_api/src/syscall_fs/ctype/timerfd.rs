@@ -0,0 +1,162 @@
+//! timerfd 文件
+//!
+//! 定时器的到期次数在读取或检查就绪状态时根据当前时间计算；到期时由内核的定时器列表唤醒
+//! poll 等待者，使 poll 与 epoll 能够及时看到定时器就绪。
+extern crate alloc;
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, Kstat, OpenFlags};
+use axhal::time::current_time;
+use axtask::{notify_pollers_at, Mutex};
+
+use crate::{normal_file_mode, StMode};
+
+/// 定时器的状态
+struct TimerState {
+    /// 下一次到期的时间，为空时定时器未启动
+    deadline: Option<Duration>,
+    /// 到期后重新设置的周期，为 0 时只到期一次
+    interval: Duration,
+    /// 尚未被读取的到期次数
+    expirations: u64,
+    /// 定时器列表中的唤醒事件持有它的弱引用，重新设置定时器时替换，使原有的唤醒事件失效
+    wakeup_owner: Arc<()>,
+}
+
+impl TimerState {
+    /// 根据当前时间累计到期次数
+    fn update(&mut self, now: Duration) {
+        let Some(deadline) = self.deadline else {
+            return;
+        };
+        if now < deadline {
+            return;
+        }
+        if self.interval.is_zero() {
+            self.expirations += 1;
+            self.deadline = None;
+        } else {
+            let count = (now - deadline).as_nanos() / self.interval.as_nanos() + 1;
+            self.expirations += count as u64;
+            self.deadline = Some(deadline + self.interval * count as u32);
+        }
+    }
+}
+
+/// timerfd 文件
+pub struct TimerFd {
+    state: Mutex<TimerState>,
+    /// 文件状态标志，如 O_NONBLOCK
+    flags: Mutex<OpenFlags>,
+}
+
+impl TimerFd {
+    /// 创建一个未启动的定时器
+    pub fn new(flags: OpenFlags) -> Self {
+        Self {
+            state: Mutex::new(TimerState {
+                deadline: None,
+                interval: Duration::ZERO,
+                expirations: 0,
+                wakeup_owner: Arc::new(()),
+            }),
+            flags: Mutex::new(flags | OpenFlags::RDONLY),
+        }
+    }
+
+    /// 获取定时器的设置，返回 (距离下一次到期的时间, 周期)，未启动时前者为 0
+    pub fn get_time(&self) -> (Duration, Duration) {
+        let now = current_time();
+        let mut state = self.state.lock();
+        state.update(now);
+        let remaining = state
+            .deadline
+            .map_or(Duration::ZERO, |deadline| deadline - now);
+        (remaining, state.interval)
+    }
+
+    /// 重新设置定时器，并清空尚未被读取的到期次数，返回原有的设置
+    ///
+    /// # Arguments
+    /// * `deadline`: 下一次到期的时间，为空时停止定时器
+    /// * `interval`: 到期后重新设置的周期
+    pub fn set_time(&self, deadline: Option<Duration>, interval: Duration) -> (Duration, Duration) {
+        let old = self.get_time();
+        let mut state = self.state.lock();
+        state.deadline = deadline;
+        state.interval = interval;
+        state.expirations = 0;
+        state.wakeup_owner = Arc::new(());
+        if let Some(deadline) = deadline {
+            notify_pollers_at(deadline, interval, Arc::downgrade(&state.wakeup_owner));
+        }
+        old
+    }
+}
+
+impl FileIO for TimerFd {
+    fn read(&self, buf: &mut [u8]) -> AxResult<usize> {
+        if buf.len() < 8 {
+            return Err(AxError::InvalidInput);
+        }
+        // 到期时定时器列表会唤醒 poll 等待者，阻塞的读取由系统调用等到那时再重试
+        let mut state = self.state.lock();
+        state.update(current_time());
+        if state.expirations == 0 {
+            return Err(AxError::WouldBlock);
+        }
+        buf[..8].copy_from_slice(&state.expirations.to_ne_bytes());
+        state.expirations = 0;
+        Ok(8)
+    }
+
+    fn write(&self, _buf: &[u8]) -> AxResult<usize> {
+        Err(AxError::InvalidInput)
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn executable(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> FileIOType {
+        FileIOType::Other
+    }
+
+    fn get_stat(&self) -> AxResult<Kstat> {
+        Ok(Kstat {
+            st_dev: 1,
+            st_mode: normal_file_mode(StMode::S_IFREG).bits(),
+            st_nlink: 1,
+            ..Default::default()
+        })
+    }
+
+    fn set_status(&self, flags: OpenFlags) -> bool {
+        *self.flags.lock() = flags;
+        true
+    }
+
+    fn get_status(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn ready_to_read(&self) -> bool {
+        let mut state = self.state.lock();
+        state.update(current_time());
+        state.expirations > 0
+    }
+
+    fn ready_to_write(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,442 @@
+//! 负责与工作目录、目录创建、重命名以及文件描述符控制相关的系统调用
+extern crate alloc;
+use crate::syscall_fs::MemFd;
+use crate::{
+    CloseRangeFlags, Fcntl64Cmd, RenameFlags, SyscallError, SyscallResult, FD_CLOEXEC,
+};
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use axerrno::AxError;
+use axfs::api::{self, FileIO, FileIOType, OpenFlags, FIOCLEX, FIONBIO, FIONCLEX};
+use axlog::{debug, info};
+use axtask::{current_task, UserPtr};
+use axtask::link::{deal_with_path, deal_with_path_nofollow, AT_FDCWD, SYMLINK_MAP};
+
+/// 若路径对应一个符号链接，则将其记录移动到新的路径
+fn move_symlink(old: &str, new: &str) {
+    let mut map = SYMLINK_MAP.lock();
+    if let Some(target) = map.remove(old) {
+        map.insert(new.to_string(), target);
+    }
+}
+
+/// 功能:获取当前工作目录；
+/// # Arguments
+/// * `buf`: *mut u8, 一块缓存区,用于保存当前工作目录的字符串。
+/// * `len`: usize, buf缓存区的大小。
+/// 返回值:成功执行,返回写入的字节数（包含结尾的 '\0'）。缓冲区不足时返回 ERANGE。
+pub fn syscall_getcwd(args: [usize; 6]) -> SyscallResult {
+    let buf = args[0] as *mut u8;
+    let len = args[1];
+    debug!("Into syscall_getcwd. buf: {}, len: {}", buf as usize, len);
+    let task = current_task();
+    let mut cwd = task.get_cwd();
+    // 内部保存的工作目录以 '/' 结尾，返回给用户时去掉
+    if cwd.len() > 1 {
+        cwd.pop();
+    }
+    if len < cwd.len() + 1 {
+        return Err(SyscallError::ERANGE);
+    }
+    cwd.push('\0');
+    UserPtr::<u8>::from(buf).write_array(cwd.as_bytes())?;
+    Ok(cwd.len() as isize)
+}
+
+/// 将当前任务的工作目录设置为 path，path 需要是一个存在的目录
+fn set_cwd(path: &str) -> SyscallResult {
+    if !api::is_dir(path) {
+        return if api::path_exists(path.trim_end_matches('/')) {
+            Err(SyscallError::ENOTDIR)
+        } else {
+            Err(SyscallError::ENOENT)
+        };
+    }
+    let mut cwd = path.to_string();
+    if !cwd.ends_with('/') {
+        cwd.push('/');
+    }
+    info!("change cwd to {}", cwd);
+    *current_task().fd_manager.cwd.lock() = cwd;
+    Ok(0)
+}
+
+/// 功能:切换工作目录；
+/// # Arguments
+/// * `path`: *const u8, 需要切换到的目录。
+/// 返回值:成功执行:返回0。失败，返回-1。
+pub fn syscall_chdir(args: [usize; 6]) -> SyscallResult {
+    let path = args[0] as *const u8;
+    let path = deal_with_path(AT_FDCWD, Some(path), true).ok_or(SyscallError::ENOENT)?;
+    debug!("Into syscall_chdir. path: {:?}", path.path());
+    set_cwd(path.path())
+}
+
+/// 功能:通过文件描述符切换工作目录；
+/// # Arguments
+/// * `fd`: usize, 需要切换到的目录的文件描述符。
+/// 返回值:成功执行:返回0。失败，返回-1。
+pub fn syscall_fchdir(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let file = match current_task().fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    if file.get_type() != FileIOType::DirDesc {
+        return Err(SyscallError::ENOTDIR);
+    }
+    set_cwd(file.get_path().as_str())
+}
+
+/// 功能:创建目录；
+/// # Arguments
+/// * `dir_fd`: usize, 要创建的目录所在的目录的文件描述符。
+/// * `path`: *const u8, 要创建的目录的名称。如果path是相对路径，则它是相对于dirfd目录而言的。
+/// * `mode`: u32, 文件的所有权描述，目前被忽略。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_mkdirat(args: [usize; 6]) -> SyscallResult {
+    let dir_fd = args[0];
+    let path = args[1] as *const u8;
+    let mode = args[2] as u32;
+    let path = deal_with_path(dir_fd, Some(path), true).ok_or(SyscallError::EINVAL)?;
+    debug!(
+        "Into syscall_mkdirat. dirfd: {}, path: {:?}, mode: {:o}",
+        dir_fd,
+        path.path(),
+        mode
+    );
+    let dir = path.path().trim_end_matches('/');
+    if api::path_exists(dir) {
+        return Err(SyscallError::EEXIST);
+    }
+    api::create_dir(dir).map_err(SyscallError::from)?;
+    Ok(0)
+}
+
+/// 功能:创建目录，供 x86_64 的 mkdir 使用；
+/// # Arguments
+/// * `path`: *const u8, 要创建的目录的名称。
+/// * `mode`: u32, 文件的所有权描述，目前被忽略。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_mkdir(args: [usize; 6]) -> SyscallResult {
+    syscall_mkdirat([AT_FDCWD, args[0], args[1], 0, 0, 0])
+}
+
+/// 功能:重命名文件或目录；
+/// # Arguments
+/// * `old_dirfd`: usize, 原文件所在目录的文件描述符。
+/// * `old_path`: *const u8, 原文件的路径。
+/// * `new_dirfd`: usize, 新文件所在目录的文件描述符。
+/// * `new_path`: *const u8, 新文件的路径。
+/// * `flags`: u32, RenameFlags，支持 NOREPLACE。
+/// 返回值:成功返回0，失败返回-1。
+///
+/// 底层文件系统不能原子地交换两个路径，因此 EXCHANGE 返回 EINVAL
+pub fn syscall_renameat2(args: [usize; 6]) -> SyscallResult {
+    let old_dirfd = args[0];
+    let old_path = args[1] as *const u8;
+    let new_dirfd = args[2];
+    let new_path = args[3] as *const u8;
+    let flags = RenameFlags::from_bits(args[4] as u32).ok_or(SyscallError::EINVAL)?;
+    if flags.intersects(RenameFlags::EXCHANGE | RenameFlags::WHITEOUT) {
+        return Err(SyscallError::EINVAL);
+    }
+    let old_path =
+        deal_with_path_nofollow(old_dirfd, Some(old_path), false).ok_or(SyscallError::ENOENT)?;
+    let new_path =
+        deal_with_path_nofollow(new_dirfd, Some(new_path), false).ok_or(SyscallError::ENOENT)?;
+    let old = old_path.path().trim_end_matches('/');
+    let new = new_path.path().trim_end_matches('/');
+    debug!(
+        "Into syscall_renameat2. old: {}, new: {}, flags: {:?}",
+        old, new, flags
+    );
+    if !api::path_exists(old) {
+        return Err(SyscallError::ENOENT);
+    }
+    let new_exists = api::path_exists(new);
+    if old == new {
+        return Ok(0);
+    }
+    // 不能把目录移动到自己的子目录下
+    if new.starts_with(old) && new.as_bytes().get(old.len()) == Some(&b'/') {
+        return Err(SyscallError::EINVAL);
+    }
+    if new_exists {
+        if flags.contains(RenameFlags::NOREPLACE) {
+            return Err(SyscallError::EEXIST);
+        }
+        match (api::is_dir(old), api::is_dir(new)) {
+            (true, false) => return Err(SyscallError::ENOTDIR),
+            (false, true) => return Err(SyscallError::EISDIR),
+            (true, true) => {
+                // 只能覆盖空目录
+                if api::read_dir(new).map_or(false, |mut entries| entries.next().is_some()) {
+                    return Err(SyscallError::ENOTEMPTY);
+                }
+            }
+            (false, false) => {
+                SYMLINK_MAP.lock().remove(new);
+            }
+        }
+    }
+    api::rename(old, new).map_err(SyscallError::from)?;
+    move_symlink(old, new);
+    Ok(0)
+}
+
+/// 功能:重命名文件或目录，等价于 flags 为 0 的 renameat2；
+/// # Arguments
+/// * `old_dirfd`: usize, 原文件所在目录的文件描述符。
+/// * `old_path`: *const u8, 原文件的路径。
+/// * `new_dirfd`: usize, 新文件所在目录的文件描述符。
+/// * `new_path`: *const u8, 新文件的路径。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_renameat(args: [usize; 6]) -> SyscallResult {
+    syscall_renameat2([args[0], args[1], args[2], args[3], 0, 0])
+}
+
+/// 功能:重命名文件或目录，供 x86_64 的 rename 使用；
+/// # Arguments
+/// * `old_path`: *const u8, 原文件的路径。
+/// * `new_path`: *const u8, 新文件的路径。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_rename(args: [usize; 6]) -> SyscallResult {
+    syscall_renameat2([AT_FDCWD, args[0], AT_FDCWD, args[1], 0, 0])
+}
+
+/// 获取文件描述符对应的文件，不存在时返回 EBADF
+fn get_file(fd: usize) -> Result<Arc<dyn FileIO>, SyscallError> {
+    match current_task().fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => Ok(file.clone()),
+        _ => Err(SyscallError::EBADF),
+    }
+}
+
+/// 将 old_fd 复制到不小于 min_fd 的最小可用文件描述符上
+fn dup_from(old_fd: usize, min_fd: usize, cloexec: bool) -> SyscallResult {
+    let task = current_task();
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let file = match fd_table.get(old_fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    let new_fd = task
+        .alloc_fd_from(&mut fd_table, min_fd)
+        .map_err(|_| SyscallError::EMFILE)?;
+    fd_table[new_fd] = Some(file);
+    if cloexec {
+        task.fd_manager.set_close_on_exec(new_fd, true);
+    }
+    Ok(new_fd as isize)
+}
+
+/// 功能:复制文件描述符；
+/// # Arguments
+/// * `fd`: usize, 被复制的文件描述符。
+/// 返回值:成功执行,返回新的文件描述符，新描述符不带有 FD_CLOEXEC 标志。失败,返回-1。
+pub fn syscall_dup(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    debug!("Into syscall_dup. fd: {}", fd);
+    dup_from(fd, 0, false)
+}
+
+/// 功能:复制文件描述符，并指定了新的文件描述符；
+/// # Arguments
+/// * `old`: usize, 被复制的文件描述符。
+/// * `new`: usize, 新的文件描述符，若已经打开则先将其关闭。
+/// * `flags`: usize, 只能为 0 或 O_CLOEXEC。
+/// 返回值:成功执行,返回新的文件描述符。失败,返回-1。
+pub fn syscall_dup3(args: [usize; 6]) -> SyscallResult {
+    let old_fd = args[0];
+    let new_fd = args[1];
+    let flags = OpenFlags::from_bits(args[2] as u32).ok_or(SyscallError::EINVAL)?;
+    debug!(
+        "Into syscall_dup3. old_fd: {}, new_fd: {}, flags: {:?}",
+        old_fd, new_fd, flags
+    );
+    if !(flags - OpenFlags::CLOEXEC).is_empty() || old_fd == new_fd {
+        return Err(SyscallError::EINVAL);
+    }
+    let task = current_task();
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let file = match fd_table.get(old_fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    if new_fd >= task.fd_manager.get_limit() as usize {
+        return Err(SyscallError::EBADF);
+    }
+    if new_fd >= fd_table.len() {
+        fd_table.resize(new_fd + 1, None);
+    }
+    // 原有的文件会随着引用计数归零而被关闭
+    fd_table[new_fd] = Some(file);
+    task.fd_manager
+        .set_close_on_exec(new_fd, flags.is_close_on_exec());
+    Ok(new_fd as isize)
+}
+
+/// 功能:复制文件描述符，并指定了新的文件描述符，供 x86_64 的 dup2 使用；
+/// # Arguments
+/// * `old`: usize, 被复制的文件描述符。
+/// * `new`: usize, 新的文件描述符。
+/// 返回值:成功执行,返回新的文件描述符。失败,返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_dup2(args: [usize; 6]) -> SyscallResult {
+    let old_fd = args[0];
+    let new_fd = args[1];
+    if old_fd == new_fd {
+        // 与 dup3 不同，两者相同时只检查文件描述符是否有效
+        get_file(old_fd)?;
+        return Ok(new_fd as isize);
+    }
+    syscall_dup3([old_fd, new_fd, 0, 0, 0, 0])
+}
+
+/// 功能:操作文件描述符；
+/// # Arguments
+/// * `fd`: usize, 被操作的文件描述符。
+/// * `cmd`: usize, 操作类型，见 Fcntl64Cmd。
+/// * `arg`: usize, 操作的参数。
+/// 返回值:F_DUPFD 与 F_DUPFD_CLOEXEC 返回新的文件描述符，F_GETFD 返回文件描述符标志，
+/// F_GETFL 返回文件状态标志，F_GET_SEALS 返回 memfd 的封印，其余操作成功时返回0。失败,返回-1。
+///
+/// 系统不记录文件的可写共享映射，因此添加 F_SEAL_WRITE 时不会因已有映射返回 EBUSY，
+/// 已有映射的写回会因封印而失败
+pub fn syscall_fcntl64(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let cmd = args[1];
+    let arg = args[2];
+    let cmd = Fcntl64Cmd::try_from(cmd).map_err(|_| SyscallError::EINVAL)?;
+    debug!("Into syscall_fcntl64. fd: {}, cmd: {:?}, arg: {}", fd, cmd, arg);
+    let task = current_task();
+    let file = get_file(fd)?;
+    match cmd {
+        Fcntl64Cmd::F_DUPFD | Fcntl64Cmd::F_DUPFD_CLOEXEC => {
+            if arg >= task.fd_manager.get_limit() as usize {
+                return Err(SyscallError::EINVAL);
+            }
+            dup_from(fd, arg, matches!(cmd, Fcntl64Cmd::F_DUPFD_CLOEXEC))
+        }
+        Fcntl64Cmd::F_GETFD => {
+            if task.fd_manager.is_close_on_exec(fd) {
+                Ok(FD_CLOEXEC as isize)
+            } else {
+                Ok(0)
+            }
+        }
+        Fcntl64Cmd::F_SETFD => {
+            task.fd_manager
+                .set_close_on_exec(fd, arg & FD_CLOEXEC != 0);
+            Ok(0)
+        }
+        Fcntl64Cmd::F_GETFL => {
+            // FD_CLOEXEC 属于文件描述符，不属于文件状态
+            let status = file.get_status() - OpenFlags::CLOEXEC;
+            Ok(status.bits() as isize)
+        }
+        Fcntl64Cmd::F_SETFL => {
+            // 只有 O_APPEND 与 O_NONBLOCK 可以被修改，访问模式与创建选项保持不变
+            let changeable = OpenFlags::APPEND | OpenFlags::NON_BLOCK;
+            let status = (file.get_status() - changeable)
+                | (OpenFlags::from_bits_truncate(arg as u32) & changeable);
+            file.set_status(status);
+            Ok(0)
+        }
+        Fcntl64Cmd::F_ADD_SEALS | Fcntl64Cmd::F_GET_SEALS => {
+            // 只有 memfd 支持封印
+            let Some(memfd) = file.as_any().downcast_ref::<MemFd>() else {
+                return Err(SyscallError::EINVAL);
+            };
+            if matches!(cmd, Fcntl64Cmd::F_GET_SEALS) {
+                return Ok(memfd.seals() as isize);
+            }
+            if !memfd.writable() {
+                return Err(SyscallError::EPERM);
+            }
+            memfd.add_seals(arg as u32).map_err(|err| match err {
+                AxError::PermissionDenied => SyscallError::EPERM,
+                AxError::ResourceBusy => SyscallError::EBUSY,
+                _ => SyscallError::EINVAL,
+            })?;
+            Ok(0)
+        }
+    }
+}
+
+/// 功能:控制设备；
+/// # Arguments
+/// * `fd`: usize, 文件描述符。
+/// * `request`: usize, 请求码。
+/// * `arg`: usize, 请求的参数，通常是一个用户地址。
+/// 返回值:成功执行,返回值取决于请求，通常为0。失败,返回-1。
+///
+/// 说明:FIOCLEX、FIONCLEX 与 FIONBIO 对所有文件都有效，其余请求交给文件自身处理，不支持时返回 ENOTTY
+pub fn syscall_ioctl(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let request = args[1];
+    let arg = args[2];
+    debug!(
+        "Into syscall_ioctl. fd: {}, request: {:#x}, arg: {:#x}",
+        fd, request, arg
+    );
+    let task = current_task();
+    let file = get_file(fd)?;
+    match request {
+        FIOCLEX | FIONCLEX => {
+            task.fd_manager.set_close_on_exec(fd, request == FIOCLEX);
+            Ok(0)
+        }
+        FIONBIO => {
+            let nonblock = UserPtr::<i32>::new(arg).read()?;
+            let mut status = file.get_status();
+            status.set(OpenFlags::NON_BLOCK, nonblock != 0);
+            file.set_status(status);
+            Ok(0)
+        }
+        _ => file.ioctl(request, arg).map_err(|err| match err {
+            AxError::Unsupported => SyscallError::ENOTTY,
+            AxError::PermissionDenied => SyscallError::EPERM,
+            err => err.into(),
+        }),
+    }
+}
+
+/// 功能:关闭或标记一段范围内的文件描述符；
+/// # Arguments
+/// * `first`: usize, 范围的起始文件描述符。
+/// * `last`: usize, 范围的结束文件描述符（包含）。
+/// * `flags`: usize, CloseRangeFlags。
+/// 返回值:成功执行,返回0。失败,返回-1。
+///
+/// 说明:任务的文件描述符表在创建后不能被替换，因此 CLOSE_RANGE_UNSHARE 被忽略
+pub fn syscall_close_range(args: [usize; 6]) -> SyscallResult {
+    let first = args[0];
+    let last = args[1];
+    let flags = CloseRangeFlags::from_bits(args[2] as u32).ok_or(SyscallError::EINVAL)?;
+    debug!(
+        "Into syscall_close_range. first: {}, last: {}, flags: {:?}",
+        first, last, flags
+    );
+    if first > last {
+        return Err(SyscallError::EINVAL);
+    }
+    let task = current_task();
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let end = fd_table.len().min(last.saturating_add(1));
+    for fd in first..end {
+        if fd_table[fd].is_none() {
+            continue;
+        }
+        if flags.contains(CloseRangeFlags::CLOEXEC) {
+            task.fd_manager.set_close_on_exec(fd, true);
+        } else {
+            fd_table[fd] = None;
+            task.fd_manager.set_close_on_exec(fd, false);
+        }
+    }
+    Ok(0)
+}
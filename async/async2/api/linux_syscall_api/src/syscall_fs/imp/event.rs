@@ -0,0 +1,237 @@
+//! 负责创建与控制事件通知文件的系统调用：eventfd、timerfd 与 signalfd
+//!
+//! 这些文件都保存在文件描述符表中，可以与 poll、select 和 epoll 一起使用。
+extern crate alloc;
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use axfs::api::{FileIO, OpenFlags};
+use axhal::paging::MappingFlags;
+use axhal::time::current_time;
+use axlog::info;
+use axsignal::signal_no::SignalNo;
+use axtask::{current_task, UserPtr};
+
+use crate::syscall_fs::ctype::{eventfd::EventFd, signalfd::SignalFd, timerfd::TimerFd};
+use crate::{
+    ClockId, EventFdFlags, ITimerSpec, SignalFdFlags, SyscallError, SyscallResult, TimeSecs,
+    TimerFdFlags, NSEC_PER_SEC, SIGSET_SIZE_IN_BYTE, TFD_TIMER_ABSTIME, TFD_TIMER_CANCEL_ON_SET,
+};
+
+/// 将文件加入当前任务的文件描述符表，返回分配的文件描述符
+fn add_file(file: Arc<dyn FileIO>, close_on_exec: bool) -> SyscallResult {
+    let task = current_task();
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let fd = task
+        .alloc_fd(&mut fd_table)
+        .map_err(|_| SyscallError::EMFILE)?;
+    fd_table[fd] = Some(file);
+    task.fd_manager.set_close_on_exec(fd, close_on_exec);
+    Ok(fd as isize)
+}
+
+/// 获取文件描述符对应的文件，类型不是 T 时返回 EINVAL
+fn typed_file<T: 'static>(fd: usize) -> Result<Arc<dyn FileIO>, SyscallError> {
+    let task = current_task();
+    let fd_table = task.fd_manager.fd_table.lock();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return Err(SyscallError::EBADF);
+    };
+    if file.as_any().downcast_ref::<T>().is_none() {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(file.clone())
+}
+
+/// 将 Duration 转换为 TimeSecs
+fn duration_to_time_secs(duration: Duration) -> TimeSecs {
+    TimeSecs {
+        tv_sec: duration.as_secs() as usize,
+        tv_nsec: duration.subsec_nanos() as usize,
+    }
+}
+
+/// 读取用户传入的 TimeSecs 并转换为 Duration，纳秒数不合法时返回 EINVAL
+fn time_secs_to_duration(time: TimeSecs) -> Result<Duration, SyscallError> {
+    if time.tv_nsec >= NSEC_PER_SEC {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+/// 功能:创建一个 eventfd；
+/// # Arguments
+/// * `initval`: u32, 计数器的初始值。
+/// * `flags`: u32, 见 EventFdFlags。
+/// 返回值:成功返回 eventfd 的文件描述符，失败返回-1。
+pub fn syscall_eventfd2(args: [usize; 6]) -> SyscallResult {
+    let initval = args[0] as u32;
+    let Some(flags) = EventFdFlags::from_bits(args[1] as u32) else {
+        return Err(SyscallError::EINVAL);
+    };
+    info!("[eventfd2()] initval: {}, flags: {:?}", initval, flags);
+    let open_flags = if flags.contains(EventFdFlags::EFD_NONBLOCK) {
+        OpenFlags::NON_BLOCK
+    } else {
+        OpenFlags::empty()
+    };
+    let eventfd = EventFd::new(
+        initval as u64,
+        flags.contains(EventFdFlags::EFD_SEMAPHORE),
+        open_flags,
+    );
+    add_file(Arc::new(eventfd), flags.contains(EventFdFlags::EFD_CLOEXEC))
+}
+
+/// 功能:创建一个 eventfd；
+/// # Arguments
+/// * `initval`: u32, 计数器的初始值。
+/// 返回值:成功返回 eventfd 的文件描述符，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_eventfd(args: [usize; 6]) -> SyscallResult {
+    syscall_eventfd2([args[0], 0, 0, 0, 0, 0])
+}
+
+/// 功能:创建一个 timerfd；
+/// # Arguments
+/// * `clock_id`: usize, 定时器使用的时钟，只支持 CLOCK_REALTIME、CLOCK_MONOTONIC 与 CLOCK_BOOTTIME。
+/// * `flags`: u32, 见 TimerFdFlags。
+/// 返回值:成功返回 timerfd 的文件描述符，失败返回-1。
+///
+/// 系统中没有实时时钟，所有时钟均以开机时间为准
+pub fn syscall_timerfd_create(args: [usize; 6]) -> SyscallResult {
+    let clock_id = args[0];
+    let Some(flags) = TimerFdFlags::from_bits(args[1] as u32) else {
+        return Err(SyscallError::EINVAL);
+    };
+    info!("[timerfd_create()] clock: {}, flags: {:?}", clock_id, flags);
+    match ClockId::try_from(clock_id) {
+        Ok(ClockId::CLOCK_REALTIME | ClockId::CLOCK_MONOTONIC | ClockId::CLOCK_BOOTTIME) => {}
+        _ => return Err(SyscallError::EINVAL),
+    }
+    let open_flags = if flags.contains(TimerFdFlags::TFD_NONBLOCK) {
+        OpenFlags::NON_BLOCK
+    } else {
+        OpenFlags::empty()
+    };
+    add_file(
+        Arc::new(TimerFd::new(open_flags)),
+        flags.contains(TimerFdFlags::TFD_CLOEXEC),
+    )
+}
+
+/// 功能:启动或停止 timerfd 的定时器；
+/// # Arguments
+/// * `fd`: usize, timerfd 的文件描述符。
+/// * `flags`: usize, 可以为 TFD_TIMER_ABSTIME，表示 it_value 为绝对时间。
+/// * `new_value`: *const ITimerSpec, 新的设置。it_value 为 0 时停止定时器。
+/// * `old_value`: *mut ITimerSpec, 用于保存原有的设置，可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_timerfd_settime(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let flags = args[1];
+    let new_value = UserPtr::<ITimerSpec>::new(args[2]);
+    let old_value = UserPtr::<ITimerSpec>::new(args[3]);
+    if flags & !(TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET) != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let new_value = new_value.read()?;
+    if !old_value.is_null() {
+        old_value.check(1, MappingFlags::WRITE)?;
+    }
+    let value = time_secs_to_duration(new_value.it_value)?;
+    let interval = time_secs_to_duration(new_value.it_interval)?;
+    info!(
+        "[timerfd_settime()] fd: {}, flags: {}, value: {:?}, interval: {:?}",
+        fd, flags, value, interval
+    );
+    let file = typed_file::<TimerFd>(fd)?;
+    let timerfd = file.as_any().downcast_ref::<TimerFd>().unwrap();
+    let deadline = if value.is_zero() {
+        None
+    } else if flags & TFD_TIMER_ABSTIME != 0 {
+        Some(value)
+    } else {
+        Some(current_time() + value)
+    };
+    let (remaining, old_interval) = timerfd.set_time(deadline, interval);
+    if !old_value.is_null() {
+        old_value.write(ITimerSpec {
+            it_interval: duration_to_time_secs(old_interval),
+            it_value: duration_to_time_secs(remaining),
+        })?;
+    }
+    Ok(0)
+}
+
+/// 功能:获取 timerfd 的定时器设置；
+/// # Arguments
+/// * `fd`: usize, timerfd 的文件描述符。
+/// * `curr_value`: *mut ITimerSpec, 用于保存距离下一次到期的时间与周期。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_timerfd_gettime(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let curr_value = UserPtr::<ITimerSpec>::new(args[1]);
+    let file = typed_file::<TimerFd>(fd)?;
+    let timerfd = file.as_any().downcast_ref::<TimerFd>().unwrap();
+    let (remaining, interval) = timerfd.get_time();
+    curr_value.write(ITimerSpec {
+        it_interval: duration_to_time_secs(interval),
+        it_value: duration_to_time_secs(remaining),
+    })?;
+    Ok(0)
+}
+
+/// 功能:创建一个 signalfd，或修改已有 signalfd 关心的信号集；
+/// # Arguments
+/// * `fd`: i32, 为 -1 时创建新的 signalfd，否则为要修改的 signalfd。
+/// * `mask`: *const usize, 关心的信号集，其中的 SIGKILL 与 SIGSTOP 会被忽略。
+/// * `sizemask`: usize, 信号集的大小，必须为 8。
+/// * `flags`: u32, 见 SignalFdFlags。
+/// 返回值:成功返回 signalfd 的文件描述符，失败返回-1。
+pub fn syscall_signalfd4(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0] as i32;
+    let mask = UserPtr::<usize>::new(args[1]);
+    let sizemask = args[2];
+    let Some(flags) = SignalFdFlags::from_bits(args[3] as u32) else {
+        return Err(SyscallError::EINVAL);
+    };
+    if sizemask != SIGSET_SIZE_IN_BYTE {
+        return Err(SyscallError::EINVAL);
+    }
+    let unblockable =
+        (1 << (SignalNo::SIGKILL as usize - 1)) | (1 << (SignalNo::SIGSTOP as usize - 1));
+    let mask = mask.read()? & !unblockable;
+    info!(
+        "[signalfd4()] fd: {}, mask: {:#x}, flags: {:?}",
+        fd, mask, flags
+    );
+    if fd != -1 {
+        let file = typed_file::<SignalFd>(fd as usize)?;
+        file.as_any()
+            .downcast_ref::<SignalFd>()
+            .unwrap()
+            .set_mask(mask);
+        return Ok(fd as isize);
+    }
+    let open_flags = if flags.contains(SignalFdFlags::SFD_NONBLOCK) {
+        OpenFlags::NON_BLOCK
+    } else {
+        OpenFlags::empty()
+    };
+    add_file(
+        Arc::new(SignalFd::new(mask, open_flags)),
+        flags.contains(SignalFdFlags::SFD_CLOEXEC),
+    )
+}
+
+/// 功能:创建一个 signalfd，或修改已有 signalfd 关心的信号集；
+/// # Arguments
+/// * `fd`: i32, 为 -1 时创建新的 signalfd，否则为要修改的 signalfd。
+/// * `mask`: *const usize, 关心的信号集。
+/// * `sizemask`: usize, 信号集的大小，必须为 8。
+/// 返回值:成功返回 signalfd 的文件描述符，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_signalfd(args: [usize; 6]) -> SyscallResult {
+    syscall_signalfd4([args[0], args[1], args[2], 0, 0, 0])
+}
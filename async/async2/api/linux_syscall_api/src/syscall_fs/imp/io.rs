@@ -1,54 +1,91 @@
 //! 负责与 IO 相关的系统调用
 extern crate alloc;
-use crate::{SyscallError, SyscallResult};
+use crate::{
+    IoVec, SyscallError, SyscallResult, SyscallReturn, IOV_MAX, MAX_RW_COUNT, RW_CHUNK_SIZE,
+    SEEK_CUR, SEEK_DATA, SEEK_END, SEEK_HOLE, SEEK_SET,
+};
 use alloc::string::ToString;
 use alloc::sync::Arc;
-use axerrno::AxError;
-use axfs::api::{FileIOType, OpenFlags};
+use alloc::vec;
+use alloc::vec::Vec;
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, OpenFlags, SeekFrom};
+use axhal::paging::MappingFlags;
+use axhal::time::current_time;
 
 use axlog::{debug, info};
-use axtask::current_task;
+use axsignal::signal_no::SignalNo;
 use axtask::link::{create_link, deal_with_path};
+use axtask::{
+    copy_from_user, current_task, poll_event_count, poll_wait_async, send_signal_to_thread, UserPtr,
+};
+use core::time::Duration;
 
 use crate::syscall_fs::ctype::{
     dir::new_dir,
     file::{new_fd, new_inode},
+    pipe::make_pipe,
 };
+/// 等待控制台输入时的最长挂起时间
+///
+/// 控制台的输入没有中断通知，需要定期重新检查
+const CONSOLE_RECHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 将读取文件时的错误转换为对应的错误码
+pub(crate) fn read_error(err: AxError) -> SyscallError {
+    match err {
+        AxError::WouldBlock => SyscallError::EAGAIN,
+        AxError::Interrupted => SyscallError::EINTR,
+        AxError::InvalidInput => SyscallError::EINVAL,
+        AxError::NotConnected => SyscallError::ENOTCONN,
+        AxError::ConnectionRefused => SyscallError::ECONNREFUSED,
+        // 用户缓冲区不可访问
+        AxError::BadAddress => SyscallError::EFAULT,
+        _ => SyscallError::EPERM,
+    }
+}
+
+/// 将写入文件时的错误转换为对应的错误码
+///
+/// 对端已经关闭时会向当前任务发送 SIGPIPE
+pub(crate) fn write_error(err: AxError) -> SyscallError {
+    match err {
+        // socket with send half closed, or pipe without readers
+        AxError::ConnectionReset => {
+            let _ = send_signal_to_thread(current_task().tid() as isize, SignalNo::SIGPIPE as isize);
+            SyscallError::EPIPE
+        }
+        AxError::WouldBlock => SyscallError::EAGAIN,
+        AxError::Interrupted => SyscallError::EINTR,
+        AxError::InvalidInput => SyscallError::EINVAL,
+        AxError::NotConnected => SyscallError::ENOTCONN,
+        AxError::ConnectionRefused => SyscallError::ECONNREFUSED,
+        // 用户缓冲区不可访问
+        AxError::BadAddress => SyscallError::EFAULT,
+        _ => SyscallError::EPERM,
+    }
+}
+
 /// 功能:从一个文件描述符中读取；
 /// # Arguments
 /// * `fd`: usize, 要读取文件的文件描述符。
 /// * `buf`: *mut u8, 一个缓存区,用于存放读取的内容。
 /// * `count`: usize, 要读取的字节数。
 /// 返回值:成功执行,返回读取的字节数。如为0,表示文件结束。错误,则返回-1。
-pub fn syscall_read(args: [usize; 6]) -> SyscallResult {
+pub fn syscall_read(args: [usize; 6]) -> SyscallReturn {
     let fd = args[0];
     let buf = args[1] as *mut u8;
     let count = args[2];
     info!("[read()] fd: {fd}, buf: {buf:?}, len: {count}",);
 
-    if buf.is_null() {
-        return Err(SyscallError::EFAULT);
-    }
-
-    let task = current_task();
-
-    // TODO: 左闭右开
-    let buf = match task.manual_alloc_range_for_lazy(
-        (buf as usize).into(),
-        (unsafe { buf.add(count) as usize } - 1).into(),
-    ) {
-        Ok(_) => unsafe { core::slice::from_raw_parts_mut(buf, count) },
-        Err(_) => return Err(SyscallError::EFAULT),
-    };
-
-    let file = match task.fd_manager.fd_table.lock().get(fd) {
+    let file = match current_task().fd_manager.fd_table.lock().get(fd) {
         Some(Some(f)) => f.clone(),
-        _ => return Err(SyscallError::EBADF),
+        _ => return SyscallError::EBADF.into(),
     };
 
     if file.get_type() == FileIOType::DirDesc {
         axlog::error!("fd is a dir");
-        return Err(SyscallError::EISDIR);
+        return SyscallError::EISDIR.into();
     }
 
     // for sockets:
@@ -58,12 +95,8 @@ pub fn syscall_read(args: [usize; 6]) -> SyscallResult {
     //   this will return Ok(0)
     // - ready to accept new connections
 
-    match file.read(buf) {
-        Ok(len) => Ok(len as isize),
-        Err(AxError::WouldBlock) => Err(SyscallError::EAGAIN),
-        Err(AxError::InvalidInput) => Err(SyscallError::EINVAL),
-        Err(_) => Err(SyscallError::EPERM),
-    }
+    let buf = UserPtr::from(buf);
+    read_blocking(file, move |file| read_to_user(file, buf, count, None))
 }
 
 /// 功能:从一个文件描述符中写入；
@@ -72,35 +105,19 @@ pub fn syscall_read(args: [usize; 6]) -> SyscallResult {
 /// * `buf`: *const u8, 一个缓存区,用于存放要写入的内容。
 /// * `count`: usize, 要写入的字节数。
 /// 返回值:成功执行,返回写入的字节数。错误,则返回-1。
-pub fn syscall_write(args: [usize; 6]) -> SyscallResult {
+pub fn syscall_write(args: [usize; 6]) -> SyscallReturn {
     let fd = args[0];
     let buf = args[1] as *const u8;
-    let count = args[2];
-    if buf.is_null() {
-        return Err(SyscallError::EFAULT);
-    }
+    let count = args[2].min(MAX_RW_COUNT);
 
-    let task = current_task();
-
-    // TODO: 左闭右开
-    let buf = match task.manual_alloc_range_for_lazy(
-        (buf as usize).into(),
-        (unsafe { buf.add(count) as usize } - 1).into(),
-    ) {
-        Ok(_) => unsafe { core::slice::from_raw_parts(buf, count) },
-        Err(_) => {
-            return Err(SyscallError::EFAULT);
-        }
-    };
-
-    let file = match task.fd_manager.fd_table.lock().get(fd) {
+    let file = match current_task().fd_manager.fd_table.lock().get(fd) {
         Some(Some(f)) => f.clone(),
-        _ => return Err(SyscallError::EBADF),
+        _ => return SyscallError::EBADF.into(),
     };
 
     if file.get_type() == FileIOType::DirDesc {
         debug!("fd is a dir");
-        return Err(SyscallError::EBADF);
+        return SyscallError::EBADF.into();
     }
 
     // for sockets:
@@ -109,15 +126,10 @@ pub fn syscall_write(args: [usize; 6]) -> SyscallResult {
     // - sent FIN packet, local send half is closed (this will return 0 immediately)
     //   this will return Err(ConnectionReset)
 
-    match file.write(buf) {
-        Ok(len) => Ok(len as isize),
-        // socket with send half closed
-        // TODO: send a SIGPIPE signal to the task
-        Err(axerrno::AxError::ConnectionReset) => Err(SyscallError::EPIPE),
-        Err(AxError::WouldBlock) => Err(SyscallError::EAGAIN),
-        Err(AxError::InvalidInput) => Err(SyscallError::EINVAL),
-        Err(_) => Err(SyscallError::EPERM),
-    }
+    let buf = UserPtr::<u8>::from(buf);
+    write_blocking(file, count, move |file, done| {
+        write_from_user(file, buf.add(done), count - done, None)
+    })
 }
 
 /// 功能:打开或创建一个文件；
@@ -158,6 +170,9 @@ pub fn syscall_openat(args: [usize; 6]) -> SyscallResult {
         if let Ok(dir) = new_dir(path.path().to_string(), flags.into()) {
             debug!("new dir_desc successfully allocated: {}", path.path());
             fd_table[fd_num] = Some(Arc::new(dir));
+            if OpenFlags::from(flags).is_close_on_exec() {
+                task.fd_manager.set_close_on_exec(fd_num, true);
+            }
             Ok(fd_num as isize)
         } else {
             debug!("open dir failed");
@@ -170,6 +185,9 @@ pub fn syscall_openat(args: [usize; 6]) -> SyscallResult {
         if let Ok(file) = new_fd(path.path().to_string(), flags.into()) {
             debug!("new file_desc successfully allocated");
             fd_table[fd_num] = Some(Arc::new(file));
+            if OpenFlags::from(flags).is_close_on_exec() {
+                task.fd_manager.set_close_on_exec(fd_num, true);
+            }
             let _ = create_link(&path, &path); // 不需要检查是否成功,因为如果成功,说明是新建的文件,如果失败,说明已经存在了
             Ok(fd_num as isize)
         } else {
@@ -191,15 +209,577 @@ pub fn syscall_close(args: [usize; 6]) -> SyscallResult {
     let mut fd_table = task.fd_manager.fd_table.lock();
     if fd >= fd_table.len() {
         debug!("fd {} is out of range", fd);
-        return Err(SyscallError::EPERM);
+        return Err(SyscallError::EBADF);
     }
 
     if fd_table[fd].is_none() {
         debug!("fd {} is none", fd);
-        return Err(SyscallError::EPERM);
+        return Err(SyscallError::EBADF);
     }
 
     fd_table[fd] = None;
+    task.fd_manager.set_close_on_exec(fd, false);
 
     Ok(0)
 }
+
+/// 功能:创建管道；
+/// # Arguments
+/// * `fd`: *mut u32, 用于保存2个文件描述符。其中，fd[0]为管道的读出端，fd[1]为管道的写入端。
+/// * `flags`: usize, 可以包含 O_CLOEXEC 与 O_NONBLOCK。
+/// 返回值:成功执行,返回0。失败,返回-1。
+pub fn syscall_pipe2(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0] as *mut u32;
+    let flags = OpenFlags::from_bits(args[1] as u32).ok_or(SyscallError::EINVAL)?;
+    info!("Into syscall_pipe2. fd: {:?}, flags: {:?}", fd, flags);
+    if !(flags - (OpenFlags::CLOEXEC | OpenFlags::NON_BLOCK)).is_empty() {
+        return Err(SyscallError::EINVAL);
+    }
+    let fd = UserPtr::<u32>::from(fd);
+    fd.check(2, MappingFlags::WRITE)?;
+    let task = current_task();
+    let (read_end, write_end) = make_pipe(flags);
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let read_fd = task
+        .alloc_fd(&mut fd_table)
+        .map_err(|_| SyscallError::EMFILE)?;
+    fd_table[read_fd] = Some(read_end);
+    let write_fd = match task.alloc_fd(&mut fd_table) {
+        Ok(write_fd) => write_fd,
+        Err(_) => {
+            fd_table[read_fd] = None;
+            return Err(SyscallError::EMFILE);
+        }
+    };
+    fd_table[write_fd] = Some(write_end);
+    if flags.is_close_on_exec() {
+        task.fd_manager.set_close_on_exec(read_fd, true);
+        task.fd_manager.set_close_on_exec(write_fd, true);
+    }
+    drop(fd_table);
+    debug!("[pipe2()] read_fd: {}, write_fd: {}", read_fd, write_fd);
+    fd.write_array(&[read_fd as u32, write_fd as u32])?;
+    Ok(0)
+}
+
+/// 功能:创建管道，等价于 flags 为 0 的 pipe2，供 x86_64 使用；
+/// # Arguments
+/// * `fd`: *mut u32, 用于保存2个文件描述符。
+/// 返回值:成功执行,返回0。失败,返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_pipe(args: [usize; 6]) -> SyscallResult {
+    syscall_pipe2([args[0], 0, 0, 0, 0, 0])
+}
+
+/// 获取用于读写的文件，目录不能被读写
+pub(crate) fn get_rw_file(fd: usize) -> Result<Arc<dyn FileIO>, SyscallError> {
+    let file = match current_task().fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    if file.get_type() == FileIOType::DirDesc {
+        return Err(SyscallError::EISDIR);
+    }
+    Ok(file)
+}
+
+/// 文件是否以阻塞方式打开
+pub(crate) fn is_blocking(file: &Arc<dyn FileIO>) -> bool {
+    !file.get_status().contains(OpenFlags::NON_BLOCK)
+}
+
+/// 文件是否为控制台，控制台的输入没有中断通知，等待时需要定期重新检查
+pub(crate) fn is_console(file: &Arc<dyn FileIO>) -> bool {
+    file.get_type() == FileIOType::Stdin
+}
+
+/// 挂起当前任务，直到文件的状态可能发生了变化，即 poll 的事件计数不再等于 seen
+///
+/// 管道、eventfd 等文件的状态变化通过 notify_pollers 唤醒等待者，console 为真时还会定期醒来重新检查。
+/// 收到信号时返回 EINTR
+async fn wait_ready(seen: usize, console: bool) -> Result<(), SyscallError> {
+    let recheck = console.then(|| current_time() + CONSOLE_RECHECK_INTERVAL);
+    match poll_wait_async(seen, recheck).await {
+        Err(AxError::Interrupted) => Err(SyscallError::EINTR),
+        _ => Ok(()),
+    }
+}
+
+/// 执行 op，op 返回 EAGAIN 且 block 为真时挂起等待，之后重新执行 op
+///
+/// 第一次尝试同步进行，只有需要等待时才返回挂起的系统调用。op 在每次重试时重新执行，
+/// 因此不能持有跨越等待的用户态数据
+pub(crate) fn retry_blocking<F>(block: bool, console: bool, mut op: F) -> SyscallReturn
+where
+    F: FnMut() -> SyscallResult + Send + 'static,
+{
+    let mut seen = poll_event_count();
+    match op() {
+        Err(SyscallError::EAGAIN) if block => {}
+        ret => return ret.into(),
+    }
+    SyscallReturn::pending(async move {
+        loop {
+            wait_ready(seen, console).await?;
+            seen = poll_event_count();
+            match op() {
+                Err(SyscallError::EAGAIN) => {}
+                ret => return ret,
+            }
+        }
+    })
+}
+
+/// 读取文件，文件以阻塞方式打开时等待直到读到数据，见 [`retry_blocking`]
+fn read_blocking<F>(file: Arc<dyn FileIO>, mut op: F) -> SyscallReturn
+where
+    F: FnMut(&Arc<dyn FileIO>) -> SyscallResult + Send + 'static,
+{
+    retry_blocking(is_blocking(&file), is_console(&file), move || op(&file))
+}
+
+/// 向文件写入 `len` 字节，文件以阻塞方式打开时挂起等待直到全部写入，返回写入的字节数
+///
+/// op 的参数为已经写入的字节数，返回本次写入的字节数，需要等待时返回 EAGAIN。
+/// 已经写入部分数据后出错或被信号打断时，返回已写入的字节数
+fn write_blocking<F>(file: Arc<dyn FileIO>, len: usize, mut op: F) -> SyscallReturn
+where
+    F: FnMut(&Arc<dyn FileIO>, usize) -> SyscallResult + Send + 'static,
+{
+    let block = is_blocking(&file);
+    let mut done = 0;
+    let mut seen = poll_event_count();
+    if let Some(ret) = write_step(&file, block, len, &mut done, &mut op) {
+        return ret.into();
+    }
+    SyscallReturn::pending(async move {
+        loop {
+            if let Err(err) = wait_ready(seen, is_console(&file)).await {
+                return if done > 0 {
+                    Ok(done as isize)
+                } else {
+                    Err(err)
+                };
+            }
+            seen = poll_event_count();
+            if let Some(ret) = write_step(&file, block, len, &mut done, &mut op) {
+                return ret;
+            }
+        }
+    })
+}
+
+/// 尽可能多地写入，需要等待时返回 None
+fn write_step(
+    file: &Arc<dyn FileIO>,
+    block: bool,
+    len: usize,
+    done: &mut usize,
+    op: &mut impl FnMut(&Arc<dyn FileIO>, usize) -> SyscallResult,
+) -> Option<SyscallResult> {
+    loop {
+        match op(file, *done) {
+            Ok(count) => {
+                *done += count as usize;
+                // 非阻塞的文件写入部分数据后即返回
+                if *done >= len || count == 0 || !block {
+                    return Some(Ok(*done as isize));
+                }
+            }
+            Err(SyscallError::EAGAIN) if block => return None,
+            Err(_) if *done > 0 => return Some(Ok(*done as isize)),
+            Err(err) => return Some(Err(err)),
+        }
+    }
+}
+
+/// 从文件读取至多 `len` 字节到用户缓冲区 `buf`，返回读取的字节数
+///
+/// `offset` 不为空时从该偏移处读取，不改变文件的读写位置。
+/// 单次最多读取 MAX_RW_COUNT 字节，数据经由固定大小的内核缓冲区分段拷贝。只有普通文件会连续读取多段，
+/// 其他文件读到一段数据后即返回，避免为了填满缓冲区而阻塞
+pub(crate) fn read_to_user(
+    file: &Arc<dyn FileIO>,
+    buf: UserPtr<u8>,
+    len: usize,
+    offset: Option<u64>,
+) -> SyscallResult {
+    let len = len.min(MAX_RW_COUNT);
+    let regular = file.get_type() == FileIOType::FileDesc;
+    let mut chunk = vec![0u8; len.min(RW_CHUNK_SIZE)];
+    let mut total = 0;
+    while total < len {
+        let size = (len - total).min(RW_CHUNK_SIZE);
+        let chunk_offset = offset.map(|offset| offset + total as u64);
+        let count = match read_chunk(file, buf.add(total), &mut chunk[..size], chunk_offset) {
+            Ok(count) => count,
+            // 已经读到数据时返回已读的长度，错误留给下一次调用
+            Err(_) if total > 0 => break,
+            Err(err) => return Err(read_error(err)),
+        };
+        total += count;
+        if count < size || !regular {
+            break;
+        }
+    }
+    Ok(total as isize)
+}
+
+/// 从文件读取一段数据到用户缓冲区 `dst`，返回读取的字节数
+///
+/// 读取之前先检查缓冲区，避免数据被读出后才发现无法写回用户态
+fn read_chunk(
+    file: &Arc<dyn FileIO>,
+    dst: UserPtr<u8>,
+    chunk: &mut [u8],
+    offset: Option<u64>,
+) -> AxResult<usize> {
+    dst.check(chunk.len(), MappingFlags::WRITE)?;
+    let count = match offset {
+        Some(offset) => file.read_at(offset, chunk)?,
+        None => file.read(chunk)?,
+    };
+    dst.write_array(&chunk[..count])?;
+    Ok(count)
+}
+
+/// 将用户缓冲区 `buf` 中的 `len` 字节写入文件，返回写入的字节数
+///
+/// `offset` 不为空时写入到该偏移处，不改变文件的读写位置。
+/// 单次最多写入 MAX_RW_COUNT 字节，数据经由固定大小的内核缓冲区分段拷贝，某一段没有完整写入时停止
+pub(crate) fn write_from_user(
+    file: &Arc<dyn FileIO>,
+    buf: UserPtr<u8>,
+    len: usize,
+    offset: Option<u64>,
+) -> SyscallResult {
+    let len = len.min(MAX_RW_COUNT);
+    let mut chunk = vec![0u8; len.min(RW_CHUNK_SIZE)];
+    let mut total = 0;
+    while total < len {
+        let size = (len - total).min(RW_CHUNK_SIZE);
+        let result =
+            copy_from_user(&mut chunk[..size], buf.add(total).addr()).and_then(|_| match offset {
+                Some(offset) => file.write_at(offset + total as u64, &chunk[..size]),
+                None => file.write(&chunk[..size]),
+            });
+        let count = match result {
+            Ok(count) => count,
+            // 已经写入数据时返回已写的长度，错误留给下一次调用
+            Err(_) if total > 0 => break,
+            Err(err) => return Err(write_error(err)),
+        };
+        total += count;
+        if count < size {
+            break;
+        }
+    }
+    Ok(total as isize)
+}
+
+/// 读取用户传入的 iovec 数组，检查其中每一段缓冲区的长度
+///
+/// 长度为 0 的缓冲区会被跳过，缓冲区的地址在读写时检查
+pub(crate) fn user_iovecs(iov: UserPtr<IoVec>, iovcnt: usize) -> Result<Vec<IoVec>, SyscallError> {
+    if iovcnt > IOV_MAX {
+        return Err(SyscallError::EINVAL);
+    }
+    let mut total: usize = 0;
+    let mut bufs = iov.read_array(iovcnt)?;
+    bufs.retain(|io_vec| io_vec.len != 0);
+    for io_vec in bufs.iter() {
+        // 总长度不能超过 isize 的范围
+        total = total
+            .checked_add(io_vec.len)
+            .filter(|&total| total <= isize::MAX as usize)
+            .ok_or(SyscallError::EINVAL)?;
+    }
+    Ok(bufs)
+}
+
+/// 依次读入各个缓冲区，读到的数据不足一个缓冲区时停止。`offset` 与 read_to_user 的相同
+fn read_vectored(file: &Arc<dyn FileIO>, bufs: Vec<IoVec>, offset: Option<u64>) -> SyscallResult {
+    let mut total = 0;
+    for buf in bufs {
+        // 总长度同样不超过 MAX_RW_COUNT
+        let len = buf.len.min(MAX_RW_COUNT - total as usize);
+        if len == 0 {
+            break;
+        }
+        let buf_offset = offset.map(|offset| offset + total as u64);
+        match read_to_user(file, buf.base.into(), len, buf_offset) {
+            Ok(count) => {
+                total += count;
+                if (count as usize) < len {
+                    break;
+                }
+            }
+            // 已经读到数据时返回已读的长度，错误留给下一次调用
+            Err(_) if total > 0 => break,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(total)
+}
+
+/// 跳过各个缓冲区中开头的 `count` 字节
+fn skip_iovecs(bufs: &mut Vec<IoVec>, mut count: usize) {
+    let skipped = bufs.iter().take_while(|buf| buf.len <= count).count();
+    count -= bufs[..skipped].iter().map(|buf| buf.len).sum::<usize>();
+    bufs.drain(..skipped);
+    if let Some(buf) = bufs.first_mut() {
+        buf.base = buf.base.wrapping_add(count);
+        buf.len -= count;
+    }
+}
+
+/// 依次写出各个缓冲区，至多写入 `limit` 字节，写入的数据不足一个缓冲区时停止。
+/// `offset` 与 write_from_user 的相同
+fn write_vectored(
+    file: &Arc<dyn FileIO>,
+    bufs: Vec<IoVec>,
+    limit: usize,
+    offset: Option<u64>,
+) -> SyscallResult {
+    let mut total = 0;
+    for buf in bufs {
+        let len = buf.len.min(limit - total as usize);
+        if len == 0 {
+            break;
+        }
+        let buf_offset = offset.map(|offset| offset + total as u64);
+        match write_from_user(file, buf.base.into(), len, buf_offset) {
+            Ok(count) => {
+                total += count;
+                if (count as usize) < len {
+                    break;
+                }
+            }
+            Err(_) if total > 0 => break,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(total)
+}
+
+/// 检查 pread/pwrite 等系统调用的文件与偏移，返回读写开始的偏移
+///
+/// 不可 seek 的文件（如管道）返回 ESPIPE，负的偏移返回 EINVAL
+fn positional_offset(file: &Arc<dyn FileIO>, offset: usize) -> Result<u64, SyscallError> {
+    file.seek(SeekFrom::Current(0))
+        .map_err(|_| SyscallError::ESPIPE)?;
+    if (offset as isize) < 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(offset as u64)
+}
+
+/// 功能:从一个文件描述符中读取到多个缓冲区；
+/// # Arguments
+/// * `fd`: usize, 要读取文件的文件描述符。
+/// * `iov`: *const IoVec, 缓冲区数组。
+/// * `iovcnt`: usize, 缓冲区的个数。
+/// 返回值:成功执行,返回读取的总字节数。失败,返回-1。
+pub fn syscall_readv(args: [usize; 6]) -> SyscallReturn {
+    let fd = args[0];
+    let iov = args[1] as *const IoVec;
+    let iovcnt = args[2];
+    info!("[readv()] fd: {}, iov: {:?}, iovcnt: {}", fd, iov, iovcnt);
+    let file = match get_rw_file(fd) {
+        Ok(file) => file,
+        Err(err) => return err.into(),
+    };
+    let iov = UserPtr::from(iov);
+    // IoVec 中含有裸指针，不能跨越等待持有，每次重试时重新读取
+    read_blocking(file, move |file| {
+        read_vectored(file, user_iovecs(iov, iovcnt)?, None)
+    })
+}
+
+/// 功能:将多个缓冲区的内容写入一个文件描述符；
+/// # Arguments
+/// * `fd`: usize, 要写入文件的文件描述符。
+/// * `iov`: *const IoVec, 缓冲区数组。
+/// * `iovcnt`: usize, 缓冲区的个数。
+/// 返回值:成功执行,返回写入的总字节数。失败,返回-1。
+pub fn syscall_writev(args: [usize; 6]) -> SyscallReturn {
+    let fd = args[0];
+    let iov = args[1] as *const IoVec;
+    let iovcnt = args[2];
+    info!("[writev()] fd: {}, iov: {:?}, iovcnt: {}", fd, iov, iovcnt);
+    let file = match get_rw_file(fd) {
+        Ok(file) => file,
+        Err(err) => return err.into(),
+    };
+    let iov = UserPtr::from(iov);
+    let len = match user_iovecs(iov, iovcnt) {
+        Ok(bufs) => bufs
+            .iter()
+            .map(|buf| buf.len)
+            .sum::<usize>()
+            .min(MAX_RW_COUNT),
+        Err(err) => return err.into(),
+    };
+    // 与 readv 相同，每次重试时重新读取 iovec，并跳过已经写入的部分
+    write_blocking(file, len, move |file, done| {
+        let mut bufs = user_iovecs(iov, iovcnt)?;
+        skip_iovecs(&mut bufs, done);
+        write_vectored(file, bufs, len - done, None)
+    })
+}
+
+/// 功能:从文件的指定偏移处读取，不改变文件的偏移；
+/// # Arguments
+/// * `fd`: usize, 要读取文件的文件描述符。
+/// * `buf`: *mut u8, 一个缓存区,用于存放读取的内容。
+/// * `count`: usize, 要读取的字节数。
+/// * `offset`: usize, 读取开始的偏移。
+/// 返回值:成功执行,返回读取的字节数。失败,返回-1。
+pub fn syscall_pread64(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let buf = args[1] as *mut u8;
+    let count = args[2];
+    let offset = args[3];
+    info!("[pread64()] fd: {}, len: {}, offset: {}", fd, count, offset);
+    let file = get_rw_file(fd)?;
+    let offset = positional_offset(&file, offset)?;
+    read_to_user(&file, buf.into(), count, Some(offset))
+}
+
+/// 功能:向文件的指定偏移处写入，不改变文件的偏移；
+/// # Arguments
+/// * `fd`: usize, 要写入文件的文件描述符。
+/// * `buf`: *const u8, 一个缓存区,用于存放要写入的内容。
+/// * `count`: usize, 要写入的字节数。
+/// * `offset`: usize, 写入开始的偏移。
+/// 返回值:成功执行,返回写入的字节数。失败,返回-1。
+pub fn syscall_pwrite64(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let buf = args[1] as *mut u8;
+    let count = args[2];
+    let offset = args[3];
+    info!("[pwrite64()] fd: {}, len: {}, offset: {}", fd, count, offset);
+    let file = get_rw_file(fd)?;
+    let offset = positional_offset(&file, offset)?;
+    write_from_user(&file, buf.into(), count, Some(offset))
+}
+
+/// 功能:从文件的指定偏移处读取到多个缓冲区，不改变文件的偏移；
+/// # Arguments
+/// * `fd`: usize, 要读取文件的文件描述符。
+/// * `iov`: *const IoVec, 缓冲区数组。
+/// * `iovcnt`: usize, 缓冲区的个数。
+/// * `offset`: usize, 读取开始的偏移，64 位架构上高位参数总是为 0。
+/// 返回值:成功执行,返回读取的总字节数。失败,返回-1。
+pub fn syscall_preadv(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let iov = args[1] as *const IoVec;
+    let iovcnt = args[2];
+    let offset = args[3];
+    info!("[preadv()] fd: {}, iovcnt: {}, offset: {}", fd, iovcnt, offset);
+    let file = get_rw_file(fd)?;
+    let offset = positional_offset(&file, offset)?;
+    let bufs = user_iovecs(iov.into(), iovcnt)?;
+    read_vectored(&file, bufs, Some(offset))
+}
+
+/// 功能:将多个缓冲区的内容写入文件的指定偏移处，不改变文件的偏移；
+/// # Arguments
+/// * `fd`: usize, 要写入文件的文件描述符。
+/// * `iov`: *const IoVec, 缓冲区数组。
+/// * `iovcnt`: usize, 缓冲区的个数。
+/// * `offset`: usize, 写入开始的偏移，64 位架构上高位参数总是为 0。
+/// 返回值:成功执行,返回写入的总字节数。失败,返回-1。
+pub fn syscall_pwritev(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let iov = args[1] as *const IoVec;
+    let iovcnt = args[2];
+    let offset = args[3];
+    info!("[pwritev()] fd: {}, iovcnt: {}, offset: {}", fd, iovcnt, offset);
+    let file = get_rw_file(fd)?;
+    let offset = positional_offset(&file, offset)?;
+    let bufs = user_iovecs(iov.into(), iovcnt)?;
+    write_vectored(&file, bufs, MAX_RW_COUNT, Some(offset))
+}
+
+/// 功能:带有 flags 的 preadv，offset 为 -1 时从当前偏移处读取并更新偏移；
+/// # Arguments
+/// * `fd`: usize, 要读取文件的文件描述符。
+/// * `iov`: *const IoVec, 缓冲区数组。
+/// * `iovcnt`: usize, 缓冲区的个数。
+/// * `offset`: usize, 读取开始的偏移。
+/// * `flags`: usize, RWF_* 选项，目前被忽略。
+/// 返回值:成功执行,返回读取的总字节数。失败,返回-1。
+pub fn syscall_preadv2(args: [usize; 6]) -> SyscallReturn {
+    if args[3] as isize == -1 {
+        return syscall_readv(args);
+    }
+    syscall_preadv(args).into()
+}
+
+/// 功能:带有 flags 的 pwritev，offset 为 -1 时从当前偏移处写入并更新偏移；
+/// # Arguments
+/// * `fd`: usize, 要写入文件的文件描述符。
+/// * `iov`: *const IoVec, 缓冲区数组。
+/// * `iovcnt`: usize, 缓冲区的个数。
+/// * `offset`: usize, 写入开始的偏移。
+/// * `flags`: usize, RWF_* 选项，目前被忽略。
+/// 返回值:成功执行,返回写入的总字节数。失败,返回-1。
+pub fn syscall_pwritev2(args: [usize; 6]) -> SyscallReturn {
+    if args[3] as isize == -1 {
+        return syscall_writev(args);
+    }
+    syscall_pwritev(args).into()
+}
+
+/// 功能:移动文件的读写偏移；
+/// # Arguments
+/// * `fd`: usize, 文件描述符。
+/// * `offset`: isize, 偏移量。
+/// * `whence`: usize, SEEK_SET、SEEK_CUR、SEEK_END、SEEK_DATA 或 SEEK_HOLE。
+/// 返回值:成功执行,返回新的偏移。失败,返回-1。
+///
+/// 说明:文件系统不支持空洞，因此整个文件都被视为数据区域，文件末尾是唯一的空洞
+pub fn syscall_lseek(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let offset = args[1] as isize;
+    let whence = args[2];
+    info!("[lseek()] fd: {}, offset: {}, whence: {}", fd, offset, whence);
+    let file = match current_task().fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    let pos = match whence {
+        SEEK_SET => {
+            if offset < 0 {
+                return Err(SyscallError::EINVAL);
+            }
+            SeekFrom::Start(offset as u64)
+        }
+        SEEK_CUR => SeekFrom::Current(offset as i64),
+        SEEK_END => SeekFrom::End(offset as i64),
+        SEEK_DATA | SEEK_HOLE => {
+            // 先确认文件可以 seek
+            file.seek(SeekFrom::Current(0))
+                .map_err(|_| SyscallError::ESPIPE)?;
+            let size = file
+                .get_stat()
+                .map_err(|_| SyscallError::EINVAL)?
+                .st_size;
+            if offset < 0 || offset as u64 >= size {
+                return Err(SyscallError::ENXIO);
+            }
+            if whence == SEEK_DATA {
+                SeekFrom::Start(offset as u64)
+            } else {
+                SeekFrom::Start(size)
+            }
+        }
+        _ => return Err(SyscallError::EINVAL),
+    };
+    match file.seek(pos) {
+        Ok(offset) => Ok(offset as isize),
+        Err(AxError::Unsupported) => Err(SyscallError::ESPIPE),
+        Err(_) => Err(SyscallError::EINVAL),
+    }
+}
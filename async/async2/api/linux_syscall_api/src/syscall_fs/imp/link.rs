@@ -0,0 +1,229 @@
+//! 负责与硬链接、符号链接以及删除文件相关的系统调用
+extern crate alloc;
+use crate::{SyscallError, SyscallResult, AT_REMOVEDIR};
+use axfs::api;
+use axlog::debug;
+use axtask::{current_task, UserPtr};
+use axtask::link::{
+    create_link, create_symlink, deal_with_path, deal_with_path_nofollow, read_symlink,
+    remove_link, remove_symlink, FilePath, AT_FDCWD,
+};
+
+/// 功能:创建文件的硬链接；
+/// # Arguments
+/// * `old_dirfd`: usize, 原文件所在目录的文件描述符。
+/// * `old_path`: *const u8, 原文件的路径。
+/// * `new_dirfd`: usize, 新链接所在目录的文件描述符。
+/// * `new_path`: *const u8, 新链接的路径。
+/// * `flags`: usize, 目前被忽略。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_linkat(args: [usize; 6]) -> SyscallResult {
+    let old_dirfd = args[0];
+    let old_path = args[1] as *const u8;
+    let new_dirfd = args[2];
+    let new_path = args[3] as *const u8;
+    let old_path = deal_with_path(old_dirfd, Some(old_path), false).ok_or(SyscallError::ENOENT)?;
+    let new_path =
+        deal_with_path_nofollow(new_dirfd, Some(new_path), false).ok_or(SyscallError::ENOENT)?;
+    debug!(
+        "Into syscall_linkat. old: {}, new: {}",
+        old_path.path(),
+        new_path.path()
+    );
+    if !api::path_exists(old_path.path()) {
+        return Err(SyscallError::ENOENT);
+    }
+    if api::is_dir(old_path.path()) {
+        return Err(SyscallError::EPERM);
+    }
+    if api::path_exists(new_path.path()) || read_symlink(&new_path).is_some() {
+        return Err(SyscallError::EEXIST);
+    }
+    // 原文件自身也算作一个链接，保证删除任意一个名字时链接数正确
+    create_link(&old_path, &old_path);
+    if !create_link(&new_path, &old_path) {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(0)
+}
+
+/// 功能:创建文件的硬链接，供 x86_64 的 link 使用；
+/// # Arguments
+/// * `old_path`: *const u8, 原文件的路径。
+/// * `new_path`: *const u8, 新链接的路径。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_link(args: [usize; 6]) -> SyscallResult {
+    syscall_linkat([AT_FDCWD, args[0], AT_FDCWD, args[1], 0, 0])
+}
+
+/// 删除一个文件（而非目录）的名字
+fn unlink_file(path: &FilePath) -> SyscallResult {
+    let name = path.path();
+    if remove_symlink(path).is_some() {
+        // 删除符号链接的占位文件
+        api::remove_file(name).map_err(SyscallError::from)?;
+        return Ok(0);
+    }
+    if remove_link(path).is_some() {
+        // 链接数降为零时 remove_link 已经删除了实际的文件
+        return Ok(0);
+    }
+    if !api::path_exists(name) {
+        return Err(SyscallError::ENOENT);
+    }
+    if api::is_dir(name) {
+        return Err(SyscallError::EISDIR);
+    }
+    api::remove_file(name).map_err(SyscallError::from)?;
+    Ok(0)
+}
+
+/// 删除一个空目录
+fn remove_dir(path: &FilePath) -> SyscallResult {
+    let name = path.path().trim_end_matches('/');
+    if !api::path_exists(name) {
+        return Err(SyscallError::ENOENT);
+    }
+    if !api::is_dir(name) {
+        return Err(SyscallError::ENOTDIR);
+    }
+    if path.is_root() {
+        return Err(SyscallError::EBUSY);
+    }
+    api::remove_dir(name).map_err(SyscallError::from)?;
+    Ok(0)
+}
+
+/// 功能:删除文件或目录；
+/// # Arguments
+/// * `dir_fd`: usize, 文件所在目录的文件描述符。
+/// * `path`: *const u8, 要删除的文件或目录的路径。
+/// * `flags`: usize, 为 AT_REMOVEDIR 时删除目录，否则删除文件。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_unlinkat(args: [usize; 6]) -> SyscallResult {
+    let dir_fd = args[0];
+    let path = args[1] as *const u8;
+    let flags = args[2];
+    if flags & !AT_REMOVEDIR != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let path = deal_with_path_nofollow(dir_fd, Some(path), false).ok_or(SyscallError::ENOENT)?;
+    debug!(
+        "Into syscall_unlinkat. path: {}, flags: {:#x}",
+        path.path(),
+        flags
+    );
+    if flags & AT_REMOVEDIR != 0 {
+        remove_dir(&path)
+    } else if path.path().ends_with('/') {
+        // 以 '/' 结尾的路径只能指向目录
+        if api::is_dir(path.path()) {
+            Err(SyscallError::EISDIR)
+        } else {
+            Err(SyscallError::ENOTDIR)
+        }
+    } else {
+        unlink_file(&path)
+    }
+}
+
+/// 功能:删除文件，供 x86_64 的 unlink 使用；
+/// # Arguments
+/// * `path`: *const u8, 要删除的文件的路径。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_unlink(args: [usize; 6]) -> SyscallResult {
+    syscall_unlinkat([AT_FDCWD, args[0], 0, 0, 0, 0])
+}
+
+/// 功能:删除空目录，供 x86_64 的 rmdir 使用；
+/// # Arguments
+/// * `path`: *const u8, 要删除的目录的路径。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_rmdir(args: [usize; 6]) -> SyscallResult {
+    syscall_unlinkat([AT_FDCWD, args[0], AT_REMOVEDIR, 0, 0, 0])
+}
+
+/// 功能:创建符号链接；
+/// # Arguments
+/// * `target`: *const u8, 符号链接的内容，不要求其指向的文件存在。
+/// * `new_dirfd`: usize, 符号链接所在目录的文件描述符。
+/// * `link_path`: *const u8, 符号链接的路径。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_symlinkat(args: [usize; 6]) -> SyscallResult {
+    let target = UserPtr::<u8>::new(args[0]).read_cstr()?;
+    let new_dirfd = args[1];
+    let link_path = args[2] as *const u8;
+    if target.is_empty() {
+        return Err(SyscallError::ENOENT);
+    }
+    let link_path =
+        deal_with_path_nofollow(new_dirfd, Some(link_path), false).ok_or(SyscallError::ENOENT)?;
+    debug!(
+        "Into syscall_symlinkat. target: {}, link: {}",
+        target,
+        link_path.path()
+    );
+    let link = link_path.path().trim_end_matches('/');
+    if api::path_exists(link) || read_symlink(&link_path).is_some() {
+        return Err(SyscallError::EEXIST);
+    }
+    create_symlink(&link_path, target.as_str()).map_err(SyscallError::from)?;
+    Ok(0)
+}
+
+/// 功能:创建符号链接，供 x86_64 的 symlink 使用；
+/// # Arguments
+/// * `target`: *const u8, 符号链接的内容。
+/// * `link_path`: *const u8, 符号链接的路径。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_symlink(args: [usize; 6]) -> SyscallResult {
+    syscall_symlinkat([args[0], AT_FDCWD, args[1], 0, 0, 0])
+}
+
+/// 功能:读取符号链接的内容；
+/// # Arguments
+/// * `dir_fd`: usize, 符号链接所在目录的文件描述符。
+/// * `path`: *const u8, 符号链接的路径。
+/// * `buf`: *mut u8, 接收内容的缓冲区，内容不以 '\0' 结尾。
+/// * `bufsiz`: usize, 缓冲区的大小，内容过长时会被截断。
+/// 返回值:成功返回写入缓冲区的字节数，失败返回-1。
+pub fn syscall_readlinkat(args: [usize; 6]) -> SyscallResult {
+    let dir_fd = args[0];
+    let path = args[1] as *const u8;
+    let buf = args[2] as *mut u8;
+    let bufsiz = args[3];
+    if bufsiz == 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let path = deal_with_path_nofollow(dir_fd, Some(path), false).ok_or(SyscallError::ENOENT)?;
+    debug!("Into syscall_readlinkat. path: {}", path.path());
+    let task = current_task();
+    let target = if path.path() == "/proc/self/exe" {
+        // 程序可以借此找到自身的可执行文件
+        task.get_file_path()
+    } else {
+        match read_symlink(&path) {
+            Some(target) => target,
+            None if api::path_exists(path.path()) => return Err(SyscallError::EINVAL),
+            None => return Err(SyscallError::ENOENT),
+        }
+    };
+    let len = target.len().min(bufsiz);
+    UserPtr::<u8>::from(buf).write_array(&target.as_bytes()[..len])?;
+    Ok(len as isize)
+}
+
+/// 功能:读取符号链接的内容，供 x86_64 的 readlink 使用；
+/// # Arguments
+/// * `path`: *const u8, 符号链接的路径。
+/// * `buf`: *mut u8, 接收内容的缓冲区。
+/// * `bufsiz`: usize, 缓冲区的大小。
+/// 返回值:成功返回写入缓冲区的字节数，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_readlink(args: [usize; 6]) -> SyscallResult {
+    syscall_readlinkat([AT_FDCWD, args[0], args[1], args[2], 0, 0])
+}
@@ -0,0 +1,51 @@
+//! 负责创建匿名内存文件的系统调用 memfd_create
+//!
+//! 封印通过 fcntl 的 F_ADD_SEALS 与 F_GET_SEALS 操作，见 syscall_fcntl64。
+extern crate alloc;
+use alloc::sync::Arc;
+
+use axfs::axfs_ramfs::SEAL_SEAL;
+use axlog::info;
+use axtask::{current_task, UserPtr};
+
+use crate::syscall_fs::ctype::memfd::MemFd;
+use crate::{MemfdFlags, SyscallError, SyscallResult, MFD_NAME_MAX_LEN};
+
+/// 功能:创建一个匿名的内存文件；
+/// # Arguments
+/// * `name`: *const u8, 文件名，只用于在 /proc/self/fd 中显示，不超过 249 字节。
+/// * `flags`: u32, 见 MemfdFlags。
+/// 返回值:成功返回文件描述符，失败返回-1。
+///
+/// 文件可以被读写、截断与映射。未指定 MFD_ALLOW_SEALING 时，文件创建时即带有 F_SEAL_SEAL，
+/// 无法再添加其他封印
+pub fn syscall_memfd_create(args: [usize; 6]) -> SyscallResult {
+    let name = args[0] as *const u8;
+    let Some(flags) = MemfdFlags::from_bits(args[1] as u32) else {
+        return Err(SyscallError::EINVAL);
+    };
+    if flags.contains(MemfdFlags::MFD_HUGETLB) {
+        return Err(SyscallError::EINVAL);
+    }
+    let name = UserPtr::<u8>::from(name).read_cstr()?;
+    info!("[memfd_create()] name: {}, flags: {:?}", name, flags);
+    if name.len() > MFD_NAME_MAX_LEN {
+        return Err(SyscallError::EINVAL);
+    }
+    let seals = if flags.contains(MemfdFlags::MFD_ALLOW_SEALING) {
+        0
+    } else {
+        SEAL_SEAL
+    };
+    let memfd = MemFd::new(&name, seals).map_err(|_| SyscallError::ENOMEM)?;
+
+    let task = current_task();
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let fd = task
+        .alloc_fd(&mut fd_table)
+        .map_err(|_| SyscallError::EMFILE)?;
+    fd_table[fd] = Some(Arc::new(memfd));
+    task.fd_manager
+        .set_close_on_exec(fd, flags.contains(MemfdFlags::MFD_CLOEXEC));
+    Ok(fd as isize)
+}
@@ -4,3 +4,26 @@ extern crate alloc;
 mod io;
 pub use io::*;
 
+mod stat;
+pub use stat::*;
+
+mod ctl;
+pub use ctl::*;
+
+mod link;
+pub use link::*;
+
+mod poll;
+pub use poll::*;
+
+mod event;
+pub use event::*;
+
+mod memfd;
+pub use memfd::*;
+
+mod mount;
+pub use mount::*;
+
+mod splice;
+pub use splice::*;
@@ -0,0 +1,125 @@
+//! 负责挂载与卸载文件系统的系统调用
+//!
+//! 挂载表保存在 axfs 中，/proc/mounts 的内容在每次读取时由挂载表生成。
+extern crate alloc;
+use alloc::string::{String, ToString};
+
+use axerrno::AxError;
+use axfs::api::{self, FileIOType};
+use axlog::info;
+use axtask::link::{deal_with_path, deal_with_path_nofollow, AT_FDCWD};
+use axtask::task::TID2TASK;
+use axtask::UserPtr;
+
+use crate::{MountFlags, SyscallError, SyscallResult, UmountFlags, MS_MGC_MSK};
+
+/// 将 axfs 返回的错误转换为挂载相关的错误码
+fn mount_error(err: AxError) -> SyscallError {
+    match err {
+        AxError::Unsupported => SyscallError::ENODEV,
+        _ => SyscallError::from(err),
+    }
+}
+
+/// 规范的绝对路径 `path` 是否为 `target` 或位于其下
+fn is_under(path: &str, target: &str) -> bool {
+    let path = api::canonicalize(path).unwrap_or_else(|_| path.to_string());
+    path.starts_with(target)
+        && (path.len() == target.len() || path[target.len()..].starts_with('/'))
+}
+
+/// 是否有任务的工作目录或打开的文件位于 `target` 下
+fn mount_point_in_use(target: &str) -> bool {
+    TID2TASK
+        .lock()
+        .values()
+        .filter(|task| !task.get_zombie())
+        .any(|task| {
+            if is_under(&task.get_cwd(), target) {
+                return true;
+            }
+            task.fd_manager
+                .fd_table
+                .lock()
+                .iter()
+                .flatten()
+                .any(|file| {
+                    matches!(file.get_type(), FileIOType::FileDesc | FileIOType::DirDesc)
+                        && is_under(&file.get_path(), target)
+                })
+        })
+}
+
+/// 功能:挂载文件系统；
+/// # Arguments
+/// * `source`: *const u8, 文件系统的来源。块设备上的文件系统为设备名，如 /dev/vdb，其余文件系统只用于显示，可以为空。
+/// * `target`: *const u8, 挂载点，必须是已经存在的目录。
+/// * `fstype`: *const u8, 文件系统类型，支持 tmpfs、ramfs、proc、devtmpfs、sysfs 以及块设备的文件系统类型。
+/// * `flags`: u32, 见 MountFlags。
+/// * `data`: *const u8, 文件系统的选项，被忽略。
+/// 返回值:成功返回0，失败返回-1。
+///
+/// 挂载点可以位于其他挂载的文件系统中，路径由最长匹配的挂载点解析。
+/// 同一个块设备可以被挂载多次，各挂载点共享同一个文件系统
+pub fn syscall_mount(args: [usize; 6]) -> SyscallResult {
+    let source = args[0] as *const u8;
+    let target = args[1] as *const u8;
+    let fstype = args[2] as *const u8;
+    let flags = MountFlags::from_bits_truncate(args[3] as u32 & !MS_MGC_MSK);
+    if flags.intersects(MountFlags::MS_REMOUNT | MountFlags::MS_BIND | MountFlags::MS_MOVE) {
+        return Err(SyscallError::EINVAL);
+    }
+    if fstype.is_null() {
+        return Err(SyscallError::EINVAL);
+    }
+    let fstype = UserPtr::<u8>::from(fstype).read_cstr()?;
+    let source = if source.is_null() {
+        String::from("none")
+    } else {
+        UserPtr::<u8>::from(source).read_cstr()?
+    };
+    let target = deal_with_path(AT_FDCWD, Some(target), false).ok_or(SyscallError::ENOENT)?;
+    info!(
+        "[mount()] source: {}, target: {}, fstype: {}, flags: {:?}",
+        source,
+        target.path(),
+        fstype,
+        flags
+    );
+    api::mount(&source, target.path(), &fstype).map_err(mount_error)?;
+    Ok(0)
+}
+
+/// 功能:卸载文件系统；
+/// # Arguments
+/// * `target`: *const u8, 挂载点。
+/// * `flags`: u32, 见 UmountFlags。
+/// 返回值:成功返回0，失败返回-1。
+///
+/// 有任务的工作目录或打开的文件位于挂载点下时返回 EBUSY，指定 MNT_DETACH 时不做该检查；
+/// 挂载点下还挂载有其他文件系统时总是返回 EBUSY
+pub fn syscall_umount2(args: [usize; 6]) -> SyscallResult {
+    let target = args[0] as *const u8;
+    let Some(flags) = UmountFlags::from_bits(args[1] as u32) else {
+        return Err(SyscallError::EINVAL);
+    };
+    if flags.contains(UmountFlags::MNT_EXPIRE) {
+        return Err(SyscallError::EINVAL);
+    }
+    let target = if flags.contains(UmountFlags::UMOUNT_NOFOLLOW) {
+        deal_with_path_nofollow(AT_FDCWD, Some(target), false)
+    } else {
+        deal_with_path(AT_FDCWD, Some(target), false)
+    }
+    .ok_or(SyscallError::ENOENT)?;
+    let target = api::canonicalize(target.path()).map_err(|_| SyscallError::EINVAL)?;
+    info!("[umount2()] target: {}, flags: {:?}", target, flags);
+    if !api::mount_table().iter().any(|info| info.target == target) {
+        return Err(SyscallError::EINVAL);
+    }
+    if !flags.contains(UmountFlags::MNT_DETACH) && mount_point_in_use(&target) {
+        return Err(SyscallError::EBUSY);
+    }
+    api::umount(&target).map_err(mount_error)?;
+    Ok(0)
+}
@@ -0,0 +1,473 @@
+//! 负责 I/O 多路复用的系统调用：ppoll、pselect6 与 epoll
+//!
+//! 没有文件就绪时，系统调用的 future 挂起当前任务，直到管道、socket 等文件的就绪状态发生变化、
+//! 超时或收到信号后再重新检查；挂起期间任务不占用内核栈。
+extern crate alloc;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use axerrno::AxError;
+use axfs::api::{FileIO, FileIOType, OpenFlags};
+use axhal::paging::MappingFlags;
+use axhal::time::current_time;
+use axlog::debug;
+use axtask::{current_task, poll_event_count, poll_wait_async, set_temporary_signal_mask, UserPtr};
+
+use crate::syscall_fs::ctype::epoll::{file_events, EpollFile};
+#[cfg(target_arch = "x86_64")]
+use crate::TimeVal;
+use crate::{
+    EpollCtl, EpollEvent, PollEvents, PollFd, SigSetWithSize, SyscallError, SyscallResult,
+    SyscallReturn, TimeSecs, EPOLL_CLOEXEC, FD_SETSIZE, SIGSET_SIZE_IN_BYTE,
+};
+
+/// 等待 socket 时的最长阻塞时间
+///
+/// socket 的状态还会因网卡收到报文而改变，这些变化无法通知到等待者，需要定期重新检查
+const SOCKET_RECHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 一次 epoll_pwait 最多返回的事件数
+const EPOLL_MAX_EVENTS: usize = 1024;
+
+/// 读取用户传入的 timespec 形式的超时时间，为空时表示一直等待
+fn read_timeout(timeout: UserPtr<TimeSecs>) -> Result<Option<Duration>, SyscallError> {
+    let Some(timeout) = timeout.read_opt()? else {
+        return Ok(None);
+    };
+    if timeout.tv_nsec >= 1_000_000_000 {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(Some(Duration::new(
+        timeout.tv_sec as u64,
+        timeout.tv_nsec as u32,
+    )))
+}
+
+/// 将距离截止时间的剩余时间写回用户传入的 timespec
+///
+/// 与 Linux 一致，写回失败时不影响系统调用的结果
+fn write_remaining(timeout: UserPtr<TimeSecs>, deadline: Option<Duration>) {
+    if let Some(deadline) = deadline {
+        let remaining = deadline.saturating_sub(current_time());
+        let _ = timeout.write_opt(TimeSecs {
+            tv_sec: remaining.as_secs() as usize,
+            tv_nsec: remaining.subsec_nanos() as usize,
+        });
+    }
+}
+
+/// 在等待期间使用用户给定的信号掩码，为空时不修改
+fn apply_sigmask(sigmask: UserPtr<usize>, sigsetsize: usize) -> Result<(), SyscallError> {
+    if sigmask.is_null() {
+        return Ok(());
+    }
+    if sigsetsize != SIGSET_SIZE_IN_BYTE {
+        return Err(SyscallError::EINVAL);
+    }
+    set_temporary_signal_mask(sigmask.read()?);
+    Ok(())
+}
+
+/// 反复检查文件，直到有文件就绪、超时或收到信号，两次检查之间挂起当前任务
+///
+/// # Arguments
+/// * `deadline`: 等待的截止时间，为空时一直等待
+/// * `check`: 检查一遍文件，参数为检查之前的事件计数，返回就绪的个数以及是否检查了 socket
+///
+/// 返回就绪的个数，超时返回 0，收到信号返回 EINTR
+async fn wait_for_events<F>(deadline: Option<Duration>, mut check: F) -> SyscallResult
+where
+    F: FnMut(usize) -> Result<(usize, bool), SyscallError>,
+{
+    loop {
+        #[cfg(feature = "net")]
+        axnet::poll_interfaces();
+        let count = poll_event_count();
+        let (ready, has_sockets) = check(count)?;
+        if ready > 0 {
+            return Ok(ready as isize);
+        }
+        let now = current_time();
+        if deadline.is_some_and(|deadline| now >= deadline) {
+            return Ok(0);
+        }
+        let wait_deadline = if has_sockets {
+            let recheck = now + SOCKET_RECHECK_INTERVAL;
+            Some(deadline.map_or(recheck, |deadline| deadline.min(recheck)))
+        } else {
+            deadline
+        };
+        if let Err(AxError::Interrupted) = poll_wait_async(count, wait_deadline).await {
+            return Err(SyscallError::EINTR);
+        }
+    }
+}
+
+/// ppoll 与 poll 的共同部分
+async fn do_poll(fds: UserPtr<PollFd>, nfds: usize, deadline: Option<Duration>) -> SyscallResult {
+    if nfds > current_task().fd_manager.get_limit() as usize {
+        return Err(SyscallError::EINVAL);
+    }
+    fds.check(nfds, MappingFlags::WRITE)?;
+    let mut poll_fds = fds.read_array(nfds)?;
+    let ans = wait_for_events(deadline, |_| {
+        // 当前任务的引用不能跨越 await 持有，每次检查时重新获取
+        let task = current_task();
+        let fd_table = task.fd_manager.fd_table.lock();
+        let mut ready = 0;
+        let mut has_sockets = false;
+        for poll_fd in poll_fds.iter_mut() {
+            poll_fd.revents = 0;
+            if poll_fd.fd < 0 {
+                continue;
+            }
+            let revents = match fd_table.get(poll_fd.fd as usize) {
+                Some(Some(file)) => {
+                    has_sockets |= file.get_type() == FileIOType::Socket;
+                    let wanted = PollEvents::from_bits_truncate(poll_fd.events)
+                        | PollEvents::POLLERR
+                        | PollEvents::POLLHUP;
+                    file_events(file.as_ref()) & wanted
+                }
+                _ => PollEvents::POLLNVAL,
+            };
+            if !revents.is_empty() {
+                poll_fd.revents = revents.bits();
+                ready += 1;
+            }
+        }
+        Ok((ready, has_sockets))
+    })
+    .await?;
+    fds.write_array(&poll_fds)?;
+    Ok(ans)
+}
+
+/// 功能:等待一组文件描述符上的事件；
+/// # Arguments
+/// * `fds`: *mut PollFd, 要等待的文件描述符与事件，返回时写入已发生的事件。
+/// * `nfds`: usize, fds 的个数。
+/// * `timeout`: *mut TimeSecs, 最长等待时间，为空时一直等待，返回时写入剩余的时间。
+/// * `sigmask`: *const usize, 等待期间使用的信号掩码，为空时不修改。
+/// * `sigsetsize`: usize, 信号掩码的字节数。
+/// 返回值:成功返回就绪的文件描述符个数，超时返回0，失败返回-1。
+pub fn syscall_ppoll(args: [usize; 6]) -> SyscallReturn {
+    let fds = UserPtr::<PollFd>::new(args[0]);
+    let nfds = args[1];
+    let timeout = UserPtr::<TimeSecs>::new(args[2]);
+    let sigmask = UserPtr::<usize>::new(args[3]);
+    let sigsetsize = args[4];
+    debug!("Into syscall_ppoll. nfds: {}", nfds);
+    SyscallReturn::pending(async move {
+        let deadline = read_timeout(timeout)?.map(|timeout| current_time() + timeout);
+        apply_sigmask(sigmask, sigsetsize)?;
+        let ans = do_poll(fds, nfds, deadline).await;
+        write_remaining(timeout, deadline);
+        ans
+    })
+}
+
+/// 功能:等待一组文件描述符上的事件；
+/// # Arguments
+/// * `fds`: *mut PollFd, 要等待的文件描述符与事件，返回时写入已发生的事件。
+/// * `nfds`: usize, fds 的个数。
+/// * `timeout`: i32, 最长等待的毫秒数，为负数时一直等待。
+/// 返回值:成功返回就绪的文件描述符个数，超时返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_poll(args: [usize; 6]) -> SyscallReturn {
+    let fds = UserPtr::<PollFd>::new(args[0]);
+    let nfds = args[1];
+    let timeout = args[2] as i32;
+    let deadline = (timeout >= 0).then(|| current_time() + Duration::from_millis(timeout as u64));
+    SyscallReturn::pending(do_poll(fds, nfds, deadline))
+}
+
+/// 从用户态读取 fd_set，为空时视为空集
+fn read_fd_set(set: UserPtr<usize>, words: usize) -> Result<Vec<usize>, SyscallError> {
+    if set.is_null() {
+        return Ok(vec![0; words]);
+    }
+    Ok(set.read_array(words)?)
+}
+
+/// 将结果写回用户态的 fd_set，为空时忽略
+fn write_fd_set(set: UserPtr<usize>, bits: &[usize]) -> Result<(), SyscallError> {
+    if !set.is_null() {
+        set.write_array(bits)?;
+    }
+    Ok(())
+}
+
+/// pselect6 与 select 的共同部分
+async fn do_select(
+    nfds: usize,
+    read_set: UserPtr<usize>,
+    write_set: UserPtr<usize>,
+    except_set: UserPtr<usize>,
+    deadline: Option<Duration>,
+) -> SyscallResult {
+    if nfds > FD_SETSIZE {
+        return Err(SyscallError::EINVAL);
+    }
+    const BITS: usize = usize::BITS as usize;
+    let words = (nfds + BITS - 1) / BITS;
+    let wanted = [
+        read_fd_set(read_set, words)?,
+        read_fd_set(write_set, words)?,
+        read_fd_set(except_set, words)?,
+    ];
+    // 分别对应可读、可写与异常的条件
+    let conditions = [
+        PollEvents::POLLIN | PollEvents::POLLHUP | PollEvents::POLLERR,
+        PollEvents::POLLOUT | PollEvents::POLLERR,
+        PollEvents::POLLPRI,
+    ];
+    let mut result = [vec![0; words], vec![0; words], vec![0; words]];
+    let ans = wait_for_events(deadline, |_| {
+        let task = current_task();
+        let fd_table = task.fd_manager.fd_table.lock();
+        let mut ready = 0;
+        let mut has_sockets = false;
+        for fd in 0..nfds {
+            let (word, bit) = (fd / BITS, 1 << (fd % BITS));
+            if wanted.iter().all(|set| set[word] & bit == 0) {
+                continue;
+            }
+            let Some(Some(file)) = fd_table.get(fd) else {
+                return Err(SyscallError::EBADF);
+            };
+            has_sockets |= file.get_type() == FileIOType::Socket;
+            let events = file_events(file.as_ref());
+            for index in 0..3 {
+                if wanted[index][word] & bit != 0 && events.intersects(conditions[index]) {
+                    result[index][word] |= bit;
+                    ready += 1;
+                }
+            }
+        }
+        Ok((ready, has_sockets))
+    })
+    .await?;
+    write_fd_set(read_set, &result[0])?;
+    write_fd_set(write_set, &result[1])?;
+    write_fd_set(except_set, &result[2])?;
+    Ok(ans)
+}
+
+/// 功能:等待一组文件描述符变为可读、可写或出现异常；
+/// # Arguments
+/// * `nfds`: usize, 最大的文件描述符加一，不能超过 FD_SETSIZE。
+/// * `readfds`: *mut usize, 等待可读的文件描述符集合，返回时只保留就绪的文件描述符，可以为空。
+/// * `writefds`: *mut usize, 等待可写的文件描述符集合，可以为空。
+/// * `exceptfds`: *mut usize, 等待异常的文件描述符集合，可以为空。
+/// * `timeout`: *mut TimeSecs, 最长等待时间，为空时一直等待，返回时写入剩余的时间。
+/// * `sigmask`: *const SigSetWithSize, 等待期间使用的信号掩码及其大小，可以为空。
+/// 返回值:成功返回就绪的文件描述符个数，超时返回0，失败返回-1。
+pub fn syscall_pselect6(args: [usize; 6]) -> SyscallReturn {
+    let nfds = args[0];
+    let read_set = UserPtr::<usize>::new(args[1]);
+    let write_set = UserPtr::<usize>::new(args[2]);
+    let except_set = UserPtr::<usize>::new(args[3]);
+    let timeout = UserPtr::<TimeSecs>::new(args[4]);
+    let sigmask = UserPtr::<SigSetWithSize>::new(args[5]);
+    debug!("Into syscall_pselect6. nfds: {}", nfds);
+    SyscallReturn::pending(async move {
+        let deadline = read_timeout(timeout)?.map(|timeout| current_time() + timeout);
+        if let Some(sigmask) = sigmask.read_opt()? {
+            apply_sigmask(sigmask.sigset.into(), sigmask.size)?;
+        }
+        let ans = do_select(nfds, read_set, write_set, except_set, deadline).await;
+        write_remaining(timeout, deadline);
+        ans
+    })
+}
+
+/// 功能:等待一组文件描述符变为可读、可写或出现异常；
+/// # Arguments
+/// * `nfds`: usize, 最大的文件描述符加一，不能超过 FD_SETSIZE。
+/// * `readfds`: *mut usize, 等待可读的文件描述符集合，可以为空。
+/// * `writefds`: *mut usize, 等待可写的文件描述符集合，可以为空。
+/// * `exceptfds`: *mut usize, 等待异常的文件描述符集合，可以为空。
+/// * `timeout`: *mut TimeVal, 最长等待时间，为空时一直等待，返回时写入剩余的时间。
+/// 返回值:成功返回就绪的文件描述符个数，超时返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_select(args: [usize; 6]) -> SyscallReturn {
+    let timeout = UserPtr::<TimeVal>::new(args[4]);
+    SyscallReturn::pending(async move {
+        let deadline = match timeout.read_opt()? {
+            None => None,
+            Some(timeout) if timeout.usec >= 1_000_000 => return Err(SyscallError::EINVAL),
+            Some(timeout) => {
+                Some(current_time() + Duration::from_nanos(timeout.turn_to_nanos() as u64))
+            }
+        };
+        let ans = do_select(
+            args[0],
+            UserPtr::new(args[1]),
+            UserPtr::new(args[2]),
+            UserPtr::new(args[3]),
+            deadline,
+        )
+        .await;
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_sub(current_time());
+            let _ = timeout.write(TimeVal::from_micro(remaining.as_micros() as usize));
+        }
+        ans
+    })
+}
+
+/// 获取文件描述符对应的 epoll 实例，不是 epoll 实例时返回 EINVAL
+fn epoll_file(epfd: usize) -> Result<Arc<dyn FileIO>, SyscallError> {
+    let task = current_task();
+    let fd_table = task.fd_manager.fd_table.lock();
+    let Some(Some(file)) = fd_table.get(epfd) else {
+        return Err(SyscallError::EBADF);
+    };
+    if file.as_any().downcast_ref::<EpollFile>().is_none() {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(file.clone())
+}
+
+/// 功能:创建一个 epoll 实例；
+/// # Arguments
+/// * `flags`: usize, 只能为 0 或 EPOLL_CLOEXEC。
+/// 返回值:成功返回 epoll 实例的文件描述符，失败返回-1。
+pub fn syscall_epoll_create1(args: [usize; 6]) -> SyscallResult {
+    let flags = args[0];
+    if flags & !EPOLL_CLOEXEC != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let close_on_exec = flags & EPOLL_CLOEXEC != 0;
+    let open_flags = if close_on_exec {
+        OpenFlags::CLOEXEC
+    } else {
+        OpenFlags::empty()
+    };
+    let task = current_task();
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let fd = task
+        .alloc_fd(&mut fd_table)
+        .map_err(|_| SyscallError::EMFILE)?;
+    fd_table[fd] = Some(Arc::new(EpollFile::new(open_flags)));
+    task.fd_manager.set_close_on_exec(fd, close_on_exec);
+    Ok(fd as isize)
+}
+
+/// 功能:创建一个 epoll 实例；
+/// # Arguments
+/// * `size`: i32, 只需要大于 0，没有其他作用。
+/// 返回值:成功返回 epoll 实例的文件描述符，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_epoll_create(args: [usize; 6]) -> SyscallResult {
+    if args[0] as i32 <= 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    syscall_epoll_create1([0; 6])
+}
+
+/// 功能:在 epoll 实例中添加、修改或删除被监听的文件描述符；
+/// # Arguments
+/// * `epfd`: usize, epoll 实例的文件描述符。
+/// * `op`: usize, 操作的种类，见 EpollCtl。
+/// * `fd`: usize, 被监听的文件描述符，不能是普通文件或目录。
+/// * `event`: *const EpollEvent, 关心的事件与用户数据，删除时可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_epoll_ctl(args: [usize; 6]) -> SyscallResult {
+    let epfd = args[0];
+    let fd = args[2];
+    let event = UserPtr::<EpollEvent>::new(args[3]);
+    debug!(
+        "Into syscall_epoll_ctl. epfd: {}, op: {}, fd: {}",
+        epfd, args[1], fd
+    );
+    let epoll = epoll_file(epfd)?;
+    let file = match current_task().fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    if Arc::ptr_eq(&epoll, &file) {
+        return Err(SyscallError::EINVAL);
+    }
+    // 普通文件总是就绪的，Linux 不允许监听它们
+    if matches!(file.get_type(), FileIOType::FileDesc | FileIOType::DirDesc) {
+        return Err(SyscallError::EPERM);
+    }
+    let op = EpollCtl::try_from(args[1]).map_err(|_| SyscallError::EINVAL)?;
+    let epoll = epoll.as_any().downcast_ref::<EpollFile>().unwrap();
+    let result = match op {
+        EpollCtl::DEL => epoll.delete(fd),
+        _ => {
+            let event = event.read()?;
+            if op == EpollCtl::ADD {
+                epoll.add(fd, &file, event)
+            } else {
+                epoll.modify(fd, event)
+            }
+        }
+    };
+    result.map_err(|err| match err {
+        AxError::AlreadyExists => SyscallError::EEXIST,
+        _ => SyscallError::ENOENT,
+    })?;
+    Ok(0)
+}
+
+/// 功能:等待 epoll 实例中被监听的文件上的事件；
+/// # Arguments
+/// * `epfd`: usize, epoll 实例的文件描述符。
+/// * `events`: *mut EpollEvent, 用于保存发生的事件。
+/// * `maxevents`: i32, events 的容量，必须大于 0。
+/// * `timeout`: i32, 最长等待的毫秒数，为负数时一直等待。
+/// * `sigmask`: *const usize, 等待期间使用的信号掩码，为空时不修改。
+/// * `sigsetsize`: usize, 信号掩码的字节数。
+/// 返回值:成功返回发生的事件个数，超时返回0，失败返回-1。
+pub fn syscall_epoll_pwait(args: [usize; 6]) -> SyscallReturn {
+    let epfd = args[0];
+    let events = UserPtr::<EpollEvent>::new(args[1]);
+    let maxevents = args[2] as i32;
+    let timeout = args[3] as i32;
+    let sigmask = UserPtr::<usize>::new(args[4]);
+    let sigsetsize = args[5];
+    debug!(
+        "Into syscall_epoll_pwait. epfd: {}, maxevents: {}, timeout: {}",
+        epfd, maxevents, timeout
+    );
+    SyscallReturn::pending(async move {
+        if maxevents <= 0 || maxevents as usize > EPOLL_MAX_EVENTS {
+            return Err(SyscallError::EINVAL);
+        }
+        let maxevents = maxevents as usize;
+        events.check(maxevents, MappingFlags::WRITE)?;
+        let file = epoll_file(epfd)?;
+        let epoll = file.as_any().downcast_ref::<EpollFile>().unwrap();
+        let deadline =
+            (timeout >= 0).then(|| current_time() + Duration::from_millis(timeout as u64));
+        apply_sigmask(sigmask, sigsetsize)?;
+        let mut ready_events = vec![EpollEvent { events: 0, data: 0 }; maxevents];
+        let ans = wait_for_events(deadline, |count| {
+            Ok((
+                epoll.poll_events(&mut ready_events, count),
+                epoll.has_sockets(),
+            ))
+        })
+        .await?;
+        events.write_array(&ready_events[..ans as usize])?;
+        Ok(ans)
+    })
+}
+
+/// 功能:等待 epoll 实例中被监听的文件上的事件；
+/// # Arguments
+/// * `epfd`: usize, epoll 实例的文件描述符。
+/// * `events`: *mut EpollEvent, 用于保存发生的事件。
+/// * `maxevents`: i32, events 的容量，必须大于 0。
+/// * `timeout`: i32, 最长等待的毫秒数，为负数时一直等待。
+/// 返回值:成功返回发生的事件个数，超时返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_epoll_wait(args: [usize; 6]) -> SyscallReturn {
+    syscall_epoll_pwait([args[0], args[1], args[2], args[3], 0, 0])
+}
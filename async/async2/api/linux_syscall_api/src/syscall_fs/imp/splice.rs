@@ -0,0 +1,465 @@
+//! 负责在文件之间直接传输数据的系统调用：sendfile64、copy_file_range、splice 与 tee
+//!
+//! 数据一般通过内核中的缓冲区逐块读出再写入；两端都是同一文件系统上的普通文件且文件系统支持时，
+//! 由文件系统直接复制，不经过缓冲区。
+extern crate alloc;
+use alloc::sync::Arc;
+use alloc::vec;
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, OpenFlags, SeekFrom};
+use axlog::info;
+use axtask::UserPtr;
+
+use super::io::{get_rw_file, is_blocking, is_console, read_error, retry_blocking, write_error};
+use crate::syscall_fs::ctype::pipe::Pipe;
+use crate::syscall_fs::FileDesc;
+use crate::{SpliceFlags, SyscallError, SyscallResult, SyscallReturn, MAX_RW_COUNT};
+
+/// 通过缓冲区传输时每次读写的最大字节数
+const TRANSFER_CHUNK: usize = 0x10000;
+
+/// 传输的两端：读取的文件及其起始偏移，写入的文件及其起始偏移
+type TransferEnds = (Arc<dyn FileIO>, Option<u64>, Arc<dyn FileIO>, Option<u64>);
+
+/// 读取用户传入的 loff_t 偏移，指针为空时返回 None
+fn read_user_offset(ptr: UserPtr<i64>) -> Result<Option<u64>, SyscallError> {
+    let Some(offset) = ptr.read_opt()? else {
+        return Ok(None);
+    };
+    if offset < 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(Some(offset as u64))
+}
+
+/// 将更新后的偏移写回用户传入的指针
+fn write_user_offset(ptr: UserPtr<i64>, offset: Option<u64>) -> Result<(), SyscallError> {
+    if let Some(offset) = offset {
+        ptr.write(offset as i64)?;
+    }
+    Ok(())
+}
+
+/// 检查文件是否可以定位，管道与 socket 等不可定位的文件返回 ESPIPE
+fn check_seekable(file: &Arc<dyn FileIO>) -> Result<(), SyscallError> {
+    file.seek(SeekFrom::Current(0))
+        .map(|_| ())
+        .map_err(|_| SyscallError::ESPIPE)
+}
+
+/// 在给定的偏移处读取文件，成功后更新偏移且不改变文件的读写位置；偏移为空时在文件的读写位置处读取
+fn read_at(file: &Arc<dyn FileIO>, offset: Option<&mut u64>, buf: &mut [u8]) -> AxResult<usize> {
+    let Some(offset) = offset else {
+        return file.read(buf);
+    };
+    let count = file.read_at(*offset, buf)?;
+    *offset += count as u64;
+    Ok(count)
+}
+
+/// 在给定的偏移处写入文件，偏移的处理与 read_at 相同
+fn write_at(file: &Arc<dyn FileIO>, offset: Option<&mut u64>, buf: &[u8]) -> AxResult<usize> {
+    let Some(offset) = offset else {
+        return file.write(buf);
+    };
+    let count = file.write_at(*offset, buf)?;
+    *offset += count as u64;
+    Ok(count)
+}
+
+/// 两端都是普通文件时尝试由文件系统直接复制
+///
+/// 不是普通文件或文件系统不支持在两个文件之间直接复制时返回 None，由调用者改为通过缓冲区传输
+fn copy_in_fs(
+    src: &Arc<dyn FileIO>,
+    src_off: Option<u64>,
+    dst: &Arc<dyn FileIO>,
+    dst_off: Option<u64>,
+    len: usize,
+) -> Option<AxResult<usize>> {
+    let src_desc = src.as_any().downcast_ref::<FileDesc>()?;
+    let dst_desc = dst.as_any().downcast_ref::<FileDesc>()?;
+    let src_pos = match src_off {
+        Some(offset) => offset,
+        None => src.seek(SeekFrom::Current(0)).ok()?,
+    };
+    let dst_pos = match dst_off {
+        Some(offset) => offset,
+        None => dst.seek(SeekFrom::Current(0)).ok()?,
+    };
+    // 先复制出文件对象再复制数据，两端为同一个文件时不会重复加锁
+    let src_file = src_desc.file.lock().clone();
+    let dst_file = dst_desc.file.lock().clone();
+    match src_file.copy_range_to(src_pos, &dst_file, dst_pos, len) {
+        Err(AxError::Unsupported) => None,
+        Err(err) => Some(Err(err)),
+        Ok(count) => {
+            if src_off.is_none() {
+                let _ = src.seek(SeekFrom::Start(src_pos + count as u64));
+            }
+            if dst_off.is_none() {
+                let _ = dst.seek(SeekFrom::Start(dst_pos + count as u64));
+            }
+            Some(Ok(count))
+        }
+    }
+}
+
+/// 从 `src` 向 `dst` 传输至多 `len` 字节，返回传输的字节数
+///
+/// 偏移不为空时从该偏移处读写并更新它，否则使用并更新文件的读写位置。
+/// 已经传输了部分数据后，源文件没有就绪的数据或读写出错时返回已传输的字节数；
+/// 还没有传输数据时需要等待则返回 EAGAIN，由调用者决定是否挂起等待后重试
+fn transfer(
+    src: &Arc<dyn FileIO>,
+    mut src_off: Option<&mut u64>,
+    dst: &Arc<dyn FileIO>,
+    mut dst_off: Option<&mut u64>,
+    len: usize,
+) -> SyscallResult {
+    if len == 0 {
+        return Ok(0);
+    }
+    if let Some(ret) = copy_in_fs(
+        src,
+        src_off.as_deref().copied(),
+        dst,
+        dst_off.as_deref().copied(),
+        len,
+    ) {
+        let count = ret.map_err(SyscallError::from)?;
+        if let Some(offset) = src_off {
+            *offset += count as u64;
+        }
+        if let Some(offset) = dst_off {
+            *offset += count as u64;
+        }
+        return Ok(count as isize);
+    }
+
+    let dst_pipe = dst.as_any().downcast_ref::<Pipe>();
+    let mut buf = vec![0u8; len.min(TRANSFER_CHUNK)];
+    let mut total = 0;
+    while total < len {
+        if total > 0 && !src.ready_to_read() {
+            break;
+        }
+        // 写入管道时只读出能够立即写入的数据，避免读出的数据因为管道已满而无法写回
+        let space = dst_pipe.and_then(Pipe::write_space).unwrap_or(usize::MAX);
+        if space == 0 {
+            if total > 0 {
+                break;
+            }
+            return Err(SyscallError::EAGAIN);
+        }
+        let chunk = &mut buf[..(len - total).min(TRANSFER_CHUNK).min(space)];
+        let read = match read_at(src, src_off.as_deref_mut(), chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(_) if total > 0 => break,
+            Err(err) => return Err(read_error(err)),
+        };
+        let mut written = 0;
+        let mut error = None;
+        while written < read {
+            match write_at(dst, dst_off.as_deref_mut(), &chunk[written..read]) {
+                Ok(0) => break,
+                Ok(count) => written += count,
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            }
+        }
+        total += written;
+        if written < read {
+            // 把没有写出的数据退回源文件，源文件不可定位时这部分数据会丢失
+            let unwritten = read - written;
+            match src_off.as_deref_mut() {
+                Some(offset) => *offset -= unwritten as u64,
+                None => {
+                    let _ = src.seek(SeekFrom::Current(-(unwritten as i64)));
+                }
+            }
+            match error {
+                Some(err) if total == 0 => return Err(write_error(err)),
+                _ => break,
+            }
+        }
+    }
+    Ok(total as isize)
+}
+
+/// 功能:在两个文件描述符之间传输数据；
+/// # Arguments
+/// * `out_fd`: usize, 写入的文件描述符，不能以 O_APPEND 打开。
+/// * `in_fd`: usize, 读取的文件描述符。
+/// * `offset`: *mut i64, 读取的起始偏移。不为空时从该偏移读取并更新它，不改变 in_fd 的读写位置；
+///   为空时从 in_fd 的读写位置读取并更新读写位置。
+/// * `count`: usize, 最多传输的字节数。
+/// 返回值:成功返回传输的字节数，失败返回-1。
+pub fn syscall_sendfile64(args: [usize; 6]) -> SyscallReturn {
+    let out_fd = args[0];
+    let in_fd = args[1];
+    let offset = UserPtr::<i64>::new(args[2]);
+    let count = args[3].min(MAX_RW_COUNT);
+    info!(
+        "[sendfile64()] out_fd: {}, in_fd: {}, offset: {:?}, count: {}",
+        out_fd, in_fd, offset, count
+    );
+    let (input, mut in_offset, output, _) = match sendfile_ends(in_fd, out_fd, offset) {
+        Ok(args) => args,
+        Err(err) => return err.into(),
+    };
+    // 两端都以阻塞方式打开时等待数据或空位
+    let block = is_blocking(&input) && is_blocking(&output);
+    retry_blocking(block, is_console(&input), move || {
+        let ret = transfer(&input, in_offset.as_mut(), &output, None, count);
+        write_user_offset(offset, in_offset)?;
+        ret
+    })
+}
+
+/// 检查 sendfile64 的参数，返回传输的两端
+fn sendfile_ends(
+    in_fd: usize,
+    out_fd: usize,
+    offset: UserPtr<i64>,
+) -> Result<TransferEnds, SyscallError> {
+    let input = get_rw_file(in_fd)?;
+    let output = get_rw_file(out_fd)?;
+    if !input.readable() || !output.writable() {
+        return Err(SyscallError::EBADF);
+    }
+    if output.get_status().contains(OpenFlags::APPEND) {
+        return Err(SyscallError::EINVAL);
+    }
+    let in_offset = read_user_offset(offset)?;
+    if in_offset.is_some() {
+        check_seekable(&input)?;
+    }
+    Ok((input, in_offset, output, None))
+}
+
+/// 功能:在两个普通文件之间复制数据；
+/// # Arguments
+/// * `fd_in`: usize, 读取的文件描述符。
+/// * `off_in`: *mut i64, 读取的起始偏移，为空时使用 fd_in 的读写位置，语义同 sendfile64。
+/// * `fd_out`: usize, 写入的文件描述符，不能以 O_APPEND 打开。
+/// * `off_out`: *mut i64, 写入的起始偏移，为空时使用 fd_out 的读写位置。
+/// * `len`: usize, 最多复制的字节数。
+/// * `flags`: u32, 必须为 0。
+/// 返回值:成功返回复制的字节数，到达 fd_in 的末尾时返回0，失败返回-1。
+///
+/// 两端为同一个文件时，读写的范围不能重叠
+pub fn syscall_copy_file_range(args: [usize; 6]) -> SyscallResult {
+    let fd_in = args[0];
+    let off_in = UserPtr::<i64>::new(args[1]);
+    let fd_out = args[2];
+    let off_out = UserPtr::<i64>::new(args[3]);
+    let len = args[4].min(MAX_RW_COUNT);
+    let flags = args[5] as u32;
+    info!(
+        "[copy_file_range()] fd_in: {}, off_in: {:?}, fd_out: {}, off_out: {:?}, len: {}",
+        fd_in, off_in, fd_out, off_out, len
+    );
+    if flags != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let input = get_rw_file(fd_in)?;
+    let output = get_rw_file(fd_out)?;
+    if !input.readable() || !output.writable() || output.get_status().contains(OpenFlags::APPEND) {
+        return Err(SyscallError::EBADF);
+    }
+    if input.get_type() != FileIOType::FileDesc || output.get_type() != FileIOType::FileDesc {
+        return Err(SyscallError::EINVAL);
+    }
+    let mut in_offset = read_user_offset(off_in)?;
+    let mut out_offset = read_user_offset(off_out)?;
+
+    let in_stat = input.get_stat().map_err(SyscallError::from)?;
+    let out_stat = output.get_stat().map_err(SyscallError::from)?;
+    if (in_stat.st_dev, in_stat.st_ino) == (out_stat.st_dev, out_stat.st_ino) {
+        let in_pos = match in_offset {
+            Some(offset) => offset,
+            None => input
+                .seek(SeekFrom::Current(0))
+                .map_err(SyscallError::from)?,
+        };
+        let out_pos = match out_offset {
+            Some(offset) => offset,
+            None => output
+                .seek(SeekFrom::Current(0))
+                .map_err(SyscallError::from)?,
+        };
+        // 与 Linux 相同，以截断到文件末尾后的长度判断是否重叠
+        let count = (in_stat.st_size.saturating_sub(in_pos) as usize).min(len) as u64;
+        if count > 0 && in_pos < out_pos + count && out_pos < in_pos + count {
+            return Err(SyscallError::EINVAL);
+        }
+    }
+
+    let ret = transfer(
+        &input,
+        in_offset.as_mut(),
+        &output,
+        out_offset.as_mut(),
+        len,
+    );
+    write_user_offset(off_in, in_offset)?;
+    write_user_offset(off_out, out_offset)?;
+    ret
+}
+
+/// 功能:在管道与另一个文件之间移动数据；
+/// # Arguments
+/// * `fd_in`: usize, 读取的文件描述符。
+/// * `off_in`: *mut i64, 读取的起始偏移，fd_in 为管道时必须为空，语义同 sendfile64。
+/// * `fd_out`: usize, 写入的文件描述符。
+/// * `off_out`: *mut i64, 写入的起始偏移，fd_out 为管道时必须为空。
+/// * `len`: usize, 最多移动的字节数。
+/// * `flags`: u32, 见 SpliceFlags。
+/// 返回值:成功返回移动的字节数，管道的写端全部关闭且没有数据时返回0，失败返回-1。
+///
+/// 两端中至少有一端为管道。指定 SPLICE_F_NONBLOCK 时，管道一端没有数据或空位则返回 EAGAIN，
+/// 否则挂起等待
+pub fn syscall_splice(args: [usize; 6]) -> SyscallReturn {
+    let fd_in = args[0];
+    let off_in = UserPtr::<i64>::new(args[1]);
+    let fd_out = args[2];
+    let off_out = UserPtr::<i64>::new(args[3]);
+    let len = args[4].min(MAX_RW_COUNT);
+    let Some(flags) = SpliceFlags::from_bits(args[5] as u32) else {
+        return SyscallError::EINVAL.into();
+    };
+    info!(
+        "[splice()] fd_in: {}, off_in: {:?}, fd_out: {}, off_out: {:?}, len: {}, flags: {:?}",
+        fd_in, off_in, fd_out, off_out, len, flags
+    );
+    let ends = splice_ends(fd_in, off_in, fd_out, off_out, flags);
+    let (input, mut in_offset, output, mut out_offset) = match ends {
+        Ok(ends) => ends,
+        Err(err) => return err.into(),
+    };
+    let block = !flags.contains(SpliceFlags::SPLICE_F_NONBLOCK)
+        && is_blocking(&input)
+        && is_blocking(&output);
+    retry_blocking(block, is_console(&input), move || {
+        let ret = transfer(
+            &input,
+            in_offset.as_mut(),
+            &output,
+            out_offset.as_mut(),
+            len,
+        );
+        write_user_offset(off_in, in_offset)?;
+        write_user_offset(off_out, out_offset)?;
+        ret
+    })
+}
+
+/// 检查 splice 的参数，返回传输的两端
+fn splice_ends(
+    fd_in: usize,
+    off_in: UserPtr<i64>,
+    fd_out: usize,
+    off_out: UserPtr<i64>,
+    flags: SpliceFlags,
+) -> Result<TransferEnds, SyscallError> {
+    let input = get_rw_file(fd_in)?;
+    let output = get_rw_file(fd_out)?;
+    if !input.readable() || !output.writable() {
+        return Err(SyscallError::EBADF);
+    }
+    let in_pipe = input.as_any().downcast_ref::<Pipe>();
+    let out_pipe = output.as_any().downcast_ref::<Pipe>();
+    match (in_pipe, out_pipe) {
+        (None, None) => return Err(SyscallError::EINVAL),
+        (Some(in_pipe), Some(out_pipe)) if in_pipe.same_pipe(out_pipe) => {
+            return Err(SyscallError::EINVAL)
+        }
+        _ => {}
+    }
+    if (in_pipe.is_some() && !off_in.is_null()) || (out_pipe.is_some() && !off_out.is_null()) {
+        return Err(SyscallError::ESPIPE);
+    }
+    if out_pipe.is_none() && output.get_status().contains(OpenFlags::APPEND) {
+        return Err(SyscallError::EINVAL);
+    }
+    let in_offset = read_user_offset(off_in)?;
+    let out_offset = read_user_offset(off_out)?;
+    if in_offset.is_some() {
+        check_seekable(&input)?;
+    }
+    if out_offset.is_some() {
+        check_seekable(&output)?;
+    }
+    if flags.contains(SpliceFlags::SPLICE_F_NONBLOCK)
+        && ((in_pipe.is_some() && !input.ready_to_read())
+            || (out_pipe.is_some() && !output.ready_to_write()))
+    {
+        return Err(SyscallError::EAGAIN);
+    }
+    Ok((input, in_offset, output, out_offset))
+}
+
+/// 功能:把一个管道中的数据复制到另一个管道，不消耗原管道中的数据；
+/// # Arguments
+/// * `fd_in`: usize, 管道的读端。
+/// * `fd_out`: usize, 另一个管道的写端。
+/// * `len`: usize, 最多复制的字节数。
+/// * `flags`: u32, 见 SpliceFlags，只有 SPLICE_F_NONBLOCK 有效。
+/// 返回值:成功返回复制的字节数，fd_in 的写端全部关闭且没有数据时返回0，失败返回-1。
+///
+/// 没有指定 SPLICE_F_NONBLOCK 且两端都以阻塞方式打开时，fd_in 没有数据或 fd_out 没有空位则挂起等待
+pub fn syscall_tee(args: [usize; 6]) -> SyscallReturn {
+    let fd_in = args[0];
+    let fd_out = args[1];
+    let len = args[2].min(MAX_RW_COUNT);
+    let Some(flags) = SpliceFlags::from_bits(args[3] as u32) else {
+        return SyscallError::EINVAL.into();
+    };
+    info!(
+        "[tee()] fd_in: {}, fd_out: {}, len: {}, flags: {:?}",
+        fd_in, fd_out, len, flags
+    );
+    let (input, output) = match tee_ends(fd_in, fd_out) {
+        Ok(ends) => ends,
+        Err(err) => return err.into(),
+    };
+    let block = !flags.contains(SpliceFlags::SPLICE_F_NONBLOCK)
+        && is_blocking(&input)
+        && is_blocking(&output);
+    retry_blocking(block, false, move || {
+        // 两端在 tee_ends 中已经确认是管道
+        let in_pipe = input.as_any().downcast_ref::<Pipe>().unwrap();
+        let out_pipe = output.as_any().downcast_ref::<Pipe>().unwrap();
+        in_pipe
+            .tee(out_pipe, len)
+            .map(|count| count as isize)
+            .map_err(write_error)
+    })
+}
+
+/// 检查 tee 的两端是否为不同管道的读端与写端，返回两端的文件
+fn tee_ends(
+    fd_in: usize,
+    fd_out: usize,
+) -> Result<(Arc<dyn FileIO>, Arc<dyn FileIO>), SyscallError> {
+    let input = get_rw_file(fd_in)?;
+    let output = get_rw_file(fd_out)?;
+    if !input.readable() || !output.writable() {
+        return Err(SyscallError::EBADF);
+    }
+    let (Some(in_pipe), Some(out_pipe)) = (
+        input.as_any().downcast_ref::<Pipe>(),
+        output.as_any().downcast_ref::<Pipe>(),
+    ) else {
+        return Err(SyscallError::EINVAL);
+    };
+    if in_pipe.same_pipe(out_pipe) {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok((input, output))
+}
@@ -0,0 +1,286 @@
+//! 负责与文件信息、目录项相关的系统调用
+extern crate alloc;
+use crate::syscall_fs::ctype::{
+    dir::{dir_cookies, DirDesc, DOT_COOKIE},
+    file::{get_inode_number, new_fd},
+};
+use crate::{
+    get_fs_stat, DirEnt, DirEntType, FsStat, Statx, AT_EMPTY_PATH, AT_SYMLINK_NOFOLLOW,
+};
+use crate::{SyscallError, SyscallResult};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axfs::api::{self, AsAny, FileIO, FileIOType, Kstat, OpenFlags};
+use axhal::paging::MappingFlags;
+use axlog::{debug, info};
+use axtask::link::{
+    deal_with_path, deal_with_path_nofollow, read_symlink, FilePath, AT_FDCWD, SYMLINK_MAP,
+};
+use axtask::{current_task, UserPtr};
+
+/// 判断用户传入的路径是否为空字符串
+fn is_empty_path(path: *const u8) -> Result<bool, SyscallError> {
+    Ok(UserPtr::from(path).read()? == 0)
+}
+
+/// 获取某一个文件描述符对应文件的信息
+fn stat_fd(fd: usize) -> Result<Kstat, SyscallError> {
+    let task = current_task();
+    if fd == AT_FDCWD {
+        let cwd = task.get_cwd();
+        return DirDesc::new(cwd).get_stat().map_err(|_| SyscallError::ENOENT);
+    }
+    let file = match task.fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    file.get_stat().map_err(|_| SyscallError::EINVAL)
+}
+
+/// 获取某一个路径对应文件的信息
+fn stat_path(path: &FilePath) -> Result<Kstat, SyscallError> {
+    let path = path.path();
+    // 注意 new_dir 在目录不存在时会创建目录，因此需要先判断
+    if api::is_dir(path) {
+        return DirDesc::new(path.to_string())
+            .get_stat()
+            .map_err(|_| SyscallError::ENOENT);
+    }
+    if !api::path_exists(path) {
+        return Err(SyscallError::ENOENT);
+    }
+    if path.ends_with('/') {
+        return Err(SyscallError::ENOTDIR);
+    }
+    new_fd(path.to_string(), OpenFlags::RDONLY)
+        .and_then(|file| file.get_stat())
+        .map_err(|_| SyscallError::ENOENT)
+}
+
+/// 符号链接的文件类型
+const S_IFLNK: u32 = 0o120000;
+
+/// 根据 dirfd、路径与 flags 获取文件的信息，供 newfstatat 与 statx 使用
+fn stat_at(dir_fd: usize, path: *const u8, flags: usize) -> Result<Kstat, SyscallError> {
+    if flags & !(AT_EMPTY_PATH | AT_SYMLINK_NOFOLLOW) != 0 {
+        // 其余选项只影响自动挂载与缓存同步，可以忽略
+        debug!("unsupported stat flags: {:#x}", flags);
+    }
+    if flags & AT_EMPTY_PATH != 0 && is_empty_path(path)? {
+        return stat_fd(dir_fd);
+    }
+    if flags & AT_SYMLINK_NOFOLLOW != 0 {
+        if let Some(link) = deal_with_path_nofollow(dir_fd, Some(path), false) {
+            if let Some(target) = read_symlink(&link) {
+                return stat_symlink(&link, target.len());
+            }
+        }
+    }
+    match deal_with_path(dir_fd, Some(path), false) {
+        Some(path) => stat_path(&path),
+        None => Err(SyscallError::ENOENT),
+    }
+}
+
+/// 获取符号链接本身的信息，其大小为链接内容的长度
+fn stat_symlink(link: &FilePath, target_len: usize) -> Result<Kstat, SyscallError> {
+    let mut kstat = new_fd(link.path().to_string(), OpenFlags::RDONLY)
+        .and_then(|file| file.get_stat())
+        .map_err(|_| SyscallError::ENOENT)?;
+    kstat.st_mode = S_IFLNK | 0o777;
+    kstat.st_size = target_len as _;
+    Ok(kstat)
+}
+
+/// 功能:获取文件状态；
+/// # Arguments
+/// * `fd`: usize, 文件描述符。
+/// * `kst`: *mut Kstat, 接收文件状态的结构体。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_fstat(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let kst = UserPtr::<Kstat>::new(args[1]);
+    info!("[fstat()] fd: {}", fd);
+    kst.write(stat_fd(fd)?)?;
+    Ok(0)
+}
+
+/// 功能:获取相对于某一目录的文件的状态；
+/// # Arguments
+/// * `dir_fd`: usize, 目录的文件描述符，为 AT_FDCWD 时相对于当前工作目录。
+/// * `path`: *const u8, 文件路径。
+/// * `kst`: *mut Kstat, 接收文件状态的结构体。
+/// * `flags`: usize, 可以为 AT_EMPTY_PATH 与 AT_SYMLINK_NOFOLLOW。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_fstatat(args: [usize; 6]) -> SyscallResult {
+    let dir_fd = args[0];
+    let path = args[1] as *const u8;
+    let kst = UserPtr::<Kstat>::new(args[2]);
+    let flags = args[3];
+    kst.write(stat_at(dir_fd, path, flags)?)?;
+    Ok(0)
+}
+
+/// 功能:根据路径获取文件状态，供 x86_64 的 stat 与 lstat 使用；
+/// # Arguments
+/// * `path`: *const u8, 文件路径。
+/// * `kst`: *mut Kstat, 接收文件状态的结构体。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_stat(args: [usize; 6]) -> SyscallResult {
+    syscall_fstatat([AT_FDCWD, args[0], args[1], 0, 0, 0])
+}
+
+/// 功能:根据路径获取文件状态，路径为符号链接时获取链接本身的状态，供 x86_64 的 lstat 使用；
+/// # Arguments
+/// * `path`: *const u8, 文件路径。
+/// * `kst`: *mut Kstat, 接收文件状态的结构体。
+/// 返回值:成功返回0，失败返回-1。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_lstat(args: [usize; 6]) -> SyscallResult {
+    syscall_fstatat([AT_FDCWD, args[0], args[1], AT_SYMLINK_NOFOLLOW, 0, 0])
+}
+
+/// 功能:获取文件的扩展状态；
+/// # Arguments
+/// * `dir_fd`: usize, 目录的文件描述符，为 AT_FDCWD 时相对于当前工作目录。
+/// * `path`: *const u8, 文件路径。
+/// * `flags`: usize, 可以为 AT_EMPTY_PATH 与 AT_SYMLINK_NOFOLLOW。
+/// * `mask`: u32, 需要获取的字段，目前总是返回全部基本字段。
+/// * `statx`: *mut Statx, 接收文件状态的结构体。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_statx(args: [usize; 6]) -> SyscallResult {
+    let dir_fd = args[0];
+    let path = args[1] as *const u8;
+    let flags = args[2];
+    let _mask = args[3] as u32;
+    let statx = UserPtr::<Statx>::new(args[4]);
+    let kstat = stat_at(dir_fd, path, flags)?;
+    statx.write(Statx::from(kstat))?;
+    Ok(0)
+}
+
+/// 功能:获取目录的目录项；
+/// # Arguments
+/// * `fd`: usize, 目录的文件描述符。
+/// * `buf`: *mut u8, 接收目录项的缓冲区。
+/// * `len`: usize, 缓冲区的大小。
+/// 返回值:成功返回读取的字节数，为0表示读到目录末尾；失败返回-1。
+///
+/// 每一个目录项的 d_off 为它在目录中的 cookie，目录项按 cookie 递增的顺序返回。cookie 在目录项被删除前保持不变，
+/// 新的目录项得到更大的 cookie，因此读取过程中目录内容发生变化也不会导致目录项被跳过或重复返回；
+/// d_off 可以用于 lseek，之后从 cookie 更大的目录项继续读取
+pub fn syscall_getdents64(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let buf = UserPtr::<u8>::new(args[1]);
+    let len = args[2];
+    let task = current_task();
+    if len == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    buf.check(len, MappingFlags::WRITE)?;
+    let file = match task.fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    if file.get_type() != FileIOType::DirDesc {
+        return Err(SyscallError::ENOTDIR);
+    }
+    let dir_desc = file
+        .as_any()
+        .downcast_ref::<DirDesc>()
+        .ok_or(SyscallError::ENOTDIR)?;
+    let dir_path = dir_desc.dir_path.trim_end_matches('/');
+    let dir_path = if dir_path.is_empty() { "/" } else { dir_path };
+
+    // 目录项依次为 "."、".." 与目录下的各个文件
+    let mut files: Vec<(String, u64, u8)> = Vec::new();
+    for entry in api::read_dir(dir_path).map_err(|_| SyscallError::ENOENT)? {
+        let entry = entry.map_err(|_| SyscallError::EIO)?;
+        let path = entry.path();
+        // 符号链接以普通文件占位，需要单独标注类型
+        let d_type = if SYMLINK_MAP.lock().contains_key(path.as_str()) {
+            DirEntType::Lnk as u8
+        } else {
+            entry.file_type() as u8
+        };
+        files.push((entry.file_name(), get_inode_number(path.as_str()), d_type));
+    }
+    let names: Vec<&str> = files.iter().map(|(name, _, _)| name.as_str()).collect();
+    let cookies = dir_cookies(dir_path, &names);
+    let mut entries: Vec<(u64, &str, u64, u8)> = Vec::new();
+    entries.push((
+        DOT_COOKIE,
+        ".",
+        get_inode_number(dir_path),
+        DirEntType::Dir as u8,
+    ));
+    entries.push((DOT_COOKIE + 1, "..", 0, DirEntType::Dir as u8));
+    for ((name, ino, d_type), cookie) in files.iter().zip(cookies) {
+        entries.push((cookie, name.as_str(), *ino, *d_type));
+    }
+    entries.sort_unstable_by_key(|(cookie, _, _, _)| *cookie);
+
+    let mut cursor = dir_desc.cursor.lock();
+    let mut next = *cursor;
+    let mut dirents: Vec<u8> = Vec::new();
+    let remaining: Vec<_> = entries
+        .iter()
+        .filter(|(cookie, _, _, _)| *cookie > *cursor)
+        .collect();
+    for (cookie, name, ino, d_type) in remaining.iter() {
+        // 目录项长度需要按 8 字节对齐
+        let reclen = (DirEnt::fixed_size() + name.len() + 1 + 7) & !7;
+        if dirents.len() + reclen > len {
+            break;
+        }
+        let start = dirents.len();
+        dirents.extend_from_slice(&ino.to_ne_bytes());
+        dirents.extend_from_slice(&cookie.to_ne_bytes());
+        dirents.extend_from_slice(&(reclen as u16).to_ne_bytes());
+        dirents.push(*d_type);
+        dirents.extend_from_slice(name.as_bytes());
+        dirents.resize(start + reclen, 0);
+        next = *cookie;
+    }
+    if dirents.is_empty() && !remaining.is_empty() {
+        // 缓冲区连一个目录项都放不下
+        return Err(SyscallError::EINVAL);
+    }
+    // 写入用户缓冲区成功后才移动目录的读取位置
+    buf.write_array(&dirents)?;
+    *cursor = next;
+    Ok(dirents.len() as isize)
+}
+
+/// 功能:获取文件系统的信息；
+/// # Arguments
+/// * `path`: *const u8, 文件系统中任意一个文件的路径。
+/// * `buf`: *mut FsStat, 接收文件系统信息的结构体。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_statfs(args: [usize; 6]) -> SyscallResult {
+    let path = args[0] as *const u8;
+    let buf = UserPtr::<FsStat>::new(args[1]);
+    let path = deal_with_path(AT_FDCWD, Some(path), false).ok_or(SyscallError::ENOENT)?;
+    if !api::path_exists(path.path()) {
+        return Err(SyscallError::ENOENT);
+    }
+    buf.write(get_fs_stat())?;
+    Ok(0)
+}
+
+/// 功能:获取文件描述符所在文件系统的信息；
+/// # Arguments
+/// * `fd`: usize, 文件描述符。
+/// * `buf`: *mut FsStat, 接收文件系统信息的结构体。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_fstatfs(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let buf = UserPtr::<FsStat>::new(args[1]);
+    match current_task().fd_manager.fd_table.lock().get(fd) {
+        Some(Some(_)) => {}
+        _ => return Err(SyscallError::EBADF),
+    }
+    buf.write(get_fs_stat())?;
+    Ok(0)
+}
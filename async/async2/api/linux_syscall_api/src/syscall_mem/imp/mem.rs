@@ -0,0 +1,316 @@
+//! 负责与地址空间相关的系统调用
+extern crate alloc;
+use crate::syscall_fs::{FileDesc, MemFd};
+use crate::{
+    MMAPFlags, MREMAPFlags, MSyncFlags, MADV_DONTNEED, MADV_FREE, MADV_NORMAL, MADV_RANDOM,
+    MADV_SEQUENTIAL, MADV_WILLNEED, MMAPPROT,
+};
+use crate::{SyscallError, SyscallResult};
+use alloc::boxed::Box;
+use axconfig::MAX_USER_HEAP_SIZE;
+use axfs::api::{AsAny, FileIO};
+use axhal::arch::flush_tlb;
+use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::MappingFlags;
+use axlog::info;
+use axmem::MemBackend;
+use axtask::{clear_user, current_task};
+
+/// 功能:修改数据段的大小，即用户堆顶；
+/// # Arguments
+/// * `brk`: usize, 新的堆顶。为 0 时仅查询当前堆顶。
+/// 返回值:成功时返回新的堆顶；参数不合法时不做修改，返回当前的堆顶。
+pub fn syscall_brk(args: [usize; 6]) -> SyscallResult {
+    let brk = args[0];
+    let task = current_task();
+    let heap_bottom = task.get_heap_bottom() as usize;
+    let heap_top = task.get_heap_top() as usize;
+    if brk == 0 || brk < heap_bottom || brk > heap_bottom + MAX_USER_HEAP_SIZE {
+        return Ok(heap_top as isize);
+    }
+    if brk > heap_top {
+        // 扩展出的部分可能在之前缩小堆时留有旧数据，需要清零
+        if clear_user(heap_top, brk - heap_top).is_err() {
+            return Ok(heap_top as isize);
+        }
+    }
+    task.set_heap_top(brk as u64);
+    Ok(brk as isize)
+}
+
+/// 功能:将文件或设备映射到内存中，或者创建匿名映射；
+/// # Arguments
+/// * `start`: usize, 映射的起始地址。为 0 时由内核选择地址，否则作为提示；指定 MAP_FIXED 时必须映射到该地址。
+/// * `len`: usize, 映射的长度。
+/// * `prot`: u32, 映射区域的保护方式，见 MMAPPROT。
+/// * `flags`: u32, 映射的选项，见 MMAPFlags。MAP_SHARED 与 MAP_PRIVATE 必须指定其一。
+/// * `fd`: usize, 被映射的文件描述符，匿名映射时忽略。
+/// * `offset`: usize, 映射在文件中的偏移，必须页对齐。
+/// 返回值:成功返回映射的起始地址，失败返回 -1。
+///
+/// 私有的文件映射与共享映射一样以已打开的文件作为后端，但对映射区域的修改不会写回文件
+pub fn syscall_mmap(args: [usize; 6]) -> SyscallResult {
+    let start = args[0];
+    let len = args[1];
+    let prot = MMAPPROT::from_bits_truncate(args[2] as u32);
+    let flags = MMAPFlags::from_bits_truncate(args[3] as u32);
+    let fd = args[4] as i32;
+    let offset = args[5];
+    info!(
+        "[mmap()] start: {:#x}, len: {:#x}, prot: {:?}, flags: {:?}, fd: {}, offset: {:#x}",
+        start, len, prot, flags, fd, offset
+    );
+    if len == 0 || offset % PAGE_SIZE_4K != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    if !flags.intersects(MMAPFlags::MAP_SHARED | MMAPFlags::MAP_PRIVATE) {
+        return Err(SyscallError::EINVAL);
+    }
+    let fixed = flags.contains(MMAPFlags::MAP_FIXED);
+    if fixed && (start % PAGE_SIZE_4K != 0 || start == 0) {
+        return Err(SyscallError::EINVAL);
+    }
+    if start.checked_add(len).is_none() {
+        return Err(SyscallError::ENOMEM);
+    }
+    let shared = flags.contains(MMAPFlags::MAP_SHARED);
+    let mapping_flags: MappingFlags = prot.into();
+    let task = current_task();
+    // 映射后地址空间的大小不能超过 RLIMIT_AS
+    let as_limit = task.rlimits.lock().address_space_limit();
+    if task.memory_set.lock().mapped_size().saturating_add(len) > as_limit {
+        return Err(SyscallError::ENOMEM);
+    }
+
+    let addr = if flags.contains(MMAPFlags::MAP_ANONYMOUS) {
+        if shared {
+            task.memory_set.lock().mmap_shared_anonymous(
+                start.into(),
+                len,
+                mapping_flags,
+                fixed,
+                task.get_process_id(),
+            )
+        } else {
+            task.memory_set
+                .lock()
+                .mmap(start.into(), len, mapping_flags, fixed, None)
+        }
+    } else {
+        let file = match task.fd_manager.fd_table.lock().get(fd as usize) {
+            Some(Some(file)) if fd >= 0 => file.clone(),
+            _ => return Err(SyscallError::EBADF),
+        };
+        let write = shared && prot.contains(MMAPPROT::PROT_WRITE);
+        if !file.readable() || (write && !file.writable()) {
+            return Err(SyscallError::EACCES);
+        }
+        let (backend_file, mapping) =
+            if let Some(file_desc) = file.as_any().downcast_ref::<FileDesc>() {
+                (file_desc.file.lock().clone(), None)
+            } else if let Some(memfd) = file.as_any().downcast_ref::<MemFd>() {
+                // 有写入封印的 memfd 不能建立可写的共享映射
+                memfd
+                    .mmap_file(shared, write)
+                    .map_err(|_| SyscallError::EPERM)?
+            } else {
+                return Err(SyscallError::ENODEV);
+            };
+        let mut backend = MemBackend::new(Box::new(backend_file), offset as u64, shared);
+        // 可写共享映射的登记随映射一起释放
+        if let Some(mapping) = mapping {
+            backend.keep_alive(mapping);
+        }
+        task.memory_set
+            .lock()
+            .mmap(start.into(), len, mapping_flags, fixed, Some(backend))
+    };
+    if addr < 0 {
+        return Err(SyscallError::ENOMEM);
+    }
+    Ok(addr)
+}
+
+/// 将长度向上对齐到页，溢出时返回 None
+fn page_align_up(len: usize) -> Option<usize> {
+    len.checked_add(PAGE_SIZE_4K - 1)
+        .map(|len| len / PAGE_SIZE_4K * PAGE_SIZE_4K)
+}
+
+/// 功能:解除一段内存映射；
+/// # Arguments
+/// * `start`: usize, 起始地址，必须页对齐。
+/// * `len`: usize, 长度。
+/// 返回值:成功返回 0，失败返回 -1。
+pub fn syscall_munmap(args: [usize; 6]) -> SyscallResult {
+    let start = args[0];
+    let len = args[1];
+    if start % PAGE_SIZE_4K != 0 || len == 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let task = current_task();
+    let result = task.memory_set.lock().munmap(start.into(), len);
+    flush_tlb(None);
+    result.map_err(|_| SyscallError::EINVAL)?;
+    Ok(0)
+}
+
+/// 功能:修改一段内存映射的保护方式；
+/// # Arguments
+/// * `start`: usize, 起始地址，必须页对齐。
+/// * `len`: usize, 长度，会向上对齐到页。
+/// * `prot`: u32, 新的保护方式，见 MMAPPROT。
+/// 返回值:成功返回 0，失败返回 -1。区间中有未映射的部分时返回 ENOMEM，且不做任何修改。
+pub fn syscall_mprotect(args: [usize; 6]) -> SyscallResult {
+    let start = args[0];
+    let len = args[1];
+    let prot = MMAPPROT::from_bits_truncate(args[2] as u32);
+    if start % PAGE_SIZE_4K != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    if len == 0 {
+        return Ok(0);
+    }
+    // 区间超出地址空间时一定有未映射的部分
+    let len = match len.checked_add(PAGE_SIZE_4K - 1) {
+        Some(len) if start.checked_add(len).is_some() => len / PAGE_SIZE_4K * PAGE_SIZE_4K,
+        _ => return Err(SyscallError::ENOMEM),
+    };
+    let task = current_task();
+    task.memory_set
+        .lock()
+        .mprotect(start.into(), len, prot.into())
+        .map_err(|_| SyscallError::ENOMEM)?;
+    flush_tlb(None);
+    Ok(0)
+}
+
+/// 功能:扩大或缩小一段内存映射，可能会移动映射的位置；
+/// # Arguments
+/// * `old_addr`: usize, 原映射的起始地址，必须页对齐。
+/// * `old_size`: usize, 原映射的长度。
+/// * `new_size`: usize, 新映射的长度。
+/// * `flags`: u32, 见 MREMAPFlags。
+/// 返回值:成功返回新映射的起始地址，失败返回 -1。
+///
+/// 目前扩大映射时总是移动到新的位置，因此需要指定 MREMAP_MAYMOVE；暂不支持 MREMAP_FIXED 与 MREMAP_DONTUNMAP
+pub fn syscall_mremap(args: [usize; 6]) -> SyscallResult {
+    let old_addr = args[0];
+    let old_size = args[1];
+    let new_size = args[2];
+    let flags = MREMAPFlags::from_bits_truncate(args[3] as u32);
+    info!(
+        "[mremap()] old_addr: {:#x}, old_size: {:#x}, new_size: {:#x}, flags: {:?}",
+        old_addr, old_size, new_size, flags
+    );
+    if old_addr % PAGE_SIZE_4K != 0 || new_size == 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    if flags.intersects(MREMAPFlags::MREMAP_FIXED | MREMAPFlags::MREMAP_DONTUNMAP)
+        && !flags.contains(MREMAPFlags::MREMAP_MAYMOVE)
+    {
+        return Err(SyscallError::EINVAL);
+    }
+    if flags.intersects(MREMAPFlags::MREMAP_FIXED | MREMAPFlags::MREMAP_DONTUNMAP) {
+        return Err(SyscallError::EINVAL);
+    }
+    let (Some(old_len), Some(new_len)) = (page_align_up(old_size), page_align_up(new_size)) else {
+        return Err(SyscallError::EINVAL);
+    };
+    if old_size == 0 || old_addr.checked_add(old_len).is_none() {
+        return Err(SyscallError::EINVAL);
+    }
+    let old_pages = old_len / PAGE_SIZE_4K;
+    let new_pages = new_len / PAGE_SIZE_4K;
+    let task = current_task();
+    if new_pages <= old_pages {
+        // 缩小时原地解除多余部分的映射
+        if new_pages < old_pages {
+            let result = task.memory_set.lock().munmap(
+                VirtAddr::from(old_addr + new_pages * PAGE_SIZE_4K),
+                (old_pages - new_pages) * PAGE_SIZE_4K,
+            );
+            flush_tlb(None);
+            result.map_err(|_| SyscallError::EINVAL)?;
+        }
+        return Ok(old_addr as isize);
+    }
+    if !flags.contains(MREMAPFlags::MREMAP_MAYMOVE) {
+        return Err(SyscallError::ENOMEM);
+    }
+    let as_limit = task.rlimits.lock().address_space_limit();
+    let grown = (new_pages - old_pages) * PAGE_SIZE_4K;
+    if task.memory_set.lock().mapped_size().saturating_add(grown) > as_limit {
+        return Err(SyscallError::ENOMEM);
+    }
+    let addr = task
+        .memory_set
+        .lock()
+        .mremap(old_addr.into(), old_size, new_pages * PAGE_SIZE_4K);
+    flush_tlb(None);
+    if addr < 0 {
+        return Err(SyscallError::ENOMEM);
+    }
+    Ok(addr)
+}
+
+/// 功能:将文件映射中被修改的内容写回文件；
+/// # Arguments
+/// * `start`: usize, 起始地址，必须页对齐。
+/// * `len`: usize, 长度。
+/// * `flags`: u32, 见 MSyncFlags。MS_ASYNC 与 MS_SYNC 不能同时指定。
+/// 返回值:成功返回 0，失败返回 -1。写回文件失败时返回 EIO。
+pub fn syscall_msync(args: [usize; 6]) -> SyscallResult {
+    let start = args[0];
+    let len = args[1];
+    let Some(flags) = MSyncFlags::from_bits(args[2] as u32) else {
+        return Err(SyscallError::EINVAL);
+    };
+    if start % PAGE_SIZE_4K != 0
+        || flags.contains(MSyncFlags::MS_ASYNC | MSyncFlags::MS_SYNC)
+    {
+        return Err(SyscallError::EINVAL);
+    }
+    let task = current_task();
+    let result = task.memory_set.lock().msync(start.into(), len);
+    // 写回的页面会重新设为只读，以记录之后的修改
+    flush_tlb(None);
+    result.map_err(|_| SyscallError::EIO)?;
+    Ok(0)
+}
+
+/// 功能:向内核提供一段内存的使用建议；
+/// # Arguments
+/// * `start`: usize, 起始地址，必须页对齐。
+/// * `len`: usize, 长度。
+/// * `advice`: usize, 使用建议。
+/// 返回值:成功返回 0，失败返回 -1。区间越界时返回 EINVAL，有未映射的部分时返回 ENOMEM。
+///
+/// MADV_DONTNEED 会丢弃私有映射中已分配的页面，之后访问时匿名映射得到清零的页面，私有文件映射重新从文件读取；
+/// 共享映射的页面保存着共享的内容，不做处理。其余建议均不做处理
+pub fn syscall_madvise(args: [usize; 6]) -> SyscallResult {
+    let start = args[0];
+    let len = args[1];
+    let advice = args[2];
+    if start % PAGE_SIZE_4K != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let Some(len) = page_align_up(len).filter(|&len| start.checked_add(len).is_some()) else {
+        return Err(SyscallError::EINVAL);
+    };
+    match advice {
+        MADV_DONTNEED => {
+            if len == 0 {
+                return Ok(0);
+            }
+            current_task()
+                .memory_set
+                .lock()
+                .madvise_dontneed(start.into(), len)
+                .map_err(|_| SyscallError::ENOMEM)?;
+            Ok(0)
+        }
+        MADV_NORMAL | MADV_RANDOM | MADV_SEQUENTIAL | MADV_WILLNEED | MADV_FREE => Ok(0),
+        _ => Err(SyscallError::EINVAL),
+    }
+}
@@ -0,0 +1,6 @@
+//! Implementations of the syscall about memory management
+mod mem;
+mod shm;
+
+pub use mem::*;
+pub use shm::*;
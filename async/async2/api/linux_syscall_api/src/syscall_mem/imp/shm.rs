@@ -0,0 +1,229 @@
+//! 负责 System V 共享内存相关的系统调用
+//!
+//! 以 IPC_PRIVATE 创建的共享内存保存在进程的地址空间中，fork 时会被子进程继承；
+//! 其余共享内存保存在全局的表中，可以通过 key 查找。
+extern crate alloc;
+use alloc::sync::Arc;
+
+use crate::{
+    IpcPerm, ShmAtFlags, ShmGetFlags, ShmidDs, SyscallError, SyscallResult, IPC_64, IPC_PRIVATE,
+    IPC_RMID, IPC_SET, IPC_STAT, SHMLBA,
+};
+use axerrno::AxError;
+use axhal::arch::flush_tlb;
+use axhal::mem::{phys_to_virt, VirtAddr};
+use axhal::paging::MappingFlags;
+use axhal::time::current_time;
+use axlog::info;
+use axmem::{MemorySet, SharedMem};
+use axtask::{current_task, UserPtr};
+
+/// 根据 shmid 查找当前进程可以访问的共享内存，同时返回它是否为私有的共享内存
+fn find_shared_mem(shmid: i32) -> Option<(Arc<SharedMem>, bool)> {
+    if let Some(mem) = current_task()
+        .memory_set
+        .lock()
+        .get_private_shared_mem(shmid)
+    {
+        return Some((mem, true));
+    }
+    MemorySet::get_shared_mem(shmid).map(|mem| (mem, false))
+}
+
+/// 功能:获取或创建 System V 共享内存；
+/// # Arguments
+/// * `key`: i32, 共享内存的 key。为 IPC_PRIVATE 时总是创建新的共享内存。
+/// * `size`: usize, 共享内存的大小。获取已有的共享内存时不能超过其大小。
+/// * `shmflg`: u32, 见 ShmGetFlags，低 9 位为访问权限。
+/// 返回值:成功返回共享内存的 shmid，失败返回-1。
+pub fn syscall_shmget(args: [usize; 6]) -> SyscallResult {
+    let key = args[0] as i32;
+    let size = args[1];
+    let flags = ShmGetFlags::from_bits_truncate(args[2] as u32);
+    let mode = (args[2] & 0o777) as u16;
+    info!(
+        "[shmget()] key: {}, size: {:#x}, flags: {:?}, mode: {:o}",
+        key, size, flags, mode
+    );
+    if key != IPC_PRIVATE {
+        if let Some(shmid) = MemorySet::get_shmid_by_key(key) {
+            if flags.contains(ShmGetFlags::IPC_CREAT | ShmGetFlags::IPC_EXCL) {
+                return Err(SyscallError::EEXIST);
+            }
+            let mem = MemorySet::get_shared_mem(shmid).ok_or(SyscallError::ENOENT)?;
+            if size > mem.info.lock().size {
+                return Err(SyscallError::EINVAL);
+            }
+            return Ok(shmid as isize);
+        }
+        if !flags.contains(ShmGetFlags::IPC_CREAT) {
+            return Err(SyscallError::ENOENT);
+        }
+    }
+    if size == 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let task = current_task();
+    let (shmid, mem) = MemorySet::create_shared_mem(key, size, task.get_process_id(), 0, 0, mode)
+        .map_err(|err| match err {
+        AxError::AlreadyExists => SyscallError::EEXIST,
+        _ => SyscallError::ENOMEM,
+    })?;
+    // 新创建的共享内存内容为 0
+    unsafe {
+        core::ptr::write_bytes(phys_to_virt(mem.paddr()).as_mut_ptr(), 0, mem.size());
+    }
+    if key == IPC_PRIVATE {
+        task.memory_set.lock().add_private_shared_mem(shmid, mem);
+    } else {
+        MemorySet::add_shared_mem(shmid, mem);
+    }
+    Ok(shmid as isize)
+}
+
+/// 功能:将共享内存挂载到当前进程的地址空间；
+/// # Arguments
+/// * `shmid`: i32, 共享内存的 shmid。
+/// * `addr`: usize, 挂载的地址。为 0 时由内核选择，否则必须对齐到 SHMLBA，指定 SHM_RND 时向下对齐。
+/// * `shmflg`: u32, 见 ShmAtFlags。
+/// 返回值:成功返回挂载的地址，失败返回-1。
+///
+/// 指定的地址上已有映射时，若指定了 SHM_REMAP 则替换原有的映射，否则失败
+pub fn syscall_shmat(args: [usize; 6]) -> SyscallResult {
+    let shmid = args[0] as i32;
+    let addr = args[1];
+    let flags = ShmAtFlags::from_bits_truncate(args[2] as u32);
+    info!(
+        "[shmat()] shmid: {}, addr: {:#x}, flags: {:?}",
+        shmid, addr, flags
+    );
+    let (mem, _) = find_shared_mem(shmid).ok_or(SyscallError::EINVAL)?;
+    let addr = if flags.contains(ShmAtFlags::SHM_RND) {
+        addr / SHMLBA * SHMLBA
+    } else {
+        addr
+    };
+    if addr % SHMLBA != 0 || (addr == 0 && flags.contains(ShmAtFlags::SHM_REMAP)) {
+        return Err(SyscallError::EINVAL);
+    }
+    let mut mapping_flags = MappingFlags::USER | MappingFlags::READ;
+    if !flags.contains(ShmAtFlags::SHM_RDONLY) {
+        mapping_flags |= MappingFlags::WRITE;
+    }
+    if flags.contains(ShmAtFlags::SHM_EXEC) {
+        mapping_flags |= MappingFlags::EXECUTE;
+    }
+
+    let task = current_task();
+    // 挂载后地址空间的大小不能超过 RLIMIT_AS
+    let as_limit = task.rlimits.lock().address_space_limit();
+    let mut memory_set = task.memory_set.lock();
+    if memory_set.mapped_size().saturating_add(mem.size()) > as_limit {
+        return Err(SyscallError::ENOMEM);
+    }
+    let start = if addr == 0 {
+        memory_set
+            .find_free_area(VirtAddr::from(0), mem.size())
+            .ok_or(SyscallError::ENOMEM)?
+    } else {
+        let start = VirtAddr::from(addr);
+        if flags.contains(ShmAtFlags::SHM_REMAP) {
+            memory_set
+                .munmap(start, mem.size())
+                .map_err(|_| SyscallError::EINVAL)?;
+        } else if !memory_set.is_free_area(start, mem.size()) {
+            return Err(SyscallError::EINVAL);
+        }
+        start
+    };
+    memory_set.attach_shared_mem(mem.clone(), start, mapping_flags);
+    drop(memory_set);
+    mem.info.lock().l_pid = task.get_process_id();
+    flush_tlb(None);
+    Ok(start.as_usize() as isize)
+}
+
+/// 功能:解除共享内存的挂载；
+/// # Arguments
+/// * `addr`: usize, shmat 返回的挂载地址。
+/// 返回值:成功返回0，失败返回-1。
+///
+/// 已被标记删除的共享内存在最后一次解除挂载后被释放
+pub fn syscall_shmdt(args: [usize; 6]) -> SyscallResult {
+    let addr = args[0];
+    info!("[shmdt()] addr: {:#x}", addr);
+    let task = current_task();
+    let mem = task
+        .memory_set
+        .lock()
+        .detach_shared_mem(addr.into())
+        .map_err(|_| SyscallError::EINVAL)?;
+    mem.info.lock().l_pid = task.get_process_id();
+    flush_tlb(None);
+    Ok(0)
+}
+
+/// 功能:控制共享内存；
+/// # Arguments
+/// * `shmid`: i32, 共享内存的 shmid。
+/// * `cmd`: usize, 支持 IPC_STAT、IPC_SET 与 IPC_RMID。
+/// * `buf`: *mut ShmidDs, IPC_STAT 时用于保存共享内存的信息，IPC_SET 时为新的属主与权限。
+/// 返回值:成功返回0，失败返回-1。
+///
+/// IPC_RMID 后共享内存无法再被获取或挂载，已有的挂载不受影响，全部解除挂载后才被释放
+pub fn syscall_shmctl(args: [usize; 6]) -> SyscallResult {
+    let shmid = args[0] as i32;
+    let cmd = args[1] & !IPC_64;
+    let buf = UserPtr::<ShmidDs>::new(args[2]);
+    info!("[shmctl()] shmid: {}, cmd: {}, buf: {:?}", shmid, cmd, buf);
+    let (mem, private) = find_shared_mem(shmid).ok_or(SyscallError::EINVAL)?;
+    match cmd {
+        IPC_STAT => {
+            let info = mem.info.lock();
+            let ds = ShmidDs {
+                shm_perm: IpcPerm {
+                    key: info.perm.key,
+                    uid: info.perm.uid,
+                    gid: info.perm.gid,
+                    cuid: info.perm.cuid,
+                    cgid: info.perm.cgid,
+                    mode: info.perm.mode as u32,
+                    ..Default::default()
+                },
+                shm_segsz: info.size,
+                shm_atime: info.a_time as isize,
+                shm_dtime: info.d_time as isize,
+                shm_ctime: info.c_time as isize,
+                shm_cpid: info.c_pid as i32,
+                shm_lpid: info.l_pid as i32,
+                shm_nattch: info.n_attach,
+                ..Default::default()
+            };
+            // 写入用户内存时可能需要分配页面，不能持有锁
+            drop(info);
+            buf.write(ds)?;
+            Ok(0)
+        }
+        IPC_SET => {
+            let perm = buf.read()?.shm_perm;
+            let mut info = mem.info.lock();
+            info.perm.uid = perm.uid;
+            info.perm.gid = perm.gid;
+            info.perm.mode = (perm.mode & 0o777) as u16;
+            info.c_time = current_time().as_secs() as usize;
+            Ok(0)
+        }
+        IPC_RMID => {
+            if private {
+                current_task()
+                    .memory_set
+                    .lock()
+                    .remove_private_shared_mem(shmid);
+            } else {
+                MemorySet::remove_shared_mem(shmid);
+            }
+            Ok(0)
+        }
+        _ => Err(SyscallError::EINVAL),
+    }
+}
@@ -0,0 +1,5 @@
+//! 提供和地址空间相关的 syscall
+
+mod imp;
+
+pub use imp::*;
@@ -0,0 +1,678 @@
+//! 负责与 socket 相关的系统调用
+extern crate alloc;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::net::SocketAddr;
+
+use axerrno::AxError;
+use axfs::api::{FileIO, FileIOType};
+use axhal::paging::MappingFlags;
+use axlog::{debug, info};
+use axsignal::signal_no::SignalNo;
+use axtask::{current_task, send_signal_to_thread, UserPtr};
+
+use super::socket::*;
+use crate::syscall_fs::imp::user_iovecs;
+use crate::{SyscallError, SyscallResult, TimeVal};
+
+/// 将 socket 操作的错误转换为对应的错误码
+fn net_error(err: AxError) -> SyscallError {
+    match err {
+        AxError::Unsupported => SyscallError::EOPNOTSUPP,
+        // 设置了 SO_RCVTIMEO 的 socket 超时返回 EAGAIN
+        AxError::Timeout => SyscallError::EAGAIN,
+        AxError::BadState => SyscallError::EINVAL,
+        err => err.into(),
+    }
+}
+
+/// 将发送数据时的错误转换为对应的错误码
+///
+/// 对端已经关闭或写端已被 shutdown 时，除非指定了 MSG_NOSIGNAL，否则向当前任务发送 SIGPIPE
+fn send_error(err: AxError, flags: MsgFlags) -> SyscallError {
+    match err {
+        AxError::ConnectionReset => {
+            if !flags.contains(MsgFlags::MSG_NOSIGNAL) {
+                let _ = send_signal_to_thread(
+                    current_task().tid() as isize,
+                    SignalNo::SIGPIPE as isize,
+                );
+            }
+            SyscallError::EPIPE
+        }
+        err => net_error(err),
+    }
+}
+
+/// 对 fd 对应的 socket 进行操作，fd 不是 socket 时返回 ENOTSOCK
+fn with_socket<R>(
+    fd: usize,
+    f: impl FnOnce(&Socket) -> Result<R, SyscallError>,
+) -> Result<R, SyscallError> {
+    let file = match current_task().fd_manager.fd_table.lock().get(fd) {
+        Some(Some(file)) => file.clone(),
+        _ => return Err(SyscallError::EBADF),
+    };
+    if file.get_type() != FileIOType::Socket {
+        return Err(SyscallError::ENOTSOCK);
+    }
+    let socket = file
+        .as_any()
+        .downcast_ref::<Socket>()
+        .ok_or(SyscallError::ENOTSOCK)?;
+    f(socket)
+}
+
+/// 将 socket 放入文件描述符表，返回新的文件描述符
+fn add_socket_fd(socket: Socket, cloexec: bool) -> SyscallResult {
+    let task = current_task();
+    let mut fd_table = task.fd_manager.fd_table.lock();
+    let fd = task
+        .alloc_fd(&mut fd_table)
+        .map_err(|_| SyscallError::EMFILE)?;
+    fd_table[fd] = Some(Arc::new(socket));
+    task.fd_manager.set_close_on_exec(fd, cloexec);
+    Ok(fd as isize)
+}
+
+/// 从用户空间读取 socket 地址，目前只支持 AF_INET
+fn read_socket_address(addr: UserPtr<u8>, addrlen: usize) -> Result<SocketAddr, SyscallError> {
+    if addrlen < size_of::<u16>() || addrlen > u32::MAX as usize {
+        return Err(SyscallError::EINVAL);
+    }
+    let family = addr.cast::<u16>().read()? as usize;
+    match Domain::try_from(family) {
+        Ok(Domain::AF_INET) => {
+            if addrlen < size_of::<SockAddrIn>() {
+                return Err(SyscallError::EINVAL);
+            }
+            let addr = addr.cast::<SockAddrIn>().read()?;
+            Ok(SocketAddr::V4(addr.into()))
+        }
+        _ => Err(SyscallError::EAFNOSUPPORT),
+    }
+}
+
+/// 将 socket 地址写入用户空间的 addr，地址会被截断到 len 字节，返回地址的实际长度
+fn copy_socket_address(
+    sock_addr: SocketAddr,
+    addr: UserPtr<u8>,
+    len: u32,
+) -> Result<u32, SyscallError> {
+    if (len as i32) < 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let SocketAddr::V4(sock_addr) = sock_addr else {
+        return Err(SyscallError::EAFNOSUPPORT);
+    };
+    let sock_addr = SockAddrIn::from(sock_addr);
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            &sock_addr as *const SockAddrIn as *const u8,
+            size_of::<SockAddrIn>(),
+        )
+    };
+    let count = bytes.len().min(len as usize);
+    addr.write_array(&bytes[..count])?;
+    Ok(bytes.len() as u32)
+}
+
+/// 将 socket 地址写回用户空间
+///
+/// 地址会被截断到 addrlen 指定的长度，addrlen 被设置为地址的实际长度。addr 为空时不做任何事
+fn write_socket_address(
+    sock_addr: SocketAddr,
+    addr: UserPtr<u8>,
+    addrlen: UserPtr<u32>,
+) -> Result<(), SyscallError> {
+    if addr.is_null() {
+        return Ok(());
+    }
+    let len = copy_socket_address(sock_addr, addr, addrlen.read()?)?;
+    addrlen.write(len)?;
+    Ok(())
+}
+
+/// 按照 flags 发送数据，MSG_DONTWAIT 时若不可写则直接返回 EAGAIN
+fn send_with_flags(
+    socket: &Socket,
+    buf: &[u8],
+    to: Option<SocketAddr>,
+    flags: MsgFlags,
+) -> SyscallResult {
+    if flags.contains(MsgFlags::MSG_DONTWAIT) && !socket.ready_to_write() {
+        return Err(SyscallError::EAGAIN);
+    }
+    socket
+        .send(buf, to)
+        .map(|len| len as isize)
+        .map_err(|err| send_error(err, flags))
+}
+
+/// 按照 flags 接收数据，返回接收的字节数与数据的来源
+///
+/// MSG_DONTWAIT 时若不可读则直接返回 EAGAIN；MSG_WAITALL 时 TCP socket 会一直接收到填满缓冲区、
+/// 连接关闭或出错为止
+fn recv_with_flags(
+    socket: &Socket,
+    buf: &mut [u8],
+    flags: MsgFlags,
+) -> Result<(usize, SocketAddr), SyscallError> {
+    if flags.contains(MsgFlags::MSG_OOB) {
+        return Err(SyscallError::EOPNOTSUPP);
+    }
+    let dont_wait = flags.contains(MsgFlags::MSG_DONTWAIT);
+    if dont_wait && !socket.ready_to_read() {
+        return Err(SyscallError::EAGAIN);
+    }
+    let (mut len, from) = socket.recv(buf, flags).map_err(net_error)?;
+    if flags.contains(MsgFlags::MSG_WAITALL)
+        && !flags.contains(MsgFlags::MSG_PEEK)
+        && !dont_wait
+        && socket.socket_type() == SocketType::SOCK_STREAM
+    {
+        while len > 0 && len < buf.len() {
+            match socket.recv(&mut buf[len..], flags) {
+                Ok((count, _)) if count > 0 => len += count,
+                // 已经收到数据时返回已收到的长度，错误留给下一次调用
+                _ => break,
+            }
+        }
+    }
+    Ok((len, from))
+}
+
+/// 功能:创建一个 socket；
+/// # Arguments
+/// * `domain`: usize, 协议族，目前只支持 AF_INET。
+/// * `socket_type`: usize, socket 的类型，可以带有 SOCK_NONBLOCK 与 SOCK_CLOEXEC 标志。
+/// * `protocol`: usize, 协议号，为 0 时根据类型选择。
+/// 返回值:成功返回新的文件描述符，失败返回-1。
+pub fn syscall_socket(args: [usize; 6]) -> SyscallResult {
+    let domain = args[0];
+    let socket_type = args[1];
+    let protocol = args[2];
+    info!(
+        "socket: domain: {}, type: {:#x}, protocol: {}",
+        domain, socket_type, protocol
+    );
+    let domain = match Domain::try_from(domain) {
+        Ok(Domain::AF_INET) => Domain::AF_INET,
+        _ => return Err(SyscallError::EAFNOSUPPORT),
+    };
+    let flags = socket_type & (SOCK_NONBLOCK | SOCK_CLOEXEC);
+    let socket_type =
+        SocketType::try_from(socket_type & !flags).map_err(|_| SyscallError::EINVAL)?;
+    match (socket_type, protocol) {
+        (SocketType::SOCK_STREAM, 0 | IPPROTO_TCP) | (SocketType::SOCK_DGRAM, 0 | IPPROTO_UDP) => {}
+        (SocketType::SOCK_STREAM | SocketType::SOCK_DGRAM, _) => {
+            return Err(SyscallError::EPROTONOSUPPORT)
+        }
+        _ => return Err(SyscallError::ESOCKTNOSUPPORT),
+    }
+    let socket = Socket::new(domain, socket_type).map_err(net_error)?;
+    if flags & SOCK_NONBLOCK != 0 {
+        socket.set_nonblocking(true);
+    }
+    add_socket_fd(socket, flags & SOCK_CLOEXEC != 0)
+}
+
+/// 功能:将 socket 绑定到本地地址；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `addr`: *const u8, 本地地址，端口为 0 时自动分配。
+/// * `addrlen`: usize, 地址的长度。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_bind(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let addr = read_socket_address(UserPtr::new(args[1]), args[2])?;
+    info!("bind: fd: {}, addr: {}", fd, addr);
+    with_socket(fd, |socket| {
+        socket.bind(addr).map_err(net_error)?;
+        Ok(0)
+    })
+}
+
+/// 功能:开始监听 socket 上的连接，只有 SOCK_STREAM 类型的 socket 可以监听；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `backlog`: usize, 等待队列的长度，目前被忽略。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_listen(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    info!("listen: fd: {}, backlog: {}", fd, args[1]);
+    with_socket(fd, |socket| {
+        socket.listen().map_err(net_error)?;
+        Ok(0)
+    })
+}
+
+/// 功能:接受 socket 上的一个连接；
+/// # Arguments
+/// * `fd`: usize, 监听中的 socket 的文件描述符。
+/// * `addr`: *mut u8, 用于保存对端的地址，可以为空。
+/// * `addrlen`: *mut u32, 地址缓冲区的长度，返回时为地址的实际长度。
+/// * `flags`: usize, SOCK_NONBLOCK 与 SOCK_CLOEXEC 的组合。
+/// 返回值:成功返回新连接的文件描述符，失败返回-1。
+pub fn syscall_accept4(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let addr = UserPtr::<u8>::new(args[1]);
+    let addrlen = UserPtr::<u32>::new(args[2]);
+    let flags = args[3];
+    info!("accept4: fd: {}, flags: {:#x}", fd, flags);
+    if flags & !(SOCK_NONBLOCK | SOCK_CLOEXEC) != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let new_socket = with_socket(fd, |socket| socket.accept().map_err(net_error))?;
+    let peer_addr = new_socket.peer_addr().map_err(net_error)?;
+    debug!("accept4: fd: {}, peer: {}", fd, peer_addr);
+    write_socket_address(peer_addr, addr, addrlen)?;
+    if flags & SOCK_NONBLOCK != 0 {
+        new_socket.set_nonblocking(true);
+    }
+    add_socket_fd(new_socket, flags & SOCK_CLOEXEC != 0)
+}
+
+/// 功能:接受 socket 上的一个连接，等价于 flags 为 0 的 accept4；
+/// # Arguments
+/// * `fd`: usize, 监听中的 socket 的文件描述符。
+/// * `addr`: *mut u8, 用于保存对端的地址，可以为空。
+/// * `addrlen`: *mut u32, 地址缓冲区的长度，返回时为地址的实际长度。
+/// 返回值:成功返回新连接的文件描述符，失败返回-1。
+pub fn syscall_accept(args: [usize; 6]) -> SyscallResult {
+    syscall_accept4([args[0], args[1], args[2], 0, 0, 0])
+}
+
+/// 功能:将 socket 连接到远端地址；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `addr`: *const u8, 远端地址。
+/// * `addrlen`: usize, 地址的长度。
+/// 返回值:成功返回0，非阻塞的 TCP socket 正在连接时返回 EINPROGRESS，失败返回-1。
+pub fn syscall_connect(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let addr = read_socket_address(UserPtr::new(args[1]), args[2])?;
+    info!("connect: fd: {}, addr: {}", fd, addr);
+    with_socket(fd, |socket| {
+        socket.connect(addr).map_err(|err| match err {
+            AxError::WouldBlock => SyscallError::EINPROGRESS,
+            AxError::AlreadyExists => SyscallError::EISCONN,
+            err => net_error(err),
+        })?;
+        Ok(0)
+    })
+}
+
+/// 功能:获取 socket 的本地地址；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `addr`: *mut u8, 用于保存本地地址。
+/// * `addrlen`: *mut u32, 地址缓冲区的长度，返回时为地址的实际长度。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_getsockname(args: [usize; 6]) -> SyscallResult {
+    let addr = UserPtr::<u8>::new(args[1]);
+    let addrlen = UserPtr::<u32>::new(args[2]);
+    if addr.is_null() {
+        return Err(SyscallError::EFAULT);
+    }
+    let local_addr = with_socket(args[0], |socket| socket.local_addr().map_err(net_error))?;
+    write_socket_address(local_addr, addr, addrlen)?;
+    Ok(0)
+}
+
+/// 功能:获取 socket 所连接的对端的地址；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `addr`: *mut u8, 用于保存对端的地址。
+/// * `addrlen`: *mut u32, 地址缓冲区的长度，返回时为地址的实际长度。
+/// 返回值:成功返回0，没有连接时返回 ENOTCONN，失败返回-1。
+pub fn syscall_getpeername(args: [usize; 6]) -> SyscallResult {
+    let addr = UserPtr::<u8>::new(args[1]);
+    let addrlen = UserPtr::<u32>::new(args[2]);
+    if addr.is_null() {
+        return Err(SyscallError::EFAULT);
+    }
+    let peer_addr = with_socket(args[0], |socket| socket.peer_addr().map_err(net_error))?;
+    write_socket_address(peer_addr, addr, addrlen)?;
+    Ok(0)
+}
+
+/// 功能:通过 socket 发送数据；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `buf`: *const u8, 要发送的数据。
+/// * `len`: usize, 数据的长度。
+/// * `flags`: u32, MsgFlags 的组合。
+/// * `dest_addr`: *const u8, 目标地址，为空时发往已连接的对端，TCP socket 会忽略该参数。
+/// * `addrlen`: usize, 目标地址的长度。
+/// 返回值:成功返回发送的字节数，失败返回-1。
+pub fn syscall_sendto(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let buf = UserPtr::<u8>::new(args[1]).read_array(args[2])?;
+    let flags = MsgFlags::from_bits_truncate(args[3] as u32);
+    let dest_addr = UserPtr::<u8>::new(args[4]);
+    let to = if dest_addr.is_null() {
+        None
+    } else {
+        Some(read_socket_address(dest_addr, args[5])?)
+    };
+    debug!("sendto: fd: {}, len: {}, flags: {:?}", fd, buf.len(), flags);
+    with_socket(fd, |socket| send_with_flags(socket, &buf, to, flags))
+}
+
+/// 功能:从 socket 接收数据；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `buf`: *mut u8, 用于保存接收到的数据。
+/// * `len`: usize, 缓冲区的长度。
+/// * `flags`: u32, MsgFlags 的组合。
+/// * `src_addr`: *mut u8, 用于保存数据的来源，可以为空。
+/// * `addrlen`: *mut u32, 地址缓冲区的长度，返回时为地址的实际长度。
+/// 返回值:成功返回接收的字节数，对端关闭时返回0，失败返回-1。
+pub fn syscall_recvfrom(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let buf = UserPtr::<u8>::new(args[1]);
+    let flags = MsgFlags::from_bits_truncate(args[3] as u32);
+    let src_addr = UserPtr::<u8>::new(args[4]);
+    let addrlen = UserPtr::<u32>::new(args[5]);
+    debug!("recvfrom: fd: {}, len: {}, flags: {:?}", fd, args[2], flags);
+    buf.check(args[2], MappingFlags::WRITE)?;
+    let mut data = vec![0_u8; args[2]];
+    let (len, from) = with_socket(fd, |socket| recv_with_flags(socket, &mut data, flags))?;
+    buf.write_array(&data[..len])?;
+    write_socket_address(from, src_addr, addrlen)?;
+    Ok(len as isize)
+}
+
+/// 功能:通过 socket 发送多个缓冲区中的数据，辅助数据会被忽略；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `msg`: *const MsgHdr, 消息头，包含目标地址与缓冲区数组。
+/// * `flags`: u32, MsgFlags 的组合。
+/// 返回值:成功返回发送的字节数，失败返回-1。
+pub fn syscall_sendmsg(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let msg = UserPtr::<MsgHdr>::new(args[1]).read()?;
+    let flags = MsgFlags::from_bits_truncate(args[2] as u32);
+    let to = if msg.msg_name.is_null() {
+        None
+    } else {
+        Some(read_socket_address(
+            msg.msg_name.into(),
+            msg.msg_namelen as usize,
+        )?)
+    };
+    // 数据报需要一次发送，因此先将各个缓冲区拼接起来
+    let mut data: Vec<u8> = Vec::new();
+    for buf in user_iovecs(msg.msg_iov.into(), msg.msg_iovlen)? {
+        data.extend(UserPtr::from(buf.base).read_array(buf.len)?);
+    }
+    debug!(
+        "sendmsg: fd: {}, len: {}, flags: {:?}",
+        fd,
+        data.len(),
+        flags
+    );
+    with_socket(fd, |socket| send_with_flags(socket, &data, to, flags))
+}
+
+/// 功能:从 socket 接收数据到多个缓冲区中，不会返回辅助数据；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `msg`: *mut MsgHdr, 消息头，包含用于保存来源地址的缓冲区与缓冲区数组。
+/// * `flags`: u32, MsgFlags 的组合。
+/// 返回值:成功返回接收的字节数，对端关闭时返回0，失败返回-1。
+pub fn syscall_recvmsg(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let msg_ptr = UserPtr::<MsgHdr>::new(args[1]);
+    let mut msg = msg_ptr.read()?;
+    let flags = MsgFlags::from_bits_truncate(args[2] as u32);
+    let bufs = user_iovecs(msg.msg_iov.into(), msg.msg_iovlen)?;
+    for buf in bufs.iter() {
+        UserPtr::from(buf.base).check(buf.len, MappingFlags::WRITE)?;
+    }
+    let total = bufs.iter().map(|buf| buf.len).sum();
+    let mut data = vec![0_u8; total];
+    let (len, from) = with_socket(fd, |socket| recv_with_flags(socket, &mut data, flags))?;
+    debug!("recvmsg: fd: {}, len: {}, flags: {:?}", fd, len, flags);
+    // 将收到的数据依次分散到各个缓冲区中
+    let mut copied = 0;
+    for buf in bufs.iter() {
+        if copied == len {
+            break;
+        }
+        let count = buf.len.min(len - copied);
+        UserPtr::from(buf.base).write_array(&data[copied..copied + count])?;
+        copied += count;
+    }
+    if !msg.msg_name.is_null() {
+        msg.msg_namelen = copy_socket_address(from, msg.msg_name.into(), msg.msg_namelen)?;
+    }
+    msg.msg_controllen = 0;
+    msg.msg_flags = 0;
+    msg_ptr.write(msg)?;
+    Ok(len as isize)
+}
+
+/// 功能:关闭 socket 的读端或写端；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `how`: usize, 关闭的方向，见 ShutdownHow。
+/// 返回值:成功返回0，没有连接时返回 ENOTCONN，失败返回-1。
+pub fn syscall_shutdown(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let how = ShutdownHow::try_from(args[1]).map_err(|_| SyscallError::EINVAL)?;
+    info!("shutdown: fd: {}, how: {:?}", fd, how);
+    with_socket(fd, |socket| {
+        socket.shutdown(how).map_err(net_error)?;
+        Ok(0)
+    })
+}
+
+/// 读取 setsockopt 传入的选项值
+fn read_option<T: Copy>(optval: UserPtr<u8>, optlen: usize) -> Result<T, SyscallError> {
+    if optlen < size_of::<T>() {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(optval.cast::<T>().read()?)
+}
+
+/// 将 getsockopt 的选项值写回用户空间，值会被截断到 optlen 指定的长度
+fn write_option<T>(value: &T, optval: UserPtr<u8>, optlen: UserPtr<u32>) -> SyscallResult {
+    let len = optlen.read()?;
+    if (len as i32) < 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let bytes =
+        unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+    let count = bytes.len().min(len as usize);
+    optval.write_array(&bytes[..count])?;
+    optlen.write(count as u32)?;
+    Ok(0)
+}
+
+/// 读取 SO_RCVTIMEO 与 SO_SNDTIMEO 的值，0 表示一直等待
+fn read_timeout(optval: UserPtr<u8>, optlen: usize) -> Result<Option<TimeVal>, SyscallError> {
+    let timeout: TimeVal = read_option(optval, optlen)?;
+    if timeout.usec >= 1_000_000 {
+        return Err(SyscallError::EDOM);
+    }
+    Ok((timeout.sec != 0 || timeout.usec != 0).then_some(timeout))
+}
+
+/// TCP_CONGESTION 返回的拥塞控制算法的名称
+const TCP_CONGESTION_NAME: [u8; 16] = *b"reno\0\0\0\0\0\0\0\0\0\0\0\0";
+
+/// 功能:设置 socket 的选项；
+///
+/// SO_REUSEADDR、TCP_NODELAY、SO_RCVTIMEO 与 IP_TTL 会影响 socket 的行为，其余支持的选项只被记录
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `level`: usize, 选项所在的层，见 SocketOptionLevel。
+/// * `optname`: usize, 选项的编号。
+/// * `optval`: *const u8, 选项的值。
+/// * `optlen`: usize, 选项的值的长度。
+/// 返回值:成功返回0，不支持的选项返回 ENOPROTOOPT，失败返回-1。
+pub fn syscall_setsockopt(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let level = args[1];
+    let optname = args[2];
+    let optval = UserPtr::<u8>::new(args[3]);
+    let optlen = args[4];
+    info!(
+        "setsockopt: fd: {}, level: {}, optname: {}",
+        fd, level, optname
+    );
+    let level = SocketOptionLevel::try_from(level).map_err(|_| SyscallError::ENOPROTOOPT)?;
+    with_socket(fd, |socket| {
+        match level {
+            SocketOptionLevel::SOL_SOCKET => {
+                let option =
+                    SocketOption::try_from(optname).map_err(|_| SyscallError::ENOPROTOOPT)?;
+                match option {
+                    SocketOption::SO_REUSEADDR
+                    | SocketOption::SO_DONTROUTE
+                    | SocketOption::SO_BROADCAST
+                    | SocketOption::SO_KEEPALIVE => {
+                        let value: i32 = read_option(optval, optlen)?;
+                        socket.set_bool_option(option, value != 0);
+                    }
+                    SocketOption::SO_SNDBUF => {
+                        let value: i32 = read_option(optval, optlen)?;
+                        socket.set_send_buf_size(value.max(0) as u32);
+                    }
+                    SocketOption::SO_RCVBUF => {
+                        let value: i32 = read_option(optval, optlen)?;
+                        socket.set_recv_buf_size(value.max(0) as u32);
+                    }
+                    SocketOption::SO_LINGER => {
+                        socket.set_linger(read_option(optval, optlen)?);
+                    }
+                    SocketOption::SO_RCVTIMEO => {
+                        socket.set_recv_timeout(read_timeout(optval, optlen)?);
+                    }
+                    SocketOption::SO_SNDTIMEO => {
+                        socket.set_send_timeout(read_timeout(optval, optlen)?);
+                    }
+                    // 只读的选项
+                    SocketOption::SO_TYPE
+                    | SocketOption::SO_ERROR
+                    | SocketOption::SO_ACCEPTCONN
+                    | SocketOption::SO_PROTOCOL
+                    | SocketOption::SO_DOMAIN => return Err(SyscallError::ENOPROTOOPT),
+                }
+            }
+            SocketOptionLevel::IPPROTO_TCP => {
+                if socket.socket_type() != SocketType::SOCK_STREAM {
+                    return Err(SyscallError::ENOPROTOOPT);
+                }
+                let option =
+                    TcpSocketOption::try_from(optname).map_err(|_| SyscallError::ENOPROTOOPT)?;
+                match option {
+                    TcpSocketOption::TCP_NODELAY => {
+                        let value: i32 = read_option(optval, optlen)?;
+                        socket.set_nodelay(value != 0);
+                    }
+                    // 只检查参数，不改变 socket 的行为
+                    TcpSocketOption::TCP_MAXSEG => {
+                        read_option::<i32>(optval, optlen)?;
+                    }
+                    TcpSocketOption::TCP_CONGESTION => {
+                        optval.check(optlen, MappingFlags::READ)?;
+                    }
+                }
+            }
+            SocketOptionLevel::IPPROTO_IP => {
+                let option = IpOption::try_from(optname).map_err(|_| SyscallError::ENOPROTOOPT)?;
+                match option {
+                    IpOption::IP_TTL => {
+                        let value: i32 = read_option(optval, optlen)?;
+                        // -1 表示使用默认值
+                        let ttl = match value {
+                            -1 => 64,
+                            1..=255 => value as u8,
+                            _ => return Err(SyscallError::EINVAL),
+                        };
+                        socket.set_ttl(ttl);
+                    }
+                }
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// 功能:获取 socket 的选项；
+/// # Arguments
+/// * `fd`: usize, socket 的文件描述符。
+/// * `level`: usize, 选项所在的层，见 SocketOptionLevel。
+/// * `optname`: usize, 选项的编号。
+/// * `optval`: *mut u8, 用于保存选项的值。
+/// * `optlen`: *mut u32, 缓冲区的长度，返回时为写入的长度。
+/// 返回值:成功返回0，不支持的选项返回 ENOPROTOOPT，失败返回-1。
+pub fn syscall_getsockopt(args: [usize; 6]) -> SyscallResult {
+    let fd = args[0];
+    let level = args[1];
+    let optname = args[2];
+    let optval = UserPtr::<u8>::new(args[3]);
+    let optlen = UserPtr::<u32>::new(args[4]);
+    debug!(
+        "getsockopt: fd: {}, level: {}, optname: {}",
+        fd, level, optname
+    );
+    let level = SocketOptionLevel::try_from(level).map_err(|_| SyscallError::ENOPROTOOPT)?;
+    with_socket(fd, |socket| match level {
+        SocketOptionLevel::SOL_SOCKET => {
+            let option = SocketOption::try_from(optname).map_err(|_| SyscallError::ENOPROTOOPT)?;
+            let value = match option {
+                SocketOption::SO_REUSEADDR
+                | SocketOption::SO_DONTROUTE
+                | SocketOption::SO_BROADCAST
+                | SocketOption::SO_KEEPALIVE => socket.bool_option(option) as i32,
+                SocketOption::SO_TYPE => socket.socket_type() as i32,
+                // 目前没有异步产生的错误
+                SocketOption::SO_ERROR => 0,
+                SocketOption::SO_ACCEPTCONN => socket.is_listening() as i32,
+                SocketOption::SO_PROTOCOL => socket.protocol() as i32,
+                SocketOption::SO_DOMAIN => socket.domain() as i32,
+                SocketOption::SO_SNDBUF => socket.send_buf_size() as i32,
+                SocketOption::SO_RCVBUF => socket.recv_buf_size() as i32,
+                SocketOption::SO_LINGER => return write_option(&socket.linger(), optval, optlen),
+                SocketOption::SO_RCVTIMEO => {
+                    let timeout = socket.recv_timeout().unwrap_or_default();
+                    return write_option(&timeout, optval, optlen);
+                }
+                SocketOption::SO_SNDTIMEO => {
+                    let timeout = socket.send_timeout().unwrap_or_default();
+                    return write_option(&timeout, optval, optlen);
+                }
+            };
+            write_option(&value, optval, optlen)
+        }
+        SocketOptionLevel::IPPROTO_TCP => {
+            if socket.socket_type() != SocketType::SOCK_STREAM {
+                return Err(SyscallError::ENOPROTOOPT);
+            }
+            let option =
+                TcpSocketOption::try_from(optname).map_err(|_| SyscallError::ENOPROTOOPT)?;
+            match option {
+                TcpSocketOption::TCP_NODELAY => {
+                    write_option(&(socket.nodelay() as i32), optval, optlen)
+                }
+                // 以太网 MTU 减去 IP 与 TCP 头部的长度
+                TcpSocketOption::TCP_MAXSEG => write_option(&1460_i32, optval, optlen),
+                TcpSocketOption::TCP_CONGESTION => {
+                    write_option(&TCP_CONGESTION_NAME, optval, optlen)
+                }
+            }
+        }
+        SocketOptionLevel::IPPROTO_IP => {
+            let option = IpOption::try_from(optname).map_err(|_| SyscallError::ENOPROTOOPT)?;
+            match option {
+                IpOption::IP_TTL => write_option(&(socket.ttl() as i32), optval, optlen),
+            }
+        }
+    })
+}
@@ -0,0 +1,6 @@
+//! 提供与网络 socket 相关的 syscall
+
+mod imp;
+pub mod socket;
+
+pub use imp::*;
@@ -0,0 +1,673 @@
+//! 基于 axnet 的 socket
+//!
+//! socket 作为 `FileIO` 保存在文件描述符表中，因此 read、write、dup、close 等操作与普通文件相同。
+//! 目前只支持 AF_INET 下的 TCP 与 UDP，使用的网络设备为回环设备
+extern crate alloc;
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+
+use axerrno::{AxError, AxResult};
+use axfs::api::{FileIO, FileIOType, Kstat, OpenFlags};
+use axnet::{TcpSocket, UdpSocket};
+use axtask::{notify_pollers, Mutex};
+use bitflags::bitflags;
+
+use crate::{normal_file_mode, IoVec, StMode, TimeVal};
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// socket 的协议族
+    pub enum Domain {
+        /// 本地通信
+        AF_UNIX = 1,
+        /// IPv4
+        AF_INET = 2,
+        /// IPv6
+        AF_INET6 = 10,
+    }
+}
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// socket 的类型
+    pub enum SocketType {
+        /// 面向连接的字节流，即 TCP
+        SOCK_STREAM = 1,
+        /// 数据报，即 UDP
+        SOCK_DGRAM = 2,
+        /// 原始套接字
+        SOCK_RAW = 3,
+        /// 面向连接的数据报
+        SOCK_SEQPACKET = 5,
+    }
+}
+
+/// socket 类型中表示非阻塞的标志
+pub const SOCK_NONBLOCK: usize = 0x800;
+/// socket 类型中表示 close-on-exec 的标志
+pub const SOCK_CLOEXEC: usize = 0x80000;
+
+/// TCP 的协议号
+pub const IPPROTO_TCP: usize = 6;
+/// UDP 的协议号
+pub const IPPROTO_UDP: usize = 17;
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// setsockopt / getsockopt 的 level 参数
+    pub enum SocketOptionLevel {
+        /// IP 层的选项
+        IPPROTO_IP = 0,
+        /// socket 层的选项
+        SOL_SOCKET = 1,
+        /// TCP 层的选项
+        IPPROTO_TCP = 6,
+    }
+}
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// SOL_SOCKET 层的选项
+    pub enum SocketOption {
+        /// 允许重用本地地址
+        SO_REUSEADDR = 2,
+        /// socket 的类型，只读
+        SO_TYPE = 3,
+        /// 待处理的错误，只读
+        SO_ERROR = 4,
+        /// 不经过网关直接发送
+        SO_DONTROUTE = 5,
+        /// 允许发送广播
+        SO_BROADCAST = 6,
+        /// 发送缓冲区大小
+        SO_SNDBUF = 7,
+        /// 接收缓冲区大小
+        SO_RCVBUF = 8,
+        /// 保持连接
+        SO_KEEPALIVE = 9,
+        /// 关闭时等待数据发送完毕
+        SO_LINGER = 13,
+        /// 接收超时时间
+        SO_RCVTIMEO = 20,
+        /// 发送超时时间
+        SO_SNDTIMEO = 21,
+        /// 是否正在监听，只读
+        SO_ACCEPTCONN = 30,
+        /// socket 的协议，只读
+        SO_PROTOCOL = 38,
+        /// socket 的协议族，只读
+        SO_DOMAIN = 39,
+    }
+}
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// IPPROTO_TCP 层的选项
+    pub enum TcpSocketOption {
+        /// 关闭 Nagle 算法
+        TCP_NODELAY = 1,
+        /// 最大报文段长度
+        TCP_MAXSEG = 2,
+        /// 拥塞控制算法
+        TCP_CONGESTION = 13,
+    }
+}
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// IPPROTO_IP 层的选项
+    pub enum IpOption {
+        /// 发送报文的 TTL
+        IP_TTL = 2,
+    }
+}
+
+numeric_enum_macro::numeric_enum! {
+    #[repr(usize)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+    /// shutdown 关闭的方向
+    pub enum ShutdownHow {
+        /// 关闭读端
+        SHUT_RD = 0,
+        /// 关闭写端
+        SHUT_WR = 1,
+        /// 同时关闭读端与写端
+        SHUT_RDWR = 2,
+    }
+}
+
+bitflags! {
+    /// send / recv 系列系统调用的 flags 参数
+    #[derive(Clone, Copy, Debug)]
+    pub struct MsgFlags: u32 {
+        /// 带外数据，目前不支持
+        const MSG_OOB = 1;
+        /// 读取数据但不将其从接收队列中移除
+        const MSG_PEEK = 2;
+        /// 数据报被截断时仍返回其实际长度
+        const MSG_TRUNC = 0x20;
+        /// 本次调用不阻塞
+        const MSG_DONTWAIT = 0x40;
+        /// 等待直到读满缓冲区
+        const MSG_WAITALL = 0x100;
+        /// 对端关闭时不发送 SIGPIPE
+        const MSG_NOSIGNAL = 0x4000;
+    }
+}
+
+/// IPv4 的 socket 地址，即 struct sockaddr_in
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SockAddrIn {
+    /// 协议族，为 AF_INET
+    pub sin_family: u16,
+    /// 端口号，网络字节序
+    pub sin_port: u16,
+    /// IP 地址，网络字节序
+    pub sin_addr: [u8; 4],
+    /// 填充
+    pub sin_zero: [u8; 8],
+}
+
+impl From<SocketAddrV4> for SockAddrIn {
+    fn from(addr: SocketAddrV4) -> Self {
+        Self {
+            sin_family: Domain::AF_INET as u16,
+            sin_port: addr.port().to_be(),
+            sin_addr: addr.ip().octets(),
+            sin_zero: [0; 8],
+        }
+    }
+}
+
+impl From<SockAddrIn> for SocketAddrV4 {
+    fn from(addr: SockAddrIn) -> Self {
+        SocketAddrV4::new(Ipv4Addr::from(addr.sin_addr), u16::from_be(addr.sin_port))
+    }
+}
+
+/// sendmsg / recvmsg 使用的消息头，即 struct msghdr
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct MsgHdr {
+    /// socket 地址
+    pub msg_name: *mut u8,
+    /// socket 地址的长度
+    pub msg_namelen: u32,
+    /// 缓冲区数组
+    pub msg_iov: *mut IoVec,
+    /// 缓冲区的个数
+    pub msg_iovlen: usize,
+    /// 辅助数据，目前不支持
+    pub msg_control: *mut u8,
+    /// 辅助数据的长度
+    pub msg_controllen: usize,
+    /// 接收到的消息的标志
+    pub msg_flags: i32,
+}
+
+/// SO_LINGER 使用的结构体
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct Linger {
+    /// 是否启用
+    pub l_onoff: i32,
+    /// 等待的秒数
+    pub l_linger: i32,
+}
+
+/// socket 缓冲区的默认大小，与 axnet 中 TCP 与 UDP 缓冲区的大小一致
+const DEFAULT_BUF_SIZE: u32 = 64 * 1024;
+
+/// 发送报文的默认 TTL
+const DEFAULT_TTL: u8 = 64;
+
+/// socket 底层使用的 axnet 套接字
+pub enum SocketInner {
+    /// TCP 套接字
+    Tcp(TcpSocket),
+    /// UDP 套接字
+    Udp(UdpSocket),
+}
+
+/// 保存在文件描述符表中的 socket
+pub struct Socket {
+    domain: Domain,
+    socket_type: SocketType,
+    inner: SocketInner,
+    /// 文件状态标志，其中的 O_NONBLOCK 会同步到底层套接字
+    flags: Mutex<OpenFlags>,
+    /// 只需要记录开关状态的 SOL_SOCKET 选项，第 n 位对应编号为 n 的选项
+    bool_options: AtomicU64,
+    /// 是否关闭 Nagle 算法，在连接建立后同步到底层套接字
+    nodelay: AtomicBool,
+    /// 发送报文的 TTL
+    ttl: AtomicU8,
+    send_buf_size: AtomicU32,
+    recv_buf_size: AtomicU32,
+    linger: Mutex<Linger>,
+    /// 接收超时时间，为 None 时一直等待
+    recv_timeout: Mutex<Option<TimeVal>>,
+    /// 发送超时时间，目前只被记录
+    send_timeout: Mutex<Option<TimeVal>>,
+    /// 读端已被 shutdown
+    read_shutdown: AtomicBool,
+    /// 写端已被 shutdown
+    write_shutdown: AtomicBool,
+}
+
+impl Socket {
+    /// 创建一个新的 socket，类型只能为 SOCK_STREAM 或 SOCK_DGRAM
+    pub fn new(domain: Domain, socket_type: SocketType) -> AxResult<Self> {
+        let inner = match socket_type {
+            SocketType::SOCK_STREAM => SocketInner::Tcp(TcpSocket::new()),
+            SocketType::SOCK_DGRAM => SocketInner::Udp(UdpSocket::new()),
+            _ => return Err(AxError::Unsupported),
+        };
+        Ok(Self::with_inner(domain, socket_type, inner))
+    }
+
+    fn with_inner(domain: Domain, socket_type: SocketType, inner: SocketInner) -> Self {
+        Self {
+            domain,
+            socket_type,
+            inner,
+            flags: Mutex::new(OpenFlags::RDWR),
+            bool_options: AtomicU64::new(0),
+            nodelay: AtomicBool::new(false),
+            ttl: AtomicU8::new(DEFAULT_TTL),
+            send_buf_size: AtomicU32::new(DEFAULT_BUF_SIZE),
+            recv_buf_size: AtomicU32::new(DEFAULT_BUF_SIZE),
+            linger: Mutex::new(Linger::default()),
+            recv_timeout: Mutex::new(None),
+            send_timeout: Mutex::new(None),
+            read_shutdown: AtomicBool::new(false),
+            write_shutdown: AtomicBool::new(false),
+        }
+    }
+
+    /// socket 的协议族
+    pub fn domain(&self) -> Domain {
+        self.domain
+    }
+
+    /// socket 的类型
+    pub fn socket_type(&self) -> SocketType {
+        self.socket_type
+    }
+
+    /// socket 的协议号
+    pub fn protocol(&self) -> usize {
+        match self.inner {
+            SocketInner::Tcp(_) => IPPROTO_TCP,
+            SocketInner::Udp(_) => IPPROTO_UDP,
+        }
+    }
+
+    /// 是否处于非阻塞模式
+    pub fn is_nonblocking(&self) -> bool {
+        self.flags.lock().contains(OpenFlags::NON_BLOCK)
+    }
+
+    /// 设置是否处于非阻塞模式
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        let mut flags = self.flags.lock();
+        flags.set(OpenFlags::NON_BLOCK, nonblocking);
+        match &self.inner {
+            SocketInner::Tcp(socket) => socket.set_nonblocking(nonblocking),
+            SocketInner::Udp(socket) => socket.set_nonblocking(nonblocking),
+        }
+    }
+
+    /// 绑定到本地地址，端口为 0 时自动分配
+    pub fn bind(&self, addr: SocketAddr) -> AxResult {
+        match &self.inner {
+            SocketInner::Tcp(socket) => socket.bind(addr),
+            SocketInner::Udp(socket) => {
+                socket.set_reuse_addr(self.bool_option(SocketOption::SO_REUSEADDR));
+                socket.bind(addr)
+            }
+        }
+    }
+
+    /// 开始监听，只有 TCP socket 可以监听
+    pub fn listen(&self) -> AxResult {
+        match &self.inner {
+            SocketInner::Tcp(socket) => socket.listen(),
+            SocketInner::Udp(_) => Err(AxError::Unsupported),
+        }
+    }
+
+    /// 是否正在监听
+    pub fn is_listening(&self) -> bool {
+        match &self.inner {
+            SocketInner::Tcp(socket) => socket.is_listening(),
+            SocketInner::Udp(_) => false,
+        }
+    }
+
+    /// 接受一个新的连接，新的 socket 继承当前 socket 的选项，但处于阻塞模式
+    pub fn accept(&self) -> AxResult<Socket> {
+        let SocketInner::Tcp(socket) = &self.inner else {
+            return Err(AxError::Unsupported);
+        };
+        let new_socket = socket.accept()?;
+        let accepted =
+            Self::with_inner(self.domain, self.socket_type, SocketInner::Tcp(new_socket));
+        accepted
+            .bool_options
+            .store(self.bool_options.load(Ordering::Acquire), Ordering::Release);
+        accepted
+            .nodelay
+            .store(self.nodelay.load(Ordering::Acquire), Ordering::Release);
+        accepted.apply_nodelay();
+        Ok(accepted)
+    }
+
+    /// 连接到远端地址
+    ///
+    /// 对于 UDP socket，只是设置默认的目标地址并过滤接收到的数据报
+    pub fn connect(&self, addr: SocketAddr) -> AxResult {
+        match &self.inner {
+            SocketInner::Tcp(socket) => {
+                socket.connect(addr)?;
+                self.apply_nodelay();
+                // 监听端在回环设备上收到连接后变为可读
+                notify_pollers();
+                Ok(())
+            }
+            SocketInner::Udp(socket) => socket.connect(addr),
+        }
+    }
+
+    /// 本地地址，没有绑定时为 0.0.0.0:0
+    pub fn local_addr(&self) -> AxResult<SocketAddr> {
+        let addr = match &self.inner {
+            SocketInner::Tcp(socket) => socket.local_addr(),
+            SocketInner::Udp(socket) => socket.local_addr(),
+        };
+        match addr {
+            Err(AxError::NotConnected) => {
+                Ok(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)))
+            }
+            addr => addr,
+        }
+    }
+
+    /// 远端地址，没有连接时返回 `NotConnected`
+    pub fn peer_addr(&self) -> AxResult<SocketAddr> {
+        match &self.inner {
+            SocketInner::Tcp(socket) if socket.is_connected() => socket.peer_addr(),
+            SocketInner::Tcp(_) => Err(AxError::NotConnected),
+            SocketInner::Udp(socket) => socket.peer_addr(),
+        }
+    }
+
+    /// 发送数据，`to` 为 None 时发往已连接的远端
+    ///
+    /// TCP socket 会忽略 `to`；没有绑定的 UDP socket 会先绑定到随机端口。
+    /// 写端已被 shutdown 时返回 `ConnectionReset`
+    pub fn send(&self, buf: &[u8], to: Option<SocketAddr>) -> AxResult<usize> {
+        if self.write_shutdown.load(Ordering::Acquire) {
+            return Err(AxError::ConnectionReset);
+        }
+        let len = match &self.inner {
+            SocketInner::Tcp(socket) => socket.send(buf)?,
+            SocketInner::Udp(socket) => {
+                if socket.local_addr().is_err() {
+                    socket.bind(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)))?;
+                }
+                match to {
+                    Some(addr) => socket.send_to(buf, addr)?,
+                    None => socket.send(buf)?,
+                }
+            }
+        };
+        // 数据经回环设备到达后对端可能变为可读
+        notify_pollers();
+        Ok(len)
+    }
+
+    /// 接收数据，返回接收的字节数与数据的来源
+    ///
+    /// 读端已被 shutdown 时返回 0。设置了 SO_RCVTIMEO 时，超时返回 `Timeout`
+    pub fn recv(&self, buf: &mut [u8], flags: MsgFlags) -> AxResult<(usize, SocketAddr)> {
+        if self.read_shutdown.load(Ordering::Acquire) {
+            return Ok((0, self.peer_addr().or_else(|_| self.local_addr())?));
+        }
+        let timeout = self
+            .recv_timeout
+            .lock()
+            .map(|timeout| timeout.turn_to_ticks());
+        let peek = flags.contains(MsgFlags::MSG_PEEK);
+        match &self.inner {
+            SocketInner::Tcp(socket) => {
+                let len = match timeout {
+                    _ if peek => socket.peek(buf)?,
+                    Some(ticks) if ticks > 0 => socket.recv_timeout(buf, ticks)?,
+                    _ => socket.recv(buf)?,
+                };
+                Ok((len, socket.peer_addr()?))
+            }
+            SocketInner::Udp(socket) => {
+                if let Ok(peer) = socket.peer_addr() {
+                    return Ok((socket.recv(buf)?, peer));
+                }
+                match timeout {
+                    _ if peek => socket.peek_from(buf),
+                    Some(ticks) if ticks > 0 => socket.recv_from_timeout(buf, ticks),
+                    _ => socket.recv_from(buf),
+                }
+            }
+        }
+    }
+
+    /// 关闭 socket 的读端或写端
+    ///
+    /// 关闭 TCP socket 的写端会向对端发送 FIN；关闭监听中的 socket 会停止监听
+    pub fn shutdown(&self, how: ShutdownHow) -> AxResult {
+        match &self.inner {
+            SocketInner::Tcp(socket) => {
+                if socket.is_listening() {
+                    if how != ShutdownHow::SHUT_WR {
+                        socket.shutdown()?;
+                    }
+                    return Ok(());
+                }
+                if !socket.is_connected() {
+                    return Err(AxError::NotConnected);
+                }
+                if how != ShutdownHow::SHUT_RD {
+                    socket.close();
+                }
+            }
+            SocketInner::Udp(socket) => {
+                if socket.peer_addr().is_err() {
+                    return Err(AxError::NotConnected);
+                }
+            }
+        }
+        if how != ShutdownHow::SHUT_WR {
+            self.read_shutdown.store(true, Ordering::Release);
+        }
+        if how != ShutdownHow::SHUT_RD {
+            self.write_shutdown.store(true, Ordering::Release);
+        }
+        notify_pollers();
+        Ok(())
+    }
+
+    /// 获取只需要记录开关状态的选项
+    pub fn bool_option(&self, option: SocketOption) -> bool {
+        self.bool_options.load(Ordering::Acquire) & (1 << option as usize) != 0
+    }
+
+    /// 设置只需要记录开关状态的选项
+    pub fn set_bool_option(&self, option: SocketOption, value: bool) {
+        if value {
+            self.bool_options
+                .fetch_or(1 << option as usize, Ordering::AcqRel);
+        } else {
+            self.bool_options
+                .fetch_and(!(1 << option as usize), Ordering::AcqRel);
+        }
+    }
+
+    /// 是否关闭了 Nagle 算法
+    pub fn nodelay(&self) -> bool {
+        self.nodelay.load(Ordering::Acquire)
+    }
+
+    /// 设置是否关闭 Nagle 算法，只对 TCP socket 有效
+    pub fn set_nodelay(&self, nodelay: bool) {
+        self.nodelay.store(nodelay, Ordering::Release);
+        self.apply_nodelay();
+    }
+
+    /// 在底层套接字创建后同步 TCP_NODELAY，未连接时不做任何事
+    fn apply_nodelay(&self) {
+        if let SocketInner::Tcp(socket) = &self.inner {
+            let _ = socket.set_nagle_enabled(!self.nodelay());
+        }
+    }
+
+    /// 发送报文的 TTL
+    pub fn ttl(&self) -> u8 {
+        self.ttl.load(Ordering::Acquire)
+    }
+
+    /// 设置发送报文的 TTL，目前只对 UDP socket 生效
+    pub fn set_ttl(&self, ttl: u8) {
+        self.ttl.store(ttl, Ordering::Release);
+        if let SocketInner::Udp(socket) = &self.inner {
+            socket.set_socket_ttl(ttl);
+        }
+    }
+
+    /// 发送缓冲区的大小
+    pub fn send_buf_size(&self) -> u32 {
+        self.send_buf_size.load(Ordering::Acquire)
+    }
+
+    /// 设置发送缓冲区的大小，目前只被记录
+    pub fn set_send_buf_size(&self, size: u32) {
+        self.send_buf_size.store(size, Ordering::Release);
+    }
+
+    /// 接收缓冲区的大小
+    pub fn recv_buf_size(&self) -> u32 {
+        self.recv_buf_size.load(Ordering::Acquire)
+    }
+
+    /// 设置接收缓冲区的大小，目前只被记录
+    pub fn set_recv_buf_size(&self, size: u32) {
+        self.recv_buf_size.store(size, Ordering::Release);
+    }
+
+    /// SO_LINGER 的设置
+    pub fn linger(&self) -> Linger {
+        *self.linger.lock()
+    }
+
+    /// 设置 SO_LINGER，目前只被记录
+    pub fn set_linger(&self, linger: Linger) {
+        *self.linger.lock() = linger;
+    }
+
+    /// 接收超时时间
+    pub fn recv_timeout(&self) -> Option<TimeVal> {
+        *self.recv_timeout.lock()
+    }
+
+    /// 设置接收超时时间，为 None 或 0 时一直等待
+    pub fn set_recv_timeout(&self, timeout: Option<TimeVal>) {
+        *self.recv_timeout.lock() = timeout;
+    }
+
+    /// 发送超时时间
+    pub fn send_timeout(&self) -> Option<TimeVal> {
+        *self.send_timeout.lock()
+    }
+
+    /// 设置发送超时时间，目前只被记录
+    pub fn set_send_timeout(&self, timeout: Option<TimeVal>) {
+        *self.send_timeout.lock() = timeout;
+    }
+
+    /// 底层套接字是否可读、可写
+    fn poll(&self) -> (bool, bool) {
+        let state = match &self.inner {
+            SocketInner::Tcp(socket) => socket.poll(),
+            SocketInner::Udp(socket) => socket.poll(),
+        };
+        state
+            .map(|state| (state.readable, state.writable))
+            .unwrap_or((false, false))
+    }
+}
+
+impl FileIO for Socket {
+    fn read(&self, buf: &mut [u8]) -> AxResult<usize> {
+        self.recv(buf, MsgFlags::empty()).map(|(len, _)| len)
+    }
+
+    fn write(&self, buf: &[u8]) -> AxResult<usize> {
+        self.send(buf, None)
+    }
+
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn executable(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> FileIOType {
+        FileIOType::Socket
+    }
+
+    fn get_stat(&self) -> AxResult<Kstat> {
+        Ok(Kstat {
+            st_dev: 1,
+            st_mode: normal_file_mode(StMode::S_IFSOCK).bits(),
+            st_nlink: 1,
+            ..Default::default()
+        })
+    }
+
+    fn set_status(&self, flags: OpenFlags) -> bool {
+        self.set_nonblocking(flags.contains(OpenFlags::NON_BLOCK));
+        *self.flags.lock() = flags;
+        true
+    }
+
+    fn get_status(&self) -> OpenFlags {
+        *self.flags.lock()
+    }
+
+    fn ready_to_read(&self) -> bool {
+        self.read_shutdown.load(Ordering::Acquire) || self.poll().0
+    }
+
+    fn ready_to_write(&self) -> bool {
+        self.write_shutdown.load(Ordering::Acquire) || self.poll().1
+    }
+}
@@ -0,0 +1,318 @@
+//! 系统调用表
+//!
+//! 每个系统调用在表中占一行，给出其名称、在 riscv64/aarch64 与 x86_64 上的编号、处理函数以及跟踪时参数的解码方式。
+//! 系统调用的分发、跟踪时的名称查找以及已实现系统调用的列表都由这张表生成，添加系统调用时只需要在表中添加一行。
+//!
+//! 编号为 `_` 表示该系统调用在对应的架构上不存在，只在某些架构或开启某些 feature 时才有的处理函数需要在行前加上 `#[cfg(...)]`。
+//! 同一架构上的编号重复时会在编译期报错。
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::strace::Arg;
+use crate::syscall_fs::imp::*;
+use crate::syscall_mem::*;
+#[cfg(feature = "net")]
+use crate::syscall_net::*;
+use crate::syscall_task::*;
+use crate::SyscallReturn;
+
+/// 系统调用表中的一项
+pub(crate) struct SyscallEntry {
+    /// 系统调用的名称
+    pub name: &'static str,
+    /// 在当前架构上的编号，为空时表示当前架构上没有该系统调用
+    pub id: Option<usize>,
+    /// 处理函数，阻塞的系统调用返回待等待的 future
+    pub handler: fn([usize; 6]) -> SyscallReturn,
+    /// 跟踪时各个参数的解码方式
+    pub args: &'static [Arg],
+}
+
+/// 当前架构上的系统调用编号，`_` 表示不存在
+macro_rules! sysno {
+    (_) => {
+        None
+    };
+    ($id:literal) => {
+        Some($id)
+    };
+}
+
+/// 由每一行生成 [`SYSCALL_TABLE`]
+macro_rules! syscall_table {
+    ($(
+        $(#[$attr:meta])*
+        $name:ident = [$generic:tt, $x86:tt] => $handler:path, [$($arg:ident),* $(,)?];
+    )*) => {
+        /// 所有已实现的系统调用
+        const SYSCALL_TABLE: &[SyscallEntry] = &[$(
+            $(#[$attr])*
+            SyscallEntry {
+                name: stringify!($name),
+                id: if cfg!(target_arch = "x86_64") {
+                    sysno!($x86)
+                } else {
+                    sysno!($generic)
+                },
+                handler: |args| $handler(args).into(),
+                args: &[$(Arg::$arg),*],
+            },
+        )*];
+    };
+}
+
+syscall_table! {
+    // 名称 = [riscv64/aarch64 上的编号, x86_64 上的编号] => 处理函数, [参数的解码方式];
+    // 文件系统
+    openat = [56, 257] => syscall_openat, [DirFd, Str, OpenFlags, Oct];
+    close = [57, 3] => syscall_close, [Fd];
+    read = [63, 0] => syscall_read, [Fd, Hex, Dec];
+    write = [64, 1] => syscall_write, [Fd, Hex, Dec];
+    fstat = [80, 5] => syscall_fstat, [Fd, Hex];
+    fstatat = [79, 262] => syscall_fstatat, [DirFd, Str, Hex, AtFlags];
+    #[cfg(target_arch = "x86_64")]
+    stat = [_, 4] => syscall_stat, [Str, Hex];
+    #[cfg(target_arch = "x86_64")]
+    lstat = [_, 6] => syscall_lstat, [Str, Hex];
+    statx = [291, 332] => syscall_statx, [DirFd, Str, AtFlags, Hex, Hex];
+    getdents64 = [61, 217] => syscall_getdents64, [Fd, Hex, Dec];
+    statfs = [43, 137] => syscall_statfs, [Str, Hex];
+    fstatfs = [44, 138] => syscall_fstatfs, [Fd, Hex];
+    readv = [65, 19] => syscall_readv, [Fd, Hex, Dec];
+    writev = [66, 20] => syscall_writev, [Fd, Hex, Dec];
+    pread64 = [67, 17] => syscall_pread64, [Fd, Hex, Dec, Dec];
+    pwrite64 = [68, 18] => syscall_pwrite64, [Fd, Hex, Dec, Dec];
+    preadv = [69, 295] => syscall_preadv, [Fd, Hex, Dec, Dec];
+    pwritev = [70, 296] => syscall_pwritev, [Fd, Hex, Dec, Dec];
+    preadv2 = [286, 327] => syscall_preadv2, [Fd, Hex, Dec, Dec, Hex];
+    pwritev2 = [287, 328] => syscall_pwritev2, [Fd, Hex, Dec, Dec, Hex];
+    lseek = [62, 8] => syscall_lseek, [Fd, Dec, Dec];
+    pipe2 = [59, 293] => syscall_pipe2, [Hex, OpenFlags];
+    #[cfg(target_arch = "x86_64")]
+    pipe = [_, 22] => syscall_pipe, [Hex];
+    dup = [23, 32] => syscall_dup, [Fd];
+    dup3 = [24, 292] => syscall_dup3, [Fd, Fd, OpenFlags];
+    #[cfg(target_arch = "x86_64")]
+    dup2 = [_, 33] => syscall_dup2, [Fd, Fd];
+    fcntl64 = [25, 72] => syscall_fcntl64, [Fd, Dec, Hex];
+    ioctl = [29, 16] => syscall_ioctl, [Fd, Hex, Hex];
+    close_range = [436, 436] => syscall_close_range, [Fd, Fd, Hex];
+    ppoll = [73, 271] => syscall_ppoll, [Hex, Dec, Hex, Hex, Dec];
+    #[cfg(target_arch = "x86_64")]
+    poll = [_, 7] => syscall_poll, [Hex, Dec, Dec];
+    pselect6 = [72, 270] => syscall_pselect6, [Dec, Hex, Hex, Hex, Hex, Hex];
+    #[cfg(target_arch = "x86_64")]
+    select = [_, 23] => syscall_select, [Dec, Hex, Hex, Hex, Hex];
+    epoll_create1 = [20, 291] => syscall_epoll_create1, [Hex];
+    #[cfg(target_arch = "x86_64")]
+    epoll_create = [_, 213] => syscall_epoll_create, [Dec];
+    epoll_ctl = [21, 233] => syscall_epoll_ctl, [Fd, Dec, Fd, Hex];
+    epoll_pwait = [22, 281] => syscall_epoll_pwait, [Fd, Hex, Dec, Dec, Hex, Dec];
+    #[cfg(target_arch = "x86_64")]
+    epoll_wait = [_, 232] => syscall_epoll_wait, [Fd, Hex, Dec, Dec];
+    eventfd2 = [19, 290] => syscall_eventfd2, [Dec, Hex];
+    #[cfg(target_arch = "x86_64")]
+    eventfd = [_, 284] => syscall_eventfd, [Dec];
+    timerfd_create = [85, 283] => syscall_timerfd_create, [Dec, Hex];
+    timerfd_settime = [86, 286] => syscall_timerfd_settime, [Fd, Hex, Hex, Hex];
+    timerfd_gettime = [87, 287] => syscall_timerfd_gettime, [Fd, Hex];
+    signalfd4 = [74, 289] => syscall_signalfd4, [Fd, Hex, Dec, Hex];
+    #[cfg(target_arch = "x86_64")]
+    signalfd = [_, 282] => syscall_signalfd, [Fd, Hex, Dec];
+    memfd_create = [279, 319] => syscall_memfd_create, [Str, Hex];
+    mount = [40, 165] => syscall_mount, [Str, Str, Str, Hex, Hex];
+    umount2 = [39, 166] => syscall_umount2, [Str, Hex];
+    sendfile64 = [71, 40] => syscall_sendfile64, [Fd, Fd, Hex, Dec];
+    copy_file_range = [285, 326] => syscall_copy_file_range, [Fd, Hex, Fd, Hex, Dec, Hex];
+    splice = [76, 275] => syscall_splice, [Fd, Hex, Fd, Hex, Dec, Hex];
+    tee = [77, 276] => syscall_tee, [Fd, Fd, Dec, Hex];
+    getcwd = [17, 79] => syscall_getcwd, [Hex, Dec];
+    chdir = [49, 80] => syscall_chdir, [Str];
+    fchdir = [50, 81] => syscall_fchdir, [Fd];
+    mkdirat = [34, 258] => syscall_mkdirat, [DirFd, Str, Oct];
+    unlinkat = [35, 263] => syscall_unlinkat, [DirFd, Str, AtFlags];
+    linkat = [37, 265] => syscall_linkat, [DirFd, Str, DirFd, Str, Hex];
+    symlinkat = [36, 266] => syscall_symlinkat, [Str, DirFd, Str];
+    readlinkat = [78, 267] => syscall_readlinkat, [DirFd, Str, Hex, Dec];
+    // riscv64 上只有 renameat2
+    #[cfg(not(target_arch = "riscv64"))]
+    renameat = [38, 264] => syscall_renameat, [DirFd, Str, DirFd, Str, Hex];
+    renameat2 = [276, 316] => syscall_renameat2, [DirFd, Str, DirFd, Str, Hex];
+    #[cfg(target_arch = "x86_64")]
+    mkdir = [_, 83] => syscall_mkdir, [Str];
+    #[cfg(target_arch = "x86_64")]
+    rmdir = [_, 84] => syscall_rmdir, [Str];
+    #[cfg(target_arch = "x86_64")]
+    unlink = [_, 87] => syscall_unlink, [Str];
+    #[cfg(target_arch = "x86_64")]
+    link = [_, 86] => syscall_link, [Str, Str];
+    #[cfg(target_arch = "x86_64")]
+    symlink = [_, 88] => syscall_symlink, [Str, Str];
+    #[cfg(target_arch = "x86_64")]
+    readlink = [_, 89] => syscall_readlink, [Str, Hex, Dec];
+    #[cfg(target_arch = "x86_64")]
+    rename = [_, 82] => syscall_rename, [Str, Str];
+
+    // 地址空间
+    brk = [214, 12] => syscall_brk, [Hex, Dec];
+    mmap = [222, 9] => syscall_mmap, [Hex, Dec, Prot, MapFlags, Fd, Hex];
+    munmap = [215, 11] => syscall_munmap, [Hex, Dec];
+    mprotect = [226, 10] => syscall_mprotect, [Hex, Dec, Prot];
+    mremap = [216, 25] => syscall_mremap, [Hex, Dec, Dec, Hex, Hex];
+    msync = [227, 26] => syscall_msync, [Hex, Dec, Hex];
+    madvise = [233, 28] => syscall_madvise, [Hex, Dec, Dec];
+    shmget = [194, 29] => syscall_shmget, [Dec, Dec, Oct];
+    shmctl = [195, 31] => syscall_shmctl, [Dec, Dec, Hex];
+    shmat = [196, 30] => syscall_shmat, [Dec, Hex, Hex];
+    shmdt = [197, 67] => syscall_shmdt, [Hex];
+
+    // 任务、信号、时间与调度
+    exit = [93, 60] => syscall_exit, [Dec];
+    exit_group = [94, 231] => syscall_exit_group, [Dec];
+    set_tid_address = [96, 218] => syscall_set_tid_address, [Hex];
+    getpid = [172, 39] => syscall_getpid, [];
+    getppid = [173, 110] => syscall_getppid, [];
+    setpgid = [154, 109] => syscall_setpgid, [Dec, Dec];
+    getpgid = [155, 121] => syscall_getpgid, [Dec];
+    #[cfg(target_arch = "x86_64")]
+    getpgrp = [_, 111] => syscall_getpgrp, [];
+    gettid = [178, 186] => syscall_gettid, [];
+    clone = [220, 56] => syscall_clone, [CloneFlags, Hex, Hex, Hex, Hex];
+    clone3 = [435, 435] => syscall_clone3, [Hex, Dec];
+    #[cfg(target_arch = "x86_64")]
+    fork = [_, 57] => syscall_fork, [];
+    #[cfg(target_arch = "x86_64")]
+    vfork = [_, 58] => syscall_vfork, [];
+    execve = [221, 59] => syscall_execve, [Str, Hex, Hex];
+    wait4 = [260, 61] => syscall_wait4, [Dec, Hex, Hex, Hex];
+    sigaction = [134, 13] => syscall_sigaction, [Signal, Hex, Hex];
+    sigprocmask = [135, 14] => syscall_sigprocmask, [Dec, Hex, Hex, Dec];
+    sigreturn = [139, 15] => syscall_sigreturn, [];
+    sigaltstack = [132, 131] => syscall_sigaltstack, [Hex, Hex];
+    sigtimedwait = [137, 128] => syscall_sigtimedwait, [Hex, Hex, Hex, Dec];
+    kill = [129, 62] => syscall_kill, [Dec, Signal];
+    tkill = [130, 200] => syscall_tkill, [Dec, Signal];
+    tgkill = [131, 234] => syscall_tgkill, [Dec, Dec, Signal];
+    futex = [98, 202] => syscall_futex, [Hex, Dec, Dec, Hex, Hex, Dec];
+    set_robust_list = [99, 273] => syscall_set_robust_list, [Hex, Dec];
+    get_robust_list = [100, 274] => syscall_get_robust_list, [Dec, Hex, Hex];
+    nanosleep = [101, 35] => syscall_nanosleep, [Hex, Hex];
+    clock_nanosleep = [115, 230] => syscall_clock_nanosleep, [Dec, Hex, Hex, Hex];
+    clock_gettime = [113, 228] => syscall_clock_gettime, [Dec, Hex];
+    clock_getres = [114, 229] => syscall_clock_getres, [Dec, Hex];
+    gettimeofday = [169, 96] => syscall_gettimeofday, [Hex, Hex];
+    times = [153, 100] => syscall_times, [Hex];
+    getrusage = [165, 98] => syscall_getrusage, [Dec, Hex];
+    getitimer = [102, 36] => syscall_getitimer, [Dec, Hex];
+    setitimer = [103, 38] => syscall_setitimer, [Dec, Hex, Hex];
+    uname = [160, 63] => syscall_uname, [Hex];
+    sysinfo = [179, 99] => syscall_sysinfo, [Hex];
+    prlimit64 = [261, 302] => syscall_prlimit64, [Dec, Dec, Hex, Hex];
+    getrlimit = [163, 97] => syscall_getrlimit, [Dec, Hex];
+    setrlimit = [164, 160] => syscall_setrlimit, [Dec, Hex];
+    prctl = [167, 157] => syscall_prctl, [Dec, Hex, Hex, Hex, Hex];
+    getrandom = [278, 318] => syscall_getrandom, [Hex, Dec, Hex];
+    sched_yield = [124, 24] => syscall_sched_yield, [];
+    sched_setaffinity = [122, 203] => syscall_sched_setaffinity, [Dec, Dec, Hex];
+    sched_getaffinity = [123, 204] => syscall_sched_getaffinity, [Dec, Dec, Hex];
+    sched_setscheduler = [119, 144] => syscall_sched_setscheduler, [Dec, Dec, Hex];
+    sched_getscheduler = [120, 145] => syscall_sched_getscheduler, [Dec];
+    sched_setparam = [118, 142] => syscall_sched_setparam, [Dec, Hex];
+    sched_getparam = [121, 143] => syscall_sched_getparam, [Dec, Hex];
+    getpriority = [141, 140] => syscall_getpriority, [Dec, Dec];
+    setpriority = [140, 141] => syscall_setpriority, [Dec, Dec, Dec];
+
+    // 网络
+    #[cfg(feature = "net")]
+    socket = [198, 41] => syscall_socket, [Dec, Dec, Dec];
+    #[cfg(feature = "net")]
+    bind = [200, 49] => syscall_bind, [Fd, Hex, Dec];
+    #[cfg(feature = "net")]
+    listen = [201, 50] => syscall_listen, [Fd, Dec];
+    #[cfg(feature = "net")]
+    accept = [202, 43] => syscall_accept, [Fd, Hex, Hex];
+    #[cfg(feature = "net")]
+    accept4 = [242, 288] => syscall_accept4, [Fd, Hex, Hex, Hex];
+    #[cfg(feature = "net")]
+    connect = [203, 42] => syscall_connect, [Fd, Hex, Dec];
+    #[cfg(feature = "net")]
+    getsockname = [204, 51] => syscall_getsockname, [Fd, Hex, Hex];
+    #[cfg(feature = "net")]
+    getpeername = [205, 52] => syscall_getpeername, [Fd, Hex, Hex];
+    #[cfg(feature = "net")]
+    sendto = [206, 44] => syscall_sendto, [Fd, Hex, Dec, Hex, Hex, Dec];
+    #[cfg(feature = "net")]
+    recvfrom = [207, 45] => syscall_recvfrom, [Fd, Hex, Dec, Hex, Hex, Hex];
+    #[cfg(feature = "net")]
+    sendmsg = [211, 46] => syscall_sendmsg, [Fd, Hex, Hex];
+    #[cfg(feature = "net")]
+    recvmsg = [212, 47] => syscall_recvmsg, [Fd, Hex, Hex];
+    #[cfg(feature = "net")]
+    shutdown = [210, 48] => syscall_shutdown, [Fd, Dec];
+    #[cfg(feature = "net")]
+    setsockopt = [208, 54] => syscall_setsockopt, [Fd, Dec, Dec, Hex, Dec];
+    #[cfg(feature = "net")]
+    getsockopt = [209, 55] => syscall_getsockopt, [Fd, Dec, Dec, Hex, Hex];
+}
+
+/// 系统调用编号的上限
+const MAX_SYSCALL_ID: usize = 512;
+
+/// 系统调用编号到其在 [`SYSCALL_TABLE`] 中的下标的映射，u16::MAX 表示未实现
+static SYSCALL_INDEX: [u16; MAX_SYSCALL_ID] = build_index();
+
+const fn build_index() -> [u16; MAX_SYSCALL_ID] {
+    let mut index = [u16::MAX; MAX_SYSCALL_ID];
+    let mut i = 0;
+    while i < SYSCALL_TABLE.len() {
+        if let Some(id) = SYSCALL_TABLE[i].id {
+            assert!(id < MAX_SYSCALL_ID, "syscall id exceeds MAX_SYSCALL_ID");
+            assert!(
+                index[id] == u16::MAX,
+                "duplicate syscall id in SYSCALL_TABLE"
+            );
+            index[id] = i as u16;
+        }
+        i += 1;
+    }
+    index
+}
+
+/// 查找编号对应的系统调用，未实现时返回 None
+pub(crate) fn lookup(syscall_id: usize) -> Option<&'static SyscallEntry> {
+    let index = *SYSCALL_INDEX.get(syscall_id)?;
+    SYSCALL_TABLE.get(index as usize)
+}
+
+/// 当前架构的名称
+const ARCH: &str = if cfg!(target_arch = "x86_64") {
+    "x86_64"
+} else if cfg!(target_arch = "aarch64") {
+    "aarch64"
+} else {
+    "riscv64"
+};
+
+/// 生成当前架构上已实现的系统调用的列表
+///
+/// 按编号排序，每行依次为编号、名称与各个参数的解码方式
+pub fn implemented_syscalls() -> String {
+    let mut entries: Vec<(usize, &SyscallEntry)> = SYSCALL_TABLE
+        .iter()
+        .filter_map(|entry| Some((entry.id?, entry)))
+        .collect();
+    entries.sort_by_key(|(id, _)| *id);
+    let mut report = format!("# {} syscalls implemented on {}\n", entries.len(), ARCH);
+    for (id, entry) in entries {
+        let args = entry
+            .args
+            .iter()
+            .map(|arg| format!("{:?}", arg))
+            .collect::<Vec<_>>()
+            .join(", ");
+        report += &format!("{:>3} {}({})\n", id, entry.name, args);
+    }
+    report
+}
@@ -0,0 +1,164 @@
+//! 负责与 futex 相关的系统调用
+use core::time::Duration;
+
+use crate::{FutexFlags, SyscallError, SyscallResult, SyscallReturn, TimeSecs};
+use axerrno::AxError;
+use axhal::time::current_time;
+use axlog::info;
+use axtask::{
+    current_task, futex_requeue, futex_wait, futex_wake, get_task_ref, RobustList, UserPtr,
+    FUTEX_BITSET_MATCH_ANY,
+};
+
+/// 将 futex 操作的错误转换为对应的系统调用错误
+fn futex_error(err: AxError) -> SyscallError {
+    match err {
+        AxError::Timeout => SyscallError::ETIMEDOUT,
+        AxError::WouldBlock => SyscallError::EAGAIN,
+        AxError::Interrupted => SyscallError::EINTR,
+        AxError::NoMemory | AxError::BadAddress => SyscallError::EFAULT,
+        err => err.into(),
+    }
+}
+
+/// 读取用户传入的超时时间，空指针表示一直等待
+fn read_timeout(timeout: UserPtr<TimeSecs>) -> Result<Option<Duration>, SyscallError> {
+    if timeout.is_null() {
+        return Ok(None);
+    }
+    let timeout = timeout.read()?;
+    if timeout.tv_nsec >= 1_000_000_000 {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(Some(Duration::new(
+        timeout.tv_sec as u64,
+        timeout.tv_nsec as u32,
+    )))
+}
+
+/// 功能:对用户地址上的 futex 进行等待或唤醒等操作；
+/// # Arguments
+/// * `uaddr`: usize, futex 的地址，需要四字节对齐。
+/// * `futex_op`: i32, 操作码，可以带有 FUTEX_PRIVATE_FLAG 与 FUTEX_CLOCK_REALTIME 标志。
+/// * `val`: u32, WAIT 时为期望的值，WAKE 与 REQUEUE 时为唤醒的线程数。
+/// * `timeout`: *const TimeSecs, WAIT 时为相对超时时间，WAIT_BITSET 时为绝对截止时间；REQUEUE 时为转移的线程数。
+/// * `uaddr2`: usize, REQUEUE 的目标地址。
+/// * `val3`: u32, CMP_REQUEUE 时为期望的值，BITSET 操作时为 bitset。
+/// 返回值:WAIT 成功返回0，WAKE 返回唤醒的线程数，REQUEUE 返回唤醒与转移的线程数之和，失败返回-1。
+///
+/// WAIT 在等待期间挂起当前任务，不占用内核栈
+pub fn syscall_futex(args: [usize; 6]) -> SyscallReturn {
+    let uaddr = args[0];
+    let futex_op = args[1] as i32;
+    let val = args[2] as u32;
+    let timeout = args[3];
+    let uaddr2 = args[4];
+    let val3 = args[5] as u32;
+    info!(
+        "futex: uaddr: {:#x}, op: {}, val: {}, uaddr2: {:#x}, val3: {}",
+        uaddr, futex_op, val, uaddr2, val3
+    );
+    if uaddr % 4 != 0 {
+        return SyscallError::EINVAL.into();
+    }
+    match FutexFlags::new(futex_op) {
+        FutexFlags::Wait => {
+            let deadline = match read_timeout(UserPtr::new(timeout)) {
+                Ok(timeout) => timeout.map(|timeout| current_time() + timeout),
+                Err(err) => return err.into(),
+            };
+            wait(uaddr, val, deadline, FUTEX_BITSET_MATCH_ANY)
+        }
+        FutexFlags::WaitBitset => {
+            if val3 == 0 {
+                return SyscallError::EINVAL.into();
+            }
+            match read_timeout(UserPtr::new(timeout)) {
+                Ok(deadline) => wait(uaddr, val, deadline, val3),
+                Err(err) => err.into(),
+            }
+        }
+        FutexFlags::Wake => futex_wake(uaddr, val as usize, FUTEX_BITSET_MATCH_ANY)
+            .map(|woken| woken as isize)
+            .map_err(futex_error)
+            .into(),
+        FutexFlags::WakeBitset => {
+            if val3 == 0 {
+                return SyscallError::EINVAL.into();
+            }
+            futex_wake(uaddr, val as usize, val3)
+                .map(|woken| woken as isize)
+                .map_err(futex_error)
+                .into()
+        }
+        FutexFlags::Requeue | FutexFlags::CmpRequeue => {
+            if uaddr2 % 4 != 0 {
+                return SyscallError::EINVAL.into();
+            }
+            let expected = match FutexFlags::new(futex_op) {
+                FutexFlags::CmpRequeue => Some(val3),
+                _ => None,
+            };
+            // REQUEUE 时 timeout 参数的位置用于传递转移的线程数
+            futex_requeue(
+                uaddr,
+                val as usize,
+                uaddr2,
+                timeout as u32 as usize,
+                expected,
+            )
+            .map(|count| count as isize)
+            .map_err(futex_error)
+            .into()
+        }
+        FutexFlags::Unsupported => SyscallError::ENOSYS.into(),
+    }
+}
+
+/// 挂起当前任务等待 uaddr 上的唤醒，deadline 为空时一直等待
+fn wait(uaddr: usize, val: u32, deadline: Option<Duration>, bitset: u32) -> SyscallReturn {
+    SyscallReturn::pending(async move {
+        futex_wait(uaddr, val, deadline, bitset)
+            .await
+            .map_err(futex_error)?;
+        Ok(0)
+    })
+}
+
+/// 功能:设置当前线程的 robust list，线程退出时会释放其上记录的锁；
+/// # Arguments
+/// * `head`: *const RobustList, robust list 头部的地址。
+/// * `len`: usize, 头部的大小，必须等于 RobustList 的大小。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_set_robust_list(args: [usize; 6]) -> SyscallResult {
+    let head = args[0];
+    let len = args[1];
+    if len != core::mem::size_of::<RobustList>() {
+        return Err(SyscallError::EINVAL);
+    }
+    current_task().set_robust_list(head);
+    Ok(0)
+}
+
+/// 功能:获取指定线程的 robust list；
+/// # Arguments
+/// * `pid`: usize, 线程的 tid，为 0 时表示当前线程。
+/// * `head_ptr`: *mut usize, 用于保存 robust list 头部的地址。
+/// * `len_ptr`: *mut usize, 用于保存头部的大小。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_get_robust_list(args: [usize; 6]) -> SyscallResult {
+    let pid = args[0];
+    let head_ptr = UserPtr::<usize>::new(args[1]);
+    let len_ptr = UserPtr::<usize>::new(args[2]);
+    let curr = current_task();
+    let head = if pid == 0 {
+        curr.get_robust_list()
+    } else {
+        get_task_ref(pid as u64)
+            .ok_or(SyscallError::ESRCH)?
+            .get_robust_list()
+    };
+    head_ptr.write(head)?;
+    len_ptr.write(core::mem::size_of::<RobustList>())?;
+    Ok(0)
+}
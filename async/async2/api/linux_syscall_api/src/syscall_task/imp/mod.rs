@@ -1,9 +1,19 @@
 
 
+mod futex;
+mod schedule;
+mod signal;
 mod task;
+mod time;
+mod utils;
 
 
 
+pub use futex::*;
+pub use schedule::*;
+pub use signal::*;
 pub use task::*;
+pub use time::*;
+pub use utils::*;
 
 
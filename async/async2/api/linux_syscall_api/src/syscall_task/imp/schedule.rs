@@ -0,0 +1,192 @@
+//! 负责与调度相关的系统调用
+use crate::{
+    SchedParam, SyscallError, SyscallResult, PRIO_PGRP, PRIO_PROCESS, PRIO_USER,
+    SCHED_RESET_ON_FORK,
+};
+use axlog::info;
+use axtask::{
+    copy_from_user, current_task, get_task_ref, yield_now_task, AxTaskRef, SchedPolicy,
+    SchedStatus, UserPtr,
+};
+
+/// 获取 pid 指定的任务，pid 为 0 时表示当前任务
+fn target_task(pid: usize) -> Result<AxTaskRef, SyscallError> {
+    let pid = if pid == 0 {
+        current_task().tid()
+    } else {
+        pid as u64
+    };
+    match get_task_ref(pid) {
+        Some(task) if !task.get_zombie() => Ok(task),
+        _ => Err(SyscallError::ESRCH),
+    }
+}
+
+/// 检查调度优先级是否与调度策略相符：实时策略为 1 到 99，其余为 0
+fn check_sched_priority(policy: SchedPolicy, priority: i32) -> Result<usize, SyscallError> {
+    let valid = match policy {
+        SchedPolicy::SCHED_FIFO | SchedPolicy::SCHED_RR => (1..=99).contains(&priority),
+        _ => priority == 0,
+    };
+    if valid {
+        Ok(priority as usize)
+    } else {
+        Err(SyscallError::EINVAL)
+    }
+}
+
+/// 功能:主动让出 CPU；
+/// 返回值:总是返回0。
+pub fn syscall_sched_yield(_args: [usize; 6]) -> SyscallResult {
+    yield_now_task();
+    Ok(0)
+}
+
+/// 功能:设置任务的 CPU 亲和集；
+/// # Arguments
+/// * `pid`: usize, 线程号，为 0 时表示当前线程。
+/// * `cpusetsize`: usize, mask 的字节数。
+/// * `mask`: *const u8, 允许运行的 CPU 的位图。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sched_setaffinity(args: [usize; 6]) -> SyscallResult {
+    let pid = args[0];
+    let cpusetsize = args[1];
+    let mask = args[2];
+    let task = target_task(pid)?;
+    let len = cpusetsize.min(core::mem::size_of::<usize>());
+    let mut bytes = [0_u8; core::mem::size_of::<usize>()];
+    copy_from_user(&mut bytes[..len], mask)?;
+    let cpu_mask = usize::from_le_bytes(bytes);
+    // 至少要包含一个存在的 CPU
+    if cpu_mask & ((1 << axconfig::SMP) - 1) == 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    info!("sched_setaffinity: pid: {}, mask: {:#x}", pid, cpu_mask);
+    task.inner
+        .lock()
+        .set_cpu_set(cpu_mask, cpusetsize, axconfig::SMP);
+    Ok(0)
+}
+
+/// 功能:获取任务的 CPU 亲和集；
+/// # Arguments
+/// * `pid`: usize, 线程号，为 0 时表示当前线程。
+/// * `cpusetsize`: usize, mask 的字节数，不能小于 usize 的大小。
+/// * `mask`: *mut u8, 用于保存允许运行的 CPU 的位图。
+/// 返回值:成功返回写入的字节数，失败返回-1。
+pub fn syscall_sched_getaffinity(args: [usize; 6]) -> SyscallResult {
+    let pid = args[0];
+    let cpusetsize = args[1];
+    let mask = UserPtr::<usize>::new(args[2]);
+    if cpusetsize < core::mem::size_of::<usize>() || cpusetsize % core::mem::size_of::<usize>() != 0
+    {
+        return Err(SyscallError::EINVAL);
+    }
+    let task = target_task(pid)?;
+    mask.write(task.inner.lock().get_cpu_set())?;
+    Ok(core::mem::size_of::<usize>() as isize)
+}
+
+/// 功能:设置任务的调度策略与优先级；
+///
+/// SCHED_RESET_ON_FORK 标志会被接受但目前不起作用
+/// # Arguments
+/// * `pid`: usize, 线程号，为 0 时表示当前线程。
+/// * `policy`: usize, 调度策略，见 SchedPolicy。
+/// * `param`: *const SchedParam, 调度优先级。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sched_setscheduler(args: [usize; 6]) -> SyscallResult {
+    let pid = args[0];
+    let policy = SchedPolicy::from(args[1] & !SCHED_RESET_ON_FORK);
+    let param = UserPtr::<SchedParam>::new(args[2]);
+    if policy == SchedPolicy::SCHED_UNKNOWN {
+        return Err(SyscallError::EINVAL);
+    }
+    let param = param.read()?;
+    let priority = check_sched_priority(policy, param.sched_priority)?;
+    let task = target_task(pid)?;
+    info!(
+        "sched_setscheduler: pid: {}, policy: {}, priority: {}",
+        pid,
+        isize::from(policy),
+        priority
+    );
+    task.inner
+        .lock()
+        .set_sched_status(SchedStatus { policy, priority });
+    Ok(0)
+}
+
+/// 功能:获取任务的调度策略；
+/// # Arguments
+/// * `pid`: usize, 线程号，为 0 时表示当前线程。
+/// 返回值:成功返回调度策略，失败返回-1。
+pub fn syscall_sched_getscheduler(args: [usize; 6]) -> SyscallResult {
+    let task = target_task(args[0])?;
+    let policy = task.inner.lock().get_sched_status().policy;
+    Ok(isize::from(policy))
+}
+
+/// 功能:在不改变调度策略的情况下设置任务的调度优先级；
+/// # Arguments
+/// * `pid`: usize, 线程号，为 0 时表示当前线程。
+/// * `param`: *const SchedParam, 调度优先级。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sched_setparam(args: [usize; 6]) -> SyscallResult {
+    let param = UserPtr::<SchedParam>::new(args[1]).read()?;
+    let task = target_task(args[0])?;
+    let inner = task.inner.lock();
+    let mut status = inner.get_sched_status();
+    status.priority = check_sched_priority(status.policy, param.sched_priority)?;
+    inner.set_sched_status(status);
+    Ok(0)
+}
+
+/// 功能:获取任务的调度优先级；
+/// # Arguments
+/// * `pid`: usize, 线程号，为 0 时表示当前线程。
+/// * `param`: *mut SchedParam, 用于保存调度优先级。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sched_getparam(args: [usize; 6]) -> SyscallResult {
+    let param = UserPtr::<SchedParam>::new(args[1]);
+    let task = target_task(args[0])?;
+    let priority = task.inner.lock().get_sched_status().priority;
+    param.write(SchedParam {
+        sched_priority: priority as i32,
+    })?;
+    Ok(0)
+}
+
+/// 获取 getpriority / setpriority 指定的任务
+///
+/// 目前没有进程组与用户的概念，PRIO_PGRP 与 PRIO_USER 只支持 who 为 0，即当前任务
+fn priority_target(which: usize, who: usize) -> Result<AxTaskRef, SyscallError> {
+    match which {
+        PRIO_PROCESS => target_task(who),
+        PRIO_PGRP | PRIO_USER if who == 0 => target_task(0),
+        PRIO_PGRP | PRIO_USER => Err(SyscallError::ESRCH),
+        _ => Err(SyscallError::EINVAL),
+    }
+}
+
+/// 功能:获取任务的 nice 值；
+/// # Arguments
+/// * `which`: usize, who 的含义，见 PRIO_PROCESS 等。
+/// * `who`: usize, 为 0 时表示当前任务。
+/// 返回值:成功返回 20 - nice，即 1 到 40 之间的值，失败返回-1。
+pub fn syscall_getpriority(args: [usize; 6]) -> SyscallResult {
+    let task = priority_target(args[0], args[1])?;
+    Ok((20 - task.get_nice()) as isize)
+}
+
+/// 功能:设置任务的 nice 值，超出 -20 到 19 的值会被截断；
+/// # Arguments
+/// * `which`: usize, who 的含义，见 PRIO_PROCESS 等。
+/// * `who`: usize, 为 0 时表示当前任务。
+/// * `prio`: i32, 新的 nice 值。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_setpriority(args: [usize; 6]) -> SyscallResult {
+    let task = priority_target(args[0], args[1])?;
+    task.set_nice(args[2] as i32);
+    Ok(0)
+}
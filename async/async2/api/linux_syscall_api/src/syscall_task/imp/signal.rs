@@ -0,0 +1,349 @@
+//! 负责与信号相关的系统调用
+extern crate alloc;
+use crate::{
+    SigMaskFlag, SyscallError, SyscallResult, SyscallReturn, TimeSecs, SIGSET_SIZE_IN_BYTE,
+};
+use alloc::vec::Vec;
+use axhal::paging::MappingFlags;
+use axhal::time::current_time_nanos;
+use axhal::KERNEL_PROCESS_ID;
+use axlog::{debug, info};
+use axsignal::action::{SigAction, SigActionFlags, SignalDefault, SIG_DFL, SIG_IGN};
+use axsignal::info::{SigInfo, SI_TKILL, SI_USER};
+use axsignal::signal_no::{SignalNo, MAX_SIG_NUM};
+use axsignal::ucontext::{SignalStack, MINSIGSTKSZ, SS_AUTODISARM, SS_DISABLE, SS_ONSTACK};
+use axtask::task::TID2TASK;
+use axtask::{
+    current_task, get_task_ref, send_siginfo_to_process, send_siginfo_to_thread, signal_return,
+    wait_for, SignalModule, UserPtr, WakeupTimer,
+};
+use core::time::Duration;
+
+/// siginfo_t 的大小
+const SIGINFO_SIZE: usize = 128;
+
+/// 信号对应的位
+fn signal_bit(sig_num: usize) -> usize {
+    1 << (sig_num - 1)
+}
+
+/// SIGKILL 与 SIGSTOP 不能被屏蔽、捕获或忽略
+fn unblockable_mask() -> usize {
+    signal_bit(SignalNo::SIGKILL as usize) | signal_bit(SignalNo::SIGSTOP as usize)
+}
+
+/// 由当前进程通过 kill、tgkill 等系统调用发送的信号的信息
+///
+/// 没有实现用户，发送者的 uid 总是 0
+fn user_siginfo(signum: usize, code: i32) -> SigInfo {
+    SigInfo {
+        si_signo: signum as i32,
+        si_code: code,
+        si_pid: current_task().get_process_id() as i32,
+        si_uid: 0,
+        ..Default::default()
+    }
+}
+
+/// 在当前任务的信号模块上执行给定的操作
+fn with_signal_module<R>(f: impl FnOnce(&mut SignalModule) -> R) -> R {
+    let curr = current_task();
+    let tid = curr.tid();
+    let mut signal_modules = curr.signal_modules.lock();
+    // 每个任务在创建时都会插入自己的信号模块
+    f(signal_modules.get_mut(&tid).unwrap())
+}
+
+
+/// 功能:查询或设置信号的处理方式；
+/// # Arguments
+/// * `signum`: usize, 信号编号，不能为 SIGKILL 或 SIGSTOP。
+/// * `action`: *const SigAction, 新的处理方式，为空时不修改。
+/// * `old_action`: *mut SigAction, 用于保存原有的处理方式，可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sigaction(args: [usize; 6]) -> SyscallResult {
+    let signum = args[0];
+    let action = UserPtr::<SigAction>::new(args[1]);
+    let old_action = UserPtr::<SigAction>::new(args[2]);
+    info!(
+        "signum: {}, action: {:X}, old_action: {:X}",
+        signum,
+        action.addr(),
+        old_action.addr()
+    );
+    if signum == 0 || signum > MAX_SIG_NUM {
+        return Err(SyscallError::EINVAL);
+    }
+    if !action.is_null() && signal_bit(signum) & unblockable_mask() != 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    let action = action.read_opt()?;
+    let old = with_signal_module(|signal_module| {
+        let mut handler = signal_module.signal_handler.lock();
+        let old = handler.handlers[signum - 1].unwrap_or(SigAction {
+            sa_handler: SIG_DFL,
+            sa_flags: SigActionFlags::empty(),
+            restorer: 0,
+            sa_mask: 0,
+        });
+        if let Some(action) = action {
+            handler.handlers[signum - 1] = Some(action);
+            let new_handler = action.sa_handler;
+            let ignored = new_handler == SIG_IGN
+                || (new_handler == SIG_DFL
+                    && matches!(
+                        SignalDefault::get_action(SignalNo::from(signum)),
+                        SignalDefault::Ignore
+                    ));
+            // 处理方式被设为忽略时，丢弃已经未决的该信号
+            if ignored {
+                signal_module.signal_set.pending &= !signal_bit(signum);
+            }
+        }
+        old
+    });
+    old_action.write_opt(old)?;
+    Ok(0)
+}
+
+/// 功能:查询或修改当前任务的信号掩码；
+/// # Arguments
+/// * `how`: usize, 修改的方式，见 SigMaskFlag。
+/// * `set`: *const usize, 参与修改的信号集，为空时不修改。
+/// * `old_set`: *mut usize, 用于保存原有的信号掩码，可以为空。
+/// * `sigsetsize`: usize, 信号集的大小，必须为 8。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sigprocmask(args: [usize; 6]) -> SyscallResult {
+    let how = args[0];
+    let set = UserPtr::<usize>::new(args[1]);
+    let old_set = UserPtr::<usize>::new(args[2]);
+    let sigsetsize = args[3];
+    if sigsetsize != SIGSET_SIZE_IN_BYTE {
+        return Err(SyscallError::EINVAL);
+    }
+    let set = set.read_opt()?;
+    let change = match set {
+        Some(set) => Some((
+            SigMaskFlag::try_from(how).map_err(|_| SyscallError::EINVAL)?,
+            set,
+        )),
+        None => None,
+    };
+    let mask = with_signal_module(|signal_module| {
+        let mask = signal_module.signal_set.mask;
+        if let Some((how, set)) = change {
+            let new_mask = match how {
+                SigMaskFlag::Block => mask | set,
+                SigMaskFlag::Unblock => mask & !set,
+                SigMaskFlag::Setmask => set,
+            };
+            signal_module.signal_set.mask = new_mask & !unblockable_mask();
+        }
+        mask
+    });
+    old_set.write_opt(mask)?;
+    Ok(0)
+}
+
+/// 判断给定的进程是否存在
+fn process_exists(pid: u64) -> bool {
+    get_task_ref(pid).is_some_and(|task| !task.is_thread() && !task.get_zombie())
+}
+
+/// 功能:向进程发送信号；
+/// # Arguments
+/// * `pid`: isize, 大于 0 时为目标进程；为 0 时为当前进程组；为 -1 时为除 init 与自身外的所有进程；
+/// 小于 -1 时为进程组 -pid。
+/// * `signum`: usize, 信号编号，为 0 时只检查目标是否存在。
+/// 返回值:成功返回0，失败返回-1。
+///
+/// 目前没有实现进程组，每个进程自成一组，组号等于其 pid
+pub fn syscall_kill(args: [usize; 6]) -> SyscallResult {
+    let pid = args[0] as isize;
+    let signum = args[1];
+    debug!("kill: pid {}, signum {}", pid, signum);
+    if signum > MAX_SIG_NUM {
+        return Err(SyscallError::EINVAL);
+    }
+    let curr_pid = current_task().get_process_id();
+    let targets: Vec<u64> = match pid {
+        0 => alloc::vec![curr_pid],
+        -1 => TID2TASK
+            .lock()
+            .values()
+            .filter(|task| !task.is_thread() && !task.get_zombie())
+            .map(|task| task.tid())
+            .filter(|&pid| pid != KERNEL_PROCESS_ID && pid != 1 && pid != curr_pid)
+            .collect(),
+        _ => alloc::vec![pid.unsigned_abs() as u64],
+    };
+    let targets: Vec<u64> = targets
+        .into_iter()
+        .filter(|&pid| process_exists(pid))
+        .collect();
+    if targets.is_empty() {
+        return Err(SyscallError::ESRCH);
+    }
+    if signum != 0 {
+        for pid in targets {
+            let _ = send_siginfo_to_process(pid as isize, user_siginfo(signum, SI_USER));
+        }
+    }
+    Ok(0)
+}
+
+/// 向指定线程发送信号，若给出了 tgid 则要求线程属于该线程组
+fn send_to_thread(tgid: Option<isize>, tid: isize, signum: usize) -> SyscallResult {
+    if tid <= 0 || tgid.is_some_and(|tgid| tgid <= 0) || signum > MAX_SIG_NUM {
+        return Err(SyscallError::EINVAL);
+    }
+    let task = get_task_ref(tid as u64)
+        .filter(|task| !task.get_zombie())
+        .ok_or(SyscallError::ESRCH)?;
+    if tgid.is_some_and(|tgid| task.get_process_id() != tgid as u64) {
+        return Err(SyscallError::ESRCH);
+    }
+    if signum != 0 {
+        send_siginfo_to_thread(tid, user_siginfo(signum, SI_TKILL))
+            .map_err(|_| SyscallError::ESRCH)?;
+    }
+    Ok(0)
+}
+
+/// 功能:向线程发送信号；
+/// # Arguments
+/// * `tid`: isize, 目标线程。
+/// * `signum`: usize, 信号编号，为 0 时只检查目标是否存在。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_tkill(args: [usize; 6]) -> SyscallResult {
+    send_to_thread(None, args[0] as isize, args[1])
+}
+
+/// 功能:向指定线程组中的线程发送信号；
+/// # Arguments
+/// * `tgid`: isize, 目标线程所在的线程组。
+/// * `tid`: isize, 目标线程。
+/// * `signum`: usize, 信号编号，为 0 时只检查目标是否存在。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_tgkill(args: [usize; 6]) -> SyscallResult {
+    send_to_thread(Some(args[0] as isize), args[1] as isize, args[2])
+}
+
+/// 功能:从信号处理函数返回，由信号跳板调用；
+/// 返回值:被信号打断时原本的返回值，即恢复后上下文中的 a0。
+pub fn syscall_sigreturn(_args: [usize; 6]) -> SyscallResult {
+    Ok(signal_return())
+}
+
+/// 功能:查询或设置备用信号栈；
+/// # Arguments
+/// * `ss`: *const SignalStack, 新的备用信号栈，为空时不修改。
+/// * `old_ss`: *mut SignalStack, 用于保存原有的备用信号栈，可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sigaltstack(args: [usize; 6]) -> SyscallResult {
+    let ss = UserPtr::<SignalStack>::new(args[0]).read_opt()?;
+    let old_ss = UserPtr::<SignalStack>::new(args[1]);
+    let sp = current_task().get_tf().get_sp();
+    let old = with_signal_module(|signal_module| {
+        let on_stack = signal_module.on_alternate_stack(sp);
+        let mut old = signal_module.alternate_stack;
+        if on_stack {
+            old.flags |= SS_ONSTACK;
+        }
+        if let Some(new) = ss {
+            // 正在备用栈上执行时不能修改备用栈
+            if on_stack {
+                return Err(SyscallError::EPERM);
+            }
+            let flags = new.flags & !SS_AUTODISARM;
+            signal_module.alternate_stack = if flags == SS_DISABLE {
+                SignalStack::default()
+            } else if flags == 0 || flags == SS_ONSTACK {
+                if new.size < MINSIGSTKSZ {
+                    return Err(SyscallError::ENOMEM);
+                }
+                SignalStack { flags: 0, ..new }
+            } else {
+                return Err(SyscallError::EINVAL);
+            };
+        }
+        Ok(old)
+    })?;
+    old_ss.write_opt(old)?;
+    Ok(0)
+}
+
+/// 功能:同步等待给定信号集中的信号；
+/// # Arguments
+/// * `set`: *const usize, 要等待的信号集。
+/// * `info`: *mut SigInfo, 用于保存收到的信号的信息，可以为空。
+/// * `timeout`: *const TimeSecs, 最长的等待时间，为空时一直等待。
+/// * `sigsetsize`: usize, 信号集的大小，必须为 8。
+/// 返回值:成功返回收到的信号编号；超时返回 EAGAIN；被集合外的信号打断时返回 EINTR。
+pub fn syscall_sigtimedwait(args: [usize; 6]) -> SyscallReturn {
+    let set = UserPtr::<usize>::new(args[0]);
+    let info = UserPtr::<u8>::new(args[1]);
+    let timeout = UserPtr::<TimeSecs>::new(args[2]);
+    let sigsetsize = args[3];
+    if sigsetsize != SIGSET_SIZE_IN_BYTE {
+        return SyscallError::EINVAL.into();
+    }
+    let (wait_set, timeout) = match (set.read(), timeout.read_opt()) {
+        (Ok(set), Ok(timeout)) => (set & !unblockable_mask(), timeout),
+        (Err(err), _) | (_, Err(err)) => return SyscallError::from(err).into(),
+    };
+    if !info.is_null() {
+        if let Err(err) = info.check(SIGINFO_SIZE, MappingFlags::WRITE) {
+            return SyscallError::from(err).into();
+        }
+    }
+    let deadline = timeout.map(|timeout| current_time_nanos() as usize + timeout.turn_to_nanos());
+    if let Some(ret) = try_take_signal(wait_set, info, deadline) {
+        return ret.into();
+    }
+    // 由信号的发送或超时的定时器唤醒
+    SyscallReturn::pending(async move {
+        let _timer =
+            deadline.map(|deadline| WakeupTimer::new(Duration::from_nanos(deadline as u64)));
+        wait_for(move || try_take_signal(wait_set, info, deadline)).await
+    })
+}
+
+/// 尝试取出 wait_set 中的一个待处理信号，需要继续等待时返回 None
+fn try_take_signal(
+    wait_set: usize,
+    info: UserPtr<u8>,
+    deadline: Option<usize>,
+) -> Option<SyscallResult> {
+    let received = with_signal_module(|signal_module| {
+        let pending = signal_module.signal_set.pending & wait_set;
+        if pending != 0 {
+            let sig_num = pending.trailing_zeros() as usize + 1;
+            signal_module.signal_set.pending &= !signal_bit(sig_num);
+            Ok(Some(signal_module.signal_set.info(sig_num)))
+        } else if signal_module.signal_set.find_signal().is_some() {
+            Err(SyscallError::EINTR)
+        } else {
+            Ok(None)
+        }
+    });
+    match received {
+        Ok(Some(sig_info)) => {
+            Some(write_sig_info(info, sig_info).map(|()| sig_info.si_signo as isize))
+        }
+        Ok(None) if deadline.is_some_and(|deadline| current_time_nanos() as usize >= deadline) => {
+            Some(Err(SyscallError::EAGAIN))
+        }
+        Ok(None) => None,
+        Err(err) => Some(Err(err)),
+    }
+}
+
+/// 将收到的信号的信息写入用户的 siginfo_t，info 为空时不写入
+fn write_sig_info(info: UserPtr<u8>, sig_info: SigInfo) -> Result<(), SyscallError> {
+    if !info.is_null() {
+        info.write_array(&[0; SIGINFO_SIZE])?;
+        info.cast::<SigInfo>().write(sig_info)?;
+    }
+    Ok(())
+}
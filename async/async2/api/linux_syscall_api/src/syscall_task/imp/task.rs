@@ -1,10 +1,22 @@
-use crate::SyscallResult;
-use axtask::exit_current_task;
+use crate::ctypes::{CloneArgs, WaitFlags};
+use crate::{SyscallError, SyscallResult, SyscallReturn};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::AxError;
+use axhal::paging::MappingFlags;
+use axtask::link::{deal_with_path, AT_FDCWD};
+use axtask::{
+    current_have_signals, current_task, exit_current_group, exit_current_task, get_task_ref,
+    set_child_tid, vfork_suspend, wait_for, wait_pid, CloneFlags, UserPtr, WaitStatus,
+};
 
 use axlog::info;
 
 extern crate alloc;
 
+/// 子进程退出时向父进程发送的信号
+const SIGCHLD: usize = 17;
+
 // pub static TEST_FILTER: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
 
 /// # Arguments
@@ -24,3 +36,282 @@ pub fn syscall_exit(args: [usize; 6]) -> SyscallResult {
     exit_current_task(exit_code);
     Ok(0)
 }
+
+/// 退出整个进程
+///
+/// 同一线程组内的其他线程会收到 SIGKILL 并随之退出，进程的退出码为 exit_code
+/// # Arguments
+/// * `exit_code` - i32
+pub fn syscall_exit_group(args: [usize; 6]) -> SyscallResult {
+    let exit_code = args[0] as i32;
+    info!("exit_group: exit_code = {}", exit_code);
+    exit_current_group(exit_code, 0);
+    Ok(0)
+}
+
+/// 从用户空间读取一个以空指针结尾的字符串数组，如 argv 与 envp
+fn read_user_str_array(ptr: UserPtr<usize>) -> Result<Vec<String>, SyscallError> {
+    let mut strs = Vec::new();
+    if ptr.is_null() {
+        return Ok(strs);
+    }
+    for index in 0.. {
+        let str_ptr = ptr.add(index).read()?;
+        if str_ptr == 0 {
+            break;
+        }
+        strs.push(UserPtr::<u8>::new(str_ptr).read_cstr()?);
+    }
+    Ok(strs)
+}
+
+/// 根据 clone 的参数创建子任务，返回子任务的 tid
+///
+/// 若指定了 CLONE_VFORK，则当前任务会阻塞，直到子任务调用 exec 或退出
+fn clone_task(
+    flags: usize,
+    stack: usize,
+    ptid: usize,
+    tls: usize,
+    ctid: usize,
+) -> SyscallResult {
+    // 低 8 位为子任务退出时发送给父任务的信号
+    let sig_child = flags & 0xff == SIGCHLD;
+    let clone_flags = CloneFlags::from_bits_truncate((flags & !0xff) as u32);
+    let stack = if stack == 0 { None } else { Some(stack) };
+    let curr = current_task();
+    let new_tid = curr.clone_task(clone_flags, stack, ptid, tls, ctid, sig_child)?;
+    if clone_flags.contains(CloneFlags::CLONE_VFORK) {
+        if let Some(child) = get_task_ref(new_tid) {
+            vfork_suspend(&child);
+        }
+    }
+    Ok(new_tid as isize)
+}
+
+/// 功能:创建一个子任务；
+/// # Arguments
+/// * `flags`: usize, 创建的标志，低 8 位为子任务退出时发送的信号。
+/// * `stack`: usize, 子任务的用户栈，为 0 时使用父任务的栈。
+/// * `ptid`: usize, CLONE_PARENT_SETTID 时写入子任务 tid 的地址。
+/// * `tls`: usize, CLONE_SETTLS 时子任务的 TLS。
+/// * `ctid`: usize, CLONE_CHILD_SETTID/CLONE_CHILD_CLEARTID 时使用的地址。
+/// 返回值:成功时父任务返回子任务的 tid，子任务返回 0。失败返回 -1。
+///
+/// x86_64 上 `tls` 与 `ctid` 的顺序与其他架构相反
+pub fn syscall_clone(args: [usize; 6]) -> SyscallResult {
+    let flags = args[0];
+    let stack = args[1];
+    let ptid = args[2];
+    #[cfg(not(target_arch = "x86_64"))]
+    let (tls, ctid) = (args[3], args[4]);
+    #[cfg(target_arch = "x86_64")]
+    let (ctid, tls) = (args[3], args[4]);
+    info!(
+        "[clone()] flags: {:#x}, stack: {:#x}, ptid: {:#x}, tls: {:#x}, ctid: {:#x}",
+        flags, stack, ptid, tls, ctid
+    );
+    clone_task(flags, stack, ptid, tls, ctid)
+}
+
+/// 功能:以结构体的形式传入参数创建子任务；
+/// # Arguments
+/// * `args`: *const CloneArgs, 创建子任务的参数。
+/// * `size`: usize, 结构体的大小。
+/// 返回值:同 clone。
+pub fn syscall_clone3(args: [usize; 6]) -> SyscallResult {
+    let clone_args = UserPtr::<CloneArgs>::new(args[0]);
+    let size = args[1];
+    if size < core::mem::size_of::<CloneArgs>() {
+        return Err(SyscallError::EINVAL);
+    }
+    let clone_args = clone_args.read()?;
+    if clone_args.exit_signal > 0xff {
+        return Err(SyscallError::EINVAL);
+    }
+    // clone3 中给出的是栈的起始地址与大小，而非栈顶
+    let stack = if clone_args.stack == 0 {
+        0
+    } else {
+        (clone_args.stack + clone_args.stack_size) as usize
+    };
+    clone_task(
+        (clone_args.flags | clone_args.exit_signal) as usize,
+        stack,
+        clone_args.parent_tid as usize,
+        clone_args.tls as usize,
+        clone_args.child_tid as usize,
+    )
+}
+
+/// 功能:创建一个子进程，等价于只指定 SIGCHLD 的 clone；
+/// 返回值:同 clone。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_fork(_args: [usize; 6]) -> SyscallResult {
+    clone_task(SIGCHLD, 0, 0, 0, 0)
+}
+
+/// 功能:创建一个子进程并阻塞当前进程，直到子进程调用 exec 或退出；
+/// 返回值:同 clone。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_vfork(_args: [usize; 6]) -> SyscallResult {
+    let flags = CloneFlags::CLONE_VM | CloneFlags::CLONE_VFORK;
+    clone_task(flags.bits() as usize | SIGCHLD, 0, 0, 0, 0)
+}
+
+/// 功能:执行一个新的程序，替换当前进程的地址空间；
+/// # Arguments
+/// * `path`: *const u8, 要执行的程序的路径。
+/// * `argv`: *const usize, 参数数组，以空指针结尾。
+/// * `envp`: *const usize, 环境变量数组，以空指针结尾。
+/// 返回值:成功时不返回原程序，新程序从入口开始执行。失败返回 -1。
+pub fn syscall_execve(args: [usize; 6]) -> SyscallResult {
+    let path = args[0] as *const u8;
+    let argv = UserPtr::<usize>::new(args[1]);
+    let envp = UserPtr::<usize>::new(args[2]);
+    let path = match deal_with_path(AT_FDCWD, Some(path), false) {
+        Some(path) => path,
+        None => return Err(SyscallError::EINVAL),
+    };
+    let mut args_vec = read_user_str_array(argv)?;
+    let envs_vec = read_user_str_array(envp)?;
+    if args_vec.is_empty() {
+        args_vec.push(path.path().to_string());
+    }
+    info!("[execve()] path: {}, args: {:?}", path.path(), args_vec);
+    let argc = args_vec.len();
+    let curr = current_task();
+    match curr.exec(path.path().to_string(), args_vec, &envs_vec) {
+        // 返回值会写入 a0，与新程序入口处的 argc 一致
+        Ok(_) => Ok(argc as isize),
+        Err(err) => Err(exec_error(err)),
+    }
+}
+
+/// 将加载程序时的错误转换为对应的错误码
+fn exec_error(err: AxError) -> SyscallError {
+    match err {
+        // 文件不是可以执行的 ELF 格式
+        AxError::InvalidData => SyscallError::ENOEXEC,
+        // 目录不能被执行
+        AxError::IsADirectory => SyscallError::EACCES,
+        err => err.into(),
+    }
+}
+
+/// 功能:等待子进程改变状态；
+/// # Arguments
+/// * `pid`: isize, 要等待的子进程，为 -1 时等待任意子进程。
+/// * `status`: *mut i32, 用于保存子进程退出状态的地址，可以为空。
+/// * `options`: u32, 等待的选项，见 WaitFlags。
+/// 返回值:成功返回退出的子进程 pid；指定 WNOHANG 且没有子进程退出时返回 0；等待期间收到信号返回 EINTR；失败返回 -1。
+///
+/// 进程组相关的 pid（0 与小于 -1 的值）目前按 -1 处理
+pub fn syscall_wait4(args: [usize; 6]) -> SyscallReturn {
+    let mut pid = args[0] as isize;
+    let status = UserPtr::<i32>::new(args[1]);
+    let options = WaitFlags::from_bits_truncate(args[2] as u32);
+    if pid == 0 || pid < -1 {
+        pid = -1;
+    }
+    if !status.is_null() {
+        if let Err(err) = status.check(1, MappingFlags::WRITE) {
+            return SyscallError::from(err).into();
+        }
+    }
+    if let Some(ret) = try_wait_child(pid, status, options) {
+        return ret.into();
+    }
+    // 由子进程的退出或信号的发送唤醒
+    SyscallReturn::pending(wait_for(move || try_wait_child(pid, status, options)))
+}
+
+/// 尝试回收一个子进程，需要继续等待时返回 None
+fn try_wait_child(pid: isize, status: UserPtr<i32>, options: WaitFlags) -> Option<SyscallResult> {
+    let ret = match wait_pid(pid) {
+        Ok((pid, wait_status)) => match status.write_opt(wait_status) {
+            Ok(()) => Ok(pid as isize),
+            Err(err) => Err(err.into()),
+        },
+        Err(WaitStatus::NotExist) => Err(SyscallError::ECHILD),
+        Err(_) if options.contains(WaitFlags::WNOHANG) => Ok(0),
+        Err(_) if current_have_signals() => Err(SyscallError::EINTR),
+        Err(_) => return None,
+    };
+    Some(ret)
+}
+
+/// 功能:获取当前进程的 pid，即线程组号；
+/// 返回值:当前进程的 pid。
+pub fn syscall_getpid(_args: [usize; 6]) -> SyscallResult {
+    Ok(current_task().get_process_id() as isize)
+}
+
+/// 功能:获取父进程的 pid；
+/// 返回值:父进程的 pid。
+pub fn syscall_getppid(_args: [usize; 6]) -> SyscallResult {
+    Ok(current_task().get_parent() as isize)
+}
+
+/// 获取 pid 指定的进程，pid 为 0 时表示当前进程
+fn target_process(pid: usize) -> Result<u64, SyscallError> {
+    let pid = if pid == 0 {
+        current_task().get_process_id()
+    } else {
+        pid as u64
+    };
+    match get_task_ref(pid) {
+        Some(task) if !task.is_thread() && !task.get_zombie() => Ok(pid),
+        _ => Err(SyscallError::ESRCH),
+    }
+}
+
+/// 功能:设置进程的进程组；
+/// # Arguments
+/// * `pid`: usize, 目标进程，为 0 时表示当前进程，只能是当前进程或其子进程。
+/// * `pgid`: usize, 新的进程组号，为 0 时表示与 pid 相同。
+/// 返回值:成功返回0，失败返回-1。
+///
+/// 目前没有实现进程组，每个进程自成一组，因此只允许将进程组号设置为进程自身的 pid
+pub fn syscall_setpgid(args: [usize; 6]) -> SyscallResult {
+    let pid = target_process(args[0])?;
+    let pgid = if args[1] == 0 { pid } else { args[1] as u64 };
+    let curr_pid = current_task().get_process_id();
+    if pid != curr_pid && get_task_ref(pid).is_some_and(|task| task.get_parent() != curr_pid) {
+        return Err(SyscallError::ESRCH);
+    }
+    if pgid != pid {
+        return Err(SyscallError::EPERM);
+    }
+    Ok(0)
+}
+
+/// 功能:获取进程的进程组号；
+/// # Arguments
+/// * `pid`: usize, 目标进程，为 0 时表示当前进程。
+/// 返回值:成功返回进程组号，失败返回-1。
+pub fn syscall_getpgid(args: [usize; 6]) -> SyscallResult {
+    target_process(args[0]).map(|pgid| pgid as isize)
+}
+
+/// 功能:获取当前进程的进程组号；
+/// 返回值:当前进程的进程组号。
+#[cfg(target_arch = "x86_64")]
+pub fn syscall_getpgrp(_args: [usize; 6]) -> SyscallResult {
+    Ok(current_task().get_process_id() as isize)
+}
+
+/// 功能:获取当前任务的 tid；
+/// 返回值:当前任务的 tid。
+pub fn syscall_gettid(_args: [usize; 6]) -> SyscallResult {
+    Ok(current_task().tid() as isize)
+}
+
+/// 功能:设置 clear_child_tid，任务退出时会将该地址处的值清零；
+/// # Arguments
+/// * `tidptr`: usize, 要设置的地址。
+/// 返回值:当前任务的 tid。
+pub fn syscall_set_tid_address(args: [usize; 6]) -> SyscallResult {
+    set_child_tid(args[0]);
+    Ok(current_task().tid() as isize)
+}
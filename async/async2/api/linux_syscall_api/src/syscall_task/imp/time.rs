@@ -0,0 +1,275 @@
+//! 负责与时间、时钟和计时器相关的系统调用
+//!
+//! 系统中没有实时时钟，因此 CLOCK_REALTIME 与 CLOCK_MONOTONIC 均以开机时间为准
+use core::time::Duration;
+
+use crate::{
+    ClockId, ITimerVal, RUsage, RusageFlags, SyscallError, SyscallResult, SyscallReturn, TimeSecs,
+    TimeVal, Tms, NSEC_PER_SEC, TIMER_ABSTIME,
+};
+use axhal::time::{current_ticks, current_time, NANOS_PER_MICROS};
+use axlog::info;
+use axtask::task::TID2TASK;
+use axtask::{
+    current_have_signals, current_task, time_stat_output, wait_until, TimerType, UserPtr,
+    WakeupTimer,
+};
+
+/// 将纳秒数转换为 TimeSecs
+fn nanos_to_time_secs(nanos: usize) -> TimeSecs {
+    TimeSecs {
+        tv_sec: nanos / NSEC_PER_SEC,
+        tv_nsec: nanos % NSEC_PER_SEC,
+    }
+}
+
+/// 将纳秒数转换为 TimeVal
+fn nanos_to_time_val(nanos: usize) -> TimeVal {
+    TimeVal::from_micro(nanos / NANOS_PER_MICROS as usize)
+}
+
+/// 读取用户传入的 TimeSecs，并检查其合法性
+fn read_time_secs(ptr: UserPtr<TimeSecs>) -> Result<TimeSecs, SyscallError> {
+    let time = ptr.read()?;
+    if time.tv_nsec >= NSEC_PER_SEC || (time.tv_sec as isize) < 0 {
+        return Err(SyscallError::EINVAL);
+    }
+    Ok(time)
+}
+
+/// 当前进程所有线程的时间统计之和
+///
+/// The format is (user time, kernel time) in nanoseconds
+fn process_time_stat() -> (usize, usize) {
+    let pid = current_task().get_process_id();
+    TID2TASK
+        .lock()
+        .values()
+        .filter(|task| task.get_process_id() == pid)
+        .map(|task| task.inner.lock().time_stat_output())
+        .fold((0, 0), |(utime, stime), (u, s)| (utime + u, stime + s))
+}
+
+/// 获取指定时钟的当前值，单位为纳秒
+fn clock_nanos(clock_id: ClockId) -> usize {
+    match clock_id {
+        ClockId::CLOCK_PROCESS_CPUTIME_ID => {
+            let (utime, stime) = process_time_stat();
+            utime + stime
+        }
+        ClockId::CLOCK_THREAD_CPUTIME_ID => {
+            let (utime, stime) = current_task().inner.lock().time_stat_output();
+            utime + stime
+        }
+        _ => current_time().as_nanos() as usize,
+    }
+}
+
+/// 挂起当前任务直到给定的截止时间，若期间收到信号则提前返回 false
+///
+/// 由截止时间的定时器或信号的发送唤醒
+async fn sleep_until_interruptible(deadline: Duration) -> bool {
+    let _timer = WakeupTimer::new(deadline);
+    wait_until(|| current_time() >= deadline || current_have_signals()).await;
+    current_time() >= deadline
+}
+
+/// 功能:获取指定时钟的时间；
+/// # Arguments
+/// * `clock_id`: usize, 时钟编号。
+/// * `tp`: *mut TimeSecs, 用于保存时间。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_clock_gettime(args: [usize; 6]) -> SyscallResult {
+    let clock_id = ClockId::try_from(args[0]).map_err(|_| SyscallError::EINVAL)?;
+    UserPtr::<TimeSecs>::new(args[1]).write(nanos_to_time_secs(clock_nanos(clock_id)))?;
+    Ok(0)
+}
+
+/// 功能:获取指定时钟的精度；
+/// # Arguments
+/// * `clock_id`: usize, 时钟编号。
+/// * `res`: *mut TimeSecs, 用于保存精度，可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_clock_getres(args: [usize; 6]) -> SyscallResult {
+    ClockId::try_from(args[0]).map_err(|_| SyscallError::EINVAL)?;
+    // 所有时钟都由同一个硬件计时器驱动，精度为一个 tick
+    let tick_nanos = (NSEC_PER_SEC / axconfig::TIMER_FREQUENCY).max(1);
+    UserPtr::<TimeSecs>::new(args[1]).write_opt(nanos_to_time_secs(tick_nanos))?;
+    Ok(0)
+}
+
+/// 功能:让当前任务睡眠一段时间；
+/// # Arguments
+/// * `req`: *const TimeSecs, 睡眠的时间。
+/// * `rem`: *mut TimeSecs, 被信号打断时用于保存剩余的时间，可以为空。
+/// 返回值:成功返回0，被信号打断返回EINTR，失败返回-1。
+pub fn syscall_nanosleep(args: [usize; 6]) -> SyscallReturn {
+    let req = match read_time_secs(UserPtr::new(args[0])) {
+        Ok(req) => req,
+        Err(err) => return err.into(),
+    };
+    let rem = UserPtr::<TimeSecs>::new(args[1]);
+    info!("nanosleep: {}s {}ns", req.tv_sec, req.tv_nsec);
+    let deadline = current_time() + Duration::from_nanos(req.turn_to_nanos() as u64);
+    SyscallReturn::pending(async move {
+        if sleep_until_interruptible(deadline).await {
+            return Ok(0);
+        }
+        let remaining = deadline.saturating_sub(current_time());
+        rem.write_opt(nanos_to_time_secs(remaining.as_nanos() as usize))?;
+        Err(SyscallError::EINTR)
+    })
+}
+
+/// 功能:以指定的时钟让当前任务睡眠一段时间或睡眠到某一时刻；
+/// # Arguments
+/// * `clock_id`: usize, 时钟编号，不支持 CPU 时间时钟。
+/// * `flags`: usize, 为 TIMER_ABSTIME 时 req 为绝对时间。
+/// * `req`: *const TimeSecs, 睡眠的时间或截止时刻。
+/// * `rem`: *mut TimeSecs, 相对睡眠被信号打断时用于保存剩余的时间，可以为空。
+/// 返回值:成功返回0，被信号打断返回EINTR，失败返回-1。
+pub fn syscall_clock_nanosleep(args: [usize; 6]) -> SyscallReturn {
+    let flags = args[1];
+    match ClockId::try_from(args[0]) {
+        Ok(ClockId::CLOCK_PROCESS_CPUTIME_ID | ClockId::CLOCK_THREAD_CPUTIME_ID) | Err(_) => {
+            return SyscallError::EINVAL.into();
+        }
+        Ok(_) => {}
+    }
+    if flags & TIMER_ABSTIME == 0 {
+        return syscall_nanosleep([args[2], args[3], 0, 0, 0, 0]);
+    }
+    let req = match read_time_secs(UserPtr::new(args[2])) {
+        Ok(req) => req,
+        Err(err) => return err.into(),
+    };
+    let deadline = Duration::from_nanos(req.turn_to_nanos() as u64);
+    SyscallReturn::pending(async move {
+        if sleep_until_interruptible(deadline).await {
+            Ok(0)
+        } else {
+            // 绝对时间的睡眠重新执行即可，不需要写回剩余时间
+            Err(SyscallError::EINTR)
+        }
+    })
+}
+
+/// 功能:获取当前时间；
+/// # Arguments
+/// * `tv`: *mut TimeVal, 用于保存时间，可以为空。
+/// * `tz`: *mut [i32; 2], 用于保存时区，总是为 UTC，可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_gettimeofday(args: [usize; 6]) -> SyscallResult {
+    let tv = UserPtr::<TimeVal>::new(args[0]);
+    let tz = UserPtr::<[i32; 2]>::new(args[1]);
+    tv.write_opt(nanos_to_time_val(current_time().as_nanos() as usize))?;
+    tz.write_opt([0; 2])?;
+    Ok(0)
+}
+
+/// 功能:获取进程及其已回收的子进程的运行时间；
+/// # Arguments
+/// * `tms`: *mut Tms, 用于保存运行时间，单位为us，可以为空。
+/// 返回值:成功返回开机以来的 tick 数，失败返回-1。
+pub fn syscall_times(args: [usize; 6]) -> SyscallResult {
+    let (_, utime_us, _, stime_us) = time_stat_output();
+    let (children_utime, children_stime) = current_task().get_children_time();
+    UserPtr::<Tms>::new(args[0]).write_opt(Tms {
+        tms_utime: utime_us,
+        tms_stime: stime_us,
+        tms_cutime: children_utime / NANOS_PER_MICROS as usize,
+        tms_cstime: children_stime / NANOS_PER_MICROS as usize,
+    })?;
+    Ok(current_ticks() as isize)
+}
+
+/// 功能:获取资源使用情况，目前只统计运行时间；
+/// # Arguments
+/// * `who`: i32, 统计的对象，见 RusageFlags。
+/// * `usage`: *mut RUsage, 用于保存统计结果。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_getrusage(args: [usize; 6]) -> SyscallResult {
+    let who = args[0] as i32;
+    let usage = UserPtr::<RUsage>::new(args[1]);
+    let (utime, stime) = match RusageFlags::from(who) {
+        Some(RusageFlags::RUSAGE_SELF) => process_time_stat(),
+        Some(RusageFlags::RUSAGE_THREAD) => current_task().inner.lock().time_stat_output(),
+        Some(RusageFlags::RUSAGE_CHILDREN) => current_task().get_children_time(),
+        None => return Err(SyscallError::EINVAL),
+    };
+    usage.write(RUsage {
+        ru_utime: nanos_to_time_val(utime),
+        ru_stime: nanos_to_time_val(stime),
+        ..Default::default()
+    })?;
+    Ok(0)
+}
+
+/// 获取指定类型计时器的当前设定，若当前设定的不是该类型的计时器则为全零
+fn current_itimer(timer_type: TimerType) -> ITimerVal {
+    let curr = current_task();
+    let inner = curr.inner.lock();
+    if inner.timer_type() != timer_type {
+        return ITimerVal {
+            it_interval: TimeVal::default(),
+            it_value: TimeVal::default(),
+        };
+    }
+    let (interval_us, remained_us) = inner.timer_output();
+    ITimerVal {
+        it_interval: TimeVal::from_micro(interval_us),
+        it_value: TimeVal::from_micro(remained_us),
+    }
+}
+
+/// 将用户传入的计时器类型转换为 TimerType
+fn itimer_type(which: usize) -> Result<TimerType, SyscallError> {
+    match TimerType::from(which) {
+        TimerType::NONE => Err(SyscallError::EINVAL),
+        timer_type => Ok(timer_type),
+    }
+}
+
+/// 功能:获取计时器的设定；
+/// # Arguments
+/// * `which`: usize, 计时器类型，为 ITIMER_REAL、ITIMER_VIRTUAL 或 ITIMER_PROF。
+/// * `curr_value`: *mut ITimerVal, 用于保存计时器的周期与剩余时间。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_getitimer(args: [usize; 6]) -> SyscallResult {
+    let timer_type = itimer_type(args[0])?;
+    UserPtr::<ITimerVal>::new(args[1]).write(current_itimer(timer_type))?;
+    Ok(0)
+}
+
+/// 功能:设定计时器，到期时分别向进程发送 SIGALRM、SIGVTALRM 或 SIGPROF；
+///
+/// 同一时刻每个任务只能设定一种计时器，设定新类型的计时器会取代原有的计时器
+/// # Arguments
+/// * `which`: usize, 计时器类型，为 ITIMER_REAL、ITIMER_VIRTUAL 或 ITIMER_PROF。
+/// * `new_value`: *const ITimerVal, 新的设定，it_value 为 0 时取消计时器。
+/// * `old_value`: *mut ITimerVal, 用于保存原有的设定，可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_setitimer(args: [usize; 6]) -> SyscallResult {
+    let timer_type = itimer_type(args[0])?;
+    let new_value = UserPtr::<ITimerVal>::new(args[1]).read_opt()?;
+    let old_value = UserPtr::<ITimerVal>::new(args[2]);
+    let old = current_itimer(timer_type);
+    if let Some(new) = new_value {
+        if new.it_value.usec >= 1_000_000 || new.it_interval.usec >= 1_000_000 {
+            return Err(SyscallError::EINVAL);
+        }
+        let remained_ns = new.it_value.turn_to_nanos();
+        let curr = current_task();
+        let inner = curr.inner.lock();
+        // 取消一个并未设定的计时器时不应影响其他类型的计时器
+        if remained_ns != 0 || inner.timer_type() == timer_type {
+            inner.set_timer(
+                new.it_interval.turn_to_nanos(),
+                remained_ns,
+                timer_type as i32 as usize,
+            );
+        }
+    }
+    old_value.write_opt(old)?;
+    Ok(0)
+}
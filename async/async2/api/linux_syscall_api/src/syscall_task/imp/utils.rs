@@ -0,0 +1,204 @@
+//! 负责系统信息、资源上限、进程属性与随机数相关的系统调用
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{
+    GetrandomFlags, PrctlOption, RLimit, SysInfo, SyscallError, SyscallResult, UtsName,
+    MAX_RW_COUNT, PR_NAME_SIZE, RW_CHUNK_SIZE,
+};
+use axalloc::global_allocator;
+use axerrno::AxError;
+use axhal::time::current_time;
+use axlog::info;
+use axtask::task::TID2TASK;
+use axtask::{current_have_signals, current_task, sleep, AxTaskRef, UserPtr};
+
+/// 功能:获取系统的名称与版本等信息；
+/// # Arguments
+/// * `uts`: *mut UtsName, 用于保存系统信息。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_uname(args: [usize; 6]) -> SyscallResult {
+    UserPtr::<UtsName>::new(args[0]).write(UtsName::default())?;
+    Ok(0)
+}
+
+/// 功能:获取系统的运行时间、内存使用量与任务数；
+/// # Arguments
+/// * `info`: *mut SysInfo, 用于保存系统信息。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_sysinfo(args: [usize; 6]) -> SyscallResult {
+    let info = UserPtr::<SysInfo>::new(args[0]);
+    let allocator = global_allocator();
+    let used = allocator.used_bytes();
+    let available = allocator.available_bytes();
+    info.write(SysInfo {
+        uptime: current_time().as_secs() as isize,
+        totalram: used + available,
+        freeram: available,
+        procs: TID2TASK.lock().len().min(u16::MAX as usize) as u16,
+        mem_unit: 1,
+        ..Default::default()
+    })?;
+    Ok(0)
+}
+
+/// 获取 prlimit64 指定的进程的所有线程，pid 为 0 时表示当前进程
+fn process_threads(pid: usize) -> Result<Vec<AxTaskRef>, SyscallError> {
+    let pid = if pid == 0 {
+        current_task().get_process_id()
+    } else {
+        pid as u64
+    };
+    let threads: Vec<_> = TID2TASK
+        .lock()
+        .values()
+        .filter(|task| task.get_process_id() == pid && !task.get_zombie())
+        .cloned()
+        .collect();
+    if threads.is_empty() {
+        return Err(SyscallError::ESRCH);
+    }
+    Ok(threads)
+}
+
+/// 功能:获取并设置进程的资源上限；
+/// # Arguments
+/// * `pid`: usize, 进程号，为 0 时表示当前进程。
+/// * `resource`: i32, 资源的种类，如 RLIMIT_STACK、RLIMIT_NOFILE、RLIMIT_AS。
+/// * `new_limit`: *const RLimit, 新的上限，为空时不修改。
+/// * `old_limit`: *mut RLimit, 用于保存原有的上限，可以为空。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_prlimit64(args: [usize; 6]) -> SyscallResult {
+    let pid = args[0];
+    let resource = args[1] as i32;
+    let new_limit = UserPtr::<RLimit>::new(args[2]).read_opt()?;
+    let old_limit = UserPtr::<RLimit>::new(args[3]);
+    info!("prlimit64: pid: {}, resource: {}", pid, resource);
+    let threads = process_threads(pid)?;
+    let old = threads[0]
+        .get_rlimit(resource)
+        .map_err(|_| SyscallError::EINVAL)?;
+    if let Some(new) = new_limit {
+        // 资源上限属于整个进程，需要同步到进程中的每个线程
+        for thread in threads.iter() {
+            thread.set_rlimit(resource, new).map_err(|err| match err {
+                AxError::PermissionDenied => SyscallError::EPERM,
+                _ => SyscallError::EINVAL,
+            })?;
+        }
+    }
+    old_limit.write_opt(old)?;
+    Ok(0)
+}
+
+/// 功能:获取当前进程的资源上限；
+/// # Arguments
+/// * `resource`: i32, 资源的种类。
+/// * `limit`: *mut RLimit, 用于保存上限。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_getrlimit(args: [usize; 6]) -> SyscallResult {
+    if args[1] == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    syscall_prlimit64([0, args[0], 0, args[1], 0, 0])
+}
+
+/// 功能:设置当前进程的资源上限；
+/// # Arguments
+/// * `resource`: i32, 资源的种类。
+/// * `limit`: *const RLimit, 新的上限。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_setrlimit(args: [usize; 6]) -> SyscallResult {
+    if args[1] == 0 {
+        return Err(SyscallError::EFAULT);
+    }
+    syscall_prlimit64([0, args[0], args[1], 0, 0, 0])
+}
+
+/// 功能:设置或获取进程的属性，目前支持 PR_SET_NAME 与 PR_GET_NAME；
+/// # Arguments
+/// * `option`: usize, 操作的种类。
+/// * `arg2`: usize, PR_SET_NAME 与 PR_GET_NAME 时为长度为 16 字节的名称缓冲区。
+/// 返回值:成功返回0，失败返回-1。
+pub fn syscall_prctl(args: [usize; 6]) -> SyscallResult {
+    let option = PrctlOption::try_from(args[0]).map_err(|_| SyscallError::EINVAL)?;
+    let buf = UserPtr::<u8>::new(args[1]);
+    if buf.is_null() {
+        return Err(SyscallError::EFAULT);
+    }
+    let curr = current_task();
+    match option {
+        PrctlOption::PR_SET_NAME => {
+            // 名称不一定以 '\0' 结尾，最多取前 15 个字节
+            let mut bytes = Vec::new();
+            for index in 0..PR_NAME_SIZE - 1 {
+                let byte = buf.add(index).read()?;
+                if byte == 0 {
+                    break;
+                }
+                bytes.push(byte);
+            }
+            let name = String::from_utf8_lossy(&bytes);
+            curr.inner.lock().set_name(&name);
+        }
+        PrctlOption::PR_GET_NAME => {
+            let mut name = [0_u8; PR_NAME_SIZE];
+            {
+                let inner = curr.inner.lock();
+                let bytes = inner.name().as_bytes();
+                let len = bytes.len().min(PR_NAME_SIZE - 1);
+                name[..len].copy_from_slice(&bytes[..len]);
+            }
+            buf.write_array(&name)?;
+        }
+    }
+    Ok(0)
+}
+
+/// 功能:获取随机数；
+/// # Arguments
+/// * `buf`: *mut u8, 用于保存随机数的缓冲区。
+/// * `buflen`: usize, 缓冲区的长度。
+/// * `flags`: u32, 见 GetrandomFlags。
+/// 返回值:成功返回写入的字节数，失败返回-1。
+///
+/// 随机数来自内核的随机数生成器。生成器收集到足够的熵之前，除非指定 GRND_INSECURE，
+/// 否则会等待，指定 GRND_NONBLOCK 时返回 EAGAIN，等待期间收到信号时返回 EINTR
+pub fn syscall_getrandom(args: [usize; 6]) -> SyscallResult {
+    let buf = UserPtr::<u8>::new(args[0]);
+    let len = args[1].min(MAX_RW_COUNT);
+    let Some(flags) = GetrandomFlags::from_bits(args[2] as u32) else {
+        return Err(SyscallError::EINVAL);
+    };
+    if flags.contains(GetrandomFlags::GRND_INSECURE | GetrandomFlags::GRND_RANDOM) {
+        return Err(SyscallError::EINVAL);
+    }
+    if !flags.contains(GetrandomFlags::GRND_INSECURE) {
+        while !axrand::is_seeded() {
+            if flags.contains(GetrandomFlags::GRND_NONBLOCK) {
+                return Err(SyscallError::EAGAIN);
+            }
+            if current_have_signals() {
+                return Err(SyscallError::EINTR);
+            }
+            sleep(Duration::from_millis(10));
+        }
+    }
+    // 经由固定大小的缓冲区分段写入，不按用户给出的长度分配内存
+    let mut chunk = vec![0; len.min(RW_CHUNK_SIZE)];
+    let mut total = 0;
+    while total < len {
+        let size = (len - total).min(RW_CHUNK_SIZE);
+        axrand::fill_bytes(&mut chunk[..size]);
+        match buf.add(total).write_array(&chunk[..size]) {
+            Ok(()) => total += size,
+            // 已经写入数据时返回已写的长度
+            Err(_) if total > 0 => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(total as isize)
+}
@@ -37,9 +37,9 @@ pub fn handle_irq(irq_num: usize, from_user: bool) {
 /// * `syscall_id` - The id of the syscall
 ///
 /// * `args` - The arguments of the syscall
-pub fn handle_syscall(syscall_id: usize, args: [usize; 6]) -> isize {
+pub async fn handle_syscall(syscall_id: usize, args: [usize; 6]) -> isize {
     time_stat_from_user_to_kernel();
-    let ans = syscall(syscall_id, args);
+    let ans = syscall(syscall_id, args).await;
     time_stat_from_kernel_to_user();
     ans
 }
@@ -58,9 +58,13 @@ pub fn handle_page_fault(addr: VirtAddr, flags: MappingFlags) {
 }
 
 /// To handle the pending signals for current process
-pub fn handle_signals() {
+///
+/// # Arguments
+///
+/// * `syscall_arg0` - The original first argument if the trap is a syscall, used to restart the interrupted syscall
+pub fn handle_signals(syscall_arg0: Option<usize>) {
     time_stat_from_user_to_kernel();
-    axtask::signal::handle_signals();
+    axtask::signal::handle_signals(syscall_arg0);
     time_stat_from_kernel_to_user();
 }
 
@@ -15,7 +15,7 @@ mod zero;
 pub use self::dir::DirNode;
 
 pub use self::null::NullDev;
-pub use self::random::RandomDev;
+pub use self::random::{RandomDev, RandomSource};
 pub use self::zero::ZeroDev;
 use alloc::sync::Arc;
 use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
@@ -1,19 +1,28 @@
-use core::ops::DerefMut;
-
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
-use rand::{rngs::SmallRng, Fill, SeedableRng};
-use spin::Mutex;
+
+/// The source of random bytes behind a [`RandomDev`], usually the kernel
+/// random number generator.
+pub trait RandomSource: Send + Sync {
+    /// Fills `buf` with random bytes.
+    fn fill_bytes(&self, buf: &mut [u8]);
+
+    /// Mixes data written to the device into the source. It must not be
+    /// trusted to contain any entropy.
+    fn add_entropy(&self, _data: &[u8]) {}
+}
 
 /// A random device behaves like `/dev/random` or `/dev/urandom`.
 ///
-/// It always returns a chunk of random bytes when read, and all writes are discarded.
-///
-/// TODO: update entropy pool with data written.
-pub struct RandomDev(Mutex<SmallRng>);
+/// It always returns a chunk of random bytes from its [`RandomSource`] when
+/// read, and data written to it is mixed into the source.
+pub struct RandomDev {
+    source: &'static dyn RandomSource,
+}
 
-impl Default for RandomDev {
-    fn default() -> Self {
-        Self(Mutex::new(SmallRng::from_seed([0; 32])))
+impl RandomDev {
+    /// Creates a random device reading from `source`.
+    pub const fn new(source: &'static dyn RandomSource) -> Self {
+        Self { source }
     }
 }
 
@@ -28,11 +37,12 @@ impl VfsNodeOps for RandomDev {
     }
 
     fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
-        buf.try_fill(self.0.lock().deref_mut()).unwrap();
+        self.source.fill_bytes(buf);
         Ok(buf.len())
     }
 
     fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.source.add_entropy(buf);
         Ok(buf.len())
     }
 
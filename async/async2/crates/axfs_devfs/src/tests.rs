@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
@@ -113,3 +114,37 @@ fn test_devfs() {
     test_devfs_ops(&devfs).unwrap();
     test_get_parent(&devfs).unwrap();
 }
+
+struct CountingSource(AtomicUsize);
+
+impl RandomSource for CountingSource {
+    fn fill_bytes(&self, buf: &mut [u8]) {
+        for byte in buf {
+            *byte = self.0.fetch_add(1, Ordering::Relaxed) as u8;
+        }
+    }
+
+    fn add_entropy(&self, data: &[u8]) {
+        self.0.fetch_add(data.len() * 100, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_random_dev() {
+    static SOURCE: CountingSource = CountingSource(AtomicUsize::new(0));
+    let devfs = DeviceFileSystem::new();
+    devfs.add("random", Arc::new(RandomDev::new(&SOURCE)));
+
+    let node = devfs.root_dir().lookup("random").unwrap();
+    assert_eq!(
+        node.get_attr().unwrap().file_type(),
+        VfsNodeType::CharDevice
+    );
+    let mut buf = [0; 4];
+    assert_eq!(node.read_at(0, &mut buf), Ok(4));
+    assert_eq!(buf, [0, 1, 2, 3]);
+    // written data goes to the source
+    assert_eq!(node.write_at(0, &[0; 2]), Ok(2));
+    assert_eq!(node.read_at(100, &mut buf), Ok(4));
+    assert_eq!(buf, [204, 205, 206, 207]);
+}
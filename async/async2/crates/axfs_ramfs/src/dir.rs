@@ -63,6 +63,16 @@ impl DirNode {
         Ok(())
     }
 
+    /// Adds an existing node with the given name in this directory, used for
+    /// special files whose contents are generated on access.
+    pub fn add_node(&self, name: &str, node: VfsNodeRef) -> VfsResult {
+        if self.exist(name) {
+            return Err(VfsError::AlreadyExists);
+        }
+        self.children.write().insert(name.into(), node);
+        Ok(())
+    }
+
     /// Removes a node by the given name in this directory.
     pub fn remove_node(&self, name: &str) -> VfsResult {
         let mut children = self.children.write();
@@ -1,5 +1,5 @@
 use alloc::vec::Vec;
-use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{impl_vfs_non_dir_default, VfsError, VfsNodeAttr, VfsNodeOps, VfsResult};
 use spin::RwLock;
 
 /// The file node in the RAM filesystem.
@@ -53,5 +53,45 @@ impl VfsNodeOps for FileNode {
         Ok(buf.len())
     }
 
+    fn copy_range_to(
+        &self,
+        offset: u64,
+        dst: &dyn VfsNodeOps,
+        dst_offset: u64,
+        len: usize,
+    ) -> VfsResult<usize> {
+        let dst = dst
+            .as_any()
+            .downcast_ref::<FileNode>()
+            .ok_or(VfsError::Unsupported)?;
+        let (offset, dst_offset) = (offset as usize, dst_offset as usize);
+        if core::ptr::eq(self, dst) {
+            let mut content = self.content.write();
+            let start = content.len().min(offset);
+            let end = content.len().min(offset.saturating_add(len));
+            if dst_offset + (end - start) > content.len() {
+                content.resize(dst_offset + (end - start), 0);
+            }
+            content.copy_within(start..end, dst_offset);
+            return Ok(end - start);
+        }
+        // lock the two nodes in address order, so that copies in opposite
+        // directions can not deadlock
+        let (src, mut content) = if (self as *const Self) < (dst as *const Self) {
+            let src = self.content.read();
+            (src, dst.content.write())
+        } else {
+            let content = dst.content.write();
+            (self.content.read(), content)
+        };
+        let start = src.len().min(offset);
+        let end = src.len().min(offset.saturating_add(len));
+        if dst_offset + (end - start) > content.len() {
+            content.resize(dst_offset + (end - start), 0);
+        }
+        content[dst_offset..dst_offset + (end - start)].copy_from_slice(&src[start..end]);
+        Ok(end - start)
+    }
+
     impl_vfs_non_dir_default! {}
 }
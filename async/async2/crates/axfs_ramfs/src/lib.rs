@@ -9,12 +9,19 @@ extern crate alloc;
 mod dir;
 mod file;
 mod interrupts;
+mod memfd;
+mod strace;
 #[cfg(test)]
 mod tests;
 
 pub use self::dir::DirNode;
 pub use self::file::FileNode;
 pub use self::interrupts::{Interrupts, INTERRUPT};
+pub use self::memfd::{
+    MemfdNode, MemfdWritableMapping, SEAL_FUTURE_WRITE, SEAL_GROW, SEAL_SEAL, SEAL_SHRINK,
+    SEAL_WRITE,
+};
+pub use self::strace::{register_strace_handler, StraceControl, StraceHandler};
 use alloc::sync::Arc;
 use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
 use spin::once::Once;
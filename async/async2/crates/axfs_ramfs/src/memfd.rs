@@ -0,0 +1,142 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axfs_vfs::{impl_vfs_non_dir_default, VfsError, VfsNodeAttr, VfsNodeOps, VfsResult};
+use spin::RwLock;
+
+use crate::FileNode;
+
+/// Prevents further seals from being added.
+pub const SEAL_SEAL: u32 = 0x1;
+/// Prevents the file from shrinking.
+pub const SEAL_SHRINK: u32 = 0x2;
+/// Prevents the file from growing.
+pub const SEAL_GROW: u32 = 0x4;
+/// Prevents any modification of the file content.
+pub const SEAL_WRITE: u32 = 0x8;
+/// Prevents new writers, while existing writable mappings keep working.
+///
+/// The node itself does not enforce it, the owner of the file descriptor does.
+pub const SEAL_FUTURE_WRITE: u32 = 0x10;
+
+const ALL_SEALS: u32 = SEAL_SEAL | SEAL_SHRINK | SEAL_GROW | SEAL_WRITE | SEAL_FUTURE_WRITE;
+
+/// An anonymous file node that is not linked into any directory.
+///
+/// It stores its content like a [`FileNode`] and enforces the write, grow and
+/// shrink seals set on it.
+pub struct MemfdNode {
+    file: FileNode,
+    /// Held for reading by every modification, so that a seal can not be
+    /// added in the middle of one.
+    seals: RwLock<u32>,
+    /// Number of live [`MemfdWritableMapping`]s.
+    writable_mappings: AtomicUsize,
+}
+
+/// A writable shared mapping of a [`MemfdNode`], registered until it is
+/// dropped.
+///
+/// [`SEAL_WRITE`] can not be added while any of them exists.
+pub struct MemfdWritableMapping(Arc<MemfdNode>);
+
+impl Drop for MemfdWritableMapping {
+    fn drop(&mut self) {
+        self.0.writable_mappings.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl MemfdNode {
+    /// Creates an empty node with the given initial seals.
+    pub const fn new(seals: u32) -> Self {
+        Self {
+            file: FileNode::new(),
+            seals: RwLock::new(seals),
+            writable_mappings: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the seals currently set on the node.
+    pub fn seals(&self) -> u32 {
+        *self.seals.read()
+    }
+
+    /// Adds seals to the node.
+    ///
+    /// Returns [`VfsError::InvalidInput`] for unknown seals,
+    /// [`VfsError::PermissionDenied`] once [`SEAL_SEAL`] is set, and
+    /// [`VfsError::ResourceBusy`] when adding [`SEAL_WRITE`] while writable
+    /// shared mappings exist.
+    pub fn add_seals(&self, seals: u32) -> VfsResult {
+        if seals & !ALL_SEALS != 0 {
+            return Err(VfsError::InvalidInput);
+        }
+        let mut current = self.seals.write();
+        if *current & SEAL_SEAL != 0 {
+            return Err(VfsError::PermissionDenied);
+        }
+        if seals & SEAL_WRITE != 0 && self.writable_mappings.load(Ordering::Acquire) > 0 {
+            return Err(VfsError::ResourceBusy);
+        }
+        *current |= seals;
+        Ok(())
+    }
+
+    /// Registers a writable shared mapping of the node, which lasts until the
+    /// returned object is dropped.
+    ///
+    /// Returns [`VfsError::PermissionDenied`] if [`SEAL_WRITE`] is set.
+    pub fn map_writable(self: &Arc<Self>) -> VfsResult<MemfdWritableMapping> {
+        let seals = self.seals.read();
+        if *seals & SEAL_WRITE != 0 {
+            return Err(VfsError::PermissionDenied);
+        }
+        self.writable_mappings.fetch_add(1, Ordering::Acquire);
+        Ok(MemfdWritableMapping(self.clone()))
+    }
+}
+
+impl VfsNodeOps for MemfdNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        self.file.get_attr()
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        let seals = self.seals.read();
+        let old_size = self.file.get_attr()?.size();
+        if (size < old_size && *seals & SEAL_SHRINK != 0)
+            || (size > old_size && *seals & SEAL_GROW != 0)
+        {
+            return Err(VfsError::PermissionDenied);
+        }
+        self.file.truncate(size)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.file.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let seals = self.seals.read();
+        if *seals & SEAL_WRITE != 0 {
+            return Err(VfsError::PermissionDenied);
+        }
+        if *seals & SEAL_GROW != 0 && offset + buf.len() as u64 > self.file.get_attr()?.size() {
+            return Err(VfsError::PermissionDenied);
+        }
+        self.file.write_at(offset, buf)
+    }
+
+    fn copy_range_to(
+        &self,
+        offset: u64,
+        dst: &dyn VfsNodeOps,
+        dst_offset: u64,
+        len: usize,
+    ) -> VfsResult<usize> {
+        // seals only restrict modifications, the node can be copied from freely
+        self.file.copy_range_to(offset, dst, dst_offset, len)
+    }
+
+    impl_vfs_non_dir_default! {}
+}
@@ -0,0 +1,67 @@
+use alloc::string::String;
+use axerrno::AxError;
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use spin::Once;
+
+/// The syscall tracer behind [`StraceControl`].
+///
+/// The tracer and its state live in the syscall layer, the control file only
+/// forwards to it.
+pub trait StraceHandler: Sync {
+    /// The content of the control file, i.e. the traced processes.
+    fn content(&self) -> String;
+
+    /// Applies the commands written to the control file.
+    fn apply(&self, commands: &str) -> Result<(), AxError>;
+}
+
+static HANDLER: Once<&'static dyn StraceHandler> = Once::new();
+
+/// Registers the tracer behind the control file. Only the first registration
+/// takes effect.
+pub fn register_strace_handler(handler: &'static dyn StraceHandler) {
+    HANDLER.call_once(|| handler);
+}
+
+#[derive(Default)]
+/// The control file of the syscall tracer, usually mounted at `/proc/strace`.
+///
+/// It reads as empty and rejects writes until a [`StraceHandler`] is
+/// registered.
+pub struct StraceControl;
+
+impl VfsNodeOps for StraceControl {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o644),
+            VfsNodeType::File,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let content = HANDLER
+            .get()
+            .map(|handler| handler.content())
+            .unwrap_or_default();
+        let bytes = content.as_bytes();
+        let start = bytes.len().min(offset as usize);
+        let end = bytes.len().min(start + buf.len());
+        buf[..end - start].copy_from_slice(&bytes[start..end]);
+        Ok(end - start)
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let handler = HANDLER.get().ok_or(AxError::Unsupported)?;
+        let commands = core::str::from_utf8(buf).map_err(|_| AxError::InvalidInput)?;
+        handler.apply(commands).map(|_| buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        // `echo 1 > /proc/strace` truncates the file before writing
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
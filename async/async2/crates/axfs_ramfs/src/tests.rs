@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+use axfs_vfs::{VfsError, VfsNodeOps, VfsNodeType, VfsResult};
 
 use crate::*;
 
@@ -134,3 +134,131 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+#[test]
+fn test_strace_control() {
+    struct Handler(spin::Mutex<String>);
+
+    impl StraceHandler for Handler {
+        fn content(&self) -> String {
+            self.0.lock().clone()
+        }
+
+        fn apply(&self, commands: &str) -> Result<(), VfsError> {
+            if commands.trim() == "bad" {
+                return Err(VfsError::InvalidInput);
+            }
+            *self.0.lock() = String::from(commands);
+            Ok(())
+        }
+    }
+
+    static HANDLER: Handler = Handler(spin::Mutex::new(String::new()));
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    root.add_node("strace", Arc::new(StraceControl)).unwrap();
+    assert_eq!(
+        root.add_node("strace", Arc::new(StraceControl)).err(),
+        Some(VfsError::AlreadyExists)
+    );
+
+    let node = ramfs.root_dir().lookup("strace").unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(node.read_at(0, &mut buf).unwrap(), 0);
+    assert_eq!(node.write_at(0, b"12\n").err(), Some(VfsError::Unsupported));
+
+    // the control file only forwards to the registered tracer
+    register_strace_handler(&HANDLER);
+    assert_eq!(node.write_at(0, b"12\n").unwrap(), 3);
+    assert_eq!(node.read_at(0, &mut buf).unwrap(), 3);
+    assert_eq!(&buf[..3], b"12\n");
+    assert_eq!(node.read_at(1, &mut buf).unwrap(), 2);
+    assert_eq!(node.read_at(8, &mut buf).unwrap(), 0);
+    assert_eq!(node.write_at(0, b"bad").err(), Some(VfsError::InvalidInput));
+}
+
+#[test]
+fn test_memfd_seals() {
+    let node = MemfdNode::new(0);
+    assert_eq!(node.write_at(0, b"hello").unwrap(), 5);
+    node.add_seals(SEAL_GROW | SEAL_SHRINK).unwrap();
+    assert_eq!(node.seals(), SEAL_GROW | SEAL_SHRINK);
+
+    // writing inside the file is still allowed, growing or shrinking is not
+    assert_eq!(node.write_at(1, b"EL").unwrap(), 2);
+    assert_eq!(
+        node.write_at(4, b"ooo").err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(node.truncate(2).err(), Some(VfsError::PermissionDenied));
+    assert_eq!(node.truncate(8).err(), Some(VfsError::PermissionDenied));
+    assert_eq!(node.truncate(5), Ok(()));
+
+    node.add_seals(SEAL_WRITE | SEAL_SEAL).unwrap();
+    assert_eq!(
+        node.write_at(0, b"h").err(),
+        Some(VfsError::PermissionDenied)
+    );
+    let mut buf = [0; 8];
+    assert_eq!(node.read_at(0, &mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hELlo");
+
+    assert_eq!(
+        node.add_seals(SEAL_FUTURE_WRITE).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(
+        MemfdNode::new(0).add_seals(0x100).err(),
+        Some(VfsError::InvalidInput)
+    );
+}
+
+#[test]
+fn test_memfd_writable_mapping() {
+    let node = Arc::new(MemfdNode::new(0));
+    let mapping = node.map_writable().unwrap();
+    let another = node.map_writable().unwrap();
+    assert_eq!(
+        node.add_seals(SEAL_WRITE).err(),
+        Some(VfsError::ResourceBusy)
+    );
+    // other seals do not care about the mappings
+    node.add_seals(SEAL_GROW).unwrap();
+
+    drop(mapping);
+    assert_eq!(
+        node.add_seals(SEAL_WRITE).err(),
+        Some(VfsError::ResourceBusy)
+    );
+    drop(another);
+    node.add_seals(SEAL_WRITE).unwrap();
+    assert_eq!(node.map_writable().err(), Some(VfsError::PermissionDenied));
+}
+
+#[test]
+fn test_copy_range() {
+    let src = FileNode::new();
+    let dst = FileNode::new();
+    src.write_at(0, b"hello world").unwrap();
+
+    assert_eq!(src.copy_range_to(6, &dst, 2, 16).unwrap(), 5);
+    let mut buf = [0xff; 16];
+    assert_eq!(dst.read_at(0, &mut buf).unwrap(), 7);
+    assert_eq!(&buf[..7], b"\0\0world");
+    // copying past the end of the source copies nothing
+    assert_eq!(src.copy_range_to(11, &dst, 0, 4).unwrap(), 0);
+
+    // overlapping ranges inside the same file
+    assert_eq!(src.copy_range_to(0, &src, 6, 5).unwrap(), 5);
+    assert_eq!(src.read_at(0, &mut buf).unwrap(), 11);
+    assert_eq!(&buf[..11], b"hello hello");
+
+    // a memfd can be the source, but never the destination
+    let memfd = MemfdNode::new(SEAL_WRITE);
+    assert_eq!(memfd.copy_range_to(0, &dst, 0, 4).unwrap(), 0);
+    assert_eq!(
+        src.copy_range_to(0, &memfd, 0, 4).err(),
+        Some(VfsError::Unsupported)
+    );
+}
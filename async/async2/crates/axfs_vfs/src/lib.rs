@@ -160,6 +160,23 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Copy `len` bytes starting at `offset` of this file to `dst` at
+    /// `dst_offset`, without going through an intermediate buffer.
+    ///
+    /// Returns the number of bytes copied, which is less than `len` only at
+    /// the end of this file. Filesystems that can not copy between the two
+    /// nodes directly return [`AxError::Unsupported`], and the caller is
+    /// expected to fall back to reading and writing.
+    fn copy_range_to(
+        &self,
+        _offset: u64,
+        _dst: &dyn VfsNodeOps,
+        _dst_offset: u64,
+        _len: usize,
+    ) -> VfsResult<usize> {
+        ax_err!(Unsupported)
+    }
+
     /// Convert `&self` to [`&dyn Any`][1] that can use
     /// [`Any::downcast_ref`][2].
     ///
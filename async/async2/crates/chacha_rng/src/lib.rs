@@ -0,0 +1,104 @@
+//! A ChaCha20 based cryptographically secure random number generator.
+//!
+//! - [`EntropyPool`]: accumulates unpredictable samples (interrupt timing,
+//!   hardware random numbers, ...) and keeps an estimate of how much entropy
+//!   they carry.
+//! - [`ChaChaRng`]: expands a 256-bit seed extracted from the pool into an
+//!   arbitrarily long random stream. It erases its key after every request,
+//!   so a leaked state does not reveal previous outputs.
+//!
+//! Both are built on the ChaCha20 block function from [RFC 8439][1].
+//!
+//! [1]: https://www.rfc-editor.org/rfc/rfc8439
+//!
+//! # Examples
+//!
+//! ```
+//! use chacha_rng::{ChaChaRng, EntropyPool};
+//!
+//! let mut pool = EntropyPool::new();
+//! pool.mix(&0x1234_5678u64.to_ne_bytes(), 8);
+//!
+//! let mut rng = ChaChaRng::new(pool.extract());
+//! let mut buf = [0u8; 16];
+//! rng.fill_bytes(&mut buf);
+//! ```
+
+#![cfg_attr(not(test), no_std)]
+
+mod pool;
+mod rng;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::pool::EntropyPool;
+pub use self::rng::ChaChaRng;
+
+/// Size of a ChaCha20 key, and of the seeds used in this crate, in bytes.
+pub const KEY_SIZE: usize = 32;
+
+/// Size of a ChaCha20 block in bytes.
+pub const BLOCK_SIZE: usize = 64;
+
+/// Size of a ChaCha20 nonce in bytes.
+pub const NONCE_SIZE: usize = 12;
+
+/// "expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+#[inline(always)]
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// Computes one ChaCha20 block for the given key, block counter and nonce.
+pub fn chacha20_block(
+    key: &[u8; KEY_SIZE],
+    counter: u32,
+    nonce: &[u8; NONCE_SIZE],
+) -> [u8; BLOCK_SIZE] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&CONSTANTS);
+    for (word, bytes) in state[4..12].iter_mut().zip(key.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    state[12] = counter;
+    for (word, bytes) in state[13..].iter_mut().zip(nonce.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let mut working = state;
+    for _ in 0..10 {
+        quarter_round(&mut working, 0, 4, 8, 12);
+        quarter_round(&mut working, 1, 5, 9, 13);
+        quarter_round(&mut working, 2, 6, 10, 14);
+        quarter_round(&mut working, 3, 7, 11, 15);
+        quarter_round(&mut working, 0, 5, 10, 15);
+        quarter_round(&mut working, 1, 6, 11, 12);
+        quarter_round(&mut working, 2, 7, 8, 13);
+        quarter_round(&mut working, 3, 4, 9, 14);
+    }
+
+    let mut block = [0u8; BLOCK_SIZE];
+    for (i, bytes) in block.chunks_exact_mut(4).enumerate() {
+        bytes.copy_from_slice(&working[i].wrapping_add(state[i]).to_le_bytes());
+    }
+    block
+}
+
+/// Overwrites `buf` with zeros in a way the compiler can not optimize away.
+fn zeroize(buf: &mut [u8]) {
+    for byte in buf.iter_mut() {
+        // SAFETY: `byte` is a valid and aligned reference.
+        unsafe { core::ptr::write_volatile(byte, 0) };
+    }
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+}
@@ -0,0 +1,75 @@
+use crate::{chacha20_block, zeroize, KEY_SIZE, NONCE_SIZE};
+
+/// An entropy pool that accumulates unpredictable samples.
+///
+/// The pool state is a 256-bit key. Every 32-byte chunk of input is XORed into
+/// the key, and the result is run through the ChaCha20 block function to
+/// produce the next state, so an attacker who does not know the state can not
+/// predict it however the input is chosen. The caller credits each sample
+/// with an estimate of the entropy it carries, in bits.
+pub struct EntropyPool {
+    state: [u8; KEY_SIZE],
+    /// Number of chunks mixed so far, used as the nonce of each mix.
+    generation: u64,
+    /// Estimated entropy mixed in since the last extraction, in bits.
+    entropy_bits: usize,
+}
+
+impl EntropyPool {
+    /// Creates an empty pool.
+    pub const fn new() -> Self {
+        Self {
+            state: [0; KEY_SIZE],
+            generation: 0,
+            entropy_bits: 0,
+        }
+    }
+
+    /// Mixes `data` into the pool, crediting it with `bits` bits of entropy.
+    pub fn mix(&mut self, data: &[u8], bits: usize) {
+        for chunk in data.chunks(KEY_SIZE) {
+            let mut key = self.state;
+            for (k, d) in key.iter_mut().zip(chunk) {
+                *k ^= d;
+            }
+            let mut nonce = [0u8; NONCE_SIZE];
+            nonce[..8].copy_from_slice(&self.generation.to_le_bytes());
+            nonce[8..].copy_from_slice(&(chunk.len() as u32).to_le_bytes());
+            let mut block = chacha20_block(&key, 0, &nonce);
+            self.state.copy_from_slice(&block[..KEY_SIZE]);
+            self.generation = self.generation.wrapping_add(1);
+            zeroize(&mut key);
+            zeroize(&mut block);
+        }
+        self.entropy_bits = self.entropy_bits.saturating_add(bits);
+    }
+
+    /// Estimated entropy mixed in since the last extraction, in bits.
+    pub fn entropy_bits(&self) -> usize {
+        self.entropy_bits
+    }
+
+    /// Extracts a 256-bit seed from the pool and resets the entropy estimate.
+    ///
+    /// The pool state is replaced at the same time, so the seed can not be
+    /// recomputed from the pool afterwards.
+    pub fn extract(&mut self) -> [u8; KEY_SIZE] {
+        let mut nonce = [0xff; NONCE_SIZE];
+        nonce[..8].copy_from_slice(&self.generation.to_le_bytes());
+        let mut block = chacha20_block(&self.state, 0, &nonce);
+        self.state.copy_from_slice(&block[..KEY_SIZE]);
+        self.generation = self.generation.wrapping_add(1);
+        self.entropy_bits = 0;
+
+        let mut seed = [0u8; KEY_SIZE];
+        seed.copy_from_slice(&block[KEY_SIZE..]);
+        zeroize(&mut block);
+        seed
+    }
+}
+
+impl Default for EntropyPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
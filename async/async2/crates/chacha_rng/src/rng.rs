@@ -0,0 +1,70 @@
+use crate::{chacha20_block, zeroize, BLOCK_SIZE, KEY_SIZE, NONCE_SIZE};
+
+/// A cryptographically secure random number generator using ChaCha20.
+///
+/// Every request first derives two keys from the current one: the next
+/// generator key, which replaces the current one right away ("fast key
+/// erasure"), and a one-time key whose keystream becomes the output.
+pub struct ChaChaRng {
+    key: [u8; KEY_SIZE],
+}
+
+impl ChaChaRng {
+    /// Creates a generator from a 256-bit seed.
+    pub const fn new(seed: [u8; KEY_SIZE]) -> Self {
+        Self { key: seed }
+    }
+
+    /// Mixes a new seed into the generator.
+    ///
+    /// The new key depends on both the old key and `seed`, so a predictable
+    /// seed never makes the generator weaker than it was.
+    pub fn reseed(&mut self, seed: &[u8; KEY_SIZE]) {
+        let mut key = self.key;
+        for (k, s) in key.iter_mut().zip(seed) {
+            *k ^= s;
+        }
+        let mut block = chacha20_block(&key, 0, &[0xff; NONCE_SIZE]);
+        self.key.copy_from_slice(&block[..KEY_SIZE]);
+        zeroize(&mut key);
+        zeroize(&mut block);
+    }
+
+    /// Fills `buf` with random bytes.
+    pub fn fill_bytes(&mut self, buf: &mut [u8]) {
+        let mut block = chacha20_block(&self.key, 0, &[0; NONCE_SIZE]);
+        self.key.copy_from_slice(&block[..KEY_SIZE]);
+        let mut stream_key = [0u8; KEY_SIZE];
+        stream_key.copy_from_slice(&block[KEY_SIZE..]);
+
+        for (counter, chunk) in buf.chunks_mut(BLOCK_SIZE).enumerate() {
+            block = chacha20_block(&stream_key, counter as u32, &[0; NONCE_SIZE]);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        zeroize(&mut block);
+        zeroize(&mut stream_key);
+    }
+
+    /// Returns a random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_ne_bytes(buf)
+    }
+
+    /// Creates an independent generator seeded from this one.
+    ///
+    /// Useful to produce a large amount of output without holding the lock
+    /// that protects a shared generator.
+    pub fn fork(&mut self) -> Self {
+        let mut seed = [0u8; KEY_SIZE];
+        self.fill_bytes(&mut seed);
+        Self::new(seed)
+    }
+}
+
+impl Drop for ChaChaRng {
+    fn drop(&mut self) {
+        zeroize(&mut self.key);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::*;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_chacha20_block() {
+    // RFC 8439, section 2.3.2
+    let key: [u8; KEY_SIZE] = core::array::from_fn(|i| i as u8);
+    let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+    assert_eq!(
+        chacha20_block(&key, 1, &nonce).to_vec(),
+        hex(concat!(
+            "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e",
+            "d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e"
+        ))
+    );
+
+    // RFC 8439, appendix A.1, test vector #1
+    assert_eq!(
+        chacha20_block(&[0; KEY_SIZE], 0, &[0; NONCE_SIZE]).to_vec(),
+        hex(concat!(
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7",
+            "da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586"
+        ))
+    );
+}
+
+#[test]
+fn test_rng() {
+    let mut a = ChaChaRng::new([1; KEY_SIZE]);
+    let mut b = ChaChaRng::new([1; KEY_SIZE]);
+    let (mut x, mut y) = ([0u8; 200], [0u8; 200]);
+    a.fill_bytes(&mut x);
+    b.fill_bytes(&mut y);
+    assert_eq!(x, y);
+    assert_ne!(x[..100], x[100..]);
+
+    // the key is erased after every request, outputs never repeat
+    a.fill_bytes(&mut y);
+    assert_ne!(x, y);
+    assert_ne!(a.next_u64(), a.next_u64());
+
+    // reseeding with anything changes the stream
+    b.fill_bytes(&mut y);
+    b.reseed(&[0; KEY_SIZE]);
+    let (mut p, mut q) = ([0u8; 32], [0u8; 32]);
+    a.fill_bytes(&mut p);
+    b.fill_bytes(&mut q);
+    assert_ne!(p, q);
+
+    let mut c = a.fork();
+    assert_ne!(c.next_u64(), a.next_u64());
+}
+
+#[test]
+fn test_entropy_pool() {
+    let mut a = EntropyPool::new();
+    let mut b = EntropyPool::default();
+    a.mix(b"interrupt", 1);
+    a.mix(&[0x5a; 100], 8);
+    b.mix(b"interrupt", 1);
+    b.mix(&[0x5a; 99], 8);
+    assert_eq!(a.entropy_bits(), 9);
+
+    let seed = a.extract();
+    assert_eq!(a.entropy_bits(), 0);
+    assert_ne!(seed, b.extract());
+    // extraction also moves the pool forward
+    assert_ne!(seed, a.extract());
+}
@@ -64,6 +64,7 @@ pub fn get_auxv_vector(
 /// * `args` - The arguments of the app
 /// * `envs` - The environment variables of the app
 /// * `auxv` - The auxv vector of the app
+/// * `random` - The 16 random bytes `AT_RANDOM` points to
 /// * `stack_top` - The top address of the stack
 /// * `stack_size` - The size of the stack.
 ///
@@ -84,13 +85,14 @@ pub fn get_app_stack_region(
     args: Vec<String>,
     envs: &[String],
     auxv: BTreeMap<u8, usize>,
+    random: [u8; 16],
     stack_top: VirtAddr,
     stack_size: usize,
 ) -> (Vec<u8>, usize) {
     let ustack_top = stack_top;
     let ustack_bottom = ustack_top + stack_size;
     // The stack variable is actually the information carried by the stack
-    let stack = init_stack(args, envs, auxv, ustack_bottom.into());
+    let stack = init_stack(args, envs, auxv, random, ustack_bottom.into());
     let ustack_bottom = stack.get_sp();
     let mut data = [0_u8].repeat(stack_size - stack.get_len());
     data.extend(stack.get_data_front_ref());
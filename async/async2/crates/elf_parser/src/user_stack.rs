@@ -62,15 +62,17 @@ impl UserStack {
 }
 
 /// 初始化用户栈
+///
+/// `random` 为 AT_RANDOM 指向的 16 字节随机数，用户态以其初始化栈保护等机制，因此每次都应重新生成
 pub fn init_stack(
     args: Vec<String>,
     envs: &[String],
     auxv: BTreeMap<u8, usize>,
+    random: [u8; 16],
     sp: usize,
 ) -> UserStack {
     let mut stack = UserStack::new(sp);
-    let random_str: &[usize; 2] = &[3703830112808742751usize, 7081108068768079778usize];
-    stack.push(random_str.as_slice());
+    stack.push(random.as_slice());
     let random_str_pos = stack.get_sp();
     // 按照栈的结构，先加入envs和argv的对应实际内容
     let envs_slice: Vec<_> = envs
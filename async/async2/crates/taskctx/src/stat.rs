@@ -139,6 +139,11 @@ impl TimeStat {
         (self.timer_interval_ns / 1000, self.timer_remained_ns / 1000)
     }
 
+    /// 当前设定的计时器类型
+    pub fn timer_type(&self) -> TimerType {
+        self.timer_type
+    }
+
     /// 设定计时器信息
     ///
     /// 若type不为None则返回成功
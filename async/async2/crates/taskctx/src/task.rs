@@ -1,7 +1,7 @@
 #[cfg(feature = "tls")]
 use crate::tls::TlsArea;
 
-use crate::TimeStat;
+use crate::{TimeStat, TimerType};
 extern crate alloc;
 use alloc::{boxed::Box, string::String};
 
@@ -353,6 +353,13 @@ impl TaskInner {
         unsafe { (*time).output_timer_as_us() }
     }
 
+    #[inline]
+    /// 当前设定的计时器类型
+    pub fn timer_type(&self) -> TimerType {
+        let time = self.time.get();
+        unsafe { (*time).timer_type() }
+    }
+
     #[inline]
     /// 设置计时器信息
     ///
@@ -418,13 +425,15 @@ impl TaskInner {
     // }
 
     /// 设置CPU set，其中set_size为bytes长度
+    ///
+    /// 只保留 mask 中前 min(set_size * 8, max_cpu_num) 位
     pub fn set_cpu_set(&self, mask: usize, set_size: usize, max_cpu_num: usize) {
-        let len = if set_size * 4 > max_cpu_num {
-            max_cpu_num
+        let len = (set_size * 8).min(max_cpu_num);
+        let now_mask = if len >= usize::BITS as usize {
+            mask
         } else {
-            set_size * 4
+            mask & ((1 << len) - 1)
         };
-        let now_mask = mask & 1 << ((len) - 1);
         self.cpu_set.store(now_mask as u64, Ordering::Release)
     }
 
@@ -511,8 +520,8 @@ impl TaskInner {
 
             #[cfg(feature = "monolithic")]
             sched_status: UnsafeCell::new(SchedStatus {
-                policy: SchedPolicy::SCHED_FIFO,
-                priority: 1,
+                policy: SchedPolicy::SCHED_OTHER,
+                priority: 0,
             }),
 
             #[cfg(feature = "monolithic")]
@@ -1,3 +1,4 @@
+use axfs_vfs::VfsNodeRef;
 use axio::{prelude::*, Result, SeekFrom};
 use core::fmt;
 
@@ -70,6 +71,12 @@ impl OpenOptions {
     pub fn open(&self, path: &str) -> Result<File> {
         fops::File::open(path, &self.0).map(|inner| File { inner })
     }
+
+    /// Opens the given node with the options specified by `self`, without
+    /// looking up any path.
+    pub fn open_node(&self, node: VfsNodeRef) -> Result<File> {
+        fops::File::open_node(node, &self.0).map(|inner| File { inner })
+    }
 }
 
 impl Metadata {
@@ -184,6 +191,33 @@ impl File {
     pub fn truncate(&mut self, len: usize) -> Result<()> {
         self.inner.truncate(len as u64)
     }
+
+    /// Reads the file at the given position, leaving the cursor unchanged.
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        self.inner.read_at(offset, buf)
+    }
+
+    /// Writes the file at the given position, leaving the cursor unchanged.
+    ///
+    /// The position is used even if the file is opened in append mode.
+    pub fn write_at(&self, offset: u64, buf: &[u8]) -> Result<usize> {
+        self.inner.write_at(offset, buf)
+    }
+
+    /// Copies `len` bytes of this file at `offset` to `dst` at `dst_offset`
+    /// without an intermediate buffer, leaving both cursors unchanged.
+    ///
+    /// Fails with `Unsupported` if the filesystem can not do the copy itself.
+    pub fn copy_range_to(
+        &self,
+        offset: u64,
+        dst: &File,
+        dst_offset: u64,
+        len: usize,
+    ) -> Result<usize> {
+        self.inner
+            .copy_range_to(offset, &dst.inner, dst_offset, len)
+    }
 }
 
 impl Read for File {
@@ -7,6 +7,7 @@ pub mod port;
 
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
 pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
+pub use crate::root::MountInfo;
 use axerrno::AxResult;
 use axfs_vfs::VfsNodeRef;
 pub use axio::{Read, Seek, SeekFrom, Write};
@@ -100,7 +101,43 @@ pub fn path_exists(path: &str) -> bool {
     crate::root::lookup(None, path).is_ok()
 }
 
+/// Check if a path exists and is a directory.
+pub fn is_dir(path: &str) -> bool {
+    use axfs_vfs::VfsNodeOps;
+    crate::root::lookup(None, path)
+        .and_then(|node| node.get_attr())
+        .map_or(false, |attr| attr.is_dir())
+}
+
 /// Look up a file by a given path.
 pub fn lookup(path: &str) -> AxResult<VfsNodeRef> {
     crate::root::lookup(None, path)
 }
+
+/// Mounts a filesystem of type `fstype` at the existing directory `target`.
+///
+/// Supported types are `tmpfs` (or `ramfs`), `proc`, `devtmpfs`, `sysfs`, and
+/// the type of the block device filesystem, for which `source` names the
+/// device, e.g. `/dev/vdb`. Returns `Unsupported` for other types.
+pub fn mount(source: &str, target: &str, fstype: &str) -> io::Result<()> {
+    use axfs_vfs::VfsNodeOps;
+    let target = crate::root::absolute_path(target)?;
+    if !crate::root::lookup(None, &target)?.get_attr()?.is_dir() {
+        return Err(io::Error::NotADirectory);
+    }
+    let fs = crate::mounts::new_fs(source, fstype)?;
+    crate::root::mount(&target, source, fstype, fs)
+}
+
+/// Unmounts the filesystem mounted at `target`.
+///
+/// Returns `InvalidInput` if `target` is not a mount point, and
+/// `ResourceBusy` if other filesystems are mounted under it.
+pub fn umount(target: &str) -> io::Result<()> {
+    crate::root::umount(&crate::root::absolute_path(target)?)
+}
+
+/// Returns the mount table, starting with the root filesystem.
+pub fn mount_table() -> Vec<MountInfo> {
+    crate::root::mount_table()
+}
@@ -110,6 +110,8 @@ bitflags! {
         const NOCTTY = 1 << 8;
         /// 同上，在不同的库中可能会用到这个或者上一个
         const EXCL = 1 << 9;
+        /// 每次写入前将文件指针移动到文件末尾
+        const APPEND = 1 << 10;
         /// 非阻塞读写?(虽然不知道为什么但 date.lua 也要)
         /// 在 socket 中使用得较多
         const NON_BLOCK = 1 << 11;
@@ -251,15 +253,30 @@ pub trait FileExt: Read + Write + Seek + AsAny + Send + Sync {
 /// 因为文件描述符读取的时候，是用到内部File成员的读取函数，自身应当为不可变，从而可以被Arc指针调用
 pub trait FileIO: AsAny + Send + Sync {
     /// 读取操作
+    ///
+    /// 管道、终端等需要等待数据的文件在还不能读取时返回 `WouldBlock`，不会在这里阻塞，
+    /// 由系统调用根据 O_NONBLOCK 决定返回 EAGAIN 还是挂起等待后重试
     fn read(&self, _buf: &mut [u8]) -> AxResult<usize> {
         Err(AxError::Unsupported) // 如果没有实现, 则返回Unsupported
     }
 
-    /// 写入操作
+    /// 写入操作，需要等待空间时与 read 一样返回 `WouldBlock`
     fn write(&self, _buf: &[u8]) -> AxResult<usize> {
         Err(AxError::Unsupported) // 如果没有实现, 则返回Unsupported
     }
 
+    /// 在指定偏移处读取，不改变文件的读写位置
+    ///
+    /// 不可定位的文件（如管道）返回 `Unsupported`
+    fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> AxResult<usize> {
+        Err(AxError::Unsupported)
+    }
+
+    /// 在指定偏移处写入，不改变文件的读写位置，也不受 O_APPEND 的影响
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> AxResult<usize> {
+        Err(AxError::Unsupported)
+    }
+
     /// 刷新操作
     fn flush(&self) -> AxResult<()> {
         Err(AxError::Unsupported) // 如果没有实现, 则返回Unsupported
@@ -313,12 +330,6 @@ pub trait FileIO: AsAny + Send + Sync {
         OpenFlags::empty()
     }
 
-    /// 设置 close_on_exec 位
-    /// 设置成功返回false
-    fn set_close_on_exec(&self, _is_set: bool) -> bool {
-        false
-    }
-
     /// 处于“意外情况”。在 (p)select 和 (p)poll 中会使用到
     ///
     /// 当前基本默认为false
@@ -400,21 +411,56 @@ impl From<usize> for AccessMode {
 #[allow(missing_docs)]
 pub const TCGETS: usize = 0x5401;
 #[allow(missing_docs)]
+pub const TCSETS: usize = 0x5402;
+#[allow(missing_docs)]
+pub const TCSETSW: usize = 0x5403;
+#[allow(missing_docs)]
+pub const TCSETSF: usize = 0x5404;
+#[allow(missing_docs)]
 pub const TIOCGPGRP: usize = 0x540F;
 #[allow(missing_docs)]
 pub const TIOCSPGRP: usize = 0x5410;
 #[allow(missing_docs)]
 pub const TIOCGWINSZ: usize = 0x5413;
 #[allow(missing_docs)]
+pub const TIOCSWINSZ: usize = 0x5414;
+#[allow(missing_docs)]
 pub const FIONBIO: usize = 0x5421;
 #[allow(missing_docs)]
+pub const FIONCLEX: usize = 0x5450;
+#[allow(missing_docs)]
 pub const FIOCLEX: usize = 0x5451;
 #[repr(C)]
 #[derive(Clone, Copy, Default)]
 /// the size of the console window
 pub struct ConsoleWinSize {
-    ws_row: u16,
-    ws_col: u16,
-    ws_xpixel: u16,
-    ws_ypixel: u16,
+    /// 行数
+    pub ws_row: u16,
+    /// 列数
+    pub ws_col: u16,
+    /// 宽度，单位为像素
+    pub ws_xpixel: u16,
+    /// 高度，单位为像素
+    pub ws_ypixel: u16,
+}
+
+/// termios 中控制字符数组的长度
+pub const NCCS: usize = 19;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+/// 终端属性，即内核中的 `struct termios`，由 TCGETS 与 TCSETS 读写
+pub struct Termios {
+    /// 输入模式
+    pub c_iflag: u32,
+    /// 输出模式
+    pub c_oflag: u32,
+    /// 控制模式
+    pub c_cflag: u32,
+    /// 本地模式
+    pub c_lflag: u32,
+    /// 行规程
+    pub c_line: u8,
+    /// 控制字符
+    pub c_cc: [u8; NCCS],
 }
@@ -164,6 +164,26 @@ impl File {
         Self::_open_at(None, path, opts)
     }
 
+    /// Opens a node that is not necessarily reachable from any directory, such
+    /// as an anonymous in-memory file. Returns a [`File`] object.
+    pub fn open_node(node: VfsNodeRef, opts: &OpenOptions) -> AxResult<Self> {
+        if !opts.is_valid() || opts.create || opts.create_new {
+            return ax_err!(InvalidInput);
+        }
+        if node.get_attr()?.is_dir() {
+            return ax_err!(IsADirectory);
+        }
+        node.open()?;
+        if opts.truncate {
+            node.truncate(0)?;
+        }
+        Ok(Self {
+            node: WithCap::new(node, opts.into()),
+            is_append: opts.append,
+            offset: 0,
+        })
+    }
+
     /// Truncates the file to the specified size.
     pub fn truncate(&self, size: u64) -> AxResult {
         self.node.access(Cap::WRITE)?.truncate(size)?;
@@ -215,6 +235,25 @@ impl File {
         Ok(write_len)
     }
 
+    /// Copies `len` bytes of the file at `offset` to `dst` at `dst_offset`
+    /// inside the filesystem. Returns the number of bytes copied.
+    ///
+    /// It does not update the cursors of either file. Returns
+    /// [`Unsupported`](axerrno::AxError::Unsupported) if the filesystem can not
+    /// copy between the two files directly.
+    pub fn copy_range_to(
+        &self,
+        offset: u64,
+        dst: &File,
+        dst_offset: u64,
+        len: usize,
+    ) -> AxResult<usize> {
+        let node = self.node.access(Cap::READ)?;
+        let dst_node = dst.node.access(Cap::WRITE)?;
+        let copy_len = node.copy_range_to(offset, dst_node.as_ref(), dst_offset, len)?;
+        Ok(copy_len)
+    }
+
     /// Flushes the file, writes all buffered data to the underlying device.
     pub fn flush(&self) -> AxResult {
         self.node.access(Cap::WRITE)?.fsync()?;
@@ -34,10 +34,14 @@ impl Ext4FileSystem {
             disk.size(),
             disk.position()
         );
-        let inner =
-            Ext4BlockWrapper::<Disk>::new(disk).expect("failed to initialize EXT4 filesystem");
+        Self::try_new(disk).expect("failed to initialize EXT4 filesystem")
+    }
+
+    /// Opens the EXT4 filesystem on the disk.
+    pub fn try_new(disk: Disk) -> VfsResult<Self> {
+        let inner = Ext4BlockWrapper::<Disk>::new(disk).map_err(|_| VfsError::InvalidData)?;
         let root = Arc::new(FileWrapper::new("/", InodeTypes::EXT4_DE_DIR));
-        Self { inner, root }
+        Ok(Self { inner, root })
     }
 }
 
@@ -324,6 +328,10 @@ impl VfsNodeOps for FileWrapper {
             .map(|_v| ())
             .map_err(|e| e.try_into().unwrap())
     }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
 }
 
 impl Drop for FileWrapper {
@@ -47,4 +47,9 @@ pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
     let dev = blk_devs.take_one().expect("No block device found!");
     info!("  use block device 0: {:?}", dev.device_name());
     self::root::init_rootfs(self::dev::Disk::new(dev));
+    // the other block devices can be mounted later
+    while let Some(dev) = blk_devs.take_one() {
+        let name = self::mounts::add_block_device(self::dev::Disk::new(dev));
+        info!("  found block device {}", name);
+    }
 }
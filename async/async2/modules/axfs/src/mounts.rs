@@ -1,15 +1,139 @@
-use alloc::sync::Arc;
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+use axerrno::{ax_err, AxResult};
 use axfs_vfs::{VfsNodeType, VfsOps, VfsResult};
+use spinlock::SpinNoIrq;
 
+use crate::dev::Disk;
 use crate::fs;
 
+cfg_if::cfg_if! {
+    if #[cfg(feature = "myfs")] {
+        /// The type of the filesystem on block devices.
+        pub(crate) const DISK_FS_TYPE: &str = "myfs";
+
+        fn new_disk_fs(disk: Disk) -> AxResult<Arc<dyn VfsOps>> {
+            Ok(fs::myfs::new_myfs(disk))
+        }
+    } else if #[cfg(feature = "ext4fs")] {
+        /// The type of the filesystem on block devices.
+        pub(crate) const DISK_FS_TYPE: &str = "ext4";
+
+        fn new_disk_fs(disk: Disk) -> AxResult<Arc<dyn VfsOps>> {
+            Ok(Arc::new(fs::ext4fs::Ext4FileSystem::try_new(disk)?))
+        }
+    } else if #[cfg(feature = "fatfs")] {
+        /// The type of the filesystem on block devices.
+        pub(crate) const DISK_FS_TYPE: &str = "vfat";
+
+        fn new_disk_fs(disk: Disk) -> AxResult<Arc<dyn VfsOps>> {
+            // the directory nodes borrow the filesystem, so it must never be dropped
+            let fat_fs: &'static Arc<fs::fatfs::FatFileSystem> = alloc::boxed::Box::leak(
+                alloc::boxed::Box::new(Arc::new(fs::fatfs::FatFileSystem::try_new(disk)?)),
+            );
+            fat_fs.init();
+            Ok(fat_fs.clone())
+        }
+    }
+}
+
+/// A block device that can be mounted.
+enum BlockDevice {
+    /// Never mounted.
+    Idle(Disk),
+    /// Mounted at least once. The filesystem is kept, so that mounting the
+    /// device again shares it with the existing mounts.
+    Mounted(Arc<dyn VfsOps>),
+    /// Being probed, or holding no filesystem that can be recognized.
+    Unusable,
+}
+
+/// Block devices named `/dev/vda`, `/dev/vdb`, ... in the order they are found.
+static BLOCK_DEVICES: SpinNoIrq<Vec<(String, BlockDevice)>> = SpinNoIrq::new(Vec::new());
+
+fn add_block_device_entry(device: BlockDevice) -> String {
+    let mut devices = BLOCK_DEVICES.lock();
+    let name = format!("/dev/vd{}", (b'a' + devices.len() as u8) as char);
+    devices.push((name.clone(), device));
+    name
+}
+
+/// Registers a block device that is not mounted yet, returns its name.
+pub(crate) fn add_block_device(disk: Disk) -> String {
+    add_block_device_entry(BlockDevice::Idle(disk))
+}
+
+/// Registers a block device whose filesystem is already created, such as the
+/// root device, returns its name.
+pub(crate) fn add_mounted_block_device(fs: Arc<dyn VfsOps>) -> String {
+    add_block_device_entry(BlockDevice::Mounted(fs))
+}
+
+/// Returns the filesystem on the named block device, creating it on the
+/// first mount.
+fn block_device_fs(source: &str) -> AxResult<Arc<dyn VfsOps>> {
+    let mut devices = BLOCK_DEVICES.lock();
+    let Some((_, device)) = devices.iter_mut().find(|(name, _)| name == source) else {
+        return ax_err!(NotFound, "no such block device");
+    };
+    let disk = match core::mem::replace(device, BlockDevice::Unusable) {
+        BlockDevice::Idle(disk) => disk,
+        BlockDevice::Mounted(fs) => {
+            *device = BlockDevice::Mounted(fs.clone());
+            return Ok(fs);
+        }
+        BlockDevice::Unusable => return ax_err!(InvalidInput, "unusable block device"),
+    };
+    // do not hold the lock while reading the disk
+    drop(devices);
+    let fs = new_disk_fs(disk)?;
+    let mut devices = BLOCK_DEVICES.lock();
+    if let Some((_, device)) = devices.iter_mut().find(|(name, _)| name == source) {
+        *device = BlockDevice::Mounted(fs.clone());
+    }
+    Ok(fs)
+}
+
+/// Creates a filesystem of type `fstype` to be mounted.
+///
+/// `source` is only used by filesystems on block devices, where it names the
+/// device. Returns `Unsupported` for unknown filesystem types.
+pub(crate) fn new_fs(source: &str, fstype: &str) -> AxResult<Arc<dyn VfsOps>> {
+    match fstype {
+        #[cfg(feature = "ramfs")]
+        "tmpfs" | "ramfs" => Ok(ramfs()),
+        #[cfg(feature = "devfs")]
+        "devtmpfs" => Ok(devfs()),
+        #[cfg(feature = "procfs")]
+        "proc" => Ok(procfs()?),
+        #[cfg(feature = "sysfs")]
+        "sysfs" => Ok(sysfs()?),
+        _ if fstype == DISK_FS_TYPE => block_device_fs(source),
+        _ => ax_err!(Unsupported, "unknown filesystem type"),
+    }
+}
+
+/// The kernel random number generator, behind `/dev/random` and `/dev/urandom`.
+#[cfg(feature = "devfs")]
+struct KernelRandom;
+
+#[cfg(feature = "devfs")]
+impl fs::devfs::RandomSource for KernelRandom {
+    fn fill_bytes(&self, buf: &mut [u8]) {
+        axrand::fill_bytes(buf);
+    }
+
+    fn add_entropy(&self, data: &[u8]) {
+        axrand::add_entropy(data);
+    }
+}
+
 #[cfg(feature = "devfs")]
 pub(crate) fn devfs() -> Arc<fs::devfs::DeviceFileSystem> {
     let null = fs::devfs::NullDev;
     let zero = fs::devfs::ZeroDev;
     let bar = fs::devfs::ZeroDev;
-    let random = fs::devfs::RandomDev::default();
-    let urandom = fs::devfs::RandomDev::default();
+    let random = fs::devfs::RandomDev::new(&KernelRandom);
+    let urandom = fs::devfs::RandomDev::new(&KernelRandom);
 
     let devfs = fs::devfs::DeviceFileSystem::new();
     let foo_dir = devfs.mkdir("foo");
@@ -38,6 +162,62 @@ pub(crate) fn ramfs() -> Arc<fs::ramfs::RamFileSystem> {
     Arc::new(fs::ramfs::RamFileSystem::new())
 }
 
+/// `/proc/mounts`, generated from the live mount table on every read.
+#[cfg(all(feature = "procfs", feature = "monolithic"))]
+struct ProcMounts;
+
+#[cfg(all(feature = "procfs", feature = "monolithic"))]
+impl ProcMounts {
+    /// Escapes the characters that would break the space-separated format, as
+    /// Linux does.
+    fn escape(field: &str) -> String {
+        let mut escaped = String::with_capacity(field.len());
+        for ch in field.chars() {
+            match ch {
+                ' ' | '\t' | '\n' | '\\' => escaped += &format!("\\{:03o}", ch as u32),
+                _ => escaped.push(ch),
+            }
+        }
+        escaped
+    }
+
+    fn content() -> String {
+        let mut content = String::new();
+        for info in crate::root::mount_table() {
+            content += &format!(
+                "{} {} {} rw 0 0\n",
+                Self::escape(&info.source),
+                Self::escape(&info.target),
+                info.fstype
+            );
+        }
+        content
+    }
+}
+
+#[cfg(all(feature = "procfs", feature = "monolithic"))]
+impl axfs_vfs::VfsNodeOps for ProcMounts {
+    fn get_attr(&self) -> VfsResult<axfs_vfs::VfsNodeAttr> {
+        Ok(axfs_vfs::VfsNodeAttr::new(
+            axfs_vfs::VfsNodePerm::from_bits_truncate(0o444),
+            VfsNodeType::File,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let content = Self::content();
+        let bytes = content.as_bytes();
+        let start = bytes.len().min(offset as usize);
+        let end = bytes.len().min(start + buf.len());
+        buf[..end - start].copy_from_slice(&bytes[start..end]);
+        Ok(end - start)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
 #[cfg(feature = "procfs")]
 pub(crate) fn procfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
     let procfs = fs::ramfs::RamFileSystem::new();
@@ -66,8 +246,14 @@ pub(crate) fn procfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
     {
         // Create other file to pass the testcases
         proc_root.create("meminfo", VfsNodeType::File)?;
-        proc_root.create("mounts", VfsNodeType::File)?;
+        procfs
+            .root_dir_node()
+            .add_node("mounts", Arc::new(ProcMounts))?;
         proc_root.create("interrupts", VfsNodeType::File)?;
+        // 写入进程号以开启对该进程的系统调用跟踪
+        procfs
+            .root_dir_node()
+            .add_node("strace", Arc::new(fs::ramfs::StraceControl))?;
         // procfs.mount("interrupts", Arc::new(fs::devfs::Interrupts::default()))?;
     }
     Ok(Arc::new(procfs))
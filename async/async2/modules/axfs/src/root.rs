@@ -1,6 +1,8 @@
 //! Root directory of the filesystem
 //!
-//! TODO: it doesn't work very well if the mount points have containment relationships.
+//! Other filesystems can be mounted at any directory, including directories of
+//! other mounted filesystems. A path is resolved by the mount point that is the
+//! longest prefix of it, compared component by component.
 
 use alloc::{
     string::{String, ToString},
@@ -9,8 +11,8 @@ use alloc::{
 };
 use axerrno::{ax_err, AxError, AxResult};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
-use spinlock::SpinNoIrq;
 use lazy_init::LazyInit;
+use spinlock::SpinNoIrq;
 
 use crate::{api::FileType, fs, mounts};
 
@@ -18,20 +20,42 @@ static CURRENT_DIR_PATH: SpinNoIrq<String> = SpinNoIrq::new(String::new());
 static CURRENT_DIR: LazyInit<SpinNoIrq<VfsNodeRef>> = LazyInit::new();
 
 struct MountPoint {
-    path: &'static str,
+    /// The canonical absolute path of the mount point.
+    path: String,
+    /// The device or pseudo device the filesystem comes from, e.g. `/dev/vdb`.
+    source: String,
+    /// The type of the filesystem, e.g. `tmpfs`.
+    fstype: String,
     fs: Arc<dyn VfsOps>,
 }
 
+/// An entry of the mount table.
+#[derive(Debug, Clone)]
+pub struct MountInfo {
+    /// The device or pseudo device the filesystem comes from.
+    pub source: String,
+    /// The canonical absolute path of the mount point.
+    pub target: String,
+    /// The type of the filesystem.
+    pub fstype: String,
+}
+
 struct RootDirectory {
     main_fs: Arc<dyn VfsOps>,
-    mounts: Vec<MountPoint>,
+    main_source: String,
+    mounts: SpinNoIrq<Vec<MountPoint>>,
 }
 
 static ROOT_DIR: LazyInit<Arc<RootDirectory>> = LazyInit::new();
 
 impl MountPoint {
-    pub fn new(path: &'static str, fs: Arc<dyn VfsOps>) -> Self {
-        Self { path, fs }
+    pub fn new(path: String, source: &str, fstype: &str, fs: Arc<dyn VfsOps>) -> Self {
+        Self {
+            path,
+            source: source.to_string(),
+            fstype: fstype.to_string(),
+            fs,
+        }
     }
 }
 
@@ -41,37 +65,105 @@ impl Drop for MountPoint {
     }
 }
 
+/// Whether the canonical absolute `path` is `mount_path` or lies under it.
+fn is_under(path: &str, mount_path: &str) -> bool {
+    path.starts_with(mount_path)
+        && (path.len() == mount_path.len() || path[mount_path.len()..].starts_with('/'))
+}
+
 impl RootDirectory {
-    pub const fn new(main_fs: Arc<dyn VfsOps>) -> Self {
+    pub fn new(main_fs: Arc<dyn VfsOps>, main_source: &str) -> Self {
         Self {
             main_fs,
-            mounts: Vec::new(),
+            main_source: main_source.to_string(),
+            mounts: SpinNoIrq::new(Vec::new()),
         }
     }
 
-    pub fn mount(&mut self, path: &'static str, fs: Arc<dyn VfsOps>) -> AxResult {
+    /// Mounts `fs` at `path`. The mount point is created in the filesystem
+    /// containing it if it does not exist.
+    pub fn mount(&self, path: &str, source: &str, fstype: &str, fs: Arc<dyn VfsOps>) -> AxResult {
+        if !path.starts_with('/') {
+            return ax_err!(InvalidInput, "mount path must start with '/'");
+        }
+        let path = axfs_vfs::path::canonicalize(path);
         if path == "/" {
             return ax_err!(InvalidInput, "cannot mount root filesystem");
         }
-        if !path.starts_with('/') {
-            return ax_err!(InvalidInput, "mount path must start with '/'");
+        if self.contains(&path) {
+            return ax_err!(ResourceBusy, "mount point already exists");
+        }
+        let (parent_fs, rest) = self.resolve(&path);
+        match parent_fs.root_dir().create(rest, FileType::Dir) {
+            Ok(()) | Err(AxError::AlreadyExists) => {}
+            Err(e) => return Err(e),
+        }
+        let mount_point = parent_fs.root_dir().lookup(rest)?;
+        if !mount_point.get_attr()?.is_dir() {
+            return ax_err!(NotADirectory);
         }
-        if self.mounts.iter().any(|mp| mp.path == path) {
-            return ax_err!(InvalidInput, "mount point already exists");
+        fs.mount(&path, mount_point)?;
+        let mut mounts = self.mounts.lock();
+        if mounts.iter().any(|mp| mp.path == path) {
+            return ax_err!(ResourceBusy, "mount point already exists");
         }
-        // create the mount point in the main filesystem if it does not exist
-        self.main_fs.root_dir().create(path, FileType::Dir)?;
-        fs.mount(path, self.main_fs.root_dir().lookup(path)?)?;
-        self.mounts.push(MountPoint::new(path, fs));
+        mounts.push(MountPoint::new(path, source, fstype, fs));
         Ok(())
     }
 
-    pub fn _umount(&mut self, path: &str) {
-        self.mounts.retain(|mp| mp.path != path);
+    /// Unmounts the filesystem mounted at `path`. Fails with `ResourceBusy` if
+    /// other filesystems are mounted under it.
+    pub fn umount(&self, path: &str) -> AxResult {
+        let path = axfs_vfs::path::canonicalize(path);
+        let mut mounts = self.mounts.lock();
+        let Some(idx) = mounts.iter().position(|mp| mp.path == path) else {
+            return ax_err!(InvalidInput, "not a mount point");
+        };
+        if mounts
+            .iter()
+            .any(|mp| mp.path != path && is_under(&mp.path, &path))
+        {
+            return ax_err!(ResourceBusy);
+        }
+        let mount_point = mounts.remove(idx);
+        drop(mounts);
+        drop(mount_point);
+        Ok(())
     }
 
     pub fn contains(&self, path: &str) -> bool {
-        self.mounts.iter().any(|mp| mp.path == path)
+        self.mounts.lock().iter().any(|mp| mp.path == path)
+    }
+
+    /// Returns the mount table, starting with the root filesystem.
+    pub fn mount_table(&self) -> Vec<MountInfo> {
+        let mut table = Vec::new();
+        table.push(MountInfo {
+            source: self.main_source.clone(),
+            target: "/".to_string(),
+            fstype: mounts::DISK_FS_TYPE.to_string(),
+        });
+        table.extend(self.mounts.lock().iter().map(|mp| MountInfo {
+            source: mp.source.clone(),
+            target: mp.path.clone(),
+            fstype: mp.fstype.clone(),
+        }));
+        table
+    }
+
+    /// Finds the filesystem with the longest mount path containing the
+    /// canonical absolute `path`, and returns it with the rest of the path.
+    fn resolve<'a>(&self, path: &'a str) -> (Arc<dyn VfsOps>, &'a str) {
+        // TODO: more efficient, e.g. trie
+        let mounts = self.mounts.lock();
+        match mounts
+            .iter()
+            .filter(|mp| is_under(path, &mp.path))
+            .max_by_key(|mp| mp.path.len())
+        {
+            Some(mp) => (mp.fs.clone(), &path[mp.path.len()..]),
+            None => (self.main_fs.clone(), &path[1..]), // not matched any mount point
+        }
     }
 
     fn lookup_mounted_fs<F, T>(&self, path: &str, f: F) -> AxResult<T>
@@ -79,36 +171,10 @@ impl RootDirectory {
         F: FnOnce(Arc<dyn VfsOps>, &str) -> AxResult<T>,
     {
         debug!("lookup at root: {}", path);
-        let path = path.trim_matches('/');
-        if let Some(rest) = path.strip_prefix("./") {
-            return self.lookup_mounted_fs(rest, f);
-        }
-
-        let mut idx = 0;
-        let mut max_len = 0;
-
-        // Find the filesystem that has the longest mounted path match
-        // TODO: more efficient, e.g. trie
-
-        for (i, mp) in self.mounts.iter().enumerate() {
-            // skip the first '/'
-            // two conditions
-            // 1. path == mp.path, e.g. dev
-            // 2. path == mp.path + '/', e.g. dev/
-            let prev = mp.path[1..].to_string() + "/";
-            if path.starts_with(&mp.path[1..])
-                && (path.len() == prev.len() - 1 || path.starts_with(&prev))
-                && prev.len() > max_len
-            {
-                max_len = mp.path.len() - 1;
-                idx = i;
-            }
-        }
-        if max_len == 0 {
-            f(self.main_fs.clone(), path) // not matched any mount point
-        } else {
-            f(self.mounts[idx].fs.clone(), &path[max_len..]) // matched at `idx`
-        }
+        // paths here are relative to the root, `..` can not escape it
+        let path = axfs_vfs::path::canonicalize(&("/".to_string() + path));
+        let (fs, rest) = self.resolve(&path);
+        f(fs, rest)
     }
 }
 
@@ -164,43 +230,45 @@ pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
         } else if #[cfg(feature = "ext4fs")] {
             static EXT4_FS: LazyInit<Arc<fs::ext4fs::Ext4FileSystem>> = LazyInit::new();
             EXT4_FS.init_by(Arc::new(fs::ext4fs::Ext4FileSystem::new(disk)));
-            let main_fs = EXT4_FS.clone();
+            let main_fs: Arc<dyn VfsOps> = EXT4_FS.clone();
         } else if #[cfg(feature = "fatfs")] {
             // default to be fatfs
             static FAT_FS: LazyInit<Arc<fs::fatfs::FatFileSystem>> = LazyInit::new();
             FAT_FS.init_by(Arc::new(fs::fatfs::FatFileSystem::new(disk)));
             FAT_FS.init();
-            let main_fs = FAT_FS.clone();
+            let main_fs: Arc<dyn VfsOps> = FAT_FS.clone();
         }
     }
 
-    let mut root_dir = RootDirectory::new(main_fs);
+    // the root device can also be mounted elsewhere, sharing the same filesystem
+    let main_source = mounts::add_mounted_block_device(main_fs.clone());
+    let root_dir = RootDirectory::new(main_fs, &main_source);
 
     #[cfg(feature = "devfs")]
     root_dir
-        .mount("/dev", mounts::devfs())
+        .mount("/dev", "devtmpfs", "devtmpfs", mounts::devfs())
         .expect("failed to mount devfs at /dev");
 
     #[cfg(feature = "ramfs")]
     root_dir
-        .mount("/tmp", mounts::ramfs())
+        .mount("/tmp", "tmpfs", "tmpfs", mounts::ramfs())
         .expect("failed to mount ramfs at /tmp");
 
     #[cfg(feature = "ramfs")]
     root_dir
-        .mount("/var", mounts::ramfs())
+        .mount("/var", "tmpfs", "tmpfs", mounts::ramfs())
         .expect("failed to mount ramfs at /tmp");
 
     // Mount another ramfs as procfs
     #[cfg(feature = "procfs")]
     root_dir // should not fail
-        .mount("/proc", mounts::procfs().unwrap())
+        .mount("/proc", "proc", "proc", mounts::procfs().unwrap())
         .expect("fail to mount procfs at /proc");
 
     // Mount another ramfs as sysfs
     #[cfg(feature = "sysfs")]
     root_dir // should not fail
-        .mount("/sys", mounts::sysfs().unwrap())
+        .mount("/sys", "sysfs", "sysfs", mounts::sysfs().unwrap())
         .expect("fail to mount sysfs at /sys");
 
     ROOT_DIR.init_by(Arc::new(root_dir));
@@ -332,3 +400,15 @@ pub(crate) fn rename(old: &str, new: &str) -> AxResult {
     }
     parent_node_of(None, old).rename(old, new)
 }
+
+pub(crate) fn mount(target: &str, source: &str, fstype: &str, fs: Arc<dyn VfsOps>) -> AxResult {
+    ROOT_DIR.mount(target, source, fstype, fs)
+}
+
+pub(crate) fn umount(target: &str) -> AxResult {
+    ROOT_DIR.umount(target)
+}
+
+pub(crate) fn mount_table() -> Vec<MountInfo> {
+    ROOT_DIR.mount_table()
+}
@@ -133,3 +133,6 @@ pub unsafe fn write_thread_pointer(tpidr_el0: usize) {
 }
 
 core::arch::global_asm!(include_str!("signal.S"));
+
+#[cfg(feature = "monolithic")]
+core::arch::global_asm!(include_str!("uaccess.S"));
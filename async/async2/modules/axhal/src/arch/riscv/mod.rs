@@ -110,3 +110,6 @@ pub unsafe fn write_thread_pointer(tp: usize) {
 include_asm_marcos!();
 
 core::arch::global_asm!(include_str!("signal.S"));
+
+#[cfg(feature = "monolithic")]
+core::arch::global_asm!(include_str!("uaccess.S"));
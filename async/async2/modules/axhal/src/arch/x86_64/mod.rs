@@ -112,3 +112,6 @@ pub unsafe fn write_thread_pointer(fs_base: usize) {
 }
 
 core::arch::global_asm!(include_str!("signal.S"));
+
+#[cfg(feature = "monolithic")]
+core::arch::global_asm!(include_str!("uaccess.S"));
@@ -0,0 +1,50 @@
+//! Fault-tolerant access to user memory.
+//!
+//! The kernel copies data from and to user space with [`copy_user`], whose
+//! memory accesses are recorded in an exception table (the `.ex_table` section).
+//! When one of them faults, the kernel trap handler calls [`fixup_exception`]
+//! to find the address at which the copy resumes, instead of panicking, and the
+//! copy reports the bytes that were left behind.
+
+/// An entry of the exception table.
+#[repr(C)]
+struct ExceptionEntry {
+    /// Address of the instruction that may fault.
+    insn: usize,
+    /// Address to continue at when it does.
+    fixup: usize,
+}
+
+extern "C" {
+    fn _sex_table();
+    fn _eex_table();
+    fn __copy_user(dst: *mut u8, src: *const u8, len: usize) -> usize;
+}
+
+fn exception_table() -> &'static [ExceptionEntry] {
+    let start = _sex_table as usize;
+    let len = (_eex_table as usize - start) / core::mem::size_of::<ExceptionEntry>();
+    unsafe { core::slice::from_raw_parts(start as *const ExceptionEntry, len) }
+}
+
+/// Copies `len` bytes from `src` to `dst`, where either side may be user memory.
+///
+/// Returns the number of bytes that were **not** copied, which is nonzero only
+/// when an access faulted.
+///
+/// # Safety
+///
+/// The kernel side of the copy must be valid for `len` bytes, and the trap
+/// handler must redirect faults with [`fixup_exception`].
+pub unsafe fn copy_user(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    __copy_user(dst, src, len)
+}
+
+/// Returns the address to continue at if the kernel faults at `pc` while
+/// accessing user memory, or `None` if the fault is not expected.
+pub fn fixup_exception(pc: usize) -> Option<usize> {
+    exception_table()
+        .iter()
+        .find(|entry| entry.insn == pc)
+        .map(|entry| entry.fixup)
+}
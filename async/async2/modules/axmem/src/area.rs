@@ -1,6 +1,6 @@
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 use axalloc::PhysPage;
-use axerrno::AxResult;
+use axerrno::{AxError, AxResult};
 use axhal::{
     mem::{virt_to_phys, VirtAddr, PAGE_SIZE_4K},
     paging::{MappingFlags, PageSize, PageTable},
@@ -23,6 +23,8 @@ pub struct MapArea {
     pub flags: MappingFlags,
     /// whether the area is backed by a file
     pub backend: Option<MemBackend>,
+    /// whether each page has been written since it was loaded or last synced with the backend
+    pub dirty: Vec<bool>,
 }
 
 impl MapArea {
@@ -48,6 +50,7 @@ impl MapArea {
             vaddr: start,
             flags,
             backend,
+            dirty: vec![false; num_pages],
         }
     }
 
@@ -77,11 +80,13 @@ impl MapArea {
                 false,
             )
             .unwrap();
+        // The pages are mapped writable at once, so treat them as modified.
         Ok(Self {
             pages,
             vaddr: start,
             flags,
             backend,
+            dirty: vec![true; num_pages],
         })
     }
 
@@ -89,6 +94,7 @@ impl MapArea {
     pub fn dealloc(&mut self, page_table: &mut PageTable) {
         page_table.unmap_region(self.vaddr, self.size()).unwrap();
         self.pages.clear();
+        self.dirty.clear();
     }
 
     /// 如果处理失败，返回false，此时直接退出当前程序
@@ -122,6 +128,15 @@ impl MapArea {
             return false;
         }
         if self.pages[page_index].is_some() {
+            // Clean pages are mapped without `WRITE`, so the first write to them faults here.
+            if flags.contains(MappingFlags::WRITE) && self.is_write_protected(page_index) {
+                self.dirty[page_index] = true;
+                page_table
+                    .update(addr.align_down_4k(), None, Some(self.flags))
+                    .expect("Update mapping in page fault handler failed");
+                axhal::arch::flush_tlb(addr.align_down_4k().into());
+                return true;
+            }
             error!("Page fault in page already loaded");
             return false;
         }
@@ -137,6 +152,8 @@ impl MapArea {
         );
 
         // Read data from backend to fill with 0.
+        // 超出文件末尾的部分读不到数据，因此先全部清零
+        page.fill(0);
         match &mut self.backend {
             Some(backend) => {
                 if backend
@@ -154,12 +171,13 @@ impl MapArea {
         };
 
         // Map newly allocated page in the page_table
+        self.dirty[page_index] = flags.contains(MappingFlags::WRITE);
         page_table
             .map_overwrite(
                 addr.align_down_4k(),
                 virt_to_phys(page.start_vaddr),
                 axhal::paging::PageSize::Size4K,
-                self.flags,
+                self.page_flags(page_index),
             )
             .expect("Map in page fault handler failed");
 
@@ -168,26 +186,87 @@ impl MapArea {
         true
     }
 
-    /// Sync pages in index back to `self.backend` (if there is one).
+    /// Make the page containing `addr` accessible with `flags`, allocating it if it is lazy and
+    /// not loaded yet. Used when the kernel is about to access user memory directly.
+    ///
+    /// Returns false if the area doesn't allow the access.
+    pub fn fault_in(
+        &mut self,
+        addr: VirtAddr,
+        flags: MappingFlags,
+        page_table: &mut PageTable,
+    ) -> bool {
+        let page_index = (usize::from(addr) - usize::from(self.vaddr)) / PAGE_SIZE_4K;
+        if self.pages[page_index].is_some()
+            && !(flags.contains(MappingFlags::WRITE) && self.is_write_protected(page_index))
+        {
+            return true;
+        }
+        self.handle_page_fault(addr, flags, page_table)
+    }
+
+    /// Sync the page in index back to `self.backend` if it has been modified since it was loaded
+    /// or last synced. Only the part inside the file is written, so the file never grows.
+    ///
+    /// The page is write-protected again so that later writes are tracked. You need to flush TLB
+    /// after this function.
     ///
     /// # Panics
     ///
     /// Panics if index is out of bounds.
-    pub fn sync_page_with_backend(&mut self, page_index: usize) {
-        if let Some(page) = &self.pages[page_index] {
-            if let Some(backend) = &mut self.backend {
-                if backend.writable() {
-                    let _ = backend
-                        .write_to_seek(
-                            SeekFrom::Start((page_index * PAGE_SIZE_4K) as u64),
-                            page.as_slice(),
-                        )
-                        .unwrap();
-                }
+    pub fn sync_page_with_backend(
+        &mut self,
+        page_index: usize,
+        page_table: &mut PageTable,
+    ) -> AxResult<()> {
+        if !self.tracks_dirty() || !self.dirty[page_index] {
+            return Ok(());
+        }
+        let (Some(page), Some(backend)) = (&self.pages[page_index], &mut self.backend) else {
+            return Ok(());
+        };
+        // backend 的游标保存的是映射起始处在文件中的偏移
+        let page_offset = page_index * PAGE_SIZE_4K;
+        let offset = backend.seek(SeekFrom::Current(0))? + page_offset as u64;
+        let file_size = backend.file_size()?;
+        if offset < file_size {
+            let len = (file_size - offset).min(PAGE_SIZE_4K as u64) as usize;
+            let written = backend.write_to_seek(
+                SeekFrom::Current(page_offset as i64),
+                &page.as_slice()[..len],
+            )?;
+            if written < len {
+                return Err(AxError::WriteZero);
             }
-        } else {
-            debug!("Tried to sync an unallocated page");
         }
+        self.dirty[page_index] = false;
+        page_table
+            .update(
+                self.vaddr + page_offset,
+                None,
+                Some(self.page_flags(page_index)),
+            )
+            .unwrap();
+        Ok(())
+    }
+
+    /// Drop the page in index, so that the next access to it faults and loads it again from the
+    /// backend, or fills it with zero if there is no backend.
+    ///
+    /// Return the dropped page. It must not be freed before TLB is flushed.
+    pub fn discard_page(
+        &mut self,
+        page_index: usize,
+        page_table: &mut PageTable,
+    ) -> Option<PhysPage> {
+        let page = self.pages[page_index].take()?;
+        self.dirty[page_index] = false;
+        let vaddr = self.vaddr + page_index * PAGE_SIZE_4K;
+        page_table.unmap(vaddr).unwrap();
+        page_table
+            .map_fault(vaddr, PageSize::Size4K, self.flags)
+            .unwrap();
+        Some(page)
     }
 
     /// Deallocate some pages from the start of the area.
@@ -205,6 +284,7 @@ impl MapArea {
 
         // remove (dealloc) phys pages
         drop(self.pages.drain(0..delete_pages));
+        drop(self.dirty.drain(0..delete_pages));
 
         // unmap deleted pages
         page_table.unmap_region(self.vaddr, delete_size).unwrap();
@@ -225,6 +305,7 @@ impl MapArea {
             self.pages
                 .drain((self.pages.len() - delete_pages)..self.pages.len()),
         );
+        self.dirty.truncate(self.pages.len());
 
         // unmap deleted pages
         page_table.unmap_region(new_end, delete_size).unwrap();
@@ -237,12 +318,14 @@ impl MapArea {
         let right_page_count = (self.end_va() - addr.as_usize()).as_usize() / PAGE_SIZE_4K;
         let right_page_range = self.pages.len() - right_page_count..self.pages.len();
 
-        let right_pages = self.pages.drain(right_page_range).collect();
+        let right_pages = self.pages.drain(right_page_range.clone()).collect();
+        let right_dirty = self.dirty.drain(right_page_range).collect();
 
         Self {
             pages: right_pages,
             vaddr: addr,
             flags: self.flags,
+            dirty: right_dirty,
             backend: self.backend.as_ref().map(|backend| {
                 let mut backend = backend.clone();
 
@@ -265,26 +348,20 @@ impl MapArea {
         assert!(self.vaddr < start);
         assert!(end < self.end_va());
 
-        let right_pages = self
-            .pages
-            .drain(
-                self.pages.len() - (self.end_va().as_usize() - end.as_usize()) / PAGE_SIZE_4K
-                    ..self.pages.len(),
-            )
-            .collect();
+        let right_start = (end.as_usize() - self.vaddr.as_usize()) / PAGE_SIZE_4K;
+        let mid_start = (start.as_usize() - self.vaddr.as_usize()) / PAGE_SIZE_4K;
 
-        let mid_pages = self
-            .pages
-            .drain(
-                self.pages.len() - (self.end_va().as_usize() - start.as_usize()) / PAGE_SIZE_4K
-                    ..self.pages.len(),
-            )
-            .collect();
+        let right_pages = self.pages.drain(right_start..).collect();
+        let right_dirty = self.dirty.drain(right_start..).collect();
+
+        let mid_pages = self.pages.drain(mid_start..).collect();
+        let mid_dirty = self.dirty.drain(mid_start..).collect();
 
         let mid = Self {
             pages: mid_pages,
             vaddr: start,
             flags: self.flags,
+            dirty: mid_dirty,
             backend: self.backend.as_ref().map(|backend| {
                 let mut backend = backend.clone();
 
@@ -302,6 +379,7 @@ impl MapArea {
             pages: right_pages,
             vaddr: end,
             flags: self.flags,
+            dirty: right_dirty,
             backend: self.backend.as_ref().map(|backend| {
                 let mut backend = backend.clone();
 
@@ -339,15 +417,15 @@ impl MapArea {
             ..((right_start.as_usize() - self.vaddr.as_usize()) / PAGE_SIZE_4K);
 
         // create a right area
-        let pages = self
-            .pages
-            .drain(((right_start.as_usize() - self.vaddr.as_usize()) / PAGE_SIZE_4K)..)
-            .collect();
+        let right_index = (right_start.as_usize() - self.vaddr.as_usize()) / PAGE_SIZE_4K;
+        let pages = self.pages.drain(right_index..).collect();
+        let dirty = self.dirty.drain(right_index..).collect();
 
         let right_area = Self {
             pages,
             vaddr: right_start,
             flags: self.flags,
+            dirty,
             backend: self.backend.as_ref().map(|backend| {
                 let mut backend = backend.clone();
                 let _ = backend
@@ -361,7 +439,8 @@ impl MapArea {
         };
 
         // remove pages
-        let _ = self.pages.drain(delete_range);
+        let _ = self.pages.drain(delete_range.clone());
+        let _ = self.dirty.drain(delete_range);
 
         page_table.unmap_region(left_end, delete_size).unwrap();
 
@@ -380,6 +459,28 @@ impl MapArea {
         self.vaddr + self.size()
     }
 
+    /// Whether modified pages have to be written back to the backend. Clean pages of such an area
+    /// are mapped without `WRITE`, so that the first write to them can be tracked.
+    fn tracks_dirty(&self) -> bool {
+        self.backend
+            .as_ref()
+            .is_some_and(|backend| backend.shared() && backend.writable())
+    }
+
+    /// Whether the page in index is mapped without `WRITE` only to track the first write to it.
+    fn is_write_protected(&self, page_index: usize) -> bool {
+        self.flags.contains(MappingFlags::WRITE) && self.tracks_dirty() && !self.dirty[page_index]
+    }
+
+    /// The flags that the page in index is actually mapped with.
+    fn page_flags(&self, page_index: usize) -> MappingFlags {
+        if self.is_write_protected(page_index) {
+            self.flags - MappingFlags::WRITE
+        } else {
+            self.flags
+        }
+    }
+
     /// return whether all the pages have been allocated.
     pub fn allocated(&self) -> bool {
         self.pages.iter().all(|page| page.is_some())
@@ -424,23 +525,39 @@ impl MapArea {
     /// this function.
     pub fn update_flags(&mut self, flags: MappingFlags, page_table: &mut PageTable) {
         self.flags = flags;
-        page_table
-            .update_region(self.vaddr, self.size(), flags)
-            .unwrap();
+        if self.tracks_dirty() {
+            // clean pages stay write-protected
+            for page_index in 0..self.pages.len() {
+                page_table
+                    .update(
+                        self.vaddr + page_index * PAGE_SIZE_4K,
+                        None,
+                        Some(self.page_flags(page_index)),
+                    )
+                    .unwrap();
+            }
+        } else {
+            page_table
+                .update_region(self.vaddr, self.size(), flags)
+                .unwrap();
+        }
     }
     /// Allocating new phys pages and clone it self.
     /// This function will modify the page table as well.
     pub fn clone_alloc(&self, page_table: &mut PageTable) -> AxResult<Self> {
         // All the pages have been allocated. Allocate a contiguous area in phys memory.
         if self.allocated() {
-            MapArea::new_alloc(
+            let mut area = MapArea::new_alloc(
                 self.vaddr,
                 self.pages.len(),
                 self.flags,
                 Some(unsafe { self.as_slice() }),
                 self.backend.clone(),
                 page_table,
-            )
+            )?;
+            area.dirty = self.dirty.clone();
+            area.update_flags(self.flags, page_table);
+            Ok(area)
         } else {
             let pages: Vec<_> = self
                 .pages
@@ -464,7 +581,7 @@ impl MapArea {
                                     vaddr,
                                     virt_to_phys(new_page.start_vaddr),
                                     PageSize::Size4K,
-                                    self.flags,
+                                    self.page_flags(idx),
                                 )
                                 .unwrap();
 
@@ -484,6 +601,7 @@ impl MapArea {
                 vaddr: self.vaddr,
                 flags: self.flags,
                 backend: self.backend.clone(),
+                dirty: self.dirty.clone(),
             })
         }
     }
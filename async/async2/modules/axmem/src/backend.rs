@@ -1,19 +1,35 @@
-use alloc::boxed::Box;
+use alloc::{boxed::Box, sync::Arc};
 use axfs::api::{File, FileExt};
 use axio::{Read, Seek, SeekFrom};
+use core::any::Any;
 
 /// File backend for Lazy load `MapArea`. `file` should be a file holding a offset value. Normally,
 /// `MemBackend` won't share a file with other things, so we use a `Box` here.
 pub struct MemBackend {
     file: Box<dyn FileExt>,
+    /// whether modified pages are written back to the file, i.e. the mapping is `MAP_SHARED`
+    shared: bool,
+    /// an object that lives as long as the mapping, shared by all the clones of the backend
+    guard: Option<Arc<dyn Any + Send + Sync>>,
 }
 
 impl MemBackend {
-    /// Create a new `MemBackend` with a file and the seek offset of this file.
-    pub fn new(mut file: Box<dyn FileExt>, offset: u64) -> Self {
+    /// Create a new `MemBackend` with a file and the seek offset of this file. Modified pages are
+    /// written back to the file only if `shared` is true.
+    pub fn new(mut file: Box<dyn FileExt>, offset: u64, shared: bool) -> Self {
         let _ = file.seek(SeekFrom::Start(offset)).unwrap();
 
-        Self { file }
+        Self {
+            file,
+            shared,
+            guard: None,
+        }
+    }
+
+    /// Keep `guard` alive until the mapping and all the areas split from it are gone, e.g. to
+    /// register the mapping with the file it maps.
+    pub fn keep_alive<T: Any + Send + Sync>(&mut self, guard: T) {
+        self.guard = Some(Arc::new(guard));
     }
 
     /// clone a new `MemBackend` with a delta offset of the file of the original `MemBackend`.
@@ -35,6 +51,16 @@ impl MemBackend {
         self.file.write_to_seek(pos, buf)
     }
 
+    /// the size of the file of the `MemBackend`.
+    pub fn file_size(&self) -> Result<u64, axio::Error> {
+        let file = self
+            .file
+            .as_any()
+            .downcast_ref::<File>()
+            .expect("MemBackend with a non-file object");
+        Ok(file.metadata()?.size())
+    }
+
     /// whether the file of the `MemBackend` is readable.
     pub fn readable(&self) -> bool {
         self.file.readable()
@@ -44,6 +70,11 @@ impl MemBackend {
     pub fn writable(&self) -> bool {
         self.file.writable()
     }
+
+    /// whether modified pages are written back to the file.
+    pub fn shared(&self) -> bool {
+        self.shared
+    }
 }
 
 impl Clone for MemBackend {
@@ -57,6 +88,8 @@ impl Clone for MemBackend {
 
         Self {
             file: Box::new(file),
+            shared: self.shared,
+            guard: self.guard.clone(),
         }
     }
 }
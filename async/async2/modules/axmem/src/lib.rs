@@ -11,7 +11,7 @@ extern crate alloc;
 use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
 use core::sync::atomic::{AtomicI32, Ordering};
 use page_table_entry::GenericPTE;
-use shared::SharedMem;
+pub use shared::{SharedMem, SharedMemInfo, SharedMemPermInfo};
 use spinlock::SpinNoIrq;
 #[macro_use]
 extern crate log;
@@ -106,6 +106,13 @@ impl MemorySet {
             .unwrap_or_default()
     }
 
+    /// The total size of the areas mapped in this memory set, including attached shared memory.
+    pub fn mapped_size(&self) -> usize {
+        let owned: usize = self.owned_mem.values().map(|area| area.size()).sum();
+        let attached: usize = self.attached_mem.iter().map(|(_, _, mem)| mem.size()).sum();
+        owned + attached
+    }
+
     /// Allocate contiguous region. If no data, it will create a lazy load region.
     pub fn new_region(
         &mut self,
@@ -254,6 +261,20 @@ impl MemorySet {
         None
     }
 
+    /// Whether the area doesn't overlap any area in this memory set.
+    pub fn is_free_area(&self, start: VirtAddr, size: usize) -> bool {
+        let end = start + size;
+        let owned_overlapped = self
+            .owned_mem
+            .values()
+            .any(|area| area.vaddr < end && start < area.end_va());
+        let attached_overlapped = self
+            .attached_mem
+            .iter()
+            .any(|(addr, _, mem)| *addr < end && start < *addr + mem.size());
+        !owned_overlapped && !attached_overlapped
+    }
+
     /// mmap. You need to flush tlb after this.
     pub fn mmap(
         &mut self,
@@ -303,18 +324,98 @@ impl MemorySet {
         addr
     }
 
-    /// munmap. You need to flush TLB after this.
-    pub fn munmap(&mut self, start: VirtAddr, size: usize) {
+    /// Map a new anonymous area with `MAP_SHARED`. You need to flush tlb after this.
+    ///
+    /// The area is backed by a [`SharedMem`], so it will still be shared with the memory set
+    /// cloned from this one.
+    pub fn mmap_shared_anonymous(
+        &mut self,
+        start: VirtAddr,
+        size: usize,
+        flags: MappingFlags,
+        fixed: bool,
+        pid: u64,
+    ) -> isize {
         // align up to 4k
         let size = (size + PAGE_SIZE_4K - 1) / PAGE_SIZE_4K * PAGE_SIZE_4K;
-        info!("[munmap] [{:?}, {:?})", start, (start + size).align_up_4k());
 
+        info!(
+            "[mmap shared] vaddr: [{:?}, {:?}), {:?}, fixed: {}",
+            start,
+            start + size,
+            flags,
+            fixed
+        );
+
+        let start = if fixed {
+            if self.munmap(start, size).is_err() {
+                return -1;
+            }
+            start
+        } else {
+            match self.find_free_area(start, size) {
+                Some(start) => start,
+                None => return -1,
+            }
+        };
+        let mem = match SharedMem::try_new(0, size, pid, 0, 0, 0o600) {
+            Ok(mem) => mem,
+            Err(_) => return -1,
+        };
+        // 匿名共享内存需要清零
+        unsafe {
+            core::ptr::write_bytes(phys_to_virt(mem.paddr()).as_mut_ptr(), 0, mem.size());
+        }
+        self.attach_shared_mem(Arc::new(mem), start, flags);
+        flush_tlb(None);
+
+        start.as_usize() as isize
+    }
+
+    /// munmap. You need to flush TLB after this.
+    ///
+    /// Modified pages of file-backed areas are written back before unmapping. Attached shared
+    /// memory overlapping the range is detached as a whole.
+    ///
+    /// Return `InvalidInput` if the range overflows the address space. Failing to write back pages
+    /// doesn't stop the range from being unmapped.
+    pub fn munmap(&mut self, start: VirtAddr, size: usize) -> AxResult<()> {
+        // align up to 4k
+        let size = size
+            .checked_add(PAGE_SIZE_4K - 1)
+            .ok_or(AxError::InvalidInput)?
+            / PAGE_SIZE_4K
+            * PAGE_SIZE_4K;
+        if start.as_usize().checked_add(size).is_none() {
+            return Err(AxError::InvalidInput);
+        }
+        info!("[munmap] [{:?}, {:?})", start, start + size);
+
+        if let Err(err) = self.msync(start, size) {
+            warn!("[munmap] failed to write back modified pages: {:?}", err);
+        }
         self.split_for_area(start, size);
+
+        let end = start + size;
+        let page_table = &mut self.page_table;
+        self.attached_mem.retain(|(addr, _, mem)| {
+            let overlapped = *addr < end && start < *addr + mem.size();
+            if overlapped {
+                page_table.unmap_region(*addr, mem.size()).unwrap();
+                mem.on_detach();
+            }
+            !overlapped
+        });
+        Ok(())
     }
 
-    /// msync
-    pub fn msync(&mut self, start: VirtAddr, size: usize) {
+    /// msync. Write modified pages of file-backed areas in the range back to their files. You need
+    /// to flush TLB after this.
+    ///
+    /// All the pages are tried even if some of them fail, and the first error is returned.
+    pub fn msync(&mut self, start: VirtAddr, size: usize) -> AxResult<()> {
         let end = start + size;
+        let mut result = Ok(());
         for area in self.owned_mem.values_mut() {
             if area.backend.is_none() {
                 continue;
@@ -324,11 +425,46 @@ impl MemorySet {
                     let page_vaddr = area.vaddr + page_index * PAGE_SIZE_4K;
 
                     if page_vaddr >= start && page_vaddr < end {
-                        area.sync_page_with_backend(page_index);
+                        let synced = area.sync_page_with_backend(page_index, &mut self.page_table);
+                        result = result.and(synced);
                     }
                 }
             }
         }
+        result
+    }
+
+    /// madvise(MADV_DONTNEED). Drop the private pages in the range, so that the next access to them
+    /// reads the file again for a private file mapping, or gets a zero-filled page otherwise.
+    ///
+    /// Pages of `MAP_SHARED` file mappings and attached shared memory hold the shared contents, so
+    /// they are kept. Return `NoMemory` without changing anything if some part of the range is not
+    /// mapped.
+    pub fn madvise_dontneed(&mut self, start: VirtAddr, size: usize) -> AxResult<()> {
+        let end = start + size;
+        if !self.is_fully_mapped(start, end) {
+            return Err(AxError::NoMemory);
+        }
+        let mut discarded = Vec::new();
+        for area in self.owned_mem.values_mut() {
+            let shared = area
+                .backend
+                .as_ref()
+                .is_some_and(|backend| backend.shared());
+            if shared || !area.overlap_with(start, end) {
+                continue;
+            }
+            for page_index in 0..area.pages.len() {
+                let page_vaddr = area.vaddr + page_index * PAGE_SIZE_4K;
+                if page_vaddr >= start && page_vaddr < end {
+                    discarded.extend(area.discard_page(page_index, &mut self.page_table));
+                }
+            }
+        }
+        // the pages may still be accessed through stale TLB entries until the flush
+        flush_tlb(None);
+        drop(discarded);
+        Ok(())
     }
 
     /// Edit the page table to update flags in given virt address segment. You need to flush TLB
@@ -336,7 +472,9 @@ impl MemorySet {
     ///
     /// NOTE: It's possible that this function will break map areas into two for different mapping
     /// flag settings.
-    pub fn mprotect(&mut self, start: VirtAddr, size: usize, flags: MappingFlags) {
+    ///
+    /// Return `NoMemory` without changing anything if some part of the range is not mapped.
+    pub fn mprotect(&mut self, start: VirtAddr, size: usize, flags: MappingFlags) -> AxResult<()> {
         info!(
             "[mprotect] addr: [{:?}, {:?}), flags: {:?}",
             start,
@@ -345,6 +483,9 @@ impl MemorySet {
         );
         let end = start + size;
         assert!(end.is_aligned_4k());
+        if !self.is_fully_mapped(start, end) {
+            return Err(AxError::NoMemory);
+        }
 
         flush_tlb(None);
         //self.manual_alloc_range_for_lazy(start, end - 1).unwrap();
@@ -393,6 +534,31 @@ impl MemorySet {
             assert!(self.owned_mem.insert(area.vaddr.into(), area).is_none());
         }
         axhal::arch::flush_tlb(None);
+        Ok(())
+    }
+
+    /// Whether every page in [start, end) belongs to a map area or an attached shared memory.
+    fn is_fully_mapped(&self, start: VirtAddr, end: VirtAddr) -> bool {
+        let mut addr = start.as_usize();
+        while addr < end.as_usize() {
+            let owned_end = self
+                .owned_mem
+                .range(..=addr)
+                .next_back()
+                .map(|(_, area)| area.end_va().as_usize())
+                .filter(|area_end| addr < *area_end);
+            let attached_end = self
+                .attached_mem
+                .iter()
+                .map(|(vaddr, _, mem)| (vaddr.as_usize(), vaddr.as_usize() + mem.size()))
+                .find(|(vaddr, mem_end)| *vaddr <= addr && addr < *mem_end)
+                .map(|(_, mem_end)| mem_end);
+            match owned_end.or(attached_end) {
+                Some(next) => addr = next,
+                None => return false,
+            }
+        }
+        true
     }
 
     /// It will map newly allocated page in the page table. You need to flush TLB after this.
@@ -416,11 +582,25 @@ impl MemorySet {
     }
 
     /// 将用户分配的页面从页表中直接解映射，内核分配的页面依然保留
+    ///
+    /// 文件映射中被修改的页面会先写回文件，挂载的共享内存也会被解除挂载
     pub fn unmap_user_areas(&mut self) {
         for (_, area) in self.owned_mem.iter_mut() {
+            if area.backend.is_some() {
+                for page_index in 0..area.pages.len() {
+                    if let Err(err) = area.sync_page_with_backend(page_index, &mut self.page_table)
+                    {
+                        warn!("failed to write back a page of {:?}: {:?}", area.vaddr, err);
+                    }
+                }
+            }
             area.dealloc(&mut self.page_table);
         }
         self.owned_mem.clear();
+        for (addr, _, mem) in self.attached_mem.drain(..) {
+            self.page_table.unmap_region(addr, mem.size()).unwrap();
+            mem.on_detach();
+        }
     }
 
     /// Query the page table to get the physical address, flags and page size of the given virtual
@@ -447,7 +627,8 @@ impl MemorySet {
     /// Create a new SharedMem with given key.
     /// You need to add the returned SharedMem to global SHARED_MEMS or process's private_mem.
     ///
-    /// Panics: SharedMem with the key already exist.
+    /// The key 0 (IPC_PRIVATE) is not recorded in KEY_TO_SHMID, so it can be used repeatedly.
+    /// For other keys, return `AlreadyExists` if a SharedMem with the key already exists.
     pub fn create_shared_mem(
         key: i32,
         size: usize,
@@ -457,15 +638,25 @@ impl MemorySet {
         mode: u16,
     ) -> AxResult<(i32, SharedMem)> {
         let mut key_map = KEY_TO_SHMID.lock();
-
-        let shmid = SHMID.fetch_add(1, Ordering::Release);
-        key_map.insert(key, shmid);
+        if key != 0 && key_map.contains_key(&key) {
+            return Err(AxError::AlreadyExists);
+        }
 
         let mem = SharedMem::try_new(key, size, pid, uid, gid, mode)?;
 
+        let shmid = SHMID.fetch_add(1, Ordering::Release);
+        if key != 0 {
+            key_map.insert(key, shmid);
+        }
+
         Ok((shmid, mem))
     }
 
+    /// Get the shmid of a SharedMem by its key.
+    pub fn get_shmid_by_key(key: i32) -> Option<i32> {
+        KEY_TO_SHMID.lock().get(&key).cloned()
+    }
+
     /// Panics: shmid is already taken.
     pub fn add_shared_mem(shmid: i32, mem: SharedMem) {
         let mut mem_map = SHARED_MEMS.lock();
@@ -484,8 +675,35 @@ impl MemorySet {
     }
 
     /// Get a private SharedMem by shmid.
+    ///
+    /// A private SharedMem removed by another process sharing it is ignored.
     pub fn get_private_shared_mem(&self, shmid: i32) -> Option<Arc<SharedMem>> {
-        self.private_mem.get(&shmid).cloned()
+        self.private_mem
+            .get(&shmid)
+            .filter(|mem| !mem.is_removed())
+            .cloned()
+    }
+
+    /// Mark a SharedMem as removed, so it can no longer be found by its shmid or key.
+    ///
+    /// The memory is freed after it's detached from all the memory sets.
+    pub fn remove_shared_mem(shmid: i32) {
+        let Some(mem) = SHARED_MEMS.lock().remove(&shmid) else {
+            return;
+        };
+        let mut info = mem.info.lock();
+        info.removed = true;
+        let mut key_map = KEY_TO_SHMID.lock();
+        if key_map.get(&info.perm.key) == Some(&shmid) {
+            key_map.remove(&info.perm.key);
+        }
+    }
+
+    /// Mark a private SharedMem as removed. See [`MemorySet::remove_shared_mem`].
+    pub fn remove_private_shared_mem(&mut self, shmid: i32) {
+        if let Some(mem) = self.private_mem.remove(&shmid) {
+            mem.info.lock().removed = true;
+        }
     }
 
     /// Attach a SharedMem to the memory set.
@@ -494,14 +712,24 @@ impl MemorySet {
             .map_region(addr, mem.paddr(), mem.size(), flags, false)
             .unwrap();
 
+        mem.on_attach();
         self.attached_mem.push((addr, flags, mem));
     }
 
-    /// Detach a SharedMem from the memory set.
+    /// Detach the SharedMem attached at the given address from the memory set. You need to flush
+    /// TLB after this.
     ///
-    /// TODO: implement this
-    pub fn detach_shared_mem(&mut self, _shmid: i32) {
-        todo!()
+    /// Return the detached SharedMem, or `InvalidInput` if nothing is attached at the address.
+    pub fn detach_shared_mem(&mut self, addr: VirtAddr) -> AxResult<Arc<SharedMem>> {
+        let index = self
+            .attached_mem
+            .iter()
+            .position(|(start, _, _)| *start == addr)
+            .ok_or(AxError::InvalidInput)?;
+        let (_, _, mem) = self.attached_mem.remove(index);
+        self.page_table.unmap_region(addr, mem.size()).unwrap();
+        mem.on_detach();
+        Ok(mem)
     }
 
     /// mremap: change the size of a mapping, potentially moving it at the same time.
@@ -565,7 +793,8 @@ impl MemorySet {
                         _ => return -1,
                     };
                 }
-                self.munmap(old_start, old_size);
+                // 旧的区域刚刚检查过，不会解除映射失败
+                let _ = self.munmap(old_start, old_size);
                 flush_tlb(None);
                 start.as_usize() as isize
             }
@@ -593,16 +822,15 @@ impl MemorySet {
         {
             match check_page_table_entry_validity(addr, &self.page_table) {
                 Err(PagingError::NoMemory) => Err(AxError::InvalidInput),
-                Err(PagingError::NotMapped) => {
-                    // 若未分配物理页面，则手动为其分配一个页面，写入到对应页表中
-                    let entry = self.page_table.get_entry_mut(addr).unwrap().0;
-
-                    if !area.handle_page_fault(addr, entry.flags(), &mut self.page_table) {
+                _ => {
+                    // 若未分配物理页面，则手动为其分配一个页面，写入到对应页表中。
+                    // 调用者之后可能直接写入该页面，因此按区域的全部权限处理，文件映射的页面会被视为已修改
+                    let flags = area.flags;
+                    if !area.fault_in(addr, flags, &mut self.page_table) {
                         return Err(AxError::BadAddress);
                     }
                     Ok(())
                 }
-                _ => Ok(()),
             }
         } else {
             Err(AxError::InvalidInput)
@@ -623,11 +851,53 @@ impl MemorySet {
         }
         Ok(())
     }
-    /// 判断某一个类型的某一个对象是否被分配
-    pub fn manual_alloc_type_for_lazy<T: Sized>(&mut self, obj: *const T) -> AxResult<()> {
-        let start = obj as usize;
-        let end = start + core::mem::size_of::<T>() - 1;
-        self.manual_alloc_range_for_lazy(start.into(), end.into())
+
+    /// 检查用户地址区间 `[start, start + len)` 是否完整地落在内存集中，且具有 `flags` 所要求的权限，
+    /// 并为区间中 lazy 分配、暂未分配物理页的页面分配物理页。
+    ///
+    /// 区间不合法或者权限不足时返回 `BadAddress`。
+    pub fn check_user_range(
+        &mut self,
+        start: VirtAddr,
+        len: usize,
+        flags: MappingFlags,
+    ) -> AxResult<()> {
+        let start = start.as_usize();
+        let end = start.checked_add(len).ok_or(AxError::BadAddress)?;
+        let flags = flags | MappingFlags::USER;
+        let mut addr = start;
+        while addr < end {
+            // 区间可能跨越多个相邻的区域，逐个区域检查
+            if let Some((_, area)) = self.owned_mem.range_mut(..=addr).next_back() {
+                let area_end = area.end_va().as_usize();
+                if addr < area_end {
+                    if !area.flags.contains(flags) {
+                        return Err(AxError::BadAddress);
+                    }
+                    let next = area_end.min(end);
+                    for page in (VirtAddr::from(addr).align_down_4k().as_usize()..next)
+                        .step_by(PAGE_SIZE_4K)
+                    {
+                        // 只有写入时才会把文件映射的页面标记为已修改
+                        if !area.fault_in(page.into(), flags, &mut self.page_table) {
+                            return Err(AxError::BadAddress);
+                        }
+                    }
+                    addr = next;
+                    continue;
+                }
+            }
+            // 挂载的共享内存在挂载时已经分配好物理页
+            match self.attached_mem.iter().find(|(vaddr, _, mem)| {
+                vaddr.as_usize() <= addr && addr < vaddr.as_usize() + mem.size()
+            }) {
+                Some((vaddr, mem_flags, mem)) if mem_flags.contains(flags) => {
+                    addr = end.min(vaddr.as_usize() + mem.size());
+                }
+                _ => return Err(AxError::BadAddress),
+            }
+        }
+        Ok(())
     }
 }
 
@@ -666,7 +936,12 @@ impl MemorySet {
             page_table,
             owned_mem,
 
-            private_mem: self.private_mem.clone(),
+            private_mem: self
+                .private_mem
+                .iter()
+                .filter(|(_, mem)| !mem.is_removed())
+                .map(|(shmid, mem)| (*shmid, mem.clone()))
+                .collect(),
             attached_mem: Vec::new(),
         };
 
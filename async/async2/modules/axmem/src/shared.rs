@@ -4,12 +4,12 @@ use axhal::{
     mem::{virt_to_phys, PhysAddr, PAGE_SIZE_4K},
     time::current_time,
 };
+use spinlock::SpinNoIrq;
 
-#[allow(dead_code)]
 pub struct SharedMem {
     pages: GlobalPage,
     /// The information of the shared memory.
-    pub info: SharedMemInfo,
+    pub info: SpinNoIrq<SharedMemInfo>,
 }
 
 impl SharedMem {
@@ -27,15 +27,14 @@ impl SharedMem {
         let num_pages = (size + PAGE_SIZE_4K - 1) / PAGE_SIZE_4K;
 
         let pages = GlobalPage::alloc_contiguous(num_pages, PAGE_SIZE_4K)?;
-        let size = pages.size();
 
         Ok(Self {
             pages,
-            info: SharedMemInfo::new(key, size, pid, uid, gid, mode),
+            info: SpinNoIrq::new(SharedMemInfo::new(key, size, pid, uid, gid, mode)),
         })
     }
 
-    /// Return the size of the shared memory.
+    /// Return the size of the shared memory, which is rounded up to pages.
     pub fn size(&self) -> usize {
         self.pages.size()
     }
@@ -44,29 +43,66 @@ impl SharedMem {
     pub fn paddr(&self) -> PhysAddr {
         self.pages.start_paddr(virt_to_phys)
     }
+
+    /// Whether the shared memory has been marked to be destroyed by IPC_RMID.
+    pub fn is_removed(&self) -> bool {
+        self.info.lock().removed
+    }
+
+    /// Record that the shared memory is attached to a memory set.
+    pub(crate) fn on_attach(&self) {
+        let mut info = self.info.lock();
+        info.n_attach += 1;
+        info.a_time = current_time().as_secs() as usize;
+    }
+
+    /// Record that the shared memory is detached from a memory set.
+    pub(crate) fn on_detach(&self) {
+        let mut info = self.info.lock();
+        info.n_attach -= 1;
+        info.d_time = current_time().as_secs() as usize;
+    }
 }
 
-#[allow(dead_code)]
+/// The information of a shared memory, reported by `shmctl(IPC_STAT)`.
 pub struct SharedMemInfo {
-    perm: SharedMemPermInfo,
-    size: usize,
+    /// The owner and permissions.
+    pub perm: SharedMemPermInfo,
+    /// The size requested when creating, in bytes.
+    pub size: usize,
 
-    a_time: usize,
-    d_time: usize,
-    c_time: usize,
+    /// The last attach time, in seconds.
+    pub a_time: usize,
+    /// The last detach time, in seconds.
+    pub d_time: usize,
+    /// The last change time, in seconds.
+    pub c_time: usize,
 
-    c_pid: u64,
-    l_pid: u64,
+    /// The process which created the shared memory.
+    pub c_pid: u64,
+    /// The process which last attached or detached the shared memory.
+    pub l_pid: u64,
+
+    /// The number of current attaches.
+    pub n_attach: usize,
+    /// Marked by IPC_RMID. The memory is freed after the last detach.
+    pub removed: bool,
 }
 
-#[allow(dead_code)]
+/// The owner and permissions of a shared memory.
 pub struct SharedMemPermInfo {
-    key: i32,
-    uid: u32,
-    gid: u32,
-    cuid: u32,
-    cgid: u32,
-    mode: u16,
+    /// The key given to `shmget`.
+    pub key: i32,
+    /// The effective uid of the owner.
+    pub uid: u32,
+    /// The effective gid of the owner.
+    pub gid: u32,
+    /// The effective uid of the creator.
+    pub cuid: u32,
+    /// The effective gid of the creator.
+    pub cgid: u32,
+    /// The lower 9 bits of the access mode.
+    pub mode: u16,
 }
 
 impl SharedMemInfo {
@@ -90,6 +126,9 @@ impl SharedMemInfo {
 
             c_pid: pid,
             l_pid: 0,
+
+            n_attach: 0,
+            removed: false,
         }
     }
 }
@@ -43,10 +43,16 @@ pub use smoltcp::wire::{IpAddress as IpAddr, IpEndpoint as SocketAddr, Ipv4Addre
 use axdriver::{prelude::*, AxDeviceContainer};
 
 /// Initializes the network subsystem by NIC devices.
+///
+/// With the `ip` feature, only the loopback device is used and a NIC is not
+/// required.
 pub fn init_network(mut net_devs: AxDeviceContainer<AxNetDevice>) {
     info!("Initialize network subsystem...");
 
-    let dev = net_devs.take_one().expect("No NIC device found!");
-    info!("  use NIC 0: {:?}", dev.device_name());
+    let dev = net_devs.take_one();
+    match &dev {
+        Some(dev) => info!("  use NIC 0: {:?}", dev.device_name()),
+        None => info!("  no NIC device found"),
+    }
     net_impl::init(dev);
 }
@@ -364,7 +364,7 @@ pub fn add_membership(_multicast_addr: IpAddress, _interface_addr: IpAddress) {
     }
 }
 
-pub(crate) fn init(_net_dev: AxNetDevice) {
+pub(crate) fn init(_net_dev: Option<AxNetDevice>) {
     #[cfg(feature = "ip")]
     {
         let mut device = LoopbackDev::new(Medium::Ip);
@@ -386,6 +386,7 @@ pub(crate) fn init(_net_dev: AxNetDevice) {
 
     #[cfg(not(feature = "ip"))]
     {
+        let _net_dev = _net_dev.expect("No NIC device found!");
         let ether_addr = EthernetAddress(_net_dev.mac_address().0);
         let eth0 = InterfaceWrapper::new("eth0", _net_dev, ether_addr);
 
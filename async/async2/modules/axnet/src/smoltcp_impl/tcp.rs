@@ -286,7 +286,7 @@ impl TcpSocket {
     ///
     /// It won't change TCP state.
     /// It won't affect unconnected sockets (listener).
-    pub fn close(&mut self) {
+    pub fn close(&self) {
         let handle = match unsafe { self.handle.get().read() } {
             Some(h) => h,
             None => return,
@@ -327,6 +327,34 @@ impl TcpSocket {
             })
         })
     }
+    /// Receives data from the socket without removing it from the receive
+    /// queue.
+    pub fn peek(&self, buf: &mut [u8]) -> AxResult<usize> {
+        if self.is_connecting() {
+            return Err(AxError::WouldBlock);
+        } else if !self.is_connected() {
+            return ax_err!(NotConnected, "socket peek() failed");
+        }
+
+        // SAFETY: `self.handle` should be initialized in a connected socket.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        self.block_on(|| {
+            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                if socket.recv_queue() > 0 {
+                    socket
+                        .peek_slice(buf)
+                        .map_err(|_| ax_err_type!(BadState, "socket peek() failed"))
+                } else if !socket.is_active() {
+                    ax_err!(ConnectionRefused, "socket peek() failed")
+                } else if !socket.may_recv() {
+                    Ok(0)
+                } else {
+                    Err(AxError::WouldBlock)
+                }
+            })
+        })
+    }
+
     /// Receives data from the socket, stores it in the given buffer.
     ///
     /// It will return [`Err(Timeout)`](AxError::Timeout) if expired.
@@ -525,7 +553,8 @@ impl TcpSocket {
     }
 
     #[inline]
-    fn is_listening(&self) -> bool {
+    /// Whether the socket is listening.
+    pub fn is_listening(&self) -> bool {
         self.get_state() == STATE_LISTENING
     }
 
@@ -0,0 +1,88 @@
+//! Hardware random number generators of the CPU.
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        use core::arch::asm;
+
+        /// Returns a random number from `RDSEED`, or `RDRAND` if the CPU has no
+        /// `RDSEED`. Each instruction is retried a few times, as it may fail
+        /// transiently when the generator is drained.
+        pub fn random_u64() -> Option<u64> {
+            let cpuid = raw_cpuid::CpuId::new();
+            let has_rdseed = cpuid
+                .get_extended_feature_info()
+                .map_or(false, |info| info.has_rdseed());
+            let has_rdrand = cpuid
+                .get_feature_info()
+                .map_or(false, |info| info.has_rdrand());
+            for _ in 0..10 {
+                let value: u64;
+                let ok: u8;
+                if has_rdseed {
+                    unsafe {
+                        asm!(
+                            "rdseed {0}",
+                            "setc {1}",
+                            out(reg) value,
+                            out(reg_byte) ok,
+                            options(nomem, nostack)
+                        )
+                    };
+                } else if has_rdrand {
+                    unsafe {
+                        asm!(
+                            "rdrand {0}",
+                            "setc {1}",
+                            out(reg) value,
+                            out(reg_byte) ok,
+                            options(nomem, nostack)
+                        )
+                    };
+                } else {
+                    return None;
+                }
+                if ok != 0 {
+                    return Some(value);
+                }
+            }
+            None
+        }
+    } else if #[cfg(target_arch = "aarch64")] {
+        use core::arch::asm;
+
+        /// Returns a random number from `RNDR` if the CPU implements FEAT_RNG.
+        pub fn random_u64() -> Option<u64> {
+            let isar0: u64;
+            // ID_AA64ISAR0_EL1
+            unsafe { asm!("mrs {0}, s3_0_c0_c6_0", out(reg) isar0, options(nomem, nostack)) };
+            if (isar0 >> 60) & 0xf == 0 {
+                return None;
+            }
+            for _ in 0..10 {
+                let value: u64;
+                let ok: u64;
+                // RNDR, which clears NZCV.Z on success
+                unsafe {
+                    asm!(
+                        "mrs {0}, s3_3_c2_c4_0",
+                        "cset {1}, ne",
+                        out(reg) value,
+                        out(reg) ok,
+                        options(nomem, nostack)
+                    )
+                };
+                if ok != 0 {
+                    return Some(value);
+                }
+            }
+            None
+        }
+    } else {
+        /// The `seed` CSR of the Zkr extension is only accessible from
+        /// S-mode when M-mode allows it, which can not be detected safely, so
+        /// no hardware source is used on RISC-V.
+        pub fn random_u64() -> Option<u64> {
+            None
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Kernel random number generator of [ArceOS](https://github.com/rcore-os/arceos).
+//!
+//! Entropy is collected into an [`EntropyPool`] from:
+//!
+//! - the timing of every interrupt ([`add_interrupt_randomness`]);
+//! - CPU execution-time jitter measured once during [`init`];
+//! - the hardware random number generator of the CPU, if there is one
+//!   (`RDSEED`/`RDRAND` on x86_64, `RNDR` on aarch64);
+//! - data written to `/dev/random` ([`add_entropy`], not credited).
+//!
+//! Output comes from a [`ChaChaRng`] that is seeded from the pool once enough
+//! entropy has been collected, and reseeded from it periodically afterwards.
+//!
+//! [`EntropyPool`]: chacha_rng::EntropyPool
+//! [`ChaChaRng`]: chacha_rng::ChaChaRng
+
+#![no_std]
+
+#[macro_use]
+extern crate log;
+
+mod hw;
+
+use axhal::time::{current_ticks, current_time, TimeValue};
+use chacha_rng::{ChaChaRng, EntropyPool, KEY_SIZE};
+use spinlock::SpinNoIrq;
+
+/// Entropy, in bits, the pool must have collected before the generator is
+/// considered seeded.
+const SEED_BITS: usize = 256;
+
+/// How often the generator is reseeded from the pool once it is seeded.
+const RESEED_INTERVAL: TimeValue = TimeValue::from_secs(60);
+
+/// Number of execution-time samples taken by [`init`] at most.
+const JITTER_SAMPLES: usize = 4096;
+
+struct KernelRng {
+    pool: EntropyPool,
+    crng: ChaChaRng,
+    seeded: bool,
+    last_reseed: TimeValue,
+}
+
+impl KernelRng {
+    /// Reseeds the generator from the pool if it is due.
+    fn try_reseed(&mut self) {
+        let now = current_time();
+        let due = if self.seeded {
+            now.saturating_sub(self.last_reseed) >= RESEED_INTERVAL
+        } else {
+            self.pool.entropy_bits() >= SEED_BITS
+        };
+        if due {
+            self.crng.reseed(&self.pool.extract());
+            self.last_reseed = now;
+            if !self.seeded {
+                self.seeded = true;
+                info!("random number generator is seeded");
+            }
+        }
+    }
+}
+
+static RNG: SpinNoIrq<KernelRng> = SpinNoIrq::new(KernelRng {
+    pool: EntropyPool::new(),
+    crng: ChaChaRng::new([0; KEY_SIZE]),
+    seeded: false,
+    last_reseed: TimeValue::ZERO,
+});
+
+/// Mixes `data` into the pool, crediting it with `bits` bits of entropy.
+fn mix(data: &[u8], bits: usize) {
+    RNG.lock().pool.mix(data, bits);
+}
+
+/// Seeds the generator with hardware random numbers and CPU jitter.
+///
+/// It should be called once the platform timer is available.
+pub fn init() {
+    let mut hw_samples = 0;
+    for _ in 0..KEY_SIZE / 8 {
+        if let Some(value) = hw::random_u64() {
+            mix(&value.to_ne_bytes(), 64);
+            hw_samples += 1;
+        }
+    }
+    if hw_samples == 0 {
+        info!("no hardware random number generator found");
+    }
+
+    // Time how long mixing a sample into the pool takes, which varies with
+    // caches and pipelines. Only a sample whose duration differs from the
+    // previous one is credited, with one bit.
+    let mut last_delta = 0;
+    for i in 0..JITTER_SAMPLES {
+        if is_seeded() {
+            break;
+        }
+        let start = current_ticks();
+        let mut rng = RNG.lock();
+        rng.pool.mix(&start.to_ne_bytes(), 0);
+        let delta = current_ticks().wrapping_sub(start);
+        let bits = (delta != last_delta) as usize;
+        rng.pool
+            .mix(&(delta ^ ((i as u64) << 32)).to_ne_bytes(), bits);
+        rng.try_reseed();
+        last_delta = delta;
+    }
+    if !is_seeded() {
+        warn!("random number generator is not seeded yet, waiting for interrupts");
+    }
+}
+
+/// Mixes the timing of an interrupt into the pool.
+///
+/// Called on every interrupt, credited with one bit.
+pub fn add_interrupt_randomness(irq_num: usize) {
+    let sample = current_ticks() ^ ((irq_num as u64) << 48);
+    let mut rng = RNG.lock();
+    rng.pool.mix(&sample.to_ne_bytes(), 1);
+    if !rng.seeded {
+        rng.try_reseed();
+    }
+}
+
+/// Mixes data of unknown quality, such as writes to `/dev/random`, into the
+/// pool without crediting any entropy.
+pub fn add_entropy(data: &[u8]) {
+    mix(data, 0);
+}
+
+/// Whether enough entropy has been collected to seed the generator.
+pub fn is_seeded() -> bool {
+    RNG.lock().seeded
+}
+
+/// Fills `buf` with random bytes.
+///
+/// It never blocks. Callers that must not get output before the generator is
+/// seeded should check [`is_seeded`] first.
+pub fn fill_bytes(buf: &mut [u8]) {
+    let mut rng = {
+        let mut rng = RNG.lock();
+        rng.try_reseed();
+        rng.crng.fork()
+    };
+    rng.fill_bytes(buf);
+}
+
+/// Returns a random `u64`.
+pub fn random_u64() -> u64 {
+    let mut rng = RNG.lock();
+    rng.try_reseed();
+    rng.crng.next_u64()
+}
@@ -139,6 +139,8 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) {
     }
     info!("Initialize platform devices...");
     axhal::platform_init();
+    info!("Initialize random number generator...");
+    axrand::init();
     cfg_if::cfg_if! {
         if #[cfg(feature = "monolithic")] {
             axtask::init_kernel_task();
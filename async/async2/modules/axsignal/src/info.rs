@@ -2,9 +2,18 @@
 //!
 //! 错误信息：详细定义见 `https://man7.org/linux/man-pages/man2/rt_sigaction.2.html`
 
+/// `si_code` of a signal sent by `kill`
+pub const SI_USER: i32 = 0;
+/// `si_code` of a signal sent by the kernel
+pub const SI_KERNEL: i32 = 0x80;
+/// `si_code` of a signal sent by `tkill` or `tgkill`
+pub const SI_TKILL: i32 = -6;
+
 /// The information of the signal
 ///
 /// When the `SigAction` specifies that it needs information, it will return it to the user
+#[repr(C)]
+#[derive(Clone, Copy)]
 pub struct SigInfo {
     /// The signal number
     pub si_signo: i32,
@@ -12,6 +21,12 @@ pub struct SigInfo {
     pub si_errno: i32,
     /// The code of the signal
     pub si_code: i32,
+    /// Padding before the union of `siginfo_t`, which is 8-byte aligned
+    pub _pad: i32,
+    /// The process id of the sender
+    pub si_pid: i32,
+    /// The real user id of the sender
+    pub si_uid: u32,
 }
 
 impl Default for SigInfo {
@@ -19,7 +34,10 @@ impl Default for SigInfo {
         Self {
             si_signo: 0,
             si_errno: 0,
-            si_code: -6, // SI_TKILL
+            si_code: SI_KERNEL,
+            _pad: 0,
+            si_pid: 0,
+            si_uid: 0,
         }
     }
 }
@@ -7,6 +7,7 @@
 #![cfg_attr(not(test), no_std)]
 
 use action::SigAction;
+use info::SigInfo;
 use signal_no::{SignalNo, MAX_SIG_NUM};
 
 pub mod action;
@@ -69,6 +70,8 @@ pub struct SignalSet {
     pub mask: usize,
     /// 未决信号集
     pub pending: usize,
+    /// infos`[i]` 为信号 `i+1` 最近一次加入未决信号集时的信息
+    infos: [SigInfo; MAX_SIG_NUM],
 }
 
 impl Default for SignalSet {
@@ -83,6 +86,7 @@ impl SignalSet {
         Self {
             mask: 0,
             pending: 0,
+            infos: [SigInfo::default(); MAX_SIG_NUM],
         }
     }
 
@@ -129,13 +133,32 @@ impl SignalSet {
         }
     }
 
-    /// 尝试添加一个bit作为信号
+    /// 尝试添加一个bit作为信号，信号视为由内核发送
     ///
     /// 若当前信号已经加入到未决信号集中，则不作处理
     ///
     /// 若信号在掩码中，则仍然加入，但是可能不会触发
     pub fn try_add_signal(&mut self, sig_num: usize) {
+        self.try_add_signal_info(SigInfo {
+            si_signo: sig_num as i32,
+            ..Default::default()
+        });
+    }
+
+    /// 与 `try_add_signal` 相同，但同时记录信号的信息
+    ///
+    /// 信号不会排队，若当前信号已经在未决信号集中，则保留原有的信息
+    pub fn try_add_signal_info(&mut self, info: SigInfo) {
+        let sig_num = info.si_signo as usize;
         let now_mask = 1 << (sig_num - 1);
+        if self.pending & now_mask == 0 {
+            self.infos[sig_num - 1] = info;
+        }
         self.pending |= now_mask;
     }
+
+    /// 获取信号最近一次加入未决信号集时的信息，在信号被取出之后仍然可以获取
+    pub fn info(&self, sig_num: usize) -> SigInfo {
+        self.infos[sig_num - 1]
+    }
 }
@@ -6,24 +6,26 @@
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct SignalStack {
-    sp: usize,
-    flags: u32,
-    size: usize,
+    /// 栈的起始地址
+    pub sp: usize,
+    /// 栈的状态，见 `SS_ONSTACK` 与 `SS_DISABLE`
+    pub flags: u32,
+    /// 栈的大小
+    pub size: usize,
 }
 
 impl Default for SignalStack {
     fn default() -> Self {
         Self {
             sp: 0,
-            // 代表SS_DISABLE，即不使用栈
-            flags: 2,
+            flags: super::SS_DISABLE,
             size: 0,
         }
     }
 }
 
 #[repr(C)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 /// The `mcontext` struct for the signal action
 pub struct MContext {
     fault_address: usize,
@@ -61,7 +63,7 @@ impl MContext {
 }
 
 #[repr(C)]
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 /// The user context saved for the signal action, which can be accessed by the signal handler
 pub struct SignalUserContext {
     flags: usize,
@@ -73,13 +75,15 @@ pub struct SignalUserContext {
 
 impl SignalUserContext {
     /// init the user context by the pc and the mask
-    pub fn init(pc: usize, _mask: usize) -> Self {
+    pub fn init(pc: usize, mask: usize) -> Self {
+        let mut sigmask = [0; 17];
+        sigmask[0] = mask;
         Self {
             flags: 0,
             link: 0,
             stack: SignalStack::default(),
             mcontext: MContext::init_by_pc(pc),
-            sigmask: [0; 17],
+            sigmask,
         }
     }
 
@@ -87,4 +91,9 @@ impl SignalUserContext {
     pub fn get_pc(&self) -> usize {
         self.mcontext.pc
     }
+
+    /// get the signal mask to be restored when the handler returns
+    pub fn get_mask(&self) -> usize {
+        self.sigmask[0]
+    }
 }
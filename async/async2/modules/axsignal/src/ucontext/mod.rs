@@ -12,3 +12,15 @@ cfg_if::cfg_if! {
         pub use self::aarch64::*;
     }
 }
+
+/// 备用信号栈的状态：当前正在备用栈上执行信号处理函数
+pub const SS_ONSTACK: u32 = 1;
+
+/// 备用信号栈的状态：不使用备用栈
+pub const SS_DISABLE: u32 = 2;
+
+/// 备用信号栈的标志：进入信号处理函数时自动停用备用栈，目前仅被接受而不生效
+pub const SS_AUTODISARM: u32 = 1 << 31;
+
+/// 备用信号栈的最小大小
+pub const MINSIGSTKSZ: usize = 2048;
@@ -6,23 +6,25 @@
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct SignalStack {
-    sp: usize,
-    flags: u32,
-    size: usize,
+    /// 栈的起始地址
+    pub sp: usize,
+    /// 栈的状态，见 `SS_ONSTACK` 与 `SS_DISABLE`
+    pub flags: u32,
+    /// 栈的大小
+    pub size: usize,
 }
 
 impl Default for SignalStack {
     fn default() -> Self {
         Self {
             sp: 0,
-            // 代表SS_DISABLE，即不使用栈
-            flags: 2,
+            flags: super::SS_DISABLE,
             size: 0,
         }
     }
 }
 #[repr(C)]
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 /// The `mcontext` struct for the signal action
 pub struct MContext {
     // gregs
@@ -73,7 +75,7 @@ impl MContext {
 }
 
 #[repr(C)]
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 /// The user context saved for the signal action, which can be accessed by the signal handler
 pub struct SignalUserContext {
     flags: usize,
@@ -101,4 +103,9 @@ impl SignalUserContext {
     pub fn get_pc(&self) -> usize {
         self.mcontext.get_pc()
     }
+
+    /// get the signal mask to be restored when the handler returns
+    pub fn get_mask(&self) -> usize {
+        self.sigmask as usize
+    }
 }
@@ -7,15 +7,17 @@ use taskctx::TaskState;
 
 use crate::flags::WaitStatus;
 use crate::link::real_path;
+use crate::rlimit::ResourceLimits;
 use crate::Mutex;
 use alloc::{string::ToString, vec, vec::Vec};
-use axconfig::{MAX_USER_HEAP_SIZE, MAX_USER_STACK_SIZE, USER_HEAP_BASE, USER_STACK_TOP};
+use axconfig::{MAX_USER_HEAP_SIZE, USER_HEAP_BASE, USER_STACK_TOP};
 use axerrno::{AxError, AxResult};
 use axhal::arch::TrapFrame;
-use axhal::mem::VirtAddr;
+use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
 use axhal::paging::MappingFlags;
 use axhal::time::{current_time_nanos, NANOS_PER_MICROS, NANOS_PER_SEC};
 use axmem::MemorySet;
+use axsignal::signal_no::SignalNo;
 use core::ops::Deref;
 use core::ptr::copy_nonoverlapping;
 use core::str::from_utf8;
@@ -65,18 +67,15 @@ pub fn load_app(
     mut args: Vec<String>,
     envs: &Vec<String>,
     memory_set: &mut MemorySet,
+    rlimits: &ResourceLimits,
 ) -> AxResult<(VirtAddr, VirtAddr, VirtAddr)> {
     if name.ends_with(".sh") {
         args = [vec![String::from("busybox"), String::from("sh")], args].concat();
-        return load_app("busybox".to_string(), args, envs, memory_set);
+        return load_app("busybox".to_string(), args, envs, memory_set, rlimits);
     }
-    let elf_data = if let Ok(ans) = axfs::api::read(name.as_str()) {
-        ans
-    } else {
-        // exit(0)
-        return Err(AxError::NotFound);
-    };
-    let elf = xmas_elf::ElfFile::new(&elf_data).expect("Error parsing app ELF file.");
+    let elf_data = axfs::api::read(name.as_str())?;
+    // 不是合法的 ELF 文件时返回 InvalidData，由 execve 转换为 ENOEXEC
+    let elf = xmas_elf::ElfFile::new(&elf_data).map_err(|_| AxError::InvalidData)?;
     debug!("app elf data length: {}", elf_data.len());
     if let Some(interp) = elf
         .program_iter()
@@ -84,15 +83,15 @@ pub fn load_app(
     {
         let interp = match interp.get_data(&elf) {
             Ok(SegmentData::Undefined(data)) => data,
-            _ => panic!("Invalid data in Interp Elf Program Header"),
+            _ => return Err(AxError::InvalidData),
         };
 
-        let interp_path = from_utf8(interp).expect("Interpreter path isn't valid UTF-8");
+        let interp_path = from_utf8(interp).map_err(|_| AxError::InvalidData)?;
         // remove trailing '\0'
         let interp_path = interp_path.trim_matches(char::from(0)).to_string();
         let real_interp_path = real_path(&interp_path);
         args = [vec![real_interp_path.clone()], args].concat();
-        return load_app(real_interp_path, args, envs, memory_set);
+        return load_app(real_interp_path, args, envs, memory_set, rlimits);
     }
     info!("args: {:?}", args);
     let elf_base_addr = Some(0x400_0000);
@@ -100,6 +99,23 @@ pub fn load_app(
     let entry = get_elf_entry(&elf, elf_base_addr);
     let segments = get_elf_segments(&elf, elf_base_addr);
     let relocate_pairs = get_relocate_pairs(&elf, elf_base_addr);
+    // 用户栈的大小受 RLIMIT_STACK 限制，整个地址空间的大小受 RLIMIT_AS 限制
+    let stack_size = rlimits.stack_size();
+    let args_size: usize = args
+        .iter()
+        .chain(envs.iter())
+        .map(|arg| arg.len() + 1 + core::mem::size_of::<usize>())
+        .sum();
+    // 参数、环境变量与辅助向量之外至少还要留出一页的栈空间
+    if args_size + 2 * PAGE_SIZE_4K > stack_size {
+        return Err(AxError::NoMemory);
+    }
+    let total_size = segments.iter().map(|segment| segment.size).sum::<usize>()
+        + MAX_USER_HEAP_SIZE
+        + stack_size;
+    if total_size > rlimits.address_space_limit() {
+        return Err(AxError::NoMemory);
+    }
     for segment in segments {
         memory_set.new_region(
             segment.vaddr,
@@ -136,9 +152,11 @@ pub fn load_app(
     let auxv = get_auxv_vector(&elf, elf_base_addr);
 
     let stack_top = VirtAddr::from(USER_STACK_TOP);
-    let stack_size = MAX_USER_STACK_SIZE;
 
-    let (stack_data, stack_bottom) = get_app_stack_region(args, envs, auxv, stack_top, stack_size);
+    let mut random = [0u8; 16];
+    axrand::fill_bytes(&mut random);
+    let (stack_data, stack_bottom) =
+        get_app_stack_region(args, envs, auxv, random, stack_top, stack_size);
     memory_set.new_region(
         stack_top,
         stack_size,
@@ -204,7 +222,10 @@ pub fn exit_current_task(exit_code: i32) {
 
     info!("exit task id {} with code _{}_", curr_id, exit_code);
 
-    // clear_child_tid 的值不为 0，则将这个用户地址处的值写为0
+    // 释放任务仍然持有的 robust futex
+    crate::futex::exit_robust_list();
+
+    // clear_child_tid 的值不为 0，则将这个用户地址处的值写为0，并唤醒在该地址上等待的任务
     let clear_child_tid = current_task.inner.lock().get_clear_child_tid();
     if clear_child_tid != 0 {
         // 先确认是否在用户空间
@@ -215,6 +236,8 @@ pub fn exit_current_task(exit_code: i32) {
             unsafe {
                 *(clear_child_tid as *mut i32) = 0;
             }
+            let _ =
+                crate::futex::futex_wake(clear_child_tid, 1, crate::futex::FUTEX_BITSET_MATCH_ANY);
         }
     }
 
@@ -223,39 +246,82 @@ pub fn exit_current_task(exit_code: i32) {
     current_task.set_zombie(true);
 
     //归还栈到内核
-    RUN_QUEUE
-        .lock()
-        .free_stack(current_task.inner.lock().stack_idx);
+    RUN_QUEUE.lock().release_stack(current_task.as_task_ref());
 
     current_task.fd_manager.fd_table.lock().clear();
-    let tid2ta = TID2TASK.lock();
-    let kernel_task = tid2ta.get(&KERNEL_PROCESS_ID).unwrap();
-    // 将子进程交给idle进程
+    let mut tid2ta = TID2TASK.lock();
+    let kernel_task = tid2ta.get(&KERNEL_PROCESS_ID).unwrap().clone();
+    // 将子进程交给idle进程，已经退出的子进程不会再被等待，直接回收
     // process.memory_set = Arc::clone(&kernel_process.memory_set);
     for childid in current_task.children.lock().deref() {
-        let child = tid2ta.get(childid).unwrap();
+        let Some(child) = tid2ta.get(childid).cloned() else {
+            continue;
+        };
+        if child.get_zombie() {
+            tid2ta.remove(childid);
+            continue;
+        }
         child.set_parent(KERNEL_PROCESS_ID);
         kernel_task.children.lock().push(child.tid());
     }
+    current_task.children.lock().clear();
+    // 线程与父进程为内核的任务不会被 wait，直接移除；其余任务作为僵尸保留，直到父进程调用 wait
+    if current_task.is_thread() || current_task.get_parent() == KERNEL_PROCESS_ID {
+        tid2ta.remove(&curr_id);
+        kernel_task.children.lock().retain(|&tid| tid != curr_id);
+    }
     drop(tid2ta);
-    TID2TASK.lock().remove(&curr_id);
+
+    // 进程退出时按 clone 时的约定通知父进程
+    let parent_id = current_task.get_parent();
+    if !current_task.is_thread()
+        && parent_id != KERNEL_PROCESS_ID
+        && current_task.inner.lock().get_sig_child()
+    {
+        let _ = crate::signal::send_signal_to_process(
+            parent_id as isize,
+            SignalNo::SIGCHLD as isize,
+        );
+    }
+    // 唤醒可能挂起在 wait4 中的父进程
+    if !current_task.is_thread() {
+        if let Some(parent) = TID2TASK.lock().get(&parent_id).cloned() {
+            parent.wake();
+        }
+    }
 
     drop(current_task);
     RUN_QUEUE.lock().tasksub();
     RUN_QUEUE.lock().exit_current(exit_code);
 }
 
+/// 选出抢占当前任务 cid 的任务，返回 0 表示不需要抢占
+///
+/// 只考虑处于就绪状态、且优先级不低于当前任务的任务。优先级由调度策略与 nice 值在运行时决定，
+/// 优先级相同的任务中选择 tid 在 cid 之后的第一个，使它们轮流运行
 pub fn get_max_pri_task_id(cid: u64) -> u64 {
-    let mut max_pri = 0;
-    let mut max_pri_task = 0;
+    let curr_pri = TID2TASK
+        .lock()
+        .get(&cid)
+        .map(|task| task.priority())
+        .unwrap_or(0);
+    let mut best: Option<(u64, bool, u64)> = None;
     let tid2ta = TID2TASK.lock();
     for (tid, task) in tid2ta.iter() {
-        if (task.pri > max_pri) && (task.tid() != cid) {
-            max_pri = task.pri;
-            max_pri_task = *tid;
+        if *tid == cid || task.get_zombie() || task.inner.lock().state() != TaskState::Ready {
+            continue;
+        }
+        let pri = task.priority();
+        if pri < curr_pri {
+            continue;
+        }
+        // 按 (优先级, 是否在 cid 之后, 越靠前越好) 比较
+        let key = (pri, *tid > cid, u64::MAX - *tid);
+        if best.map_or(true, |best_key| key > best_key) {
+            best = Some(key);
         }
     }
-    max_pri_task
+    best.map_or(0, |(_, _, rev_tid)| u64::MAX - rev_tid)
 }
 
 
@@ -309,26 +375,30 @@ pub fn handle_page_fault(addr: VirtAddr, flags: MappingFlags) {
     {
         axhal::arch::flush_tlb(None);
     } else {
-        panic!("handle page fault failed");
+        // 访问了非法地址，由返回用户态前的信号处理终止任务或调用其处理函数
+        warn!("page fault at {:?} cannot be handled, send SIGSEGV", addr);
+        drop(current_task);
+        crate::signal::force_signal_to_current(SignalNo::SIGSEGV as usize);
     }
 }
 
 /// 在当前进程找对应的子task，并等待子task结束
-/// 若找到了则返回对应的pid
+/// 若找到了则返回对应的pid与退出状态
 /// 否则返回一个状态
 ///
-/// # Safety
-///
-/// 保证传入的 ptr 是有效的
-pub unsafe fn wait_pid(tid: isize, exit_code_ptr: *mut i32) -> Result<u64, WaitStatus> {
+/// 正常退出时退出状态为 exit_code << 8，被信号终止时为信号编号
+pub fn wait_pid(tid: isize) -> Result<(u64, i32), WaitStatus> {
     // 获取当前进程
     let curr_task = current();
     let mut exit_task_id: usize = 0;
     let mut answer_id: u64 = 0;
+    let mut wait_status: i32 = 0;
     let mut answer_status = WaitStatus::NotExist;
     for (index, childid) in curr_task.children.lock().iter().enumerate() {
         let tid2task = TID2TASK.lock();
-        let child = tid2task.get(childid).unwrap();
+        let Some(child) = tid2task.get(childid) else {
+            continue;
+        };
         if tid == -1 {
             // 任意一个task结束都可以的
             answer_status = WaitStatus::Running;
@@ -336,12 +406,7 @@ pub unsafe fn wait_pid(tid: isize, exit_code_ptr: *mut i32) -> Result<u64, WaitS
                 answer_status = WaitStatus::Exited;
                 info!("wait tid _{}_ with code _{}_", child.tid(), exit_code);
                 exit_task_id = index;
-                if !exit_code_ptr.is_null() {
-                    unsafe {
-                        // 因为没有切换页表，所以可以直接填写
-                        *exit_code_ptr = exit_code << 8;
-                    }
-                }
+                wait_status = child.get_wait_status();
                 answer_id = child.tid();
                 break;
             }
@@ -351,12 +416,7 @@ pub unsafe fn wait_pid(tid: isize, exit_code_ptr: *mut i32) -> Result<u64, WaitS
                 answer_status = WaitStatus::Exited;
                 info!("wait pid _{}_ with code _{:?}_", child.tid(), exit_code);
                 exit_task_id = index;
-                if !exit_code_ptr.is_null() {
-                    unsafe {
-                        *exit_code_ptr = exit_code << 8;
-                        // 用于WEXITSTATUS设置编码
-                    }
-                }
+                wait_status = child.get_wait_status();
                 answer_id = child.tid();
             } else {
                 answer_status = WaitStatus::Running;
@@ -367,7 +427,11 @@ pub unsafe fn wait_pid(tid: isize, exit_code_ptr: *mut i32) -> Result<u64, WaitS
     // 若task成功结束，需要将其从父task的children中删除
     if answer_status == WaitStatus::Exited {
         curr_task.children.lock().remove(exit_task_id);
-        return Ok(answer_id);
+        // 回收僵尸任务，并将其运行时间计入父任务的子任务时间
+        if let Some(child) = TID2TASK.lock().remove(&answer_id) {
+            curr_task.add_children_time(&child);
+        }
+        return Ok((answer_id, wait_status));
     }
     Err(answer_status)
 }
@@ -495,7 +559,7 @@ pub fn vfork_suspend(task: &AxTaskRef) {
     get_wait_for_exit_queue(task).map(|wait_queue| {
         wait_queue.wait_until(|| {
             // If the given task does the exec syscall, it will be the leader of the new process.
-            task.inner.lock().state() == TaskState::Exited
+            task.inner.lock().state() == TaskState::Exited || !task.inner.lock().is_vfork_child()
         });
     });
 }
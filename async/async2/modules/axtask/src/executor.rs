@@ -4,7 +4,7 @@ use core::future::Future;
 use spinlock::SpinNoIrq;
 
 use crate::future::UserTaskFuture;
-use crate::future::YieldFuture;
+use crate::future::{SyscallFuture, YieldFuture};
 use crate::AxTaskRef;
 
 use axhal::arch::TrapFrame;
@@ -91,7 +91,7 @@ pub fn spawn_user_task(task: AxTaskRef) {
     axlog::warn!(
         "spawn user task,:{},pri:{}",
         task.inner.lock().name(),
-        task.pri
+        task.priority()
     );
     // let future = schedule::OutermostFuture::new(thread.clone(), async {});
     let future = UserTaskFuture::new(task.clone(), task_loop());
@@ -104,20 +104,28 @@ pub async fn task_loop() {
     unsafe { crate::RUN_QUEUE.force_unlock() };
     let mut cccstr = 0;
     loop {
-        let task = crate::current();
-        let mut tf = task.get_tf();
+        // 被停止信号停止的任务在返回用户态之前挂起，直到收到 SIGCONT 或 SIGKILL
+        crate::signal::wait_while_stopped().await;
+        let mut tf = crate::current().get_tf();
         // 切换页表已经在switch实现了
         // 更新时间
-        task.inner
+        crate::current()
+            .inner
             .lock()
             .time_stat_from_kernel_to_user(axhal::time::current_time_nanos() as usize);
         // return to user space
-        unsafe {
+        // next time when user traps into kernel, it will come back here
+        let syscall = unsafe {
             riscv_trap_return(&mut tf);
-            // next time when user traps into kernel, it will come back here
-            riscv_trap_handler(&mut tf, true);
+            riscv_trap_handler(&mut tf, true)
+        };
+        if let Some(syscall) = syscall {
+            // 阻塞的系统调用在这里挂起，挂起期间不持有内核栈和当前任务的引用
+            let ret = syscall.await;
+            unsafe { riscv_syscall_return(&mut tf, ret) };
         }
         cccstr += 1;
+        let task = crate::current();
         task.set_tf(tf);
         if cccstr == 3 {
             let cid = task.tid();
@@ -130,5 +138,11 @@ pub async fn task_loop() {
 
 extern "C" {
     fn riscv_trap_return(tf: &mut TrapFrame);
-    fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool);
+}
+
+extern "Rust" {
+    /// 处理来自用户态的陷入，系统调用返回待执行的 future，其余陷入就地处理完毕
+    fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) -> Option<SyscallFuture>;
+    /// 把系统调用的返回值写回 trapframe，并在返回用户态之前处理信号
+    fn riscv_syscall_return(tf: &mut TrapFrame, ret: isize);
 }
@@ -2,6 +2,7 @@
 extern crate alloc;
 use core::sync::atomic::{AtomicI32, AtomicU64};
 
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::sync::Arc;
 use axfs::api::{FileIO, OpenFlags};
@@ -13,7 +14,14 @@ use crate::Mutex;
 use crate::stdio::{Stdin, Stdout};
 pub struct FdManager {
     /// 保存文件描述符的数组
-    pub fd_table: Mutex<Vec<Option<Arc<dyn FileIO>>>>,
+    ///
+    /// 使用 Arc 包裹，从而 clone 时指定 CLONE_FILES 的任务可以共享同一张表
+    pub fd_table: Arc<Mutex<Vec<Option<Arc<dyn FileIO>>>>>,
+    /// 设置了 FD_CLOEXEC 的文件描述符
+    ///
+    /// 该标志属于文件描述符而非打开的文件，因此 dup 得到的描述符不会共享它。
+    /// 与 fd_table 一同在 CLONE_FILES 时共享；需要同时加锁时，先锁 fd_table
+    pub cloexec_fds: Arc<Mutex<BTreeSet<usize>>>,
     /// 保存文件描述符的数组的最大长度
     pub limit: AtomicU64,
     /// 创建文件时的mode的掩码
@@ -24,7 +32,8 @@ pub struct FdManager {
 impl FdManager {
     pub fn new(fd_table: Vec<Option<Arc<dyn FileIO>>>, limit: usize) -> Self {
         Self {
-            fd_table: Mutex::new(fd_table),
+            fd_table: Arc::new(Mutex::new(fd_table)),
+            cloexec_fds: Arc::new(Mutex::new(BTreeSet::new())),
             limit: AtomicU64::new(limit as u64),
             umask: AtomicI32::new(0o022),
             cwd: Mutex::new(String::from("/")),
@@ -53,17 +62,33 @@ impl FdManager {
         old_mask
     }
 
+    /// 设置或清除某个文件描述符的 FD_CLOEXEC 标志
+    pub fn set_close_on_exec(&self, fd: usize, is_set: bool) {
+        let mut cloexec_fds = self.cloexec_fds.lock();
+        if is_set {
+            cloexec_fds.insert(fd);
+        } else {
+            cloexec_fds.remove(&fd);
+        }
+    }
+
+    /// 查询某个文件描述符是否设置了 FD_CLOEXEC 标志
+    pub fn is_close_on_exec(&self, fd: usize) -> bool {
+        self.cloexec_fds.lock().contains(&fd)
+    }
+
     /// 在执行 `exec()` 时关闭标记为 `CLOEXEC` 的文件
     pub fn close_on_exec(&self) {
         let mut fd_table = self.fd_table.lock();
-        for (index, fd) in fd_table.iter_mut().enumerate() {
-            if let Some(f) = fd {
-                if f.get_status().is_close_on_exec() {
+        let mut cloexec_fds = self.cloexec_fds.lock();
+        for index in core::mem::take(&mut *cloexec_fds) {
+            if let Some(fd) = fd_table.get_mut(index) {
+                if fd.take().is_some() {
                     info!("close fd: {} on exec", index);
-                    fd.take();
                 }
             }
         }
+        drop(cloexec_fds);
         if fd_table[0].is_none() {
             fd_table[0] = Some(Arc::new(Stdin {
                 flags: Mutex::new(OpenFlags::empty()),
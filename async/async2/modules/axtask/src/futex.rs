@@ -0,0 +1,260 @@
+//! futex 的等待与唤醒
+//!
+//! 等待者以用户地址对应的物理地址为键登记，因此映射了同一物理页的不同进程之间也可以通过 futex 同步。
+//! 所有等待者都挂起在同一个等待队列上，被唤醒时检查自己的登记是否已被唤醒者取走，
+//! 这样 requeue 只需要在表中移动登记项，而不需要在等待队列之间迁移任务。
+extern crate alloc;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use axerrno::{AxError, AxResult};
+use axhal::time::TimeValue;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use spinlock::SpinNoIrq;
+
+use crate::{current, current_have_signals, UserPtr, WaitQueue, WakeupTimer};
+
+/// 匹配任意等待者的 bitset
+pub const FUTEX_BITSET_MATCH_ANY: u32 = u32::MAX;
+
+/// robust futex：有任务在等待该锁
+const FUTEX_WAITERS: u32 = 0x8000_0000;
+/// robust futex：持有锁的任务已经退出
+const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+/// robust futex：锁的值中记录持有者 tid 的部分
+const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
+/// 处理 robust list 时最多遍历的项数，防止用户构造出环
+const ROBUST_LIST_LIMIT: usize = 2048;
+
+/// set_robust_list 传入的 robust list 头部，由用户态维护
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RobustList {
+    /// 链表中第一项的地址，链表为空时指向头部自身
+    pub head: usize,
+    /// 锁的值相对于链表项的偏移
+    pub off: isize,
+    /// 正在加锁或解锁、尚未加入链表的项
+    pub pending: usize,
+}
+
+/// 一个正在等待的任务的登记项
+struct FutexWaiter {
+    /// 只有 bitset 与唤醒者给出的 bitset 相交时才会被唤醒
+    bitset: u32,
+    /// 是否已被唤醒
+    woken: AtomicBool,
+}
+
+/// 以物理地址为键的等待者表，会在等待队列的条件判断中被访问，因此使用自旋锁
+static FUTEX_TABLE: SpinNoIrq<BTreeMap<usize, VecDeque<Arc<FutexWaiter>>>> =
+    SpinNoIrq::new(BTreeMap::new());
+
+/// 所有 futex 等待者共用的等待队列
+static FUTEX_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+/// 获取用户地址对应的 futex 键，即其物理地址
+fn futex_key(uaddr: usize) -> AxResult<usize> {
+    let curr = current();
+    curr.manual_alloc_for_lazy(uaddr.into())?;
+    let (paddr, _, _) = curr.memory_set.lock().query(uaddr.into())?;
+    Ok(paddr.as_usize())
+}
+
+/// 读取用户地址处的 futex 值，调用者需保证该地址已被映射
+fn futex_value(uaddr: usize) -> &'static AtomicU32 {
+    unsafe { &*(uaddr as *const AtomicU32) }
+}
+
+/// 将登记项从表中移除，若已被唤醒者取走则返回 false
+fn remove_waiter(waiter: &Arc<FutexWaiter>) -> bool {
+    let mut table = FUTEX_TABLE.lock();
+    let mut found = false;
+    table.retain(|_, waiters| {
+        waiters.retain(|w| {
+            let same = Arc::ptr_eq(w, waiter);
+            found |= same;
+            !same
+        });
+        !waiters.is_empty()
+    });
+    found
+}
+
+/// 若 uaddr 处的值等于 expected，则挂起当前任务，直到被唤醒、超时或收到信号，挂起期间不占用内核栈
+///
+/// # Arguments
+/// * `uaddr`: 用户态 futex 的地址，需要四字节对齐
+/// * `expected`: 期望的值，与实际值不同时返回 `WouldBlock`
+/// * `deadline`: 等待的截止时间，为空时一直等待；超时返回 `Timeout`
+/// * `bitset`: 等待者的 bitset，不能为 0
+///
+/// 收到信号时返回 `Interrupted`
+pub async fn futex_wait(
+    uaddr: usize,
+    expected: u32,
+    deadline: Option<TimeValue>,
+    bitset: u32,
+) -> AxResult<()> {
+    let key = futex_key(uaddr)?;
+    let waiter = Arc::new(FutexWaiter {
+        bitset,
+        woken: AtomicBool::new(false),
+    });
+    {
+        let mut table = FUTEX_TABLE.lock();
+        // 持有表锁时比较，保证不会错过比较之后、登记之前发生的唤醒
+        if futex_value(uaddr).load(Ordering::SeqCst) != expected {
+            return Err(AxError::WouldBlock);
+        }
+        table.entry(key).or_default().push_back(Arc::clone(&waiter));
+    }
+    let _timer = deadline.map(WakeupTimer::new);
+    FUTEX_WAIT_QUEUE
+        .wait_until_async(|| {
+            waiter.woken.load(Ordering::Acquire)
+                || current_have_signals()
+                || deadline.is_some_and(|deadline| axhal::time::current_time() >= deadline)
+        })
+        .await;
+    // 登记项已被取走说明确实被唤醒，即使同时发生了超时或收到了信号
+    if waiter.woken.load(Ordering::Acquire) || !remove_waiter(&waiter) {
+        return Ok(());
+    }
+    if current_have_signals() {
+        Err(AxError::Interrupted)
+    } else {
+        Err(AxError::Timeout)
+    }
+}
+
+/// 从给定键的等待者中取出至多 count 个 bitset 匹配的等待者并标记为已唤醒
+fn wake_locked(
+    table: &mut BTreeMap<usize, VecDeque<Arc<FutexWaiter>>>,
+    key: usize,
+    count: usize,
+    bitset: u32,
+) -> usize {
+    let Some(waiters) = table.get_mut(&key) else {
+        return 0;
+    };
+    let mut woken = 0;
+    waiters.retain(|waiter| {
+        if woken < count && waiter.bitset & bitset != 0 {
+            waiter.woken.store(true, Ordering::Release);
+            woken += 1;
+            false
+        } else {
+            true
+        }
+    });
+    if waiters.is_empty() {
+        table.remove(&key);
+    }
+    woken
+}
+
+/// 唤醒至多 count 个在 uaddr 上等待且 bitset 匹配的任务，返回唤醒的任务数
+pub fn futex_wake(uaddr: usize, count: usize, bitset: u32) -> AxResult<usize> {
+    let key = futex_key(uaddr)?;
+    let woken = wake_locked(&mut FUTEX_TABLE.lock(), key, count, bitset);
+    if woken > 0 {
+        FUTEX_WAIT_QUEUE.notify_all(false);
+    }
+    Ok(woken)
+}
+
+/// 唤醒至多 wake_count 个在 uaddr 上等待的任务，并将其余等待者中的至多 requeue_count 个转移到 uaddr2 上
+///
+/// 若给出了 expected，则要求 uaddr 处的值与之相等，否则返回 `WouldBlock`。
+/// 返回唤醒与转移的任务数之和
+pub fn futex_requeue(
+    uaddr: usize,
+    wake_count: usize,
+    uaddr2: usize,
+    requeue_count: usize,
+    expected: Option<u32>,
+) -> AxResult<usize> {
+    let key = futex_key(uaddr)?;
+    let key2 = futex_key(uaddr2)?;
+    let mut table = FUTEX_TABLE.lock();
+    if expected.is_some_and(|expected| futex_value(uaddr).load(Ordering::SeqCst) != expected) {
+        return Err(AxError::WouldBlock);
+    }
+    let woken = wake_locked(&mut table, key, wake_count, FUTEX_BITSET_MATCH_ANY);
+    let mut requeued = 0;
+    if key != key2 {
+        if let Some(waiters) = table.get_mut(&key) {
+            let moved: VecDeque<_> = waiters.drain(..requeue_count.min(waiters.len())).collect();
+            if waiters.is_empty() {
+                table.remove(&key);
+            }
+            requeued = moved.len();
+            table.entry(key2).or_default().extend(moved);
+        }
+    }
+    drop(table);
+    if woken > 0 {
+        FUTEX_WAIT_QUEUE.notify_all(false);
+    }
+    Ok(woken + requeued)
+}
+
+/// 持有 robust futex 的任务退出时，标记锁的持有者已退出并唤醒一个等待者
+///
+/// 若该地址无法访问则返回 false，此时应停止遍历 robust list
+fn handle_futex_death(uaddr: usize, tid: u32) -> bool {
+    if uaddr % 4 != 0 || current().manual_alloc_for_lazy(uaddr.into()).is_err() {
+        return false;
+    }
+    let futex = futex_value(uaddr);
+    let mut value = futex.load(Ordering::SeqCst);
+    while value & FUTEX_TID_MASK == tid {
+        let new_value = (value & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
+        match futex.compare_exchange(value, new_value, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => {
+                if value & FUTEX_WAITERS != 0 {
+                    let _ = futex_wake(uaddr, 1, FUTEX_BITSET_MATCH_ANY);
+                }
+                break;
+            }
+            Err(current_value) => value = current_value,
+        }
+    }
+    true
+}
+
+/// 读取用户地址处的一个值
+fn read_user<T: Copy>(addr: usize) -> Option<T> {
+    UserPtr::<T>::new(addr).read().ok()
+}
+
+/// 当前任务退出时处理其 robust list 上仍然持有的锁
+pub fn exit_robust_list() {
+    let curr = current();
+    let head_addr = curr.get_robust_list();
+    let tid = curr.tid() as u32;
+    if head_addr == 0 {
+        return;
+    }
+    let Some(head) = read_user::<RobustList>(head_addr) else {
+        return;
+    };
+    // 链表项的最低位用于标记优先级继承锁，需要去掉
+    let pending = head.pending & !1;
+    let mut entry = head.head & !1;
+    let mut limit = ROBUST_LIST_LIMIT;
+    while entry != head_addr && limit > 0 {
+        // 先读出下一项，当前项所在的内存可能在锁被释放后被回收
+        let Some(next) = read_user::<usize>(entry) else {
+            break;
+        };
+        if entry != pending && !handle_futex_death(entry.wrapping_add_signed(head.off), tid) {
+            break;
+        }
+        entry = next & !1;
+        limit -= 1;
+    }
+    if pending != 0 {
+        handle_futex_death(pending.wrapping_add_signed(head.off), tid);
+    }
+}
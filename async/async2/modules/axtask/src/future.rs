@@ -1,12 +1,119 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::task::Wake;
 use core::{
-    future::Future,
-    pin::Pin,
-    task::{Context, Poll},
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
 };
 
+use axhal::time::TimeValue;
+
 use crate::AxTaskRef;
 use crate::RUN_QUEUE;
 
+/// 系统调用分发得到的 future，输出为写回用户 a0 的返回值
+pub type SyscallFuture = Pin<Box<dyn Future<Output = isize> + Send>>;
+
+/// 挂起当前任务直到 check 返回 `Some`，返回其中的值
+///
+/// 每次轮询时先把 waker 记录在当前任务上再检查，信号的发送、子进程的退出、[`WakeupTimer`] 的到期
+/// 以及 [`crate::WaitQueue`] 的通知都通过它唤醒任务，因此检查期间发生的事件不会被错过。
+/// 挂起期间任务不占用内核栈
+pub async fn wait_for<T>(mut check: impl FnMut() -> Option<T>) -> T {
+    poll_fn(|cx| {
+        let curr = crate::current();
+        curr.register_waker(cx.waker());
+        match check() {
+            Some(value) => {
+                curr.clear_waker();
+                Poll::Ready(value)
+            }
+            None => Poll::Pending,
+        }
+    })
+    .await
+}
+
+/// 挂起当前任务直到 condition 成立，唤醒方式见 [`wait_for`]
+pub async fn wait_until(mut condition: impl FnMut() -> bool) {
+    wait_for(|| condition().then_some(())).await
+}
+
+/// 在截止时间唤醒设置它的任务，使挂起的系统调用重新检查是否超时，被释放时取消
+///
+/// 需要开启时钟中断，否则不会唤醒
+pub struct WakeupTimer {
+    #[cfg(feature = "irq")]
+    task: AxTaskRef,
+}
+
+impl WakeupTimer {
+    /// 在 deadline 唤醒当前任务
+    pub fn new(deadline: TimeValue) -> Self {
+        #[cfg(feature = "irq")]
+        {
+            let task = crate::current().clone();
+            crate::timers::set_syscall_wakeup(deadline, task.clone());
+            Self { task }
+        }
+        #[cfg(not(feature = "irq"))]
+        {
+            let _ = deadline;
+            Self {}
+        }
+    }
+}
+
+impl Drop for WakeupTimer {
+    fn drop(&mut self) {
+        #[cfg(feature = "irq")]
+        crate::timers::cancel_syscall_wakeup(&self.task);
+    }
+}
+
+/// [`block_on`] 使用的 waker，被唤醒时解除任务的阻塞
+struct BlockOnWaker {
+    task: AxTaskRef,
+    /// 上一次轮询之后是否被唤醒过，用于避免在轮询与阻塞之间错过唤醒
+    woken: AtomicBool,
+}
+
+impl Wake for BlockOnWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        // 阻塞的任务在持有 RUN_QUEUE 的锁时检查 woken，因此这里不会错过正在阻塞的任务
+        RUN_QUEUE.lock().unblock_task(self.task.clone(), false);
+    }
+}
+
+/// 在当前任务的内核栈上同步执行 future，未就绪时阻塞当前任务直到 waker 被唤醒
+///
+/// 用于没有运行在执行器中的陷入处理路径
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let state = Arc::new(BlockOnWaker {
+        task: crate::current().clone(),
+        woken: AtomicBool::new(false),
+    });
+    let waker = Waker::from(Arc::clone(&state));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(ret) = future.as_mut().poll(&mut cx) {
+            return ret;
+        }
+        let mut rq = RUN_QUEUE.lock();
+        if !state.woken.swap(false, Ordering::AcqRel) {
+            rq.block_current(|_| {});
+        }
+    }
+}
+
 pub struct YieldFuture(pub bool);
 
 impl Future for YieldFuture {
@@ -61,6 +168,11 @@ impl<F: Future + Send + 'static> Future for UserTaskFuture<F> {
         // a Mutex<Pin<Box<>>>>, which requires locking for every polling
         let ret = unsafe { Pin::new_unchecked(&mut this.task_future).poll(cx) };
 
+        if ret.is_pending() {
+            // 任务挂起在系统调用中，把内核栈还给堆栈池，下次运行时重新分配
+            RUN_QUEUE.lock().release_stack(&this.task);
+        }
+
         ret
     }
 }
@@ -48,8 +48,9 @@ pub use self::sync::mutex::{Mutex, MutexGuard};
 pub use taskctx::TaskId;
 
 mod stdio;
+mod tty;
 
-pub use taskctx::{SchedPolicy, SchedStatus, TaskState};
+pub use taskctx::{SchedPolicy, SchedStatus, TaskState, TimerType};
 
 #[cfg(feature = "irq")]
 mod timers;
@@ -61,6 +62,15 @@ pub use self::api::{sleep, sleep_until, yield_now};
 pub mod signal;
 pub use signal::*;
 
+pub mod futex;
+pub use futex::*;
+
+pub mod poll;
+pub use poll::*;
+
+pub mod rlimit;
+pub use rlimit::*;
+
 pub mod fd_manager;
 pub use fd_manager::*;
 
@@ -70,6 +80,9 @@ pub use flags::*;
 pub mod link;
 pub use link::*;
 
+pub mod uaccess;
+pub use uaccess::*;
+
 pub mod ctx;
 pub use ctx::*;
 
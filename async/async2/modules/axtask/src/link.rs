@@ -10,6 +10,7 @@ use axlog::{debug, info, trace};
 use crate::Mutex;
 
 use crate::current;
+use crate::UserPtr;
 #[allow(unused)]
 /// The file descriptor used to specify the current working directory of a process
 pub const AT_FDCWD: usize = -100isize as usize;
@@ -30,11 +31,24 @@ impl FilePath {
             // 如果原始路径以 '/' 结尾，那么canonicalize后的路径也应该以 '/' 结尾
             new_path.push('/');
         }
+        let new_path = follow_symlinks(new_path, true);
         let new_path = real_path(&new_path);
         // assert!(!path.ends_with("/"), "path should not end with '/', link only support file");      // 链接只支持文件
         Ok(Self(new_path))
     }
 
+    /// 创建一个 FilePath，但不解析路径最后一级的符号链接与硬链接
+    ///
+    /// 用于 unlink、rename、readlink 等需要操作链接本身的场合
+    pub fn new_nofollow(path: &str) -> AxResult<Self> {
+        let new_path = canonicalize(path).map_err(|_| AxError::NotFound)?;
+        let mut new_path = String::from(new_path.trim());
+        if path.ends_with('/') && !new_path.ends_with('/') {
+            new_path.push('/');
+        }
+        Ok(Self(follow_symlinks(new_path, false)))
+    }
+
     /// 获取路径
     pub fn path(&self) -> &str {
         &self.0
@@ -150,6 +164,102 @@ pub static LINK_PATH_MAP: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap:
 /// 实际文件(而不是用户文件)到链接数的映射
 pub static LINK_COUNT_MAP: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
 
+/// 符号链接到其内容的映射，键为链接所在的路径（不以 '/' 结尾）
+///
+/// fat32 不支持符号链接，因此符号链接在文件系统中以一个内容为目标路径的普通文件占位，
+/// 使其可以被 getdents 列出
+pub static SYMLINK_MAP: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// 解析一条路径时最多跟随的符号链接数，超过时认为出现了循环链接
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// 解析路径中各级目录的符号链接
+///
+/// 若 `follow_last` 为真，路径的最后一级若为符号链接也会被解析
+fn follow_symlinks(mut path: String, follow_last: bool) -> String {
+    for _ in 0..MAX_SYMLINK_DEPTH {
+        let map = SYMLINK_MAP.lock();
+        if map.is_empty() {
+            break;
+        }
+        let trailing_slash = path.ends_with('/');
+        let trimmed = path.trim_end_matches('/');
+        // 从短到长依次检查路径的每一级前缀
+        let mut resolved = None;
+        let ends = trimmed
+            .match_indices('/')
+            .map(|(idx, _)| idx)
+            .filter(|&idx| idx > 0)
+            .chain(core::iter::once(trimmed.len()));
+        for end in ends {
+            let prefix = &trimmed[..end];
+            if end == trimmed.len() && !follow_last && !trailing_slash {
+                break;
+            }
+            if let Some(target) = map.get(prefix) {
+                let base = if target.starts_with('/') {
+                    target.clone()
+                } else {
+                    // 相对路径的符号链接相对于链接所在目录
+                    let parent = &prefix[..=prefix.rfind('/').unwrap_or(0)];
+                    format!("{}{}", parent, target)
+                };
+                let rest = &trimmed[end..];
+                let mut new_path = format!("{}{}", base.trim_end_matches('/'), rest);
+                if trailing_slash {
+                    new_path.push('/');
+                }
+                resolved = Some(new_path);
+                break;
+            }
+        }
+        drop(map);
+        match resolved {
+            Some(new_path) => {
+                path = match canonicalize(new_path.as_str()) {
+                    Ok(canonical) if new_path.ends_with('/') && !canonical.ends_with('/') => {
+                        canonical + "/"
+                    }
+                    Ok(canonical) => canonical,
+                    Err(_) => return new_path,
+                };
+            }
+            None => break,
+        }
+    }
+    path
+}
+
+/// 创建一个符号链接
+///
+/// 需要保证链接所在路径尚不存在
+pub fn create_symlink(link_path: &FilePath, target: &str) -> AxResult<()> {
+    info!("create_symlink: {} -> {}", link_path.path(), target);
+    let link = link_path.path().trim_end_matches('/');
+    axfs::api::write(link, target.as_bytes())?;
+    SYMLINK_MAP
+        .lock()
+        .insert(link.to_string(), target.to_string());
+    Ok(())
+}
+
+/// 获取符号链接的内容，若该路径不是符号链接则返回 None
+pub fn read_symlink(link_path: &FilePath) -> Option<String> {
+    SYMLINK_MAP
+        .lock()
+        .get(link_path.path().trim_end_matches('/'))
+        .cloned()
+}
+
+/// 删除一个符号链接记录，返回其内容
+///
+/// 占位文件需要由调用者删除
+pub fn remove_symlink(link_path: &FilePath) -> Option<String> {
+    SYMLINK_MAP
+        .lock()
+        .remove(link_path.path().trim_end_matches('/'))
+}
+
 /// 将用户提供的路径转换成实际的路径
 ///
 /// 如果在链接列表中找不到，则直接返回自己
@@ -297,6 +407,25 @@ pub fn deal_with_path(
     dir_fd: usize,
     path_addr: Option<*const u8>,
     force_dir: bool,
+) -> Option<FilePath> {
+    deal_with_path_inner(dir_fd, path_addr, force_dir, true)
+}
+
+/// The same as [`deal_with_path`], but the last component of the path will not be resolved
+/// if it is a symbolic link or a hard link
+pub fn deal_with_path_nofollow(
+    dir_fd: usize,
+    path_addr: Option<*const u8>,
+    force_dir: bool,
+) -> Option<FilePath> {
+    deal_with_path_inner(dir_fd, path_addr, force_dir, false)
+}
+
+fn deal_with_path_inner(
+    dir_fd: usize,
+    path_addr: Option<*const u8>,
+    force_dir: bool,
+    follow: bool,
 ) -> Option<FilePath> {
     let task=current();
     let mut path = "".to_string();
@@ -305,15 +434,12 @@ pub fn deal_with_path(
             axlog::warn!("path address is null");
             return None;
         }
-        if task
-            .manual_alloc_for_lazy((path_addr as usize).into())
-            .is_ok()
-        {
-            // 直接访问前需要确保已经被分配
-            path = unsafe { raw_ptr_to_ref_str(path_addr) }.to_string().clone();
-        } else {
-            axlog::warn!("path address is invalid");
-            return None;
+        match UserPtr::from(path_addr).read_cstr() {
+            Ok(user_path) => path = user_path,
+            Err(_) => {
+                axlog::warn!("path address is invalid");
+                return None;
+            }
         }
     }
 
@@ -323,7 +449,7 @@ pub fn deal_with_path(
         // If dirfd is AT_FDCWD, the call operates on the current working directory.
         if dir_fd == AT_FDCWD && dir_fd as u32 == AT_FDCWD as u32 {
             // return Some(FilePath::new(".").unwrap());
-            path = String::from(".");
+            path = task.get_cwd();
         } else {
             let fd_table = task.fd_manager.fd_table.lock();
             if dir_fd >= fd_table.len() {
@@ -341,7 +467,10 @@ pub fn deal_with_path(
                 }
             }
         }
-    } else if !path.starts_with('/') && dir_fd != AT_FDCWD && dir_fd as u32 != AT_FDCWD as u32 {
+    } else if !path.starts_with('/') && (dir_fd == AT_FDCWD || dir_fd as u32 == AT_FDCWD as u32) {
+        // 相对路径相对于当前任务的工作目录，工作目录总是以 '/' 结尾
+        path = format!("{}{}", task.get_cwd(), path);
+    } else if !path.starts_with('/') {
         // 如果不是绝对路径, 且dir_fd不是AT_FDCWD, 则需要将dir_fd和path拼接起来
         let fd_table = task.fd_manager.fd_table.lock();
         if dir_fd >= fd_table.len() {
@@ -354,9 +483,12 @@ pub fn deal_with_path(
                     axlog::warn!("selected fd {} is not a dir", dir_fd);
                     return None;
                 }
-                let dir = dir.clone();
-                // 有没有可能dir的尾部一定是一个/号，所以不用手工添加/
-                path = format!("{}{}", dir.get_path(), path);
+                let dir_path = dir.get_path();
+                path = if dir_path.ends_with('/') {
+                    format!("{}{}", dir_path, path)
+                } else {
+                    format!("{}/{}", dir_path, path)
+                };
                 axlog::debug!("handled_path: {}", path);
             }
             None => {
@@ -372,7 +504,12 @@ pub fn deal_with_path(
         // 如果path以.或..结尾, 则加上/告诉FilePath::new它是一个目录
         path = format!("{}/", path);
     }
-    match FilePath::new(path.as_str()) {
+    let file_path = if follow {
+        FilePath::new(path.as_str())
+    } else {
+        FilePath::new_nofollow(path.as_str())
+    };
+    match file_path {
         Ok(path) => Some(path),
         Err(err) => {
             axlog::warn!("error when creating FilePath: {:?}", err);
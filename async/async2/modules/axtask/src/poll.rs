@@ -0,0 +1,72 @@
+//! I/O 多路复用的等待与唤醒
+//!
+//! 文件的就绪状态发生变化时调用 [`notify_pollers`]，将全局的事件计数加一并唤醒所有阻塞在
+//! poll、select、epoll 与文件读写上的任务。等待者在检查文件之前记下事件计数，检查完没有就绪的文件后
+//! 再以该计数挂起，因此不会错过检查期间发生的变化；被唤醒后由调用者重新检查所关心的文件。
+extern crate alloc;
+use alloc::sync::Weak;
+use axerrno::{AxError, AxResult};
+use axhal::time::TimeValue;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{current_have_signals, WaitQueue, WakeupTimer};
+
+/// 文件就绪状态变化的次数
+static POLL_EVENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 所有 poll 等待者共用的等待队列
+static POLL_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+/// 获取当前的事件计数，应在检查文件的就绪状态之前调用
+pub fn poll_event_count() -> usize {
+    POLL_EVENT_COUNT.load(Ordering::Acquire)
+}
+
+/// 文件的就绪状态可能发生了变化，唤醒所有 poll 等待者重新检查
+pub fn notify_pollers() {
+    POLL_EVENT_COUNT.fetch_add(1, Ordering::AcqRel);
+    POLL_WAIT_QUEUE.notify_all(false);
+}
+
+/// 在 deadline 时调用 [`notify_pollers`]，interval 不为 0 时此后每隔 interval 调用一次
+///
+/// owner 被释放后不再调用，因此重新设置时只需替换 owner。未开启时钟中断时不会调用。
+pub fn notify_pollers_at(deadline: TimeValue, interval: TimeValue, owner: Weak<()>) {
+    #[cfg(feature = "irq")]
+    crate::timers::set_poller_wakeup(deadline, interval, owner);
+    #[cfg(not(feature = "irq"))]
+    let _ = (deadline, interval, owner);
+}
+
+/// 挂起当前任务，直到事件计数不再等于 seen、到达截止时间或收到信号，挂起时不占用内核栈
+///
+/// # Arguments
+/// * `seen`: 检查文件之前通过 [`poll_event_count`] 获取的事件计数
+/// * `deadline`: 等待的截止时间，为空时一直等待；超时返回 `Timeout`，截止时间由定时器唤醒
+///
+/// 收到信号时返回 `Interrupted`
+pub async fn poll_wait_async(seen: usize, deadline: Option<TimeValue>) -> AxResult<()> {
+    let _timer = deadline.map(WakeupTimer::new);
+    POLL_WAIT_QUEUE
+        .wait_until_async(|| poll_wait_done(seen, deadline))
+        .await;
+    poll_wait_result(seen)
+}
+
+/// 等待是否可以结束
+fn poll_wait_done(seen: usize, deadline: Option<TimeValue>) -> bool {
+    poll_event_count() != seen
+        || current_have_signals()
+        || deadline.is_some_and(|deadline| axhal::time::current_time() >= deadline)
+}
+
+/// 等待结束的原因
+fn poll_wait_result(seen: usize) -> AxResult<()> {
+    if poll_event_count() != seen {
+        Ok(())
+    } else if current_have_signals() {
+        Err(AxError::Interrupted)
+    } else {
+        Err(AxError::Timeout)
+    }
+}
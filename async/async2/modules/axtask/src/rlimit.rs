@@ -0,0 +1,99 @@
+//! 进程的资源上限
+//!
+//! 上限在 fork 时被子进程继承，在 exec 时保持不变。
+//! 目前实际生效的上限有 RLIMIT_STACK、RLIMIT_NOFILE 与 RLIMIT_AS，其余上限只被记录
+use axconfig::MAX_USER_STACK_SIZE;
+use axerrno::{AxError, AxResult};
+use axhal::mem::PAGE_SIZE_4K;
+
+/// 表示没有上限
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+/// 用户栈大小
+pub const RLIMIT_STACK: i32 = 3;
+/// 可以打开的 fd 数
+pub const RLIMIT_NOFILE: i32 = 7;
+/// 用户地址空间的最大大小
+pub const RLIMIT_AS: i32 = 9;
+/// 资源的种类数
+pub const RLIM_NLIMITS: usize = 16;
+
+/// 初始时可以打开的 fd 数
+pub const NOFILE_DEFAULT: u64 = 1025;
+/// 可以打开的 fd 数的硬上限的最大值，防止文件描述符表无限增长
+pub const NOFILE_MAX: u64 = 1 << 20;
+
+/// 一种资源的上限
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RLimit {
+    /// 软上限
+    pub rlim_cur: u64,
+    /// 硬上限
+    pub rlim_max: u64,
+}
+
+impl RLimit {
+    /// 没有上限
+    pub const INFINITY: Self = Self::new(RLIM_INFINITY, RLIM_INFINITY);
+
+    /// 以给定的软上限与硬上限创建
+    pub const fn new(rlim_cur: u64, rlim_max: u64) -> Self {
+        Self { rlim_cur, rlim_max }
+    }
+}
+
+/// 一个进程的全部资源上限
+#[derive(Clone)]
+pub struct ResourceLimits {
+    limits: [RLimit; RLIM_NLIMITS],
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        let mut limits = [RLimit::INFINITY; RLIM_NLIMITS];
+        limits[RLIMIT_STACK as usize] = RLimit::new(MAX_USER_STACK_SIZE as u64, RLIM_INFINITY);
+        limits[RLIMIT_NOFILE as usize] = RLimit::new(NOFILE_DEFAULT, NOFILE_MAX);
+        Self { limits }
+    }
+}
+
+impl ResourceLimits {
+    /// 获取指定资源的上限，资源不存在时返回 `InvalidInput`
+    pub fn get(&self, resource: i32) -> AxResult<RLimit> {
+        usize::try_from(resource)
+            .ok()
+            .and_then(|index| self.limits.get(index))
+            .copied()
+            .ok_or(AxError::InvalidInput)
+    }
+
+    /// 设置指定资源的上限
+    ///
+    /// 软上限不能超过硬上限，否则返回 `InvalidInput`
+    pub fn set(&mut self, resource: i32, limit: RLimit) -> AxResult<()> {
+        if limit.rlim_cur > limit.rlim_max {
+            return Err(AxError::InvalidInput);
+        }
+        if resource == RLIMIT_NOFILE && limit.rlim_max > NOFILE_MAX {
+            return Err(AxError::PermissionDenied);
+        }
+        let index = usize::try_from(resource).map_err(|_| AxError::InvalidInput)?;
+        *self.limits.get_mut(index).ok_or(AxError::InvalidInput)? = limit;
+        Ok(())
+    }
+
+    /// 加载程序时用户栈的大小，不超过用户栈区域的最大大小
+    pub fn stack_size(&self) -> usize {
+        let limit = self.limits[RLIMIT_STACK as usize].rlim_cur;
+        (limit.min(MAX_USER_STACK_SIZE as u64) as usize) & !(PAGE_SIZE_4K - 1)
+    }
+
+    /// 用户地址空间的最大大小
+    pub fn address_space_limit(&self) -> usize {
+        self.limits[RLIMIT_AS as usize]
+            .rlim_cur
+            .try_into()
+            .unwrap_or(usize::MAX)
+    }
+}
@@ -146,6 +146,34 @@ impl AxRunQueue {
         axlog::warn!("Now stack pool: {:?}", self.kstack_state);
     }
 
+    /// 为任务从堆栈池里分配一个内核栈，任务陷入内核时使用这个栈
+    fn start_task(&mut self, task: &AxTaskRef) {
+        let idx = self.alloc_stack();
+        self.cur_stack = idx;
+        task.inner.lock().is_started = true;
+        task.inner.lock().stack_idx = idx;
+        let kstack_top = self.kstack[idx].top();
+        task.tf.lock().kernel_sp = kstack_top.as_usize();
+        //然后我们需要将需要运行的trapframe写入到执行器的kstack中
+        write_trapframe_to_kstack(self.get_idx_kernel_stack_top(idx), &task.get_tf());
+    }
+
+    /// 归还任务持有的内核栈
+    ///
+    /// 在系统调用中挂起的任务的状态都保存在它的 future 与 trapframe 中，不需要占用内核栈，
+    /// 只有被抢占的任务需要一直持有栈
+    pub fn release_stack(&mut self, task: &AxTaskRef) {
+        let idx = {
+            let mut inner = task.inner.lock();
+            if !inner.is_started {
+                return;
+            }
+            inner.is_started = false;
+            inner.stack_idx
+        };
+        self.free_stack(idx);
+    }
+
     pub fn get_idx_kernel_stack_top(&self, idx: usize) -> usize {
         self.kstack[idx].top().as_usize()
     }
@@ -293,6 +321,11 @@ impl AxRunQueue {
 impl AxRunQueue {
     pub fn run_task(&mut self, tid: u64) {
         self.resched(false, tid);
+        // 挂起后恢复的任务若仍是当前任务，switch_to 不会为它重新分配栈
+        let curr = crate::current();
+        if !curr.is_kernel_task() && !curr.inner.lock().is_started {
+            self.start_task(curr.as_task_ref());
+        }
     }
 
     pub fn preempt_yield(&mut self, cid: u64) {
@@ -474,15 +507,8 @@ impl AxRunQueue {
                         ttr = in(reg) ttr,
                     );
                 } else {
-                    //没有开始过，我们需要开始，从堆栈池里拿一个栈出来
-                    let idx = self.alloc_stack();
-                    self.cur_stack = idx;
-                    next_task.inner.lock().is_started = true;
-                    next_task.inner.lock().stack_idx = idx;
-                    let kstack_top = self.kstack[idx].top();
-                    next_task.tf.lock().kernel_sp = kstack_top.as_usize();
-                    //然后我们需要将需要运行的trapframe写入到执行器的kstack中
-                    write_trapframe_to_kstack(self.get_idx_kernel_stack_top(idx), &n_tf);
+                    //没有开始过或在系统调用中挂起过，我们需要开始，从堆栈池里拿一个栈出来
+                    self.start_task(&next_task);
                     //最后，设置当前指针，然后切换到新的任务
                     CurrentTask::set_current(prev_task, next_task);
                 }
@@ -1,22 +1,47 @@
 //! 负责处理进程中与信号相关的内容
+//!
+//! 信号在任务即将从 trap 返回用户态时被处理：默认行为直接在内核中完成，
+//! 用户注册的处理函数则通过改写任务保存的用户上下文来调用，处理函数返回时经由信号跳板调用 sigreturn 恢复原上下文。
 extern crate alloc;
 use crate::Mutex;
 use crate::{TaskState, RUN_QUEUE};
 use alloc::sync::Arc;
-use axerrno::{AxError, AxResult};
-use axhal::arch::{read_trapframe_from_kstack, write_trapframe_to_kstack, TrapFrame};
-use axsignal::{ucontext::SignalUserContext, SignalHandler, SignalSet};
+use alloc::vec::Vec;
+use axerrno::{AxError, AxResult, LinuxError};
+use axhal::arch::TrapFrame;
+use axsignal::{
+    action::{SigAction, SigActionFlags, SignalDefault, SIG_IGN},
+    info::SigInfo,
+    signal_no::SignalNo,
+    ucontext::{SignalStack, SignalUserContext, SS_DISABLE},
+    SignalHandler, SignalSet,
+};
+
+/// 进入信号处理函数前保存的上下文，在 sigreturn 时恢复
+#[derive(Clone, Copy)]
+pub struct SignalFrame {
+    /// 被信号打断时的trap上下文
+    pub trap_frame: TrapFrame,
+    /// 写入用户栈的 SignalUserContext 的地址
+    pub ucontext_addr: usize,
+    /// 是否存在siginfo，此时以用户可能修改过的 ucontext 中的 pc 为准
+    pub sig_info: bool,
+}
 
 /// 信号处理模块，进程间不共享
 pub struct SignalModule {
-    /// 是否存在siginfo
-    pub sig_info: bool,
-    /// 保存的trap上下文
-    pub last_trap_frame_for_signal: Option<TrapFrame>,
+    /// 正在执行的信号处理函数对应的上下文，处理函数嵌套时按顺序压栈
+    pub signal_frames: Vec<SignalFrame>,
     /// 信号处理函数集
     pub signal_handler: Arc<Mutex<SignalHandler>>,
     /// 未决信号集
     pub signal_set: SignalSet,
+    /// 由 sigaltstack 设置的备用信号栈
+    pub alternate_stack: SignalStack,
+    /// 被 ppoll、pselect6 等系统调用临时替换前的信号掩码，在返回用户态处理完信号后恢复
+    pub saved_mask: Option<usize>,
+    /// 是否被停止信号停止，返回用户态之前需要挂起直到收到 SIGCONT 或 SIGKILL
+    pub stopped: bool,
 }
 
 impl SignalModule {
@@ -25,75 +50,299 @@ impl SignalModule {
         let signal_handler =
             signal_handler.unwrap_or_else(|| Arc::new(Mutex::new(SignalHandler::new())));
         let signal_set = SignalSet::new();
-        let last_trap_frame_for_signal = None;
-        let sig_info = false;
         Self {
-            sig_info,
-            last_trap_frame_for_signal,
+            signal_frames: Vec::new(),
             signal_handler,
             signal_set,
+            alternate_stack: SignalStack::default(),
+            saved_mask: None,
+            stopped: false,
         }
     }
+
+    /// 给定的用户栈地址是否位于备用信号栈上
+    pub fn on_alternate_stack(&self, sp: usize) -> bool {
+        let stack = &self.alternate_stack;
+        stack.flags & SS_DISABLE == 0 && sp > stack.sp && sp <= stack.sp + stack.size
+    }
 }
 
-use crate::{current_task,task::TID2TASK};
+use crate::{clear_user, current_task, exit_current_task, task::TID2TASK, UserPtr};
 
-/// 将保存的trap上下文填入内核栈中
-///
-/// 若使用了SIG_INFO，此时会对原有trap上下文作一定修改。
+/// siginfo_t 在用户栈上占用的大小
+const SIGINFO_SIZE: usize = 128;
+
+/// 系统调用指令的长度，重新执行被打断的系统调用时需要将 pc 回退这一长度
+#[cfg(target_arch = "x86_64")]
+const SYSCALL_INSN_LEN: usize = 2;
+#[cfg(not(target_arch = "x86_64"))]
+const SYSCALL_INSN_LEN: usize = 4;
+
+/// 信号对应的位
+fn signal_bit(sig_num: usize) -> usize {
+    1 << (sig_num - 1)
+}
+
+/// 不能被屏蔽、捕获或忽略的信号
+fn unblockable_mask() -> usize {
+    signal_bit(SignalNo::SIGKILL as usize) | signal_bit(SignalNo::SIGSTOP as usize)
+}
+
+/// 使任务回到系统调用指令处，返回用户态后重新执行该系统调用
+fn restart_syscall(tf: &mut TrapFrame, syscall_arg0: usize) {
+    tf.set_pc(tf.get_pc() - SYSCALL_INSN_LEN);
+    tf.set_arg0(syscall_arg0);
+}
+
+/// 以给定信号终止当前进程
+fn terminate_current_process(sig_num: usize, core_dump: bool) {
+    let status = sig_num as i32 | if core_dump { 0x80 } else { 0 };
+    warn!(
+        "task {} is terminated by signal {}",
+        current_task().tid(),
+        sig_num
+    );
+    exit_current_group(0, status);
+}
+
+/// 结束当前线程组并退出当前线程
 ///
-/// 若确实存在可以被恢复的trap上下文，则返回true
-#[no_mangle]
-pub fn load_trap_for_signal() -> bool {
-    let current_task = current_task();
-
-    let mut signal_modules = current_task.signal_modules.lock();
-    let signal_module = signal_modules.get_mut(&current_task.tid()).unwrap();
-    if let Some(old_trap_frame) = signal_module.last_trap_frame_for_signal.take() {
-        unsafe {
-            // let now_trap_frame: *mut TrapFrame = current_task.get_first_trap_frame();
-            let mut now_trap_frame =
-                read_trapframe_from_kstack(RUN_QUEUE.lock().get_kernel_stack_top());
-            // 考虑当时调用信号处理函数时，sp对应的地址上的内容即是SignalUserContext
-            // 此时认为一定通过sig_return调用这个函数
-            // 所以此时sp的位置应该是SignalUserContext的位置
-            let sp = now_trap_frame.get_sp();
-            now_trap_frame = old_trap_frame;
-            if signal_module.sig_info {
-                let pc = (*(sp as *const SignalUserContext)).get_pc();
-                now_trap_frame.set_pc(pc);
-            }
-            write_trapframe_to_kstack(RUN_QUEUE.lock().get_kernel_stack_top(), &now_trap_frame);
+/// 进程的退出状态记录在主线程上，只有第一个结束线程组的线程记录的状态有效，term_signal 为 0 时表示以
+/// exit_code 正常退出。线程组内的其他线程会收到 SIGKILL，并在下一次返回用户态前退出
+pub fn exit_current_group(exit_code: i32, term_signal: i32) {
+    let curr = current_task();
+    let tid = curr.tid();
+    let process_id = curr.get_process_id();
+    let threads: Vec<_> = TID2TASK
+        .lock()
+        .values()
+        .filter(|task| task.get_process_id() == process_id)
+        .cloned()
+        .collect();
+    let leader = threads.iter().find(|task| task.tid() == process_id);
+    let first = leader.map_or(true, |leader| {
+        leader.start_group_exit(exit_code, term_signal)
+    });
+    if first {
+        for task in threads.iter().filter(|task| task.tid() != tid) {
+            let _ = send_signal_to_thread(task.tid() as isize, SignalNo::SIGKILL as isize);
         }
-        true
-    } else {
-        false
     }
+    // 各线程都以主线程上记录的退出码退出，避免主线程被 SIGKILL 结束时覆盖进程的退出状态
+    let exit_code = leader.map_or(exit_code, |leader| leader.get_exit_code());
+    drop(threads);
+    drop(curr);
+    exit_current_task(exit_code);
 }
 
+/// 在当前线程的信号模块上执行给定的操作，线程没有信号模块时返回 None
+fn with_current_module<R>(f: impl FnOnce(&mut SignalModule) -> R) -> Option<R> {
+    let curr = current_task();
+    let tid = curr.tid();
+    let mut signal_modules = curr.signal_modules.lock();
+    signal_modules.get_mut(&tid).map(f)
+}
 
-/// 处理当前进程的信号
+/// 标记当前任务被停止，任务在返回用户态之前由 wait_while_stopped 挂起
+fn stop_current() {
+    with_current_module(|module| module.stopped = true);
+}
+
+/// 若当前任务被停止信号停止，则挂起直到收到 SIGCONT 或 SIGKILL，之后处理收到的信号
+///
+/// 在处理完信号、即将返回用户态之前调用。挂起期间不占用内核栈，发送信号时会唤醒任务的 waker
+pub async fn wait_while_stopped() {
+    let wake_mask = signal_bit(SignalNo::SIGCONT as usize) | signal_bit(SignalNo::SIGKILL as usize);
+    while with_current_module(|module| module.stopped).unwrap_or(false) {
+        crate::wait_until(|| {
+            with_current_module(|module| module.signal_set.pending & wake_mask != 0).unwrap_or(true)
+        })
+        .await;
+        with_current_module(|module| module.stopped = false);
+        // 递送 SIGCONT 或 SIGKILL，期间可能再次被停止
+        handle_signals(None);
+    }
+}
+
+/// 在用户栈上构造信号处理函数的栈帧，并修改用户上下文使得返回用户态时进入处理函数
 ///
-/// 若返回值为真，代表需要进入处理信号，因此需要执行trap的返回
-pub fn handle_signals() {}
+/// 返回修改后的用户上下文，若用户栈不可写则返回错误
+fn setup_signal_frame(
+    signal_module: &mut SignalModule,
+    sig_num: usize,
+    action: &SigAction,
+    old_tf: TrapFrame,
+) -> AxResult<TrapFrame> {
+    let old_sp = old_tf.get_sp();
+    let use_alternate_stack = action.sa_flags.contains(SigActionFlags::SA_ONSTACK)
+        && signal_module.alternate_stack.flags & SS_DISABLE == 0
+        && !signal_module.on_alternate_stack(old_sp);
+    let stack_top = if use_alternate_stack {
+        signal_module.alternate_stack.sp + signal_module.alternate_stack.size
+    } else {
+        old_sp
+    };
+    let ucontext_addr = (stack_top - core::mem::size_of::<SignalUserContext>()) & !0xf;
+    let info_addr = (ucontext_addr - SIGINFO_SIZE) & !0xf;
+
+    let old_mask = signal_module.signal_set.mask;
+    // 临时替换过信号掩码时，处理函数返回后应恢复替换前的掩码
+    let restore_mask = signal_module.saved_mask.take().unwrap_or(old_mask);
+    UserPtr::<SignalUserContext>::new(ucontext_addr)
+        .write(SignalUserContext::init(old_tf.get_pc(), restore_mask))?;
+    clear_user(info_addr, SIGINFO_SIZE)?;
+    UserPtr::<SigInfo>::new(info_addr).write(signal_module.signal_set.info(sig_num))?;
+
+    let mut tf = old_tf;
+    tf.set_pc(action.sa_handler);
+    tf.set_user_sp(info_addr);
+    tf.set_ra(action.get_storer().unwrap_or(axconfig::SIGNAL_TRAMPOLINE));
+    tf.set_arg0(sig_num);
+    let sig_info = action.sa_flags.contains(SigActionFlags::SA_SIGINFO);
+    if sig_info {
+        tf.set_arg1(info_addr);
+        tf.set_arg2(ucontext_addr);
+    }
+
+    // 处理函数执行期间额外屏蔽 sa_mask，除非指定 SA_NODEFER，否则也屏蔽信号自身
+    let mut mask = old_mask | action.sa_mask;
+    if !action.sa_flags.contains(SigActionFlags::SA_NODEFER) {
+        mask |= signal_bit(sig_num);
+    }
+    signal_module.signal_set.mask = mask & !unblockable_mask();
+    if action.sa_flags.contains(SigActionFlags::SA_RESETHAND) {
+        signal_module.signal_handler.lock().handlers[sig_num - 1] = None;
+    }
+    signal_module.signal_frames.push(SignalFrame {
+        trap_frame: old_tf,
+        ucontext_addr,
+        sig_info,
+    });
+    Ok(tf)
+}
+
+/// 处理当前任务的信号
+///
+/// 在即将返回用户态时调用，此时任务中保存的用户上下文即为返回用户态后使用的上下文。
+/// 默认行为会在此直接完成；若信号注册了处理函数，则修改用户上下文使得返回用户态后进入处理函数，每次至多进入一个处理函数。
+///
+/// # Arguments
+/// * `syscall_arg0`: 若本次 trap 为系统调用，则为其原始的第一个参数，用于重新执行被信号打断而返回 EINTR 的系统调用
+pub fn handle_signals(syscall_arg0: Option<usize>) {
+    let curr = current_task();
+    let tid = curr.tid();
+    // 计时器到期时向所在进程发送对应的信号
+    let timer_signal = curr.inner.lock().check_pending_signal();
+    if let Some(sig_num) = timer_signal {
+        let _ = send_signal_to_process(curr.get_process_id() as isize, sig_num as isize);
+    }
+    let mut tf = curr.get_tf();
+    let interrupted =
+        syscall_arg0.filter(|_| tf.get_ret_code() as isize == -(LinuxError::EINTR.code() as isize));
+    let mut handled = false;
+    loop {
+        let mut signal_modules = curr.signal_modules.lock();
+        let Some(signal_module) = signal_modules.get_mut(&tid) else {
+            return;
+        };
+        let Some(sig_num) = signal_module.signal_set.get_one_signal() else {
+            break;
+        };
+        handled = true;
+        let action = signal_module
+            .signal_handler
+            .lock()
+            .get_action(sig_num)
+            .copied();
+        match action {
+            Some(action) if action.sa_handler == SIG_IGN => {}
+            Some(action) => {
+                if let Some(arg0) = interrupted {
+                    // 未指定 SA_RESTART 时，被打断的系统调用返回 EINTR
+                    if action.sa_flags.contains(SigActionFlags::SA_RESTART) {
+                        restart_syscall(&mut tf, arg0);
+                    }
+                }
+                let result = setup_signal_frame(signal_module, sig_num, &action, tf);
+                drop(signal_modules);
+                match result {
+                    Ok(new_tf) => curr.set_tf(new_tf),
+                    Err(_) => {
+                        // 无法在用户栈上构造栈帧，只能终止进程
+                        drop(curr);
+                        terminate_current_process(SignalNo::SIGSEGV as usize, true);
+                    }
+                }
+                return;
+            }
+            None => {
+                drop(signal_modules);
+                match SignalDefault::get_action(SignalNo::from(sig_num)) {
+                    SignalDefault::Terminate => terminate_current_process(sig_num, false),
+                    SignalDefault::Core => terminate_current_process(sig_num, true),
+                    SignalDefault::Stop => stop_current(),
+                    SignalDefault::Ignore | SignalDefault::Cont => {}
+                }
+            }
+        }
+    }
+    // 没有进入处理函数，恢复被临时替换的信号掩码
+    if let Some(signal_module) = curr.signal_modules.lock().get_mut(&tid) {
+        if let Some(mask) = signal_module.saved_mask.take() {
+            signal_module.signal_set.mask = mask;
+        }
+    }
+    // 没有进入处理函数，被信号打断的系统调用可以直接重新执行
+    if let Some(arg0) = interrupted.filter(|_| handled) {
+        restart_syscall(&mut tf, arg0);
+        curr.set_tf(tf);
+    }
+}
 
 /// 从信号处理函数返回
 ///
+/// 恢复进入处理函数前的用户上下文与信号掩码。
 /// 返回的值与原先syscall应当返回的值相同，即返回原先保存的trap上下文的a0的值
 pub fn signal_return() -> isize {
-    if load_trap_for_signal() {
-        // 说明确实存在着信号处理函数的trap上下文
-        // 此时内核栈上存储的是调用信号处理前的trap上下文
-        read_trapframe_from_kstack(RUN_QUEUE.lock().get_kernel_stack_top()).get_ret_code() as isize
-    } else {
+    let curr = current_task();
+    let tid = curr.tid();
+    let mut signal_modules = curr.signal_modules.lock();
+    let Some(signal_module) = signal_modules.get_mut(&tid) else {
+        return -1;
+    };
+    let Some(frame) = signal_module.signal_frames.pop() else {
         // 没有进行信号处理，但是调用了sig_return
         // 此时直接返回-1
-        -1
+        return -1;
+    };
+    let mut tf = frame.trap_frame;
+    if let Ok(ucontext) = UserPtr::<SignalUserContext>::new(frame.ucontext_addr).read() {
+        if frame.sig_info {
+            tf.set_pc(ucontext.get_pc());
+        }
+        signal_module.signal_set.mask = ucontext.get_mask() & !unblockable_mask();
     }
+    drop(signal_modules);
+    curr.set_tf(tf);
+    tf.get_ret_code() as isize
 }
 
-/// 发送信号到指定的线程
+/// 发送信号到指定的线程，信号视为由内核发送
 pub fn send_signal_to_thread(tid: isize, signum: isize) -> AxResult<()> {
+    send_siginfo_to_thread(tid, kernel_siginfo(signum))
+}
+
+/// 由内核发送的信号的信息
+fn kernel_siginfo(signum: isize) -> SigInfo {
+    SigInfo {
+        si_signo: signum as i32,
+        ..Default::default()
+    }
+}
+
+/// 发送信号到指定的线程，并记录信号的发送者等信息，信号编号由 `info.si_signo` 给出
+pub fn send_siginfo_to_thread(tid: isize, info: SigInfo) -> AxResult<()> {
+    let signum = info.si_signo as isize;
     let tid2task = TID2TASK.lock();
     let task = if let Some(task) = tid2task.get(&(tid as u64)) {
         Arc::clone(task)
@@ -106,17 +355,114 @@ pub fn send_signal_to_thread(tid: isize, signum: isize) -> AxResult<()> {
         return Err(axerrno::AxError::NotFound);
     }
     let signal_module = signal_modules.get_mut(&(tid as u64)).unwrap();
-    signal_module.signal_set.try_add_signal(signum as usize);
+    let signal = SignalNo::from(signum as usize);
+    // SIGCONT 会丢弃未决的停止信号，停止信号也会丢弃未决的 SIGCONT
+    if signal == SignalNo::SIGCONT {
+        for stop in [
+            SignalNo::SIGSTOP,
+            SignalNo::SIGTSTP,
+            SignalNo::SIGTTIN,
+            SignalNo::SIGTTOU,
+        ] {
+            signal_module.signal_set.pending &= !signal_bit(stop as usize);
+        }
+    } else if matches!(SignalDefault::get_action(signal), SignalDefault::Stop) {
+        signal_module.signal_set.pending &= !signal_bit(SignalNo::SIGCONT as usize);
+    }
+    signal_module.signal_set.try_add_signal_info(info);
     // 如果这个时候对应的线程是处于休眠状态的，则唤醒之，进入信号处理阶段
     drop(signal_modules);
+    // 被屏蔽的信号可能正被 signalfd 等待
+    crate::notify_pollers();
+    // 挂起在系统调用中的任务由它的 waker 唤醒
+    task.wake();
     if task.inner.lock().state() == TaskState::Blocked {
         RUN_QUEUE.lock().unblock_task(task, false);
     }
     Ok(())
 }
 
-/// Whether the current process has signals pending
+/// 发送信号到指定的进程，信号视为由内核发送
+///
+/// 优先选择没有屏蔽该信号的线程，主线程优先；若所有线程均屏蔽了该信号，则挂在主线程上
+pub fn send_signal_to_process(pid: isize, signum: isize) -> AxResult<()> {
+    send_siginfo_to_process(pid, kernel_siginfo(signum))
+}
+
+/// 发送信号到指定的进程，并记录信号的发送者等信息，线程的选择与 `send_signal_to_process` 相同
+pub fn send_siginfo_to_process(pid: isize, info: SigInfo) -> AxResult<()> {
+    let signum = info.si_signo as isize;
+    let mut threads: Vec<_> = TID2TASK
+        .lock()
+        .values()
+        .filter(|task| task.get_process_id() == pid as u64 && !task.get_zombie())
+        .cloned()
+        .collect();
+    if threads.is_empty() {
+        return Err(AxError::NotFound);
+    }
+    // 主线程排在最前
+    threads.sort_by_key(|task| task.tid() != pid as u64);
+    let bit = signal_bit(signum as usize);
+    let target = threads
+        .iter()
+        .find(|task| {
+            let tid = task.tid();
+            task.signal_modules
+                .lock()
+                .get(&tid)
+                .is_some_and(|module| module.signal_set.mask & bit == 0)
+        })
+        .unwrap_or(&threads[0]);
+    send_siginfo_to_thread(target.tid() as isize, info)
+}
+
+/// 向当前任务强制发送一个信号，用于处理非法内存访问等同步异常
+///
+/// 若该信号被屏蔽或忽略，则会解除屏蔽并恢复默认处理，保证任务不会在同一位置反复触发异常
+pub fn force_signal_to_current(signum: usize) {
+    let curr = current_task();
+    let tid = curr.tid();
+    let mut signal_modules = curr.signal_modules.lock();
+    let Some(signal_module) = signal_modules.get_mut(&tid) else {
+        return;
+    };
+    let bit = signal_bit(signum);
+    let mut handler = signal_module.signal_handler.lock();
+    let ignored = handler.handlers[signum - 1].is_some_and(|action| action.sa_handler == SIG_IGN);
+    if ignored || signal_module.signal_set.mask & bit != 0 {
+        handler.handlers[signum - 1] = None;
+        signal_module.signal_set.mask &= !bit;
+    }
+    drop(handler);
+    signal_module.signal_set.try_add_signal(signum);
+}
+
+/// 在等待期间临时替换当前任务的信号掩码，用于 ppoll、pselect6 等系统调用
+///
+/// 原掩码会在下一次返回用户态、处理完信号之后恢复，因此等待期间解除屏蔽的信号仍会被递送
+pub fn set_temporary_signal_mask(mask: usize) {
+    let curr = current_task();
+    let tid = curr.tid();
+    let mut signal_modules = curr.signal_modules.lock();
+    let Some(signal_module) = signal_modules.get_mut(&tid) else {
+        return;
+    };
+    let old_mask = signal_module.signal_set.mask;
+    signal_module.saved_mask.get_or_insert(old_mask);
+    signal_module.signal_set.mask = mask & !unblockable_mask();
+}
+
+/// Whether the current task has signals to be delivered
+///
+/// 只会尝试获取锁而不会阻塞，因此可以在等待队列的条件判断中使用；锁被占用时视为没有信号
 pub fn current_have_signals() -> bool {
-    //current_task().have_signals().is_some()
-    true
+    let curr = current_task();
+    let tid = curr.tid();
+    let Some(signal_modules) = curr.signal_modules.try_lock() else {
+        return false;
+    };
+    signal_modules
+        .get(&tid)
+        .is_some_and(|module| module.signal_set.find_signal().is_some())
 }
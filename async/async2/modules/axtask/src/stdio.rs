@@ -1,13 +1,8 @@
 use axerrno::{AxError, AxResult};
-use axfs::api::port::{
-    ConsoleWinSize, FileExt, FileIO, FileIOType, OpenFlags, FIOCLEX, TCGETS, TIOCGPGRP, TIOCGWINSZ,
-    TIOCSPGRP,
-};
-use axhal::console::{getchar, write_bytes};
+use axfs::api::port::{FileExt, FileIO, FileIOType, Kstat, OpenFlags};
 use axio::{Read, Seek, SeekFrom, Write};
-use axlog::warn;
+use crate::tty::{tty_ioctl, tty_read, tty_ready_to_read, tty_write};
 use crate::Mutex;
-use crate::yield_now;
 /// stdin file for getting chars from console
 pub struct Stdin {
     pub flags: Mutex<OpenFlags>,
@@ -23,34 +18,22 @@ pub struct Stderr {
     pub flags: Mutex<OpenFlags>,
 }
 
-fn stdin_read(buf: &mut [u8]) -> AxResult<usize> {
-    let ch: u8;
-    loop {
-        match getchar() {
-            Some(c) => {
-                ch = c;
-                break;
-            }
-            None => {
-                yield_now();
-                continue;
-            }
-        }
-    }
-    unsafe {
-        buf.as_mut_ptr().write_volatile(ch);
-    }
-    Ok(1)
-}
-
-fn stdout_write(buf: &[u8]) -> AxResult<usize> {
-    write_bytes(buf);
-    Ok(buf.len())
+/// 标准输入输出均视为同一个字符设备，即控制台
+fn console_stat() -> AxResult<Kstat> {
+    Ok(Kstat {
+        st_dev: 1,
+        st_ino: 0,
+        // S_IFCHR | 0o620
+        st_mode: 0o20620,
+        st_nlink: 1,
+        st_blksize: 1024,
+        ..Default::default()
+    })
 }
 
 impl Read for Stdin {
     fn read(&mut self, buf: &mut [u8]) -> AxResult<usize> {
-        stdin_read(buf)
+        tty_read(buf)
     }
 }
 
@@ -83,15 +66,19 @@ impl FileExt for Stdin {
 
 impl FileIO for Stdin {
     fn read(&self, buf: &mut [u8]) -> AxResult<usize> {
-        stdin_read(buf)
+        tty_read(buf)
     }
 
     fn get_type(&self) -> FileIOType {
         FileIOType::Stdin
     }
 
+    fn get_stat(&self) -> AxResult<Kstat> {
+        console_stat()
+    }
+
     fn ready_to_read(&self) -> bool {
-        true
+        tty_ready_to_read()
     }
 
     fn ready_to_write(&self) -> bool {
@@ -111,54 +98,17 @@ impl FileIO for Stdin {
     }
 
     fn ioctl(&self, request: usize, data: usize) -> AxResult<isize> {
-        match request {
-            TIOCGWINSZ => {
-                let winsize = data as *mut ConsoleWinSize;
-                unsafe {
-                    *winsize = ConsoleWinSize::default();
-                }
-                Ok(0)
-            }
-            TCGETS | TIOCSPGRP => {
-                warn!("stdin TCGETS | TIOCSPGRP, pretend to be tty.");
-                // pretend to be tty
-                Ok(0)
-            }
-
-            TIOCGPGRP => {
-                warn!("stdin TIOCGPGRP, pretend to be have a tty process group.");
-                unsafe {
-                    *(data as *mut u32) = 0;
-                }
-                Ok(0)
-            }
-            FIOCLEX => Ok(0),
-            _ => Err(AxError::Unsupported),
-        }
+        tty_ioctl(request, data)
     }
 
     fn set_status(&self, flags: OpenFlags) -> bool {
-        if flags.contains(OpenFlags::CLOEXEC) {
-            *self.flags.lock() = OpenFlags::CLOEXEC;
-            true
-        } else {
-            false
-        }
+        *self.flags.lock() = flags;
+        true
     }
 
     fn get_status(&self) -> OpenFlags {
         *self.flags.lock()
     }
-
-    fn set_close_on_exec(&self, is_set: bool) -> bool {
-        if is_set {
-            // 设置close_on_exec位置
-            *self.flags.lock() |= OpenFlags::CLOEXEC;
-        } else {
-            *self.flags.lock() &= !OpenFlags::CLOEXEC;
-        }
-        true
-    }
 }
 
 impl Read for Stdout {
@@ -169,8 +119,7 @@ impl Read for Stdout {
 
 impl Write for Stdout {
     fn write(&mut self, buf: &[u8]) -> AxResult<usize> {
-        write_bytes(buf);
-        Ok(buf.len())
+        tty_write(buf)
     }
     fn flush(&mut self) -> AxResult {
         // stdout is always flushed
@@ -197,7 +146,7 @@ impl FileExt for Stdout {
 }
 impl FileIO for Stdout {
     fn write(&self, buf: &[u8]) -> AxResult<usize> {
-        stdout_write(buf)
+        tty_write(buf)
     }
 
     fn flush(&self) -> AxResult {
@@ -221,6 +170,10 @@ impl FileIO for Stdout {
         FileIOType::Stdout
     }
 
+    fn get_stat(&self) -> AxResult<Kstat> {
+        console_stat()
+    }
+
     fn ready_to_read(&self) -> bool {
         false
     }
@@ -230,53 +183,16 @@ impl FileIO for Stdout {
     }
 
     fn set_status(&self, flags: OpenFlags) -> bool {
-        if flags.contains(OpenFlags::CLOEXEC) {
-            *self.flags.lock() = flags;
-            true
-        } else {
-            false
-        }
+        *self.flags.lock() = flags;
+        true
     }
 
     fn get_status(&self) -> OpenFlags {
         *self.flags.lock()
     }
 
-    fn set_close_on_exec(&self, is_set: bool) -> bool {
-        if is_set {
-            // 设置close_on_exec位置
-            *self.flags.lock() |= OpenFlags::CLOEXEC;
-        } else {
-            *self.flags.lock() &= !OpenFlags::CLOEXEC;
-        }
-        true
-    }
-
     fn ioctl(&self, request: usize, data: usize) -> AxResult<isize> {
-        match request {
-            TIOCGWINSZ => {
-                let winsize = data as *mut ConsoleWinSize;
-                unsafe {
-                    *winsize = ConsoleWinSize::default();
-                }
-                Ok(0)
-            }
-            TCGETS | TIOCSPGRP => {
-                warn!("stdout TCGETS | TIOCSPGRP, pretend to be tty.");
-                // pretend to be tty
-                Ok(0)
-            }
-
-            TIOCGPGRP => {
-                warn!("stdout TIOCGPGRP, pretend to be have a tty process group.");
-                unsafe {
-                    *(data as *mut u32) = 0;
-                }
-                Ok(0)
-            }
-            FIOCLEX => Ok(0),
-            _ => Err(AxError::Unsupported),
-        }
+        tty_ioctl(request, data)
     }
 }
 
@@ -288,8 +204,7 @@ impl Read for Stderr {
 
 impl Write for Stderr {
     fn write(&mut self, buf: &[u8]) -> AxResult<usize> {
-        write_bytes(buf);
-        Ok(buf.len())
+        tty_write(buf)
     }
 
     /// Stderr is always flushed
@@ -318,8 +233,7 @@ impl FileExt for Stderr {
 
 impl FileIO for Stderr {
     fn write(&self, buf: &[u8]) -> AxResult<usize> {
-        write_bytes(buf);
-        Ok(buf.len())
+        tty_write(buf)
     }
 
     /// Stderr is always flushed
@@ -343,6 +257,10 @@ impl FileIO for Stderr {
         FileIOType::Stderr
     }
 
+    fn get_stat(&self) -> AxResult<Kstat> {
+        console_stat()
+    }
+
     fn ready_to_read(&self) -> bool {
         false
     }
@@ -352,29 +270,6 @@ impl FileIO for Stderr {
     }
 
     fn ioctl(&self, request: usize, data: usize) -> AxResult<isize> {
-        match request {
-            TIOCGWINSZ => {
-                let winsize = data as *mut ConsoleWinSize;
-                unsafe {
-                    *winsize = ConsoleWinSize::default();
-                }
-                Ok(0)
-            }
-            TCGETS | TIOCSPGRP => {
-                warn!("stderr TCGETS | TIOCSPGRP, pretend to be tty.");
-                // pretend to be tty
-                Ok(0)
-            }
-
-            TIOCGPGRP => {
-                warn!("stderr TIOCGPGRP, pretend to be have a tty process group.");
-                unsafe {
-                    *(data as *mut u32) = 0;
-                }
-                Ok(0)
-            }
-            FIOCLEX => Ok(0),
-            _ => Err(AxError::Unsupported),
-        }
+        tty_ioctl(request, data)
     }
 }
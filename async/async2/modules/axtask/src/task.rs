@@ -1,6 +1,6 @@
 use alloc::{string::String, sync::Arc};
 
-use core::{mem::ManuallyDrop, ops::Deref};
+use core::{mem::ManuallyDrop, ops::Deref, task::Waker};
 
 use alloc::vec;
 use axerrno::{AxError, AxResult};
@@ -8,7 +8,7 @@ use riscv::register::sstatus::{self, Sstatus};
 
 use crate::stdio::{Stderr, Stdin, Stdout};
 use axfs::api::{FileIO, OpenFlags};
-use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
 use memory_addr::VirtAddr;
 use spinlock::SpinNoIrq;
 
@@ -18,10 +18,14 @@ use axhal::arch::{write_page_table_root0, TrapFrame};
 use crate::api::load_app;
 use crate::executor::spawn_user_task;
 use crate::fd_manager::FdManager;
+use crate::flags::CloneFlags;
+use crate::rlimit::{RLimit, ResourceLimits, NOFILE_DEFAULT, RLIMIT_NOFILE};
 use crate::run_queue::RUN_QUEUE;
 use crate::signal::SignalModule;
 use crate::Mutex;
-use crate::{schedule::add_wait_for_exit_queue, AxTask, AxTaskRef};
+use crate::SchedPolicy;
+use crate::UserPtr;
+use crate::{schedule::add_wait_for_exit_queue, wake_vfork_process, AxTask, AxTaskRef};
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use axhal::KERNEL_PROCESS_ID;
@@ -31,21 +35,44 @@ pub use taskctx::TaskInner;
 /// Map from task id to arc pointer of task
 pub static TID2TASK: Mutex<BTreeMap<u64, AxTaskRef>> = Mutex::new(BTreeMap::new());
 
-const FD_LIMIT_ORIGIN: usize = 1025;
+const FD_LIMIT_ORIGIN: usize = NOFILE_DEFAULT as usize;
 
 extern "C" {
     fn start_signal_trampoline();
 }
 
+/// 在给定的地址空间中映射信号跳板
+///
+/// 信号跳板所在页面不属于地址空间自身的内存区域，因此新建或复制地址空间后都需要重新映射
+fn map_signal_trampoline(memory_set: &mut MemorySet) -> AxResult<()> {
+    use axhal::mem::virt_to_phys;
+    use axhal::paging::MappingFlags;
+    let signal_trampoline_vaddr: VirtAddr = (axconfig::SIGNAL_TRAMPOLINE).into();
+    let signal_trampoline_paddr = virt_to_phys((start_signal_trampoline as usize).into());
+    memory_set.map_page_without_alloc(
+        signal_trampoline_vaddr,
+        signal_trampoline_paddr,
+        MappingFlags::READ | MappingFlags::EXECUTE | MappingFlags::USER | MappingFlags::WRITE,
+    )
+}
+
 pub struct Task {
     /// 父task号
     pub parent: AtomicU64,
+    /// 所属的线程组号，即 getpid 返回的值
+    ///
+    /// 对于进程来说等于自身的 tid，对于 CLONE_THREAD 创建的线程来说等于创建者的线程组号
+    pub process_id: AtomicU64,
     /// 子task
     pub children: Mutex<Vec<u64>>,
     /// 文件描述符管理器
     pub fd_manager: FdManager,
     /// task状态
     pub is_zombie: AtomicBool,
+    /// 导致进程终止的信号，按 wait 状态的低 8 位编码，产生 core dump 时置位 0x80；正常退出时为 0
+    pub term_signal: AtomicI32,
+    /// 线程组是否正在退出，只记录在主线程上
+    pub group_exiting: AtomicBool,
     /// 地址空间
     pub memory_set: Arc<Mutex<MemorySet>>,
 
@@ -55,6 +82,18 @@ pub struct Task {
     /// 当前用户堆的堆顶，不能小于基址，不能大于基址加堆的最大大小
     pub heap_top: AtomicU64,
 
+    /// 由 set_robust_list 设置的 robust list 头部地址，为 0 时表示未设置
+    pub robust_list: AtomicU64,
+
+    /// 资源上限
+    pub rlimits: SpinNoIrq<ResourceLimits>,
+
+    /// 已被回收的子任务的用户态时间之和，单位为纳秒
+    pub children_utime_ns: AtomicU64,
+
+    /// 已被回收的子任务的内核态时间之和，单位为纳秒
+    pub children_stime_ns: AtomicU64,
+
     /// 信号处理模块
     /// 第一维代表TaskID，第二维代表对应的信号处理模块
     pub signal_modules: Mutex<BTreeMap<u64, SignalModule>>,
@@ -71,8 +110,11 @@ pub struct Task {
     ///trapframe
     pub tf: SpinNoIrq<TrapFrame>,
 
-    ///priority
-    pub pri: u64,
+    /// nice 值，范围为 -20 到 19，越小越优先，只对普通调度策略有效
+    pub nice: AtomicI32,
+
+    /// 挂起在系统调用中时的 waker，收到信号、子进程退出或等待的事件发生时通过它重新调度任务
+    pub waker: SpinNoIrq<Option<Waker>>,
 }
 
 impl Task {
@@ -91,6 +133,16 @@ impl Task {
         self.parent.store(parent, Ordering::Release)
     }
 
+    /// get the id of the thread group which the task belongs to
+    pub fn get_process_id(&self) -> u64 {
+        self.process_id.load(Ordering::Acquire)
+    }
+
+    /// whether the task is created as a thread by `CLONE_THREAD`
+    pub fn is_thread(&self) -> bool {
+        self.get_process_id() != self.tid()
+    }
+
     /// get the exit code of the task
     pub fn get_exit_code(&self) -> i32 {
         self.inner.lock().get_exit_code()
@@ -111,6 +163,57 @@ impl Task {
         self.is_zombie.store(status, Ordering::Release)
     }
 
+    /// 记录进程被信号终止，只有第一次记录的信号有效
+    pub fn set_term_signal(&self, status: i32) {
+        let _ = self
+            .term_signal
+            .compare_exchange(0, status, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// 开始结束线程组，并在主线程上记录进程的退出状态
+    ///
+    /// term_signal 为 0 时表示以 exit_code 正常退出。线程组已经在退出时不做修改并返回 false
+    pub fn start_group_exit(&self, exit_code: i32, term_signal: i32) -> bool {
+        if self.group_exiting.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        self.set_exit_code(exit_code);
+        if term_signal != 0 {
+            self.set_term_signal(term_signal);
+        }
+        true
+    }
+
+    /// 获取供 wait 使用的退出状态
+    pub fn get_wait_status(&self) -> i32 {
+        match self.term_signal.load(Ordering::Acquire) {
+            0 => (self.get_exit_code() & 0xff) << 8,
+            status => status,
+        }
+    }
+
+    /// 记录挂起当前系统调用的 waker，只保留最近一次轮询时给出的
+    pub fn register_waker(&self, waker: &Waker) {
+        let mut slot = self.waker.lock();
+        if !slot.as_ref().is_some_and(|old| old.will_wake(waker)) {
+            *slot = Some(waker.clone());
+        }
+    }
+
+    /// 系统调用不再挂起时清除记录的 waker，避免之后的事件产生多余的唤醒
+    pub fn clear_waker(&self) {
+        self.waker.lock().take();
+    }
+
+    /// 唤醒挂起在系统调用中的任务，任务没有挂起时不做任何事
+    pub fn wake(&self) {
+        // waker 会获取任务队列的锁，在释放 waker 的锁之后再唤醒
+        let waker = self.waker.lock().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
     /// get the heap top of the task
     pub fn get_heap_top(&self) -> u64 {
         self.heap_top.load(Ordering::Acquire)
@@ -131,6 +234,73 @@ impl Task {
         self.heap_bottom.store(bottom, Ordering::Release)
     }
 
+    /// get the address of the robust list head
+    pub fn get_robust_list(&self) -> usize {
+        self.robust_list.load(Ordering::Acquire) as usize
+    }
+
+    /// set the address of the robust list head
+    pub fn set_robust_list(&self, head: usize) {
+        self.robust_list.store(head as u64, Ordering::Release)
+    }
+
+    /// 获取 nice 值
+    pub fn get_nice(&self) -> i32 {
+        self.nice.load(Ordering::Acquire)
+    }
+
+    /// 设置 nice 值，超出范围的值会被截断到 -20 到 19 之间
+    pub fn set_nice(&self, nice: i32) {
+        self.nice.store(nice.clamp(-20, 19), Ordering::Release)
+    }
+
+    /// 抢占时使用的优先级，数值越大越优先
+    ///
+    /// 实时策略 (SCHED_FIFO/SCHED_RR) 的优先级为 41 到 139，高于所有普通任务；
+    /// 普通策略 (SCHED_OTHER/SCHED_BATCH) 由 nice 值决定，为 1 到 40；SCHED_IDLE 为 0
+    pub fn priority(&self) -> u64 {
+        let status = self.inner.lock().get_sched_status();
+        match status.policy {
+            SchedPolicy::SCHED_FIFO | SchedPolicy::SCHED_RR => 40 + status.priority as u64,
+            SchedPolicy::SCHED_OTHER | SchedPolicy::SCHED_BATCH => (20 - self.get_nice()) as u64,
+            _ => 0,
+        }
+    }
+
+    /// 获取指定资源的上限
+    pub fn get_rlimit(&self, resource: i32) -> AxResult<RLimit> {
+        self.rlimits.lock().get(resource)
+    }
+
+    /// 设置指定资源的上限，RLIMIT_NOFILE 的软上限会同步到文件描述符表
+    pub fn set_rlimit(&self, resource: i32, limit: RLimit) -> AxResult<()> {
+        self.rlimits.lock().set(resource, limit)?;
+        if resource == RLIMIT_NOFILE {
+            self.fd_manager.set_limit(limit.rlim_cur);
+        }
+        Ok(())
+    }
+
+    /// 回收子任务时累计其时间统计，包括子任务自身已回收的子任务
+    pub fn add_children_time(&self, child: &Task) {
+        let (utime_ns, stime_ns) = child.inner.lock().time_stat_output();
+        let (children_utime_ns, children_stime_ns) = child.get_children_time();
+        self.children_utime_ns
+            .fetch_add((utime_ns + children_utime_ns) as u64, Ordering::AcqRel);
+        self.children_stime_ns
+            .fetch_add((stime_ns + children_stime_ns) as u64, Ordering::AcqRel);
+    }
+
+    /// 已被回收的子任务的时间统计
+    ///
+    /// The format is (user time, kernel time) in nanoseconds
+    pub fn get_children_time(&self) -> (usize, usize) {
+        (
+            self.children_utime_ns.load(Ordering::Acquire) as usize,
+            self.children_stime_ns.load(Ordering::Acquire) as usize,
+        )
+    }
+
     /// set the process as blocked by vfork
     pub fn set_vfork_block(&self, value: bool) {
         *self.blocked_by_vfork.lock() = value;
@@ -217,40 +387,33 @@ impl Task {
         let iid = inn.id().as_u64();
         Self {
             parent: AtomicU64::new(parent),
+            process_id: AtomicU64::new(iid),
             children: Mutex::new(Vec::new()),
             is_zombie: AtomicBool::new(false),
+            term_signal: AtomicI32::new(0),
+            group_exiting: AtomicBool::new(false),
             memory_set,
             heap_bottom: AtomicU64::new(heap_bottom),
             heap_top: AtomicU64::new(heap_bottom),
+            robust_list: AtomicU64::new(0),
+            rlimits: SpinNoIrq::new(ResourceLimits::default()),
+            children_utime_ns: AtomicU64::new(0),
+            children_stime_ns: AtomicU64::new(0),
             fd_manager: FdManager::new(fd_table, FD_LIMIT_ORIGIN),
-            signal_modules: Mutex::new(BTreeMap::new()),
+            signal_modules: Mutex::new(BTreeMap::from([(iid, SignalModule::init_signal(None))])),
             blocked_by_vfork: Mutex::new(false),
             file_path: Mutex::new(String::new()),
             inner: SpinNoIrq::new(inn),
             tf: SpinNoIrq::new(TrapFrame::default()),
-            //TODOWJX:优先级应该一样，但是为了演示，改成10+id
-            pri: 10 + iid,
+            nice: AtomicI32::new(0),
+            waker: SpinNoIrq::new(None),
         }
     }
     /// 根据给定参数创建一个新的进程，作为应用程序初始进程
     pub fn init(args: Vec<String>, envs: &Vec<String>) -> AxResult<AxTaskRef> {
         let path = args[0].clone();
         let mut memory_set = MemorySet::new_memory_set();
-        {
-            use axhal::mem::virt_to_phys;
-            use axhal::paging::MappingFlags;
-            // 生成信号跳板
-            let signal_trampoline_vaddr: VirtAddr = (axconfig::SIGNAL_TRAMPOLINE).into();
-            let signal_trampoline_paddr = virt_to_phys((start_signal_trampoline as usize).into());
-            memory_set.map_page_without_alloc(
-                signal_trampoline_vaddr,
-                signal_trampoline_paddr,
-                MappingFlags::READ
-                    | MappingFlags::EXECUTE
-                    | MappingFlags::USER
-                    | MappingFlags::WRITE,
-            )?;
-        }
+        map_signal_trampoline(&mut memory_set)?;
         let page_table_token = memory_set.page_table_token();
         if page_table_token != 0 {
             unsafe {
@@ -259,8 +422,9 @@ impl Task {
                 riscv::register::sstatus::set_sum();
             };
         }
+        let rlimits = ResourceLimits::default();
         let (entry, user_stack_bottom, heap_bottom) =
-            if let Ok(ans) = load_app(path.clone(), args, envs, &mut memory_set) {
+            if let Ok(ans) = load_app(path.clone(), args, envs, &mut memory_set, &rlimits) {
                 ans
             } else {
                 error!("Failed to load app {}", path);
@@ -321,6 +485,241 @@ impl Task {
     }
 }
 
+/// 与进程生命周期相关的方法
+impl Task {
+    /// 按照 clone 的参数复制当前任务，返回新任务的 tid
+    ///
+    /// # Arguments
+    /// * `flags`: clone 的选项
+    /// * `stack`: 新任务的用户栈顶，为 None 时与当前任务使用相同的 sp
+    /// * `ptid`: 指定 CLONE_PARENT_SETTID 时写入新任务 tid 的地址
+    /// * `tls`: 指定 CLONE_SETTLS 时新任务的 tls
+    /// * `ctid`: 指定 CLONE_CHILD_SETTID 或 CLONE_CHILD_CLEARTID 时使用的地址
+    /// * `sig_child`: 新任务退出时是否向父任务发送 SIGCHLD
+    ///
+    /// 注意：当前任务的用户上下文需要在调用前同步到 `tf` 中。
+    /// 由于 exec 会原地替换地址空间，vfork 出的子任务不与父任务共享地址空间，而是复制一份。
+    pub fn clone_task(
+        &self,
+        flags: CloneFlags,
+        stack: Option<usize>,
+        ptid: usize,
+        tls: usize,
+        ctid: usize,
+        sig_child: bool,
+    ) -> AxResult<u64> {
+        let memory_set = if flags.contains(CloneFlags::CLONE_VM)
+            && !flags.contains(CloneFlags::CLONE_VFORK)
+        {
+            Arc::clone(&self.memory_set)
+        } else {
+            let mut memory_set = self.memory_set.lock().clone_or_err()?;
+            map_signal_trampoline(&mut memory_set)?;
+            Arc::new(Mutex::new(memory_set))
+        };
+        let page_table_token = memory_set.lock().page_table_token();
+        let new_taski = new_task_inner(
+            || {},
+            String::from(self.inner.lock().name()),
+            axconfig::TASK_STACK_SIZE,
+            page_table_token,
+            sig_child,
+        );
+        // 父进程：CLONE_PARENT 与 CLONE_THREAD 创建的是当前任务的“兄弟”
+        let parent_id = if flags.contains(CloneFlags::CLONE_PARENT)
+            || flags.contains(CloneFlags::CLONE_THREAD)
+        {
+            self.get_parent()
+        } else {
+            self.tid()
+        };
+        let mut new_task = Self::new(
+            parent_id,
+            memory_set,
+            self.get_heap_bottom(),
+            Vec::new(),
+            new_taski,
+        );
+        let new_tid = new_task.tid();
+        new_task.set_heap_top(self.get_heap_top());
+        new_task.set_file_path(self.get_file_path());
+        if flags.contains(CloneFlags::CLONE_THREAD) {
+            new_task
+                .process_id
+                .store(self.get_process_id(), Ordering::Release);
+        }
+
+        // 文件描述符表
+        if flags.contains(CloneFlags::CLONE_FILES) {
+            new_task.fd_manager.fd_table = Arc::clone(&self.fd_manager.fd_table);
+            new_task.fd_manager.cloexec_fds = Arc::clone(&self.fd_manager.cloexec_fds);
+        } else {
+            let fd_table = self.fd_manager.fd_table.lock();
+            *new_task.fd_manager.fd_table.lock() = fd_table.clone();
+            *new_task.fd_manager.cloexec_fds.lock() = self.fd_manager.cloexec_fds.lock().clone();
+        }
+        // 调度策略、nice 值与 CPU 亲和集均继承自当前任务
+        {
+            let inner = self.inner.lock();
+            let new_inner = new_task.inner.lock();
+            new_inner.set_sched_status(inner.get_sched_status());
+            new_inner
+                .cpu_set
+                .store(inner.get_cpu_set() as u64, Ordering::Release);
+        }
+        new_task.set_nice(self.get_nice());
+
+        new_task.fd_manager.set_limit(self.fd_manager.get_limit());
+        *new_task.rlimits.lock() = self.rlimits.lock().clone();
+        new_task.fd_manager.set_mask(self.fd_manager.get_mask());
+        *new_task.fd_manager.cwd.lock() = self.get_cwd();
+
+        // 信号处理函数：CLONE_SIGHAND 时共享，否则复制一份；信号掩码总是继承，备用信号栈仅在不共享地址空间时继承
+        {
+            let signal_modules = self.signal_modules.lock();
+            let signal_module = signal_modules.get(&self.tid()).unwrap();
+            let signal_handler = if flags.contains(CloneFlags::CLONE_SIGHAND) {
+                Arc::clone(&signal_module.signal_handler)
+            } else {
+                Arc::new(Mutex::new(signal_module.signal_handler.lock().clone()))
+            };
+            let mut new_signal_module = SignalModule::init_signal(Some(signal_handler));
+            new_signal_module.signal_set.mask = signal_module.signal_set.mask;
+            if !flags.contains(CloneFlags::CLONE_VM) || flags.contains(CloneFlags::CLONE_VFORK) {
+                new_signal_module.alternate_stack = signal_module.alternate_stack;
+            }
+            new_task
+                .signal_modules
+                .lock()
+                .insert(new_tid, new_signal_module);
+        }
+
+        // 用户上下文与当前任务相同，仅返回值为 0
+        #[cfg(feature = "tls")]
+        let kernel_tls = VirtAddr::from(new_task.inner.lock().get_tls_ptr());
+        #[cfg(not(feature = "tls"))]
+        let kernel_tls = VirtAddr::from(0);
+        new_task.init_tf(
+            task_entry as usize,
+            (RUN_QUEUE.lock().get_kernel_stack_top() - core::mem::size_of::<TrapFrame>()).into(),
+            kernel_tls,
+        );
+        {
+            let old_tf = self.get_tf();
+            let mut tf = new_task.tf.lock();
+            tf.regs = old_tf.regs;
+            tf.sepc = old_tf.sepc;
+            tf.sstatus = old_tf.sstatus;
+            tf.fs = old_tf.fs;
+            tf.set_ret_code(0);
+            if let Some(stack) = stack {
+                tf.set_user_sp(stack);
+            }
+            if flags.contains(CloneFlags::CLONE_SETTLS) {
+                tf.set_tls(tls);
+            }
+        }
+
+        if flags.contains(CloneFlags::CLONE_PARENT_SETTID) {
+            let _ = UserPtr::<u32>::new(ptid).write(new_tid as u32);
+        }
+        if flags.contains(CloneFlags::CLONE_CHILD_SETTID) {
+            new_task.inner.lock().set_child_tid(ctid);
+            // 子任务的地址空间中的 lazy 页面需要在子任务的内存集中分配
+            let write = axhal::paging::MappingFlags::WRITE;
+            if new_task.check_user_range(ctid.into(), 4, write).is_ok() {
+                // 子任务的地址空间可能与当前不同，需要通过物理地址写入
+                if let Ok((paddr, _, _)) = new_task.memory_set.lock().query(ctid.into()) {
+                    unsafe {
+                        *(axhal::mem::phys_to_virt(paddr).as_mut_ptr() as *mut u32) =
+                            new_tid as u32
+                    };
+                }
+            }
+        }
+        if flags.contains(CloneFlags::CLONE_CHILD_CLEARTID) {
+            new_task.inner.lock().set_clear_child_tid(ctid);
+        }
+        if flags.contains(CloneFlags::CLONE_VFORK) {
+            new_task.inner.lock().set_vfork_child(true);
+        }
+
+        if !flags.contains(CloneFlags::CLONE_THREAD) {
+            match TID2TASK.lock().get(&parent_id) {
+                Some(parent) => parent.children.lock().push(new_tid),
+                None => return Err(AxError::NotFound),
+            }
+        }
+        let axtask = Arc::new(AxTask::new(new_task));
+        add_wait_for_exit_queue(&axtask);
+        TID2TASK.lock().insert(new_tid, axtask.clone());
+        RUN_QUEUE.lock().taskadd();
+        RUN_QUEUE.lock().add_task(Arc::clone(&axtask));
+        spawn_user_task(axtask);
+        Ok(new_tid)
+    }
+
+    /// 将当前任务替换为新的应用程序
+    ///
+    /// 原有的地址空间会被释放，标记为 `CLOEXEC` 的文件会被关闭，信号处理函数恢复为默认。
+    /// 若当前任务是 vfork 出的子任务，还会唤醒被阻塞的父任务。
+    pub fn exec(&self, name: String, args: Vec<String>, envs: &Vec<String>) -> AxResult<()> {
+        let mut memory_set = MemorySet::new_memory_set();
+        map_signal_trampoline(&mut memory_set)?;
+        let page_table_token = memory_set.page_table_token();
+        // load_app 直接向用户地址写入数据，因此需要先切换到新的页表
+        unsafe {
+            write_page_table_root0(page_table_token.into());
+        }
+        let rlimits = self.rlimits.lock().clone();
+        let (entry, user_stack_bottom, heap_bottom) =
+            match load_app(name.clone(), args, envs, &mut memory_set, &rlimits) {
+                Ok(ans) => ans,
+                Err(err) => {
+                    error!("Failed to load app {}", name);
+                    unsafe {
+                        write_page_table_root0(self.memory_set.lock().page_table_token().into());
+                    }
+                    axhal::arch::flush_tlb(None);
+                    return Err(err);
+                }
+            };
+        // 旧地址空间在此处被释放
+        *self.memory_set.lock() = memory_set;
+        self.inner.lock().set_page_table_token(page_table_token);
+        axhal::arch::flush_tlb(None);
+
+        self.set_heap_bottom(heap_bottom.as_usize() as u64);
+        self.set_heap_top(heap_bottom.as_usize() as u64);
+        self.fd_manager.close_on_exec();
+        if let Some(signal_module) = self.signal_modules.lock().get_mut(&self.tid()) {
+            signal_module.signal_handler.lock().clear();
+            signal_module.signal_frames.clear();
+            signal_module.alternate_stack = Default::default();
+        }
+        self.inner.lock().set_clear_child_tid(0);
+        self.set_robust_list(0);
+        let name_in_path = name.rsplit('/').next().unwrap_or(name.as_str());
+        self.inner.lock().set_name(name_in_path);
+        self.set_file_path(name);
+
+        {
+            let mut tf = self.tf.lock();
+            tf.regs = Default::default();
+            tf.fs = [0; 2];
+        }
+        self.app_init_tf(entry.as_usize(), user_stack_bottom.as_usize());
+
+        if self.inner.lock().is_vfork_child() {
+            self.inner.lock().set_vfork_child(false);
+            if let Some(task) = TID2TASK.lock().get(&self.tid()).cloned() {
+                wake_vfork_process(&task);
+            }
+        }
+        Ok(())
+    }
+}
+
 /// 与地址空间相关的进程方法
 impl Task {
     /// alloc physical memory for lazy allocation manually
@@ -335,9 +734,14 @@ impl Task {
             .manual_alloc_range_for_lazy(start, end)
     }
 
-    /// alloc physical memory with the given type size for lazy allocation manually
-    pub fn manual_alloc_type_for_lazy<T: Sized>(&self, obj: *const T) -> AxResult<()> {
-        self.memory_set.lock().manual_alloc_type_for_lazy(obj)
+    /// 检查用户区间 `[start, start + len)` 是否具有 `flags` 权限，并分配其中的 lazy 页面
+    pub fn check_user_range(
+        &self,
+        start: VirtAddr,
+        len: usize,
+        flags: axhal::paging::MappingFlags,
+    ) -> AxResult<()> {
+        self.memory_set.lock().check_user_range(start, len, flags)
     }
 }
 
@@ -345,17 +749,43 @@ impl Task {
 impl Task {
     /// 为进程分配一个文件描述符
     pub fn alloc_fd(&self, fd_table: &mut Vec<Option<Arc<dyn FileIO>>>) -> AxResult<usize> {
-        for (i, fd) in fd_table.iter().enumerate() {
-            if fd.is_none() {
-                return Ok(i);
-            }
-        }
-        if fd_table.len() >= self.fd_manager.get_limit() as usize {
+        self.alloc_fd_from(fd_table, 0)
+    }
+
+    /// 为进程分配一个不小于 `min_fd` 的最小可用文件描述符
+    ///
+    /// 新分配的文件描述符不带有 FD_CLOEXEC 标志
+    pub fn alloc_fd_from(
+        &self,
+        fd_table: &mut Vec<Option<Arc<dyn FileIO>>>,
+        min_fd: usize,
+    ) -> AxResult<usize> {
+        let limit = self.fd_manager.get_limit() as usize;
+        if min_fd >= limit {
             debug!("fd table is full");
             return Err(AxError::StorageFull);
         }
-        fd_table.push(None);
-        Ok(fd_table.len() - 1)
+        // 降低上限后，表中可能残留超出上限的空位，它们不能再被分配
+        let fd = match fd_table
+            .iter()
+            .enumerate()
+            .take(limit)
+            .skip(min_fd)
+            .find(|(_, fd)| fd.is_none())
+        {
+            Some((i, _)) => i,
+            None => {
+                let fd = fd_table.len().max(min_fd);
+                if fd >= limit {
+                    debug!("fd table is full");
+                    return Err(AxError::StorageFull);
+                }
+                fd_table.resize(fd + 1, None);
+                fd
+            }
+        };
+        self.fd_manager.set_close_on_exec(fd, false);
+        Ok(fd)
     }
 
     /// 获取当前进程的工作目录
@@ -1,4 +1,4 @@
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
 use axhal::time::current_time;
 use lazy_init::LazyInit;
 use spinlock::SpinNoIrq;
@@ -23,6 +23,66 @@ impl TimerEvent for TaskWakeupEvent {
     }
 }
 
+/// 到期时唤醒挂起在系统调用中的任务，由任务自己检查是否已经超时
+struct SyscallWakeupEvent(AxTaskRef);
+
+impl TimerEvent for SyscallWakeupEvent {
+    fn callback(self, _now: TimeValue) {
+        self.0.wake();
+    }
+}
+
+static SYSCALL_TIMER_LIST: LazyInit<SpinNoIrq<TimerList<SyscallWakeupEvent>>> = LazyInit::new();
+
+/// 到期时唤醒所有 poll 等待者，用于 timerfd 等定时就绪的文件
+struct PollerWakeupEvent {
+    deadline: TimeValue,
+    /// 不为 0 时在到期后按该周期重新设置
+    interval: TimeValue,
+    /// 设置者被释放后不再唤醒
+    owner: Weak<()>,
+}
+
+impl TimerEvent for PollerWakeupEvent {
+    fn callback(self, now: TimeValue) {
+        if self.owner.strong_count() == 0 {
+            return;
+        }
+        crate::notify_pollers();
+        if !self.interval.is_zero() {
+            // 错过的周期不再逐个触发
+            let mut deadline = self.deadline + self.interval;
+            if deadline <= now {
+                let missed = (now - self.deadline).as_nanos() / self.interval.as_nanos();
+                deadline = self.deadline + self.interval * missed as u32 + self.interval;
+            }
+            POLLER_TIMER_LIST.lock().set(
+                deadline,
+                PollerWakeupEvent {
+                    deadline,
+                    interval: self.interval,
+                    owner: self.owner,
+                },
+            );
+        }
+    }
+}
+
+static POLLER_TIMER_LIST: LazyInit<SpinNoIrq<TimerList<PollerWakeupEvent>>> = LazyInit::new();
+
+pub fn set_poller_wakeup(deadline: TimeValue, interval: TimeValue, owner: Weak<()>) {
+    let mut timers = POLLER_TIMER_LIST.lock();
+    timers.cancel(|event| event.owner.strong_count() == 0);
+    timers.set(
+        deadline,
+        PollerWakeupEvent {
+            deadline,
+            interval,
+            owner,
+        },
+    );
+}
+
 pub fn set_alarm_wakeup(deadline: TimeValue, task: AxTaskRef) {
     let mut timers = TIMER_LIST.lock();
     // task.set_in_timer_list(true);
@@ -37,6 +97,18 @@ pub fn cancel_alarm(task: &AxTaskRef) {
     timers.cancel(|t| Arc::ptr_eq(&t.0, task));
 }
 
+pub fn set_syscall_wakeup(deadline: TimeValue, task: AxTaskRef) {
+    SYSCALL_TIMER_LIST
+        .lock()
+        .set(deadline, SyscallWakeupEvent(task));
+}
+
+pub fn cancel_syscall_wakeup(task: &AxTaskRef) {
+    SYSCALL_TIMER_LIST
+        .lock()
+        .cancel(|event| Arc::ptr_eq(&event.0, task));
+}
+
 pub fn check_events() {
     loop {
         let now = current_time();
@@ -47,8 +119,28 @@ pub fn check_events() {
             break;
         }
     }
+    loop {
+        let now = current_time();
+        let event = SYSCALL_TIMER_LIST.lock().expire_one(now);
+        if let Some((_deadline, event)) = event {
+            event.callback(now);
+        } else {
+            break;
+        }
+    }
+    loop {
+        let now = current_time();
+        let event = POLLER_TIMER_LIST.lock().expire_one(now);
+        if let Some((_deadline, event)) = event {
+            event.callback(now);
+        } else {
+            break;
+        }
+    }
 }
 
 pub fn init() {
     TIMER_LIST.init_by(SpinNoIrq::new(TimerList::new()));
+    SYSCALL_TIMER_LIST.init_by(SpinNoIrq::new(TimerList::new()));
+    POLLER_TIMER_LIST.init_by(SpinNoIrq::new(TimerList::new()));
 }
@@ -0,0 +1,472 @@
+//! 控制台终端与行规程
+//!
+//! 标准输入、输出与错误共用同一个终端，其属性、窗口大小与前台进程组保存在全局的 `TTY` 中。
+//! 控制台没有接收中断，输入只在有任务读取终端或检查其是否可读时才从串口取出，并按照 termios 处理：
+//!
+//! - 输入转换：ISTRIP、INLCR、IGNCR、ICRNL
+//! - ISIG 开启时，VINTR、VQUIT、VSUSP 分别向前台进程组发送 SIGINT、SIGQUIT、SIGTSTP
+//! - ICANON 开启时按行缓冲，支持 VERASE、VWERASE、VKILL 行编辑，VEOF 提交当前行或表示文件结束；
+//!   关闭时按 VMIN 与 VTIME 决定读取何时返回
+//! - 回显：ECHO、ECHOE、ECHOK、ECHOKE、ECHONL、ECHOCTL
+//! - 输出转换：OPOST 开启时的 ONLCR、OCRNL
+//!
+//! 系统中每个进程各自构成一个进程组，进程组号即进程号
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use axerrno::{AxError, AxResult};
+use axfs::api::port::{
+    ConsoleWinSize, Termios, NCCS, TCGETS, TCSETS, TCSETSF, TCSETSW, TIOCGPGRP, TIOCGWINSZ,
+    TIOCSPGRP, TIOCSWINSZ,
+};
+use axhal::console::{getchar, write_bytes};
+use axhal::time::{current_time, TimeValue};
+use axsignal::signal_no::SignalNo;
+use bitflags::bitflags;
+
+use crate::{current_task, send_signal_to_process, Mutex, UserPtr, TID2TASK};
+
+bitflags! {
+    /// termios 的输入模式
+    #[derive(Clone, Copy)]
+    struct InputFlags: u32 {
+        const ISTRIP = 0o40;
+        const INLCR = 0o100;
+        const IGNCR = 0o200;
+        const ICRNL = 0o400;
+        const IXON = 0o2000;
+    }
+
+    /// termios 的输出模式
+    #[derive(Clone, Copy)]
+    struct OutputFlags: u32 {
+        const OPOST = 0o1;
+        const ONLCR = 0o4;
+        const OCRNL = 0o10;
+    }
+
+    /// termios 的本地模式
+    #[derive(Clone, Copy)]
+    struct LocalFlags: u32 {
+        const ISIG = 0o1;
+        const ICANON = 0o2;
+        const ECHO = 0o10;
+        const ECHOE = 0o20;
+        const ECHOK = 0o40;
+        const ECHONL = 0o100;
+        const NOFLSH = 0o200;
+        const ECHOCTL = 0o1000;
+        const ECHOKE = 0o4000;
+        const IEXTEN = 0o100000;
+    }
+}
+
+/// B38400 | CS8 | CREAD | HUPCL
+const DEFAULT_CFLAG: u32 = 0o2277;
+
+// c_cc 中各个控制字符的下标
+const VINTR: usize = 0;
+const VQUIT: usize = 1;
+const VERASE: usize = 2;
+const VKILL: usize = 3;
+const VEOF: usize = 4;
+const VTIME: usize = 5;
+const VMIN: usize = 6;
+const VSUSP: usize = 10;
+const VEOL: usize = 11;
+const VWERASE: usize = 14;
+const VEOL2: usize = 16;
+
+/// 与 Linux 相同的默认控制字符：^C ^\ DEL ^U ^D，VTIME 为 0，VMIN 为 1，^Q ^S ^Z，^R ^O ^W ^V
+const DEFAULT_CC: [u8; NCCS] = [
+    0o3, 0o34, 0o177, 0o25, 0o4, 0, 1, 0, 0o21, 0o23, 0o32, 0, 0o22, 0o17, 0o27, 0o26, 0, 0, 0,
+];
+
+/// 规范模式下一行的最大长度，包括行尾的换行符
+const MAX_CANON: usize = 4096;
+
+struct Tty {
+    termios: Termios,
+    winsize: ConsoleWinSize,
+    /// 前台进程组，为 0 时表示尚未设置，此时信号发给正在读取终端的进程
+    foreground: u64,
+    /// 规范模式下正在编辑、尚未提交的行
+    line: Vec<u8>,
+    /// 可以被读取的输入
+    ready: VecDeque<u8>,
+    /// 规范模式下 ready 中每一行的长度，长度为 0 的行表示文件结束
+    lines: VecDeque<usize>,
+    /// 非规范模式下 VTIME 计时开始时可读的字节数及其截止时间，一次读取完成后清除
+    read_timer: Option<(usize, TimeValue)>,
+}
+
+static TTY: Mutex<Tty> = Mutex::new(Tty {
+    termios: Termios {
+        c_iflag: InputFlags::ICRNL.bits() | InputFlags::IXON.bits(),
+        c_oflag: OutputFlags::OPOST.bits() | OutputFlags::ONLCR.bits(),
+        c_cflag: DEFAULT_CFLAG,
+        c_lflag: LocalFlags::ISIG.bits()
+            | LocalFlags::ICANON.bits()
+            | LocalFlags::ECHO.bits()
+            | LocalFlags::ECHOE.bits()
+            | LocalFlags::ECHOK.bits()
+            | LocalFlags::ECHOCTL.bits()
+            | LocalFlags::ECHOKE.bits()
+            | LocalFlags::IEXTEN.bits(),
+        c_line: 0,
+        c_cc: DEFAULT_CC,
+    },
+    winsize: ConsoleWinSize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    },
+    foreground: 0,
+    line: Vec::new(),
+    ready: VecDeque::new(),
+    lines: VecDeque::new(),
+    read_timer: None,
+});
+
+impl Tty {
+    fn iflag(&self) -> InputFlags {
+        InputFlags::from_bits_truncate(self.termios.c_iflag)
+    }
+
+    fn oflag(&self) -> OutputFlags {
+        OutputFlags::from_bits_truncate(self.termios.c_oflag)
+    }
+
+    fn lflag(&self) -> LocalFlags {
+        LocalFlags::from_bits_truncate(self.termios.c_lflag)
+    }
+
+    /// ch 是否为下标 index 处的控制字符，值为 0 的控制字符表示被禁用
+    fn is_cc(&self, ch: u8, index: usize) -> bool {
+        ch != 0 && self.termios.c_cc[index] == ch
+    }
+
+    fn flush_input(&mut self) {
+        self.line.clear();
+        self.ready.clear();
+        self.lines.clear();
+    }
+
+    /// 提交当前行，使其可以被读取
+    fn commit_line(&mut self) {
+        self.lines.push_back(self.line.len());
+        self.ready.extend(self.line.drain(..));
+    }
+
+    /// 回显一个字符，ECHOCTL 开启时控制字符显示为 ^X 的形式
+    fn echo_char(&self, ch: u8, echo: &mut Vec<u8>) {
+        if self.echoed_as_control(ch) {
+            echo.extend_from_slice(&[b'^', ch ^ 0x40]);
+        } else {
+            echo.push(ch);
+        }
+    }
+
+    fn echoed_as_control(&self, ch: u8) -> bool {
+        self.lflag().contains(LocalFlags::ECHOCTL)
+            && ((ch < b' ' && ch != b'\t' && ch != b'\n') || ch == 0x7f)
+    }
+
+    /// 删除当前行的最后一个字符，并在 ECHO 与 ECHOE 开启时从屏幕上擦除它
+    fn erase_char(&mut self, echo: &mut Vec<u8>) -> Option<u8> {
+        let ch = self.line.pop()?;
+        if self.lflag().contains(LocalFlags::ECHO | LocalFlags::ECHOE) {
+            let width = if self.echoed_as_control(ch) { 2 } else { 1 };
+            for _ in 0..width {
+                echo.extend_from_slice(b"\x08 \x08");
+            }
+        }
+        Some(ch)
+    }
+
+    /// 处理一个输入字符，需要回显的内容写入 echo，需要发送的信号记录在 signals 中
+    fn receive(&mut self, mut ch: u8, echo: &mut Vec<u8>, signals: &mut Vec<SignalNo>) {
+        let iflag = self.iflag();
+        let lflag = self.lflag();
+        if iflag.contains(InputFlags::ISTRIP) {
+            ch &= 0x7f;
+        }
+        match ch {
+            b'\r' if iflag.contains(InputFlags::IGNCR) => return,
+            b'\r' if iflag.contains(InputFlags::ICRNL) => ch = b'\n',
+            b'\n' if iflag.contains(InputFlags::INLCR) => ch = b'\r',
+            _ => {}
+        }
+        let echo_on = lflag.contains(LocalFlags::ECHO);
+
+        if lflag.contains(LocalFlags::ISIG) {
+            let signal = if self.is_cc(ch, VINTR) {
+                Some(SignalNo::SIGINT)
+            } else if self.is_cc(ch, VQUIT) {
+                Some(SignalNo::SIGQUIT)
+            } else if self.is_cc(ch, VSUSP) {
+                Some(SignalNo::SIGTSTP)
+            } else {
+                None
+            };
+            if let Some(signal) = signal {
+                if !lflag.contains(LocalFlags::NOFLSH) {
+                    self.flush_input();
+                }
+                if echo_on {
+                    self.echo_char(ch, echo);
+                }
+                signals.push(signal);
+                return;
+            }
+        }
+
+        if !lflag.contains(LocalFlags::ICANON) {
+            self.ready.push_back(ch);
+            if echo_on {
+                self.echo_char(ch, echo);
+            }
+            return;
+        }
+
+        if self.is_cc(ch, VERASE) {
+            if self.erase_char(echo).is_some() && echo_on && !lflag.contains(LocalFlags::ECHOE) {
+                self.echo_char(ch, echo);
+            }
+        } else if lflag.contains(LocalFlags::IEXTEN) && self.is_cc(ch, VWERASE) {
+            // 先删除单词之后的空白，再删除单词本身
+            while self.line.last().is_some_and(|c| c.is_ascii_whitespace()) {
+                self.erase_char(echo);
+            }
+            while self.line.last().is_some_and(|c| !c.is_ascii_whitespace()) {
+                self.erase_char(echo);
+            }
+        } else if self.is_cc(ch, VKILL) {
+            if lflag.contains(LocalFlags::ECHO | LocalFlags::ECHOE | LocalFlags::ECHOKE) {
+                while self.erase_char(echo).is_some() {}
+            } else {
+                self.line.clear();
+                if echo_on {
+                    self.echo_char(ch, echo);
+                    if lflag.contains(LocalFlags::ECHOK) {
+                        echo.push(b'\n');
+                    }
+                }
+            }
+        } else if self.is_cc(ch, VEOF) {
+            // VEOF 本身不会被读取，空行表示文件结束
+            self.commit_line();
+        } else if ch == b'\n' || self.is_cc(ch, VEOL) || self.is_cc(ch, VEOL2) {
+            self.line.push(ch);
+            if echo_on || (ch == b'\n' && lflag.contains(LocalFlags::ECHONL)) {
+                self.echo_char(ch, echo);
+            }
+            self.commit_line();
+        } else if self.line.len() < MAX_CANON - 1 {
+            // 为行尾的换行符保留位置，行满之后的字符被丢弃
+            self.line.push(ch);
+            if echo_on {
+                self.echo_char(ch, echo);
+            }
+        }
+    }
+
+    /// 规范模式下读取至多一行，非规范模式下按 VMIN 与 VTIME 读取
+    ///
+    /// 还不能返回时返回 None，VTIME 的计时保存在终端中，在多次尝试之间保持
+    fn try_read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        if self.lflag().contains(LocalFlags::ICANON) {
+            let len = *self.lines.front()?;
+            let count = len.min(buf.len());
+            self.take(&mut buf[..count]);
+            if count == len {
+                self.lines.pop_front();
+            } else {
+                self.lines[0] -= count;
+            }
+            return Some(count);
+        }
+
+        let vmin = self.termios.c_cc[VMIN] as usize;
+        let vtime = TimeValue::from_millis(self.termios.c_cc[VTIME] as u64 * 100);
+        let avail = self.ready.len();
+        let done = if vtime.is_zero() {
+            avail >= vmin.min(buf.len())
+        } else {
+            // VMIN 为 0 时 VTIME 是整个读取的超时，否则是收到第一个字节之后字节之间的超时
+            if vmin == 0 || avail > 0 {
+                match self.read_timer {
+                    Some((seen, _)) if seen == avail => {}
+                    _ => self.read_timer = Some((avail, current_time() + vtime)),
+                }
+            }
+            avail >= vmin.clamp(1, buf.len())
+                || self
+                    .read_timer
+                    .is_some_and(|(_, deadline)| current_time() >= deadline)
+        };
+        if !done {
+            return None;
+        }
+        self.read_timer = None;
+        let count = avail.min(buf.len());
+        self.take(&mut buf[..count]);
+        Some(count)
+    }
+
+    fn take(&mut self, buf: &mut [u8]) {
+        for (dst, src) in buf.iter_mut().zip(self.ready.drain(..buf.len())) {
+            *dst = src;
+        }
+    }
+
+    fn readable(&self) -> bool {
+        if self.lflag().contains(LocalFlags::ICANON) {
+            !self.lines.is_empty()
+        } else {
+            !self.ready.is_empty()
+        }
+    }
+
+    /// 设置新的终端属性，flush 为真时丢弃尚未读取的输入
+    fn set_termios(&mut self, termios: Termios, flush: bool) {
+        if flush {
+            self.flush_input();
+        }
+        let was_canonical = self.lflag().contains(LocalFlags::ICANON);
+        self.termios = termios;
+        self.read_timer = None;
+        match (was_canonical, self.lflag().contains(LocalFlags::ICANON)) {
+            (true, false) => {
+                // 正在编辑的行立即可以被读取
+                self.ready.extend(self.line.drain(..));
+                self.lines.clear();
+            }
+            (false, true) => {
+                self.lines.clear();
+                if !self.ready.is_empty() {
+                    self.lines.push_back(self.ready.len());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 按照 termios 的输出模式将 buf 写到控制台
+fn write_processed(oflag: OutputFlags, buf: &[u8]) {
+    if !oflag.contains(OutputFlags::OPOST)
+        || !oflag.intersects(OutputFlags::ONLCR | OutputFlags::OCRNL)
+    {
+        write_bytes(buf);
+        return;
+    }
+    let mut out = Vec::with_capacity(buf.len() + buf.len() / 8);
+    for &ch in buf {
+        match ch {
+            b'\n' if oflag.contains(OutputFlags::ONLCR) => out.extend_from_slice(b"\r\n"),
+            b'\r' if oflag.contains(OutputFlags::OCRNL) => out.push(b'\n'),
+            _ => out.push(ch),
+        }
+    }
+    write_bytes(&out);
+}
+
+/// 从控制台取出所有已经到达的输入交给行规程处理，再对终端执行 f
+///
+/// 回显与信号的发送在释放终端的锁之后进行
+fn with_input<R>(f: impl FnOnce(&mut Tty) -> R) -> R {
+    let mut echo = Vec::new();
+    let mut signals = Vec::new();
+    let mut tty = TTY.lock();
+    while let Some(ch) = getchar() {
+        tty.receive(ch, &mut echo, &mut signals);
+    }
+    let ret = f(&mut tty);
+    let oflag = tty.oflag();
+    let foreground = tty.foreground;
+    drop(tty);
+
+    if !echo.is_empty() {
+        write_processed(oflag, &echo);
+    }
+    if !signals.is_empty() {
+        let pgrp = match foreground {
+            0 => current_task().get_process_id(),
+            pgrp => pgrp,
+        };
+        for signal in signals {
+            let _ = send_signal_to_process(pgrp as isize, signal as isize);
+        }
+    }
+    ret
+}
+
+/// 从终端读取，还不能返回时返回 `WouldBlock`
+///
+/// 控制台的输入没有中断通知，阻塞的读取由系统调用定期重新尝试，VTIME 的超时也在重新尝试时检查
+pub(crate) fn tty_read(buf: &mut [u8]) -> AxResult<usize> {
+    if buf.is_empty() {
+        return Ok(0);
+    }
+    with_input(|tty| tty.try_read(buf)).ok_or(AxError::WouldBlock)
+}
+
+/// 按照终端的输出模式写入
+pub(crate) fn tty_write(buf: &[u8]) -> AxResult<usize> {
+    let oflag = TTY.lock().oflag();
+    write_processed(oflag, buf);
+    Ok(buf.len())
+}
+
+/// 终端中是否有可以读取的输入
+pub(crate) fn tty_ready_to_read() -> bool {
+    with_input(|tty| tty.readable())
+}
+
+/// 标准输入、输出与错误共用的终端 ioctl
+pub(crate) fn tty_ioctl(request: usize, data: usize) -> AxResult<isize> {
+    match request {
+        TCGETS => {
+            let termios = TTY.lock().termios;
+            UserPtr::<Termios>::new(data).write(termios)?;
+        }
+        TCSETS | TCSETSW | TCSETSF => {
+            // 输出总是同步写到控制台的，因此 TCSETSW 不需要等待
+            let termios = UserPtr::<Termios>::new(data).read()?;
+            TTY.lock().set_termios(termios, request == TCSETSF);
+        }
+        TIOCGWINSZ => {
+            let winsize = TTY.lock().winsize;
+            UserPtr::<ConsoleWinSize>::new(data).write(winsize)?;
+        }
+        TIOCSWINSZ => {
+            let winsize = UserPtr::<ConsoleWinSize>::new(data).read()?;
+            TTY.lock().winsize = winsize;
+        }
+        TIOCGPGRP => {
+            let pgrp = match TTY.lock().foreground {
+                0 => current_task().get_process_id(),
+                pgrp => pgrp,
+            };
+            UserPtr::<i32>::new(data).write(pgrp as i32)?;
+        }
+        TIOCSPGRP => {
+            let pgrp = UserPtr::<i32>::new(data).read()?;
+            if pgrp <= 0 {
+                return Err(AxError::InvalidInput);
+            }
+            let exists = TID2TASK
+                .lock()
+                .values()
+                .any(|task| task.get_process_id() == pgrp as u64 && !task.get_zombie());
+            if !exists {
+                return Err(AxError::PermissionDenied);
+            }
+            TTY.lock().foreground = pgrp as u64;
+        }
+        _ => return Err(AxError::Unsupported),
+    }
+    Ok(0)
+}
@@ -0,0 +1,238 @@
+//! 用户内存的类型化访问
+//!
+//! 访问前先检查地址区间是否完整地落在当前任务的内存集中且具有相应的权限，并为 lazy 分配的页面分配物理页；
+//! 数据通过 [`axhal::uaccess::copy_user`] 拷贝，拷贝时若访问出错（例如其他线程同时解除了映射），
+//! 内核会通过异常表恢复执行，拷贝返回 `BadAddress` 而不会使内核崩溃。
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+use axerrno::{AxError, AxResult};
+use axhal::{mem::PAGE_SIZE_4K, paging::MappingFlags};
+use core::{fmt, marker::PhantomData, mem::MaybeUninit};
+
+use crate::current;
+
+/// 检查当前任务对用户区间 `[addr, addr + len)` 是否具有 `flags` 权限，并分配其中的 lazy 页面
+pub fn check_user_range(addr: usize, len: usize, flags: MappingFlags) -> AxResult<()> {
+    if len == 0 {
+        return Ok(());
+    }
+    current().check_user_range(addr.into(), len, flags)
+}
+
+/// 从用户地址 `src` 读取 `dst.len()` 个字节
+pub fn copy_from_user(dst: &mut [u8], src: usize) -> AxResult<()> {
+    check_user_range(src, dst.len(), MappingFlags::READ)?;
+    match unsafe { axhal::uaccess::copy_user(dst.as_mut_ptr(), src as *const u8, dst.len()) } {
+        0 => Ok(()),
+        _ => Err(AxError::BadAddress),
+    }
+}
+
+/// 将 `src` 写入用户地址 `dst`
+pub fn copy_to_user(dst: usize, src: &[u8]) -> AxResult<()> {
+    check_user_range(dst, src.len(), MappingFlags::WRITE)?;
+    match unsafe { axhal::uaccess::copy_user(dst as *mut u8, src.as_ptr(), src.len()) } {
+        0 => Ok(()),
+        _ => Err(AxError::BadAddress),
+    }
+}
+
+/// 将用户地址 `dst` 开始的 `len` 个字节清零
+pub fn clear_user(dst: usize, len: usize) -> AxResult<()> {
+    static ZEROS: [u8; PAGE_SIZE_4K] = [0; PAGE_SIZE_4K];
+    let end = dst.checked_add(len).ok_or(AxError::BadAddress)?;
+    let mut addr = dst;
+    while addr < end {
+        let size = (end - addr).min(PAGE_SIZE_4K);
+        copy_to_user(addr, &ZEROS[..size])?;
+        addr += size;
+    }
+    Ok(())
+}
+
+/// 指向用户地址空间中一个 `T` 类型对象的指针
+///
+/// 它只记录地址，每次读写都会重新检查地址并拷贝整个对象，因此 `T` 应当是可以按字节拷贝的普通数据类型。
+pub struct UserPtr<T> {
+    addr: usize,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> Clone for UserPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UserPtr<T> {}
+
+// SAFETY: UserPtr 只记录地址，每次访问都重新检查当前任务的地址空间，因此可以跨越系统调用中的 await 持有
+unsafe impl<T> Send for UserPtr<T> {}
+unsafe impl<T> Sync for UserPtr<T> {}
+
+impl<T> fmt::Debug for UserPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "UserPtr({:#x})", self.addr)
+    }
+}
+
+impl<T> From<usize> for UserPtr<T> {
+    fn from(addr: usize) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl<T> From<*const T> for UserPtr<T> {
+    fn from(ptr: *const T) -> Self {
+        Self::new(ptr as usize)
+    }
+}
+
+impl<T> From<*mut T> for UserPtr<T> {
+    fn from(ptr: *mut T) -> Self {
+        Self::new(ptr as usize)
+    }
+}
+
+impl<T> UserPtr<T> {
+    /// 由用户地址创建指针
+    pub const fn new(addr: usize) -> Self {
+        Self {
+            addr,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 指针对应的用户地址
+    pub const fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// 是否为空指针
+    pub const fn is_null(&self) -> bool {
+        self.addr == 0
+    }
+
+    /// 指向其后第 `count` 个对象的指针
+    pub const fn add(self, count: usize) -> Self {
+        Self::new(
+            self.addr
+                .wrapping_add(count.wrapping_mul(core::mem::size_of::<T>())),
+        )
+    }
+
+    /// 转换为指向其他类型的指针
+    pub const fn cast<U>(self) -> UserPtr<U> {
+        UserPtr::new(self.addr)
+    }
+
+    /// 检查指针指向的 `len` 个对象是否可以以 `flags` 权限访问，并分配其中的 lazy 页面
+    pub fn check(&self, len: usize, flags: MappingFlags) -> AxResult<()> {
+        let size = len
+            .checked_mul(core::mem::size_of::<T>())
+            .ok_or(AxError::BadAddress)?;
+        check_user_range(self.addr, size, flags)
+    }
+
+    /// 读取指针指向的对象
+    pub fn read(&self) -> AxResult<T>
+    where
+        T: Copy,
+    {
+        let mut value = MaybeUninit::<T>::uninit();
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(
+                value.as_mut_ptr() as *mut u8,
+                core::mem::size_of::<T>(),
+            )
+        };
+        copy_from_user(bytes, self.addr)?;
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// 将 `value` 写入指针指向的位置
+    pub fn write(&self, value: T) -> AxResult<()>
+    where
+        T: Copy,
+    {
+        let bytes = unsafe {
+            core::slice::from_raw_parts(&value as *const T as *const u8, core::mem::size_of::<T>())
+        };
+        copy_to_user(self.addr, bytes)
+    }
+
+    /// 读取指针指向的对象，空指针时返回 `None`
+    pub fn read_opt(&self) -> AxResult<Option<T>>
+    where
+        T: Copy,
+    {
+        if self.is_null() {
+            return Ok(None);
+        }
+        self.read().map(Some)
+    }
+
+    /// 将 `value` 写入指针指向的位置，空指针时不做处理
+    pub fn write_opt(&self, value: T) -> AxResult<()>
+    where
+        T: Copy,
+    {
+        if self.is_null() {
+            return Ok(());
+        }
+        self.write(value)
+    }
+
+    /// 读取指针指向的连续 `len` 个对象
+    pub fn read_array(&self, len: usize) -> AxResult<Vec<T>>
+    where
+        T: Copy,
+    {
+        self.check(len, MappingFlags::READ)?;
+        let mut values = Vec::with_capacity(len);
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(
+                values.as_mut_ptr() as *mut u8,
+                len * core::mem::size_of::<T>(),
+            )
+        };
+        copy_from_user(bytes, self.addr)?;
+        unsafe { values.set_len(len) };
+        Ok(values)
+    }
+
+    /// 将 `values` 写入指针指向的连续位置
+    pub fn write_array(&self, values: &[T]) -> AxResult<()>
+    where
+        T: Copy,
+    {
+        let bytes = unsafe {
+            core::slice::from_raw_parts(
+                values.as_ptr() as *const u8,
+                core::mem::size_of_val(values),
+            )
+        };
+        copy_to_user(self.addr, bytes)
+    }
+}
+
+impl UserPtr<u8> {
+    /// 读取指针指向的以 `\0` 结尾的字符串，不包括结尾的 `\0`
+    ///
+    /// 字符串逐页读取，因此结尾之后的内存不需要可以访问。字符串不是 UTF-8 编码时返回 `InvalidInput`。
+    pub fn read_cstr(&self) -> AxResult<String> {
+        let mut bytes = Vec::new();
+        let mut addr = self.addr;
+        loop {
+            let start = bytes.len();
+            let len = PAGE_SIZE_4K - addr % PAGE_SIZE_4K;
+            bytes.resize(start + len, 0);
+            copy_from_user(&mut bytes[start..], addr)?;
+            if let Some(end) = bytes[start..].iter().position(|&c| c == 0) {
+                bytes.truncate(start + end);
+                return String::from_utf8(bytes).map_err(|_| AxError::InvalidInput);
+            }
+            addr += len;
+        }
+    }
+}
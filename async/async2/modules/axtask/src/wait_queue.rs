@@ -1,6 +1,7 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
-use spinlock::SpinRaw;
+use alloc::vec::Vec;
+use spinlock::{SpinNoIrq, SpinRaw};
 
 use crate::{
     schedule::{add_to_wait_queue, in_wait_queue, remove_from_wait_queue},
@@ -31,6 +32,8 @@ use crate::{
 /// ```
 pub struct WaitQueue {
     queue: SpinRaw<VecDeque<AxTaskRef>>, // we already disabled IRQs when lock the `RUN_QUEUE`
+    /// 挂起在系统调用中等待的任务，通知时通过各自的 waker 唤醒
+    async_waiters: SpinNoIrq<Vec<AxTaskRef>>,
 }
 
 impl WaitQueue {
@@ -38,6 +41,7 @@ impl WaitQueue {
     pub const fn new() -> Self {
         Self {
             queue: SpinRaw::new(VecDeque::new()),
+            async_waiters: SpinNoIrq::new(Vec::new()),
         }
     }
 
@@ -45,6 +49,7 @@ impl WaitQueue {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             queue: SpinRaw::new(VecDeque::with_capacity(capacity)),
+            async_waiters: SpinNoIrq::new(Vec::new()),
         }
     }
 
@@ -100,6 +105,38 @@ impl WaitQueue {
         self.cancel_events(crate::current());
     }
 
+    /// 挂起当前任务直到 condition 成立，挂起期间不占用内核栈
+    ///
+    /// 任务在检查条件之前登记到队列中，队列被通知时与阻塞的任务一起被唤醒；
+    /// 收到信号等其他唤醒方式见 [`crate::wait_for`]
+    pub async fn wait_until_async<F>(&self, mut condition: F)
+    where
+        F: FnMut() -> bool,
+    {
+        crate::wait_for(|| {
+            let curr = crate::current();
+            let mut waiters = self.async_waiters.lock();
+            if !waiters.iter().any(|task| curr.ptr_eq(task)) {
+                waiters.push(curr.clone());
+            }
+            drop(waiters);
+            condition().then_some(())
+        })
+        .await;
+        let curr = crate::current();
+        self.async_waiters.lock().retain(|task| !curr.ptr_eq(task));
+    }
+
+    /// 唤醒所有挂起在系统调用中的等待者，由它们各自重新检查条件
+    fn wake_async_waiters(&self) -> bool {
+        let waiters = core::mem::take(&mut *self.async_waiters.lock());
+        let woken = !waiters.is_empty();
+        for task in waiters {
+            task.wake();
+        }
+        woken
+    }
+
     /// Blocks the current task and put it into the wait queue, until other tasks
     /// notify it, or the given duration has elapsed.
     #[cfg(feature = "irq")]
@@ -165,11 +202,12 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_one(&self, resched: bool) -> bool {
+        let woken = self.wake_async_waiters();
         let mut rq = RUN_QUEUE.lock();
         if !self.queue.lock().is_empty() {
-            self.notify_one_locked(resched, &mut rq)
+            self.notify_one_locked(resched, &mut rq) || woken
         } else {
-            false
+            woken
         }
     }
 
@@ -178,6 +216,7 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_all(&self, resched: bool) {
+        self.wake_async_waiters();
         loop {
             let mut rq = RUN_QUEUE.lock();
             if let Some(task) = self.queue.lock().pop_front() {
@@ -196,6 +235,16 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_task(&self, resched: bool, task: &AxTaskRef) -> bool {
+        let async_waiter = {
+            let mut waiters = self.async_waiters.lock();
+            let len = waiters.len();
+            waiters.retain(|t| !Arc::ptr_eq(t, task));
+            waiters.len() != len
+        };
+        if async_waiter {
+            task.wake();
+            return true;
+        }
         let mut rq = RUN_QUEUE.lock();
         let mut wq = self.queue.lock();
         if let Some(index) = wq.iter().position(|t| Arc::ptr_eq(t, task)) {
@@ -209,17 +258,19 @@ impl WaitQueue {
     }
 
     pub(crate) fn notify_one_locked(&self, resched: bool, rq: &mut AxRunQueue) -> bool {
+        let woken = self.wake_async_waiters();
         if let Some(task) = self.queue.lock().pop_front() {
             // task.set_in_wait_queue(false);
             remove_from_wait_queue(&task);
             rq.unblock_task(task, resched);
             true
         } else {
-            false
+            woken
         }
     }
 
     pub(crate) fn notify_all_locked(&self, resched: bool, rq: &mut AxRunQueue) {
+        self.wake_async_waiters();
         while let Some(task) = self.queue.lock().pop_front() {
             // task.set_in_wait_queue(false);
             remove_from_wait_queue(&task);
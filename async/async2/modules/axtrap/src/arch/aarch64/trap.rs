@@ -75,6 +75,18 @@ fn handle_el1h_64_sync_exception(tf: &mut TrapFrame) {
             log::debug!("BRK #{:#x} @ {:#x} ", iss, tf.elr);
             tf.elr += 4;
         }
+        // 内核访问用户内存出错时跳转到异常表中的修复地址，由拷贝函数返回错误
+        #[cfg(feature = "monolithic")]
+        Some(ESR_EL1::EC::Value::DataAbortCurrentEL)
+            if axhal::uaccess::fixup_exception(tf.elr).is_some() =>
+        {
+            log::debug!(
+                "EL1 fault on user memory @ {:#x}, FAR={:#x}",
+                tf.elr,
+                FAR_EL1.get()
+            );
+            tf.elr = axhal::uaccess::fixup_exception(tf.elr).unwrap();
+        }
         Some(ESR_EL1::EC::Value::DataAbortCurrentEL)
         | Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => {
             let iss = esr.read(ESR_EL1::ISS);
@@ -122,10 +134,11 @@ fn handle_el0t_64_sync_exception(tf: &mut TrapFrame) {
     match esr.read_as_enum(ESR_EL1::EC) {
         Some(ESR_EL1::EC::Value::SVC64) => {
             enable_irqs();
-            let result = handle_syscall(
+            // 这里运行在任务自己的内核栈上，同步等待系统调用完成
+            let result = axtask::block_on(handle_syscall(
                 tf.r[8],
                 [tf.r[0], tf.r[1], tf.r[2], tf.r[3], tf.r[4], tf.r[5]],
-            );
+            ));
             tf.r[0] = result as usize;
         }
         Some(ESR_EL1::EC::Value::DataAbortLowerEL) => {
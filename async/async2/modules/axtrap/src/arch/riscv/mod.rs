@@ -3,9 +3,10 @@ mod macros;
 
 use alloc::boxed::Box;
 use axhal::arch::TrapFrame;
+use axtask::SyscallFuture;
 
 use riscv::register::scause::{self, Exception as E, Trap};
-use riscv::register::stvec;
+use riscv::register::{sepc, stvec};
 
 use crate::trap::*;
 
@@ -63,9 +64,16 @@ extern "C" fn task_entry() {
                 // return to user space
                 riscv_trap_return(&mut tf);
                 // next time when user traps into kernel, it will come back here
-                riscv_trap_handler(&mut tf, true);
+                if let Some(syscall) = riscv_trap_handler(&mut tf, true) {
+                    // 不在执行器中运行，在内核栈上阻塞等待，直到系统调用的 waker 被唤醒
+                    let ret = axtask::block_on(syscall);
+                    riscv_syscall_return(&mut tf, ret);
+                }
 
                 task.set_tf(tf);
+                // 被停止信号停止时同样阻塞在内核栈上，直到收到 SIGCONT 或 SIGKILL
+                axtask::block_on(axtask::wait_while_stopped());
+                tf = task.get_tf();
             }
         }
     }
@@ -98,8 +106,12 @@ extern "C" fn riscv_trap_test() {
     axlog::warn!("trap_test");
 }
 
+/// 处理来自用户态的陷入
+///
+/// 系统调用不在这里执行，而是返回它的 future 交给调用者等待，完成后由调用者调用
+/// [`riscv_syscall_return`]；其余陷入就地处理，并在返回前处理信号
 #[no_mangle]
-extern "C" fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
+pub fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) -> Option<SyscallFuture> {
     // 这个函数是给用户程序用的，kernel的trap有额外的处理
     set_kernel_trap_entry();
     let scause = scause::read();
@@ -118,14 +130,14 @@ extern "C" fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
             axhal::arch::enable_irqs();
             //axlog::warn!("trap_handler :syscall id:{}", tf.regs.a7);
             tf.sepc += 4;
-            let result = handle_syscall(
+            // 系统调用期间以任务中保存的上下文为准，使得 clone、execve 等调用可以读取或改写用户上下文
+            axtask::current().set_tf(*tf);
+            return Some(Box::pin(handle_syscall(
                 tf.regs.a7,
                 [
                     tf.regs.a0, tf.regs.a1, tf.regs.a2, tf.regs.a3, tf.regs.a4, tf.regs.a5,
                 ],
-            );
-            tf.regs.a0 = result as usize;
-            axhal::arch::disable_irqs();
+            )));
         }
 
         #[cfg(feature = "monolithic")]
@@ -158,23 +170,64 @@ extern "C" fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
 
     #[cfg(feature = "monolithic")]
     {
-        //handle_signals();
+        // 返回用户态前处理信号，可能会改写任务保存的用户上下文
+        let curr = axtask::current();
+        curr.set_tf(*tf);
+        handle_signals(None);
+        *tf = curr.get_tf();
     }
     //axlog::ax_println!("trap handle end");
+    None
+}
+
+/// 系统调用完成后写回返回值，并在返回用户态之前处理信号
+#[no_mangle]
+pub fn riscv_syscall_return(tf: &mut TrapFrame, ret: isize) {
+    // 此时 tf 仍是陷入时的用户上下文，a0 为系统调用的第一个参数
+    #[cfg(feature = "monolithic")]
+    let syscall_arg0 = tf.regs.a0;
+    axhal::arch::disable_irqs();
+    // clone、execve 等调用可能改写了任务保存的上下文
+    let curr = axtask::current();
+    *tf = curr.get_tf();
+    tf.regs.a0 = ret as usize;
+    #[cfg(feature = "monolithic")]
+    {
+        curr.set_tf(*tf);
+        handle_signals(Some(syscall_arg0));
+        *tf = curr.get_tf();
+    }
 }
 
 /// Kernel trap handler
 #[no_mangle]
 pub fn riscv_kernel_trap_handler() {
-    axlog::warn!("kernel trap start");
     let scause = scause::read();
-    axlog::ax_println!("user scause:{:?},bits:{}", scause.cause(), scause.bits());
+    axlog::debug!("kernel trap: {:?}, bits: {}", scause.cause(), scause.bits());
     match scause.cause() {
         Trap::Interrupt(_) => handle_irq(scause.bits(), false),
+        // 内核访问用户内存出错时跳转到异常表中的修复地址，由拷贝函数返回错误
+        #[cfg(feature = "monolithic")]
+        Trap::Exception(E::LoadPageFault)
+        | Trap::Exception(E::StorePageFault)
+        | Trap::Exception(E::LoadFault)
+        | Trap::Exception(E::StoreFault) => {
+            let (addr, pc) = (riscv::register::stval::read(), sepc::read());
+            match axhal::uaccess::fixup_exception(pc) {
+                Some(fixup) => {
+                    axlog::debug!("kernel fault on user memory {:#x} @ {:#x}", addr, pc);
+                    sepc::write(fixup);
+                }
+                None => panic!(
+                    "Unhandled kernel trap {:?} @ {:#x}, stval={:#x}",
+                    scause.cause(),
+                    pc,
+                    addr
+                ),
+            }
+        }
         _ => {
-            axlog::ax_println!("fail scause:{:?}", scause.cause());
             panic!("Unhandled kernel trap {:?}", scause.cause(),);
         }
     }
-    axlog::ax_println!("kernel trap end");
 }
@@ -19,6 +19,19 @@ pub fn init_interrupt() {
     syscall::init_syscall();
 }
 
+/// 内核访问用户内存出错时，跳转到异常表中记录的修复地址，由拷贝函数返回错误
+///
+/// 若出错的指令不在异常表中，返回 false
+fn fixup_user_access(tf: &mut TrapFrame) -> bool {
+    #[cfg(feature = "monolithic")]
+    if let Some(fixup) = axhal::uaccess::fixup_exception(tf.rip as usize) {
+        tf.rip = fixup as u64;
+        return true;
+    }
+    let _ = tf;
+    false
+}
+
 #[no_mangle]
 fn x86_trap_handler(tf: &mut TrapFrame) {
     match tf.vector as u8 {
@@ -53,6 +66,12 @@ fn x86_trap_handler(tf: &mut TrapFrame) {
                     axlog::debug!("error_code: {:?}", tf.error_code);
                     crate::trap::handle_page_fault(unsafe { cr2() }.into(), map_flags);
                 }
+            } else if fixup_user_access(tf) {
+                axlog::debug!(
+                    "Kernel #PF on user memory, fault_vaddr={:#x}, error_code={:#x}",
+                    unsafe { cr2() },
+                    tf.error_code,
+                );
             } else {
                 panic!(
                     "Kernel #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}:\n{:#x?}",
@@ -65,10 +84,13 @@ fn x86_trap_handler(tf: &mut TrapFrame) {
         }
         BREAKPOINT_VECTOR => axlog::debug!("#BP @ {:#x} ", tf.rip),
         GENERAL_PROTECTION_FAULT_VECTOR => {
-            panic!(
-                "#GP @ {:#x}, error_code={:#x}:\n{:#x?}",
-                tf.rip, tf.error_code, tf
-            );
+            // 用户传入非规范地址时，内核访问它引发的是 #GP 而不是 #PF
+            if tf.is_user() || !fixup_user_access(tf) {
+                panic!(
+                    "#GP @ {:#x}, error_code={:#x}:\n{:#x?}",
+                    tf.rip, tf.error_code, tf
+                );
+            }
         }
         IRQ_VECTOR_START..=IRQ_VECTOR_END => crate::trap::handle_irq(tf.vector as _, false),
         _ => {
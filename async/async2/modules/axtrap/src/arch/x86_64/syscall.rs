@@ -48,7 +48,9 @@ pub(super) fn init_syscall() {
 
 #[no_mangle]
 fn x86_syscall_handler(tf: &mut TrapFrame) {
-    tf.rax = crate::trap::handle_syscall(tf.get_syscall_num(), tf.get_syscall_args()) as u64;
+    // 这里运行在任务自己的内核栈上，同步等待系统调用完成
+    let syscall = crate::trap::handle_syscall(tf.get_syscall_num(), tf.get_syscall_args());
+    tf.rax = axtask::block_on(syscall) as u64;
     #[cfg(feature = "monolithic")]
     if tf.is_user() {
         //crate::trap::handle_signals();
@@ -1,4 +1,6 @@
 pub fn handle_irq(_irq_num: usize, _from_user: bool) {
+    // 中断到来的时刻难以预测，作为随机数生成器的熵源
+    axrand::add_interrupt_randomness(_irq_num);
     #[cfg(feature = "irq")]
     {
         let guard = kernel_guard::NoPreempt::new();
@@ -18,4 +20,4 @@ pub fn handle_irq(_irq_num: usize, _from_user: bool) {
 }
 
 #[cfg(feature = "monolithic")]
-pub use linux_syscall_api::trap::{handle_page_fault, handle_syscall};
+pub use linux_syscall_api::trap::{handle_page_fault, handle_signals, handle_syscall};
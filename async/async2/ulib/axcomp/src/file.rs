@@ -3,14 +3,19 @@
 #[allow(unused)]
 use alloc::{format, string::ToString};
 #[allow(unused)]
-use linux_syscall_api::{create_link, new_file, FileFlags, FilePath};
+use linux_syscall_api::{
+    create_link, init_strace, new_file, write_syscall_report, FileFlags, FilePath,
+};
 
 /// 在执行系统调用前初始化文件系统
 ///
-/// 包括建立软连接，提前准备好一系列的文件与文件夹
+/// 包括建立软连接，写入已实现的系统调用列表，注册 /proc/strace 背后的跟踪器，提前准备好一系列的文件与文件夹
 ///
 /// Fat32 filesystem doesn't exists the concept of soft link, so we need to call this function every time we boot the system
 pub fn fs_init() {
+    write_syscall_report();
+    init_strace();
+
     #[cfg(target_arch = "riscv64")]
     let libc_so = &"ld-musl-riscv64-sf.so.1";
     #[cfg(target_arch = "riscv64")]